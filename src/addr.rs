@@ -0,0 +1,85 @@
+//! Parsing of server addresses
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+
+/// Address of a Redis server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Address {
+    /// IP literal, connected to without resolution
+    Ip(SocketAddr),
+    /// Host name, resolved on connect
+    Host(String, u16),
+}
+
+impl Address {
+    /// Parse `host:port`, `ipv4:port` or `[ipv6]:port`.
+    ///
+    /// IPv6 addresses may carry a numeric zone index (`[fe80::1%2]:6379`). As
+    /// nodes of a cluster announce themselves in `MOVED` and `ASK` redirections
+    /// as `ip:port` without brackets, an unbracketed IPv6 address is split at
+    /// its last colon.
+    pub fn parse(addr: &str) -> Option<Address> {
+        if addr.starts_with('[') {
+            let end = addr.find("]:")?;
+            let port = addr[end + 2..].parse().ok()?;
+            return Some(Address::Ip(ipv6(&addr[1..end], port)?));
+        }
+
+        let mut parts = addr.rsplitn(2, ':');
+        let port = parts.next()?.parse().ok()?;
+        let host = parts.next()?;
+        if host.is_empty() {
+            None
+        } else if host.contains(':') {
+            Some(Address::Ip(ipv6(host, port)?))
+        } else if let Ok(ip) = host.parse::<Ipv4Addr>() {
+            Some(Address::Ip(SocketAddr::new(IpAddr::V4(ip), port)))
+        } else {
+            Some(Address::Host(host.to_string(), port))
+        }
+    }
+
+    /// Format an address announced by a cluster node, bracketing IPv6
+    /// addresses.
+    ///
+    /// IP literals are normalized, so that the address announced in
+    /// `CLUSTER SLOTS` and in redirections compare equal.
+    pub fn format(host: &str, port: i64) -> String {
+        if host.contains(':') {
+            Address::normalize(&format!("[{}]:{}", host, port))
+        } else {
+            Address::normalize(&format!("{}:{}", host, port))
+        }
+    }
+
+    /// Normalize the address if it can be parsed
+    pub fn normalize(addr: &str) -> String {
+        match Address::parse(addr) {
+            Some(addr) => addr.to_string(),
+            None => addr.to_string(),
+        }
+    }
+}
+
+/// IPv6 address with an optional zone index after `%`
+fn ipv6(addr: &str, port: u16) -> Option<SocketAddr> {
+    let mut parts = addr.splitn(2, '%');
+    let ip = parts.next()?.parse::<Ipv6Addr>().ok()?;
+    let scope_id = match parts.next() {
+        Some(zone) => zone.parse().ok()?,
+        None => 0,
+    };
+    Some(SocketAddr::V6(SocketAddrV6::new(ip, port, 0, scope_id)))
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Address::Ip(SocketAddr::V6(addr)) if addr.scope_id() != 0 => {
+                write!(f, "[{}%{}]:{}", addr.ip(), addr.scope_id(), addr.port())
+            }
+            Address::Ip(addr) => write!(f, "{}", addr),
+            Address::Host(host, port) => write!(f, "{}:{}", host, port),
+        }
+    }
+}
@@ -2,15 +2,28 @@ use ::actix::prelude::*;
 use futures::Future;
 use redis_async::resp::RespValue;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
 
+use crate::addr::Address;
 use crate::command::*;
-use crate::redis::RespValueWrapper;
-use crate::Error;
+use crate::redis::{
+    Checkout, ConnectionState, Health, HealthReport, Readiness, RedactionPolicy,
+    RedisActorBuilder, RespValueWrapper,
+};
+use crate::resp::Frame;
+use crate::slot::{hash_slot, random_slot, HashError};
 use crate::RedisActor;
+use crate::{reply, Error, RespError};
+use tokio_timer::Delay;
 
 const MAX_RETRY: usize = 16;
 
+/// How often and how many times the slots are refreshed while waiting for a
+/// failover to complete
+const FAILOVER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const FAILOVER_POLLS: usize = 50;
+
 fn fmt_resp_value(o: &::redis_async::resp::RespValue) -> String {
     match o {
         RespValue::Nil => "nil".to_string(),
@@ -25,47 +38,142 @@ fn fmt_resp_value(o: &::redis_async::resp::RespValue) -> String {
     }
 }
 
+fn fmt_frame(o: &Frame) -> String {
+    match o {
+        Frame::Nil => "nil".to_string(),
+        Frame::Array(ref o) => {
+            format!(
+                "[{}]",
+                o.iter().map(fmt_frame).collect::<Vec<_>>().join(" ")
+            )
+        }
+        Frame::BulkString(ref o) => format!("\"{}\"", String::from_utf8_lossy(o)),
+        Frame::Error(ref o) => o.to_string(),
+        Frame::Integer(ref o) => o.to_string(),
+        Frame::SimpleString(ref o) => o.to_string(),
+    }
+}
+
+/// A reply as logged: errors in clear, values only with
+/// `RedactionPolicy::Off`
+fn fmt_reply(redaction: RedactionPolicy, o: &Frame) -> String {
+    match (redaction, o) {
+        (RedactionPolicy::Off, o) => fmt_frame(o),
+        (_, Frame::Error(ref e)) => e.to_string(),
+        (_, Frame::Nil) => "nil".to_string(),
+        _ => "<redacted>".to_string(),
+    }
+}
+
 pub struct RedisClusterActor {
     initial_addr: String,
+    connect_timeout: Duration,
+    strict: bool,
+    slow_log: Option<Duration>,
+    redaction: RedactionPolicy,
+    max_reply_bytes: Option<usize>,
+    renames: HashMap<String, String>,
+    circuit_breaker: Option<(usize, Duration)>,
+    mailbox_capacity: usize,
+    readiness: Option<Readiness>,
+    max_replica_lag: Option<u64>,
     slots: Vec<Slots>,
     connections: HashMap<String, Addr<RedisActor>>,
+    /// the read-only connections to the replicas
+    replicas: HashMap<String, Addr<RedisActor>>,
+    /// the lag of the replicas of each master, and when it was read
+    replica_lag: HashMap<String, (Instant, HashMap<String, u64>)>,
+    stats: HashMap<String, NodeStats>,
+    /// the last error refreshing the slots
+    last_error: Option<String>,
 }
 
 impl RedisClusterActor {
     pub fn start<S: Into<String>>(addr: S) -> Addr<RedisClusterActor> {
-        let addr = addr.into();
+        RedisClusterActor::builder(addr).start()
+    }
 
-        Supervisor::start(move |_ctx| RedisClusterActor {
-            initial_addr: addr,
-            slots: vec![],
-            connections: HashMap::new(),
-        })
+    /// Start new `Supervisor` with `RedisClusterActor` and wait until the slots
+    /// are discovered and the connections to all masters are established.
+    pub fn start_eagerly<S: Into<String>>(
+        addr: S,
+    ) -> impl Future<Item = Addr<RedisClusterActor>, Error = Error> {
+        RedisClusterActor::builder(addr).start_eagerly()
+    }
+
+    /// Configure a `RedisClusterActor` before starting it.
+    pub fn builder<S: Into<String>>(addr: S) -> RedisClusterActorBuilder {
+        RedisClusterActorBuilder {
+            addr: addr.into(),
+            connect_timeout: Duration::from_secs(1),
+            strict: false,
+            slow_log: None,
+            redaction: RedactionPolicy::KeyLength,
+            max_reply_bytes: None,
+            renames: HashMap::new(),
+            circuit_breaker: None,
+            mailbox_capacity: 16,
+            readiness: None,
+            max_replica_lag: None,
+        }
+    }
+
+    fn connect(&self, addr: String) -> Addr<RedisActor> {
+        let mut builder = self.node_builder(addr);
+        if let Some(ref readiness) = self.readiness {
+            builder = builder.readiness(readiness.clone());
+        }
+        builder.start()
+    }
+
+    /// Connect to a replica, which does not count for the readiness
+    fn connect_replica(&self, addr: String) -> Addr<RedisActor> {
+        self.node_builder(addr).read_only(true).start()
+    }
+
+    fn node_builder(&self, addr: String) -> RedisActorBuilder {
+        let mut builder = RedisActor::builder(addr)
+            .connect_timeout(self.connect_timeout)
+            .strict(self.strict)
+            .command_rename_map(
+                self.renames
+                    .iter()
+                    .map(|(name, alias)| (name.as_str(), alias.as_str()))
+                    .collect(),
+            )
+            .redaction(self.redaction)
+            .mailbox_capacity(self.mailbox_capacity);
+        if let Some(threshold) = self.slow_log {
+            builder = builder.slow_log(threshold);
+        }
+        if let Some(max) = self.max_reply_bytes {
+            builder = builder.max_reply_bytes(max);
+        }
+        if let Some((threshold, cooldown)) = self.circuit_breaker {
+            builder = builder.circuit_breaker(threshold, cooldown);
+        }
+        builder
     }
 
     fn refresh_slots(&mut self) -> ResponseActFuture<Self, (), ()> {
         let addr = self.initial_addr.clone();
-        let control_connection = self
-            .connections
-            .entry(addr.clone())
-            .or_insert_with(move || RedisActor::start(addr));
+        if !self.connections.contains_key(&addr) {
+            let connection = self.connect(addr.clone());
+            self.connections.insert(addr.clone(), connection);
+        }
+        let control_connection = &self.connections[&addr];
 
         Box::new(
-            control_connection
-                .send(ClusterSlots)
-                .then(|res| match res {
-                    Ok(Ok(slots)) => Ok(slots),
-                    Ok(Err(e)) => Err(e),
-                    Err(_) => Err(Error::Disconnected),
-                })
+            reply(control_connection.send(ClusterSlots))
                 .into_actor(self)
                 .then(|res, this, _ctx| match res {
                     Ok(slots) => {
                         for slots in slots.iter() {
-                            this.connections
-                                .entry(slots.master().to_string())
-                                .or_insert_with(|| {
-                                    RedisActor::start(slots.master().clone())
-                                });
+                            let master = slots.master();
+                            if !this.connections.contains_key(&master) {
+                                let connection = this.connect(master.clone());
+                                this.connections.insert(master, connection);
+                            }
                         }
                         this.slots = slots;
                         debug!("slots: {:?}", this.slots);
@@ -73,6 +181,7 @@ impl RedisClusterActor {
                     }
                     Err(e) => {
                         warn!("refreshing slots failed: {:?}", e);
+                        this.last_error = Some(e.to_string());
                         actix::fut::err(())
                     }
                 }),
@@ -80,6 +189,153 @@ impl RedisClusterActor {
     }
 }
 
+/// Builder for `RedisClusterActor`
+pub struct RedisClusterActorBuilder {
+    addr: String,
+    connect_timeout: Duration,
+    strict: bool,
+    slow_log: Option<Duration>,
+    redaction: RedactionPolicy,
+    max_reply_bytes: Option<usize>,
+    renames: HashMap<String, String>,
+    circuit_breaker: Option<(usize, Duration)>,
+    mailbox_capacity: usize,
+    readiness: Option<Readiness>,
+    max_replica_lag: Option<u64>,
+}
+
+impl RedisClusterActorBuilder {
+    /// Set the timeout of connection attempts to each node. Defaults to 1 second.
+    ///
+    /// An unreachable node delays neither the slot discovery nor the commands
+    /// for other nodes, and the commands for its slots fail with
+    /// `Error::NotConnected` after at most this long.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Refuse to send requests other than arrays of bulk strings to any node,
+    /// see `RedisActorBuilder::strict`.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Log the commands whose reply takes longer than `threshold` to arrive,
+    /// see `RedisActorBuilder::slow_log`.
+    pub fn slow_log(mut self, threshold: Duration) -> Self {
+        self.slow_log = Some(threshold);
+        self
+    }
+
+    /// Set how the keys and values of commands are redacted in the logs, see
+    /// `RedisActorBuilder::redaction`.
+    pub fn redaction(mut self, policy: RedactionPolicy) -> Self {
+        self.redaction = policy;
+        self
+    }
+
+    /// Drop the connection to a node when a reply larger than `max` bytes
+    /// arrives, see `RedisActorBuilder::max_reply_bytes`.
+    pub fn max_reply_bytes(mut self, max: usize) -> Self {
+        self.max_reply_bytes = Some(max);
+        self
+    }
+
+    /// Send renamed commands under the names the nodes know them under, see
+    /// `RedisActorBuilder::command_rename_map`.
+    pub fn command_rename_map(mut self, renames: HashMap<&str, &str>) -> Self {
+        self.renames = renames
+            .into_iter()
+            .map(|(name, alias)| (name.to_owned(), alias.to_owned()))
+            .collect();
+        self
+    }
+
+    /// Fail the commands for a node fast after repeated failures, see
+    /// `RedisActorBuilder::circuit_breaker`.
+    pub fn circuit_breaker(mut self, threshold: usize, cooldown: Duration) -> Self {
+        self.circuit_breaker = Some((threshold, cooldown));
+        self
+    }
+
+    /// Set the mailbox capacity of the actor and of the actors connected to
+    /// each node, see `RedisActorBuilder::mailbox_capacity`.
+    pub fn mailbox_capacity(mut self, capacity: usize) -> Self {
+        self.mailbox_capacity = capacity;
+        self
+    }
+
+    /// Report to `readiness` whether the actors connected to the nodes are all
+    /// ready to take more commands, see `RedisActorBuilder::readiness`.
+    pub fn readiness(mut self, readiness: Readiness) -> Self {
+        self.readiness = Some(readiness);
+        self
+    }
+
+    /// Skip the replicas lagging behind their master by more than `bytes` of
+    /// the replication stream for the reads sent with `ReadReplica`.
+    ///
+    /// The lag is read from `INFO replication` of the master, at most once per
+    /// second for each master.
+    pub fn max_replica_lag(mut self, bytes: u64) -> Self {
+        self.max_replica_lag = Some(bytes);
+        self
+    }
+
+    /// Start new `Supervisor` with `RedisClusterActor`.
+    pub fn start(self) -> Addr<RedisClusterActor> {
+        let RedisClusterActorBuilder {
+            addr,
+            connect_timeout,
+            strict,
+            slow_log,
+            redaction,
+            max_reply_bytes,
+            renames,
+            circuit_breaker,
+            mailbox_capacity,
+            readiness,
+            max_replica_lag,
+        } = self;
+
+        Supervisor::start(move |ctx: &mut Context<RedisClusterActor>| {
+            ctx.set_mailbox_capacity(mailbox_capacity);
+            RedisClusterActor {
+                initial_addr: Address::normalize(&addr),
+                connect_timeout,
+                strict,
+                slow_log,
+                redaction,
+                max_reply_bytes,
+                renames,
+                circuit_breaker,
+                mailbox_capacity,
+                readiness,
+                max_replica_lag,
+                slots: vec![],
+                connections: HashMap::new(),
+                replicas: HashMap::new(),
+                replica_lag: HashMap::new(),
+                stats: HashMap::new(),
+                last_error: None,
+            }
+        })
+    }
+
+    /// Start new `Supervisor` with `RedisClusterActor` and wait until the slots
+    /// are discovered and the connections to all masters are established.
+    pub fn start_eagerly(
+        self,
+    ) -> impl Future<Item = Addr<RedisClusterActor>, Error = Error> {
+        let addr = self.start();
+
+        // messages are not processed until the slots are discovered
+        reply(addr.send(WarmUp)).map(move |()| addr)
+    }
+}
+
 impl Actor for RedisClusterActor {
     type Context = Context<Self>;
 
@@ -87,12 +343,76 @@ impl Actor for RedisClusterActor {
         // TODO: does this wait prevent the issue (#1?)?
         ctx.wait(self.refresh_slots());
     }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        // dropping the addresses closes the node connections
+        self.slots.clear();
+        self.connections.clear();
+        self.replicas.clear();
+    }
 }
 
 impl Supervised for RedisClusterActor {
     fn restarting(&mut self, _: &mut Self::Context) {
         self.slots.clear();
         self.connections.clear();
+        self.replicas.clear();
+        self.replica_lag.clear();
+        self.stats.clear();
+    }
+}
+
+/// Wait until the connections to all known nodes are established
+struct WarmUp;
+
+impl Message for WarmUp {
+    type Result = Result<(), Error>;
+}
+
+impl Handler<WarmUp> for RedisClusterActor {
+    type Result = ResponseFuture<(), Error>;
+
+    fn handle(&mut self, _: WarmUp, _ctx: &mut Self::Context) -> Self::Result {
+        if self.slots.is_empty() {
+            return Box::new(futures::future::err(Error::NotConnected));
+        }
+
+        let pings = self
+            .connections
+            .values()
+            .map(|connection| reply(connection.send(Ping(None))).map(|_| ()))
+            .collect::<Vec<_>>();
+
+        Box::new(futures::future::join_all(pings).map(|_| ()))
+    }
+}
+
+/// Refresh the slots and look up the address of the master serving the slot
+pub(crate) struct MasterOf(pub u16);
+
+impl Message for MasterOf {
+    type Result = Result<String, Error>;
+}
+
+impl Handler<MasterOf> for RedisClusterActor {
+    type Result = ResponseActFuture<RedisClusterActor, String, Error>;
+
+    fn handle(&mut self, msg: MasterOf, _ctx: &mut Self::Context) -> Self::Result {
+        let slot = msg.0;
+
+        Box::new(self.refresh_slots().then(move |_, this, _ctx| {
+            match this
+                .slots
+                .iter()
+                .find(|slots| slots.start <= slot && slot <= slots.end)
+            {
+                Some(slots) => actix::fut::ok(slots.master()),
+                None => {
+                    warn!("no node is serving the slot {}", slot);
+                    actix::fut::err(Error::NotConnected)
+                }
+            }
+        }))
     }
 }
 
@@ -104,7 +424,7 @@ struct Retry {
 }
 
 impl Message for Retry {
-    type Result = Result<RespValue, Error>;
+    type Result = Result<Frame, Error>;
 }
 
 impl Retry {
@@ -114,7 +434,7 @@ impl Retry {
 }
 
 impl Handler<Retry> for RedisClusterActor {
-    type Result = ResponseActFuture<RedisClusterActor, RespValue, Error>;
+    type Result = ResponseActFuture<RedisClusterActor, Frame, Error>;
 
     fn handle(&mut self, msg: Retry, _ctx: &mut Self::Context) -> Self::Result {
         fn do_retry(
@@ -122,20 +442,24 @@ impl Handler<Retry> for RedisClusterActor {
             addr: String,
             req: RespValue,
             retry: usize,
-        ) -> ResponseActFuture<RedisClusterActor, RespValue, Error> {
+        ) -> ResponseActFuture<RedisClusterActor, Frame, Error> {
             use actix::fut::{err, ok};
 
             debug!(
                 "processing: req = {}, addr = {}, retry = {}",
-                fmt_resp_value(&req),
+                this.redaction.command(&req, || fmt_resp_value(&req)),
                 addr,
                 retry
             );
 
-            let connection = this
-                .connections
-                .entry(addr.clone())
-                .or_insert_with(move || RedisActor::start(addr));
+            if !this.connections.contains_key(&addr) {
+                let connection = this.connect(addr.clone());
+                this.connections.insert(addr.clone(), connection);
+            }
+            let connection = &this.connections[&addr];
+            let stats = this.stats.entry(addr.clone()).or_default();
+            stats.sent += 1;
+            stats.in_flight += 1;
             Box::new(
                 connection
                     .send(RespValueWrapper(req.clone()))
@@ -143,16 +467,22 @@ impl Handler<Retry> for RedisClusterActor {
                     .then(move |res, this, ctx| {
                         debug!(
                             "received: {:?}",
-                            res.as_ref().map(|res| res.as_ref().map(fmt_resp_value))
+                            res.as_ref().map(|res| res
+                                .as_ref()
+                                .map(|frame| fmt_reply(this.redaction, frame)))
                         );
+                        if let Some(stats) = this.stats.get_mut(&addr) {
+                            stats.record(&res);
+                        }
                         match res {
-                            Ok(Ok(RespValue::Error(ref e)))
+                            Ok(Ok(Frame::Error(ref e)))
                                 if e.starts_with("MOVED") && retry < MAX_RETRY =>
                             {
                                 info!(
                                     "MOVED redirection: retry = {}, request = {}",
                                     retry,
-                                    fmt_resp_value(&req)
+                                    this.redaction
+                                        .command(&req, || fmt_resp_value(&req))
                                 );
 
                                 let mut values = e.split(' ');
@@ -162,33 +492,34 @@ impl Handler<Retry> for RedisClusterActor {
 
                                 ctx.wait(this.refresh_slots());
 
-                                do_retry(this, addr.to_string(), req, retry + 1)
+                                do_retry(this, Address::normalize(addr), req, retry + 1)
                             }
-                            Ok(Ok(RespValue::Error(ref e)))
+                            Ok(Ok(Frame::Error(ref e)))
                                 if e.starts_with("ASK") && retry < MAX_RETRY =>
                             {
                                 info!(
                                     "ASK redirection: retry = {}, request = {}",
                                     retry,
-                                    fmt_resp_value(&req)
+                                    this.redaction
+                                        .command(&req, || fmt_resp_value(&req))
                                 );
 
                                 let mut values = e.split(' ');
                                 let _moved = values.next().unwrap();
                                 let _slot = values.next().unwrap();
-                                let addr = values.next().unwrap();
+                                let addr = Address::normalize(values.next().unwrap());
 
                                 ctx.spawn(
                                     // No retry for ASKING
                                     do_retry(
                                         this,
-                                        addr.to_string(),
+                                        addr.clone(),
                                         Asking.into_request(),
                                         MAX_RETRY,
                                     )
                                     .then(
                                         |res, _this, _ctx| {
-                                            match res.map(Asking::from_response) {
+                                            match res.map(Asking::from_frame) {
                                                 Ok(Ok(())) => {}
                                                 e => warn!(
                                                     "failed to issue ASKING: {:?}",
@@ -200,7 +531,7 @@ impl Handler<Retry> for RedisClusterActor {
                                     ),
                                 );
 
-                                do_retry(this, addr.to_string(), req, retry + 1)
+                                do_retry(this, addr, req, retry + 1)
                             }
                             Ok(Ok(res)) => Box::new(ok(res)),
                             Ok(Err(e)) => Box::new(err(e)),
@@ -214,6 +545,176 @@ impl Handler<Retry> for RedisClusterActor {
     }
 }
 
+/// The commands a `RedisClusterActor` sent to a node
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeStats {
+    /// the commands sent, a redirected command being counted again on the
+    /// node it is redirected to
+    pub sent: u64,
+    /// the commands answered with an error, other than a redirection, or
+    /// whose connection failed
+    pub errors: u64,
+    /// the `MOVED` and `ASK` redirections
+    pub redirections: u64,
+    /// the commands waiting for their reply
+    pub in_flight: u64,
+}
+
+impl NodeStats {
+    /// Count the reply of a command
+    fn record(&mut self, res: &Result<Result<Frame, Error>, MailboxError>) {
+        self.in_flight -= 1;
+        match res {
+            Ok(Ok(Frame::Error(ref e)))
+                if e.starts_with("MOVED") || e.starts_with("ASK") =>
+            {
+                self.redirections += 1
+            }
+            Ok(Ok(Frame::Error(_))) | Ok(Err(_)) | Err(_) => self.errors += 1,
+            Ok(Ok(_)) => (),
+        }
+    }
+}
+
+/// The statistics of the commands a `RedisClusterActor` sent to each node,
+/// keyed by the address of the node, e.g. to spot a hot shard. Transactions,
+/// the discovery of the slots and the reads sent to replicas are not counted.
+///
+/// The statistics are reset when the actor is restarted.
+pub struct ClusterStats;
+
+impl Message for ClusterStats {
+    type Result = HashMap<String, NodeStats>;
+}
+
+impl Handler<ClusterStats> for RedisClusterActor {
+    type Result = MessageResult<ClusterStats>;
+
+    fn handle(&mut self, _: ClusterStats, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.stats.clone())
+    }
+}
+
+/// The number of hash slots of a cluster
+const SLOT_COUNT: usize = 16384;
+
+/// The health of a `RedisClusterActor`, see `ClusterHealth`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClusterHealthReport {
+    /// the number of slots mapped to a master
+    pub slots_covered: usize,
+    /// whether all the 16384 slots are mapped to a master
+    pub slots_complete: bool,
+    /// the last error refreshing the slots
+    pub last_error: Option<String>,
+    /// the health of the connection to each node, keyed by its address
+    pub nodes: BTreeMap<String, HealthReport>,
+}
+
+/// Report the health of a `RedisClusterActor` and of its connections to the
+/// masters, e.g. to expose it on a `/healthz` endpoint. The report is
+/// serializable with the `serde` feature.
+///
+/// The slots are not complete until they are discovered, or if a master is
+/// missing from `CLUSTER SLOTS`, e.g. after it failed without a replica.
+/// Never fails: a connection restarting is reported disconnected.
+pub struct ClusterHealth;
+
+impl Message for ClusterHealth {
+    type Result = Result<ClusterHealthReport, Error>;
+}
+
+impl Handler<ClusterHealth> for RedisClusterActor {
+    type Result = ResponseFuture<ClusterHealthReport, Error>;
+
+    fn handle(&mut self, _: ClusterHealth, _ctx: &mut Self::Context) -> Self::Result {
+        let mut covered = vec![false; SLOT_COUNT];
+        for slots in self.slots.iter() {
+            for slot in slots.start..=slots.end {
+                if let Some(covered) = covered.get_mut(slot as usize) {
+                    *covered = true;
+                }
+            }
+        }
+        let slots_covered = covered.iter().filter(|&&covered| covered).count();
+        let last_error = self.last_error.clone();
+
+        let nodes = self.connections.iter().map(|(node, connection)| {
+            let node = node.clone();
+            connection.send(Health).then(move |report| {
+                // the connection is restarting if its mailbox is closed
+                let report = report.unwrap_or_else(|_| HealthReport {
+                    addr: node.clone(),
+                    state: ConnectionState::Disconnected,
+                    last_error: None,
+                    reconnections: 0,
+                    in_flight: 0,
+                });
+                Ok((node, report))
+            })
+        });
+        Box::new(futures::future::join_all(nodes.collect::<Vec<_>>()).map(
+            move |nodes| ClusterHealthReport {
+                slots_covered,
+                slots_complete: slots_covered == SLOT_COUNT,
+                last_error,
+                nodes: nodes.into_iter().collect(),
+            },
+        ))
+    }
+}
+
+/// Ping every master of the cluster, and their replicas if `replicas`, e.g.
+/// for a readiness check reporting the health of each shard.
+///
+/// Resolves to the address of each node and the round-trip time of its
+/// `PING`, or the error it failed with, so a single slow or failing node
+/// stands out. Fails if the slots are not discovered yet.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClusterPing {
+    pub replicas: bool,
+}
+
+impl Message for ClusterPing {
+    type Result = Result<Vec<(String, Result<Duration, Error>)>, Error>;
+}
+
+impl Handler<ClusterPing> for RedisClusterActor {
+    type Result = ResponseFuture<Vec<(String, Result<Duration, Error>)>, Error>;
+
+    fn handle(&mut self, msg: ClusterPing, _ctx: &mut Self::Context) -> Self::Result {
+        let mut nodes = vec![];
+        for slots in self.slots.iter() {
+            let count = if msg.replicas { slots.nodes.len() } else { 1 };
+            for &(ref host, port, _) in slots.nodes.iter().take(count) {
+                let node = Address::format(host, port);
+                if !nodes.contains(&node) {
+                    nodes.push(node);
+                }
+            }
+        }
+        if nodes.is_empty() {
+            return Box::new(futures::future::err(Error::NotConnected));
+        }
+
+        let pings = nodes
+            .into_iter()
+            .map(|node| {
+                if !self.connections.contains_key(&node) {
+                    let connection = self.connect(node.clone());
+                    self.connections.insert(node.clone(), connection);
+                }
+                let sent = Instant::now();
+                reply(self.connections[&node].send(Ping(None)))
+                    .then(move |res| Ok((node, res.map(|_| sent.elapsed()))))
+            })
+            .collect::<Vec<_>>();
+
+        Box::new(futures::future::join_all(pings))
+    }
+}
+
 impl<M> Handler<M> for RedisClusterActor
 where
     M: Command
@@ -234,7 +735,28 @@ where
         };
         let req = msg.into_request();
 
-        let fut = (|| match slot {
+        Box::new(
+            self.send_to_master(slot, req, ctx)
+                .and_then(|res, this, _ctx| {
+                    M::from_frame(res)
+                        .map_err(Error::Redis)
+                        .into_future()
+                        .into_actor(this)
+                }),
+        )
+    }
+}
+
+impl RedisClusterActor {
+    /// Send the request to the master serving the slot, or to the initial
+    /// node if it has no key
+    fn send_to_master(
+        &mut self,
+        slot: Option<u16>,
+        req: RespValue,
+        ctx: &mut Context<Self>,
+    ) -> ResponseActFuture<Self, Frame, Error> {
+        match slot {
             Some(slot) => {
                 for slots in self.slots.iter() {
                     if slots.start <= slot && slot <= slots.end {
@@ -255,10 +777,80 @@ where
                 Retry::new(self.initial_addr.clone(), req, 0),
                 ctx,
             ),
-        })();
+        }
+    }
+}
+
+/// How long the lag of the replicas of a master is used before it is read
+/// again
+const REPLICA_LAG_REFRESH: Duration = Duration::from_secs(1);
+
+/// Send a read-only command of a `RedisClusterActor` to a replica of the
+/// master serving the slot of its keys, e.g. to take reads off the masters.
+/// The reply may be stale, as the replica lags behind its master.
+///
+/// The first replica within `RedisClusterActorBuilder::max_replica_lag`, if
+/// set, is picked. The command is sent to the master instead if no replica
+/// qualifies, or if the replica fails or redirects it.
+#[derive(Debug)]
+pub struct ReadReplica<M>(pub M);
+
+impl<M> Message for ReadReplica<M>
+where
+    M: Command,
+    M::Output: 'static,
+{
+    type Result = Result<M::Output, Error>;
+}
+
+impl<M> Handler<ReadReplica<M>> for RedisClusterActor
+where
+    M: Command + 'static,
+    M::Output: Send + 'static,
+{
+    type Result = ResponseActFuture<RedisClusterActor, M::Output, Error>;
+
+    fn handle(&mut self, msg: ReadReplica<M>, ctx: &mut Self::Context) -> Self::Result {
+        use futures::IntoFuture;
+
+        let slot = match msg.0.key_slot() {
+            Ok(slot) => slot,
+            Err(e) => return Box::new(actix::fut::err(Error::MultipleSlot(e))),
+        };
+        let req = msg.0.into_request();
+        let slots = slot.and_then(|slot| {
+            self.slots
+                .iter()
+                .find(|slots| slots.start <= slot && slot <= slots.end)
+        });
+        let (master, replicas) = match slots {
+            Some(slots) if slots.nodes.len() > 1 => (
+                slots.master(),
+                slots.nodes[1..]
+                    .iter()
+                    .map(|&(ref host, port, _)| Address::format(host, port))
+                    .collect::<Vec<_>>(),
+            ),
+            _ => {
+                return Box::new(self.send_to_master(slot, req, ctx).and_then(
+                    |res, this, _ctx| {
+                        M::from_frame(res)
+                            .map_err(Error::Redis)
+                            .into_future()
+                            .into_actor(this)
+                    },
+                ))
+            }
+        };
 
-        Box::new(fut.and_then(|res, this, _ctx| {
-            M::from_response(res)
+        let res = self
+            .pick_replica(master, replicas)
+            .then(move |replica, this, ctx| match replica {
+                Ok(Some(replica)) => this.send_to_replica(replica, slot, req),
+                _ => this.send_to_master(slot, req, ctx),
+            });
+        Box::new(res.and_then(|res, this, _ctx| {
+            M::from_frame(res)
                 .map_err(Error::Redis)
                 .into_future()
                 .into_actor(this)
@@ -266,6 +858,410 @@ where
     }
 }
 
+impl RedisClusterActor {
+    /// The first of the replicas within the maximum lag
+    fn pick_replica(
+        &mut self,
+        master: String,
+        replicas: Vec<String>,
+    ) -> ResponseActFuture<Self, Option<String>, ()> {
+        let max = match self.max_replica_lag {
+            Some(max) => max,
+            None => return Box::new(actix::fut::ok(replicas.into_iter().next())),
+        };
+        let pick = move |lags: &HashMap<String, u64>| {
+            replicas
+                .into_iter()
+                .find(|replica| match lags.get(replica) {
+                    Some(&lag) => lag <= max,
+                    None => false,
+                })
+        };
+
+        if let Some(&(read, ref lags)) = self.replica_lag.get(&master) {
+            if read.elapsed() < REPLICA_LAG_REFRESH {
+                return Box::new(actix::fut::ok(pick(lags)));
+            }
+        }
+        if !self.connections.contains_key(&master) {
+            let connection = self.connect(master.clone());
+            self.connections.insert(master.clone(), connection);
+        }
+        let info = self.connections[&master].send(Info {
+            section: Some("replication".into()),
+        });
+        Box::new(info.into_actor(self).then(move |res, this, _ctx| {
+            let lags = match res {
+                Ok(Ok(info)) => replica_lags(&info),
+                Ok(Err(e)) => {
+                    warn!("reading the replica lag of {} failed: {}", master, e);
+                    HashMap::new()
+                }
+                Err(_) => HashMap::new(),
+            };
+            let replica = pick(&lags);
+            this.replica_lag.insert(master, (Instant::now(), lags));
+            actix::fut::ok(replica)
+        }))
+    }
+
+    /// Send the request to the replica, or to the master if it fails or
+    /// redirects it
+    fn send_to_replica(
+        &mut self,
+        replica: String,
+        slot: Option<u16>,
+        req: RespValue,
+    ) -> ResponseActFuture<Self, Frame, Error> {
+        if !self.replicas.contains_key(&replica) {
+            let connection = self.connect_replica(replica.clone());
+            self.replicas.insert(replica.clone(), connection);
+        }
+        let res = self.replicas[&replica].send(RespValueWrapper(req.clone()));
+        Box::new(res.into_actor(self).then(move |res, this, ctx| match res {
+            Ok(Ok(Frame::Error(ref e)))
+                if e.starts_with("MOVED") || e.starts_with("ASK") =>
+            {
+                debug!("replica {} redirected the read: {}", replica, e);
+                this.send_to_master(slot, req, ctx)
+            }
+            Ok(Ok(res)) => Box::new(actix::fut::ok(res)),
+            Ok(Err(e)) => {
+                warn!("read from replica {} failed: {}", replica, e);
+                this.send_to_master(slot, req, ctx)
+            }
+            Err(_) => this.send_to_master(slot, req, ctx),
+        }))
+    }
+}
+
+/// The lag in bytes of the online replicas of a master, keyed by address,
+/// from its `INFO replication`, e.g.
+/// `slave0:ip=127.0.0.1,port=7003,state=online,offset=1000,lag=0`
+fn replica_lags(
+    info: &HashMap<String, HashMap<String, String>>,
+) -> HashMap<String, u64> {
+    let replication = match info.get("Replication") {
+        Some(replication) => replication,
+        None => return HashMap::new(),
+    };
+    let offset: u64 = match replication
+        .get("master_repl_offset")
+        .and_then(|offset| offset.parse().ok())
+    {
+        Some(offset) => offset,
+        None => return HashMap::new(),
+    };
+
+    replication
+        .iter()
+        .filter(|&(name, _)| name.starts_with("slave"))
+        .filter_map(|(_, replica)| {
+            let fields = replica
+                .split(',')
+                .filter_map(|field| {
+                    let mut kv = field.splitn(2, '=');
+                    Some((kv.next()?, kv.next()?))
+                })
+                .collect::<HashMap<_, _>>();
+            if fields.get("state") != Some(&"online") {
+                return None;
+            }
+            let addr =
+                Address::format(fields.get("ip")?, fields.get("port")?.parse().ok()?);
+            let replica_offset: u64 = fields.get("offset")?.parse().ok()?;
+            Some((addr, offset.saturating_sub(replica_offset)))
+        })
+        .collect()
+}
+
+/// Where a `Routed` command is sent
+#[derive(Debug, Clone, PartialEq)]
+pub enum Routing {
+    /// The master serving the slot of the key, following redirections
+    BySlotOfKey(String),
+    /// The node at the address
+    ToNode(String),
+    /// A master chosen at random
+    ToRandomMaster,
+    /// Every master, e.g. for `DBSIZE` or `FLUSHDB`
+    ToAllMasters,
+}
+
+/// Send a command of a `RedisClusterActor` to the nodes given by `routing`,
+/// instead of the master serving the slot of its keys.
+///
+/// Resolves to the address of each node the command was sent to and its
+/// output. Fails if the command fails on any of them.
+#[derive(Debug)]
+pub struct Routed<M> {
+    pub routing: Routing,
+    pub command: M,
+}
+
+impl<M> Message for Routed<M>
+where
+    M: Command,
+    M::Output: 'static,
+{
+    type Result = Result<Vec<(String, M::Output)>, Error>;
+}
+
+impl RedisClusterActor {
+    /// The master serving the slot
+    fn master_of(&self, slot: u16) -> Option<String> {
+        self.slots
+            .iter()
+            .find(|slots| slots.start <= slot && slot <= slots.end)
+            .map(Slots::master)
+    }
+
+    /// The addresses to send a command to, and whether to follow redirections
+    fn route(&self, routing: Routing) -> Result<Vec<(String, bool)>, Error> {
+        let master = |slot| match self.master_of(slot) {
+            Some(master) => Ok(master),
+            None => {
+                warn!("no node is serving the slot {}", slot);
+                Err(Error::NotConnected)
+            }
+        };
+
+        match routing {
+            Routing::BySlotOfKey(key) => {
+                Ok(vec![(master(hash_slot(key.as_bytes()))?, true)])
+            }
+            Routing::ToNode(addr) => Ok(vec![(Address::normalize(&addr), false)]),
+            Routing::ToRandomMaster => Ok(vec![(master(random_slot())?, false)]),
+            Routing::ToAllMasters => {
+                let mut masters = vec![];
+                for slots in self.slots.iter() {
+                    let master = slots.master();
+                    if !masters.contains(&master) {
+                        masters.push(master);
+                    }
+                }
+                if masters.is_empty() {
+                    return Err(Error::NotConnected);
+                }
+                Ok(masters.into_iter().map(|master| (master, false)).collect())
+            }
+        }
+    }
+}
+
+impl<M> Handler<Routed<M>> for RedisClusterActor
+where
+    M: Command + 'static,
+    M::Output: Send + 'static,
+{
+    type Result = ResponseFuture<Vec<(String, M::Output)>, Error>;
+
+    fn handle(&mut self, msg: Routed<M>, ctx: &mut Self::Context) -> Self::Result {
+        let targets = match self.route(msg.routing) {
+            Ok(targets) => targets,
+            Err(e) => return Box::new(futures::future::err(e)),
+        };
+        let req = msg.command.into_request();
+
+        let responses = targets
+            .into_iter()
+            .map(|(addr, redirect)| {
+                // no retry for nodes given explicitly
+                let retry = if redirect { 0 } else { MAX_RETRY };
+                reply(
+                    ctx.address()
+                        .send(Retry::new(addr.clone(), req.clone(), retry)),
+                )
+                .and_then(|res| M::from_frame(res).map_err(Error::Redis))
+                .map(move |output| (addr, output))
+            })
+            .collect::<Vec<_>>();
+
+        Box::new(futures::future::join_all(responses))
+    }
+}
+
+/// Trigger a manual failover to the replica at `node` with `ClusterFailover`,
+/// then refresh the slots until the replica serves them, so the following
+/// commands are routed to it.
+///
+/// Fails if the replica does not serve any slot after about 5 seconds.
+#[derive(Debug, Clone)]
+pub struct Failover {
+    pub node: String,
+    pub mode: Option<FailoverMode>,
+}
+
+impl Message for Failover {
+    type Result = Result<(), Error>;
+}
+
+impl Handler<Failover> for RedisClusterActor {
+    type Result = ResponseActFuture<RedisClusterActor, (), Error>;
+
+    fn handle(&mut self, msg: Failover, ctx: &mut Self::Context) -> Self::Result {
+        use futures::IntoFuture;
+
+        let node = Address::normalize(&msg.node);
+        let req = ClusterFailover { mode: msg.mode }.into_request();
+        // no retry for the node given explicitly
+        let failover =
+            actix::Handler::handle(self, Retry::new(node.clone(), req, MAX_RETRY), ctx);
+
+        Box::new(
+            failover
+                .and_then(|res, this, _ctx| {
+                    ClusterFailover::from_frame(res)
+                        .map_err(Error::Redis)
+                        .into_future()
+                        .into_actor(this)
+                })
+                .and_then(move |(), this, _ctx| this.await_master(node, FAILOVER_POLLS)),
+        )
+    }
+}
+
+impl RedisClusterActor {
+    /// Refresh the slots until `node` serves some of them, at most `polls` more
+    /// times
+    fn await_master(
+        &mut self,
+        node: String,
+        polls: usize,
+    ) -> ResponseActFuture<RedisClusterActor, (), Error> {
+        Box::new(self.refresh_slots().then(move |_, this, _ctx| {
+            let res: ResponseActFuture<RedisClusterActor, (), Error> =
+                if this.slots.iter().any(|slots| slots.master() == node) {
+                    Box::new(actix::fut::ok(()))
+                } else if polls == 0 {
+                    warn!("{} did not take over any slot", node);
+                    Box::new(actix::fut::err(Error::Redis(RespError::RESP(
+                        "failover did not complete".into(),
+                        None,
+                    ))))
+                } else {
+                    Box::new(
+                        Delay::new(Instant::now() + FAILOVER_POLL_INTERVAL)
+                            .then(|_| Ok(()))
+                            .into_actor(this)
+                            .and_then(move |(), this, _ctx| {
+                                this.await_master(node, polls - 1)
+                            }),
+                    )
+                };
+            res
+        }))
+    }
+}
+
+/// Commands run in a `MULTI`/`EXEC` transaction by a `RedisClusterActor`, on
+/// the master serving the slot of their keys
+///
+/// All the keys must be in the same slot, e.g. by sharing a hash tag;
+/// otherwise the transaction fails with `Error::CrossSlot` without being sent.
+/// Redirections are not followed: a command for a moved slot fails the
+/// transaction.
+///
+/// Resolves to the replies of the commands.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    requests: Vec<RespValue>,
+    /// the distinct slots of the keys, in order
+    slots: Vec<u16>,
+    /// the first command whose keys are in several slots
+    invalid: Option<HashError>,
+}
+
+impl Transaction {
+    pub fn new() -> Transaction {
+        Transaction::default()
+    }
+
+    /// Queue a command in the transaction
+    pub fn queue<M: Command>(mut self, command: M) -> Self {
+        match command.key_slot() {
+            Ok(Some(slot)) => {
+                if !self.slots.contains(&slot) {
+                    self.slots.push(slot);
+                }
+            }
+            Ok(None) => (),
+            Err(e) => {
+                if self.invalid.is_none() {
+                    self.invalid = Some(e);
+                }
+            }
+        }
+        self.requests.push(command.into_request());
+        self
+    }
+}
+
+impl Message for Transaction {
+    type Result = Result<Vec<RespValue>, Error>;
+}
+
+impl Handler<Transaction> for RedisClusterActor {
+    type Result = ResponseFuture<Vec<RespValue>, Error>;
+
+    fn handle(&mut self, msg: Transaction, _ctx: &mut Self::Context) -> Self::Result {
+        use futures::future::{err, join_all};
+
+        let Transaction {
+            requests,
+            slots,
+            invalid,
+        } = msg;
+        if let Some(e) = invalid {
+            return Box::new(err(Error::MultipleSlot(e)));
+        }
+        let addr = match slots.as_slice() {
+            [] => self.initial_addr.clone(),
+            [slot] => match self.master_of(*slot) {
+                Some(master) => master,
+                None => {
+                    warn!("no node is serving the slot {}", slot);
+                    return Box::new(err(Error::NotConnected));
+                }
+            },
+            _ => return Box::new(err(Error::CrossSlot { slots })),
+        };
+
+        if !self.connections.contains_key(&addr) {
+            let connection = self.connect(addr.clone());
+            self.connections.insert(addr.clone(), connection);
+        }
+        let checkout = reply(self.connections[&addr].send(Checkout));
+
+        Box::new(checkout.and_then(|lease| {
+            // EXEC is awaited even if queueing fails, so the lease is not
+            // released in the middle of the transaction
+            let multi = lease.send(Multi).then(Ok);
+            let queued = join_all(
+                requests
+                    .into_iter()
+                    .map(|req| lease.queue_raw(req).then(Ok))
+                    .collect::<Vec<_>>(),
+            );
+            let exec = lease.send(Exec).then(Ok);
+
+            multi
+                .join3(queued, exec)
+                .then(move |res: Result<_, Error>| {
+                    let _lease = lease;
+                    let (multi, queued, exec) = res?;
+                    multi?;
+                    for res in queued {
+                        res?;
+                    }
+                    exec?.ok_or_else(|| {
+                        Error::Redis(RespError::RESP("transaction aborted".into(), None))
+                    })
+                })
+        }))
+    }
+}
+
 #[doc(hidden)]
 pub struct Stop;
 
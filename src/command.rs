@@ -1,8 +1,21 @@
-use crate::slot::{HashError, Hasher};
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::marker::PhantomData;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+
+use crate::addr::Address;
+use crate::resp::Frame;
+use crate::slot::{random_slot, HashError, Hasher};
 use crate::Error;
 use crate::RespError;
 use actix::Message;
 use redis_async::resp::RespValue;
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+
+pub use crate::value::{FromRedisValue, ToRedisArgs};
 
 pub trait Command {
     type Output;
@@ -13,6 +26,14 @@ pub trait Command {
     /// Parse the response of the command
     fn from_response(res: RespValue) -> Result<Self::Output, RespError>;
 
+    /// Parse the response of the command from a frame sharing the read buffer.
+    ///
+    /// By default the frame is converted to a `RespValue`, copying its bulk
+    /// strings. Commands returning `Bytes` override this to avoid the copy.
+    fn from_frame(res: Frame) -> Result<Self::Output, RespError> {
+        Self::from_response(res.into())
+    }
+
     /// Calculate the slot number of the keys of this command.
     /// If all keys (including zero) fall into the same slot, Ok(()) is returned.
     ///
@@ -30,9 +51,23 @@ pub trait Command {
         self.hash_keys(&mut hasher)?;
         Ok(hasher.get())
     }
+
+    /// Whether the server may hold the reply of this command until another
+    /// client acts, e.g. `BLPOP`. A `RedisActor` with a blocking lane sends
+    /// these commands on the connections of the lane.
+    fn is_blocking(&self) -> bool {
+        false
+    }
 }
 
-#[derive(Debug)]
+/// Decode the response of `command` through `FromRedisValue`
+fn decode<T: FromRedisValue>(res: RespValue, command: &str) -> Result<T, RespError> {
+    T::from_redis_value(res).map_err(|res| {
+        RespError::RESP(format!("invalid response for {}", command), Some(res))
+    })
+}
+
+#[derive(Debug, Clone)]
 pub struct Get {
     pub key: String,
 }
@@ -48,30 +83,176 @@ impl Command for Get {
         resp_array!["GET", self.key]
     }
 
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "GET")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// `Get` returning the value as a slice of the read buffer instead of a copy
+#[derive(Debug)]
+pub struct GetBytes {
+    pub key: String,
+}
+
+impl Message for GetBytes {
+    type Result = Result<Option<Bytes>, Error>;
+}
+
+impl Command for GetBytes {
+    type Output = Option<Bytes>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["GET", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        Get::from_response(res).map(|res| res.map(Bytes::from))
+    }
+
+    fn from_frame(res: Frame) -> Result<Self::Output, RespError> {
+        match res {
+            Frame::BulkString(s) => Ok(Some(s)),
+            Frame::Nil => Ok(None),
+            res => Err(RespError::RESP(
+                "invalid response for GET".into(),
+                Some(res.into()),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Get the bytes from `start` to `end` inclusive of the string at `key`
+/// (`GETRANGE`), as a slice of the read buffer. Negative offsets count from
+/// the end of the string.
+///
+/// The range is truncated to the string, a missing key reads as empty. See
+/// `range::RangeStream` to read a large value in chunks.
+#[derive(Debug, Clone)]
+pub struct GetRange {
+    pub key: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Message for GetRange {
+    type Result = Result<Bytes, Error>;
+}
+
+impl Command for GetRange {
+    type Output = Bytes;
+
+    fn into_request(self) -> RespValue {
+        resp_array![
+            "GETRANGE",
+            self.key,
+            self.start.to_string(),
+            self.end.to_string()
+        ]
+    }
+
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
         match res {
-            RespValue::BulkString(s) => Ok(Some(s)),
-            RespValue::Nil => Ok(None),
+            RespValue::BulkString(s) => Ok(s.into()),
             _ => Err(RespError::RESP(
-                "invalid response for GET".into(),
+                "invalid response for GETRANGE".into(),
                 Some(res),
             )),
         }
     }
 
+    fn from_frame(res: Frame) -> Result<Self::Output, RespError> {
+        match res {
+            Frame::BulkString(s) => Ok(s),
+            res => Err(RespError::RESP(
+                "invalid response for GETRANGE".into(),
+                Some(res.into()),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Append `value` to the string at `key`, created if missing (`APPEND`)
+#[derive(Debug)]
+pub struct Append {
+    pub key: String,
+    pub value: Vec<u8>,
+}
+
+impl Message for Append {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for Append {
+    /// the length of the string after the append
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        RespValue::Array(vec![
+            "APPEND".into(),
+            self.key.into(),
+            RespValue::BulkString(self.value),
+        ])
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "APPEND")
+    }
+
     fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
         hasher.hash_str(&self.key)
     }
 }
 
+/// The length of the string at `key`, 0 if missing (`STRLEN`)
 #[derive(Debug)]
+pub struct Strlen {
+    pub key: String,
+}
+
+impl Message for Strlen {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for Strlen {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["STRLEN", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "STRLEN")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum Expiration {
     Infinite,
     Ex(String),
     Px(String),
+    /// Unix time in seconds at which the key will expire
+    ExAt(String),
+    /// Unix time in milliseconds at which the key will expire
+    PxAt(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Set {
     pub key: String,
     pub value: String,
@@ -92,6 +273,8 @@ impl Command for Set {
             Infinite => resp_array!["SET", self.key, self.value],
             Ex(ex) => resp_array!["SET", self.key, self.value, "EX", ex],
             Px(px) => resp_array!["SET", self.key, self.value, "PX", px],
+            ExAt(t) => resp_array!["SET", self.key, self.value, "EXAT", t],
+            PxAt(t) => resp_array!["SET", self.key, self.value, "PXAT", t],
         }
     }
 
@@ -111,7 +294,124 @@ impl Command for Set {
     }
 }
 
+const SET_REPORT_EXISTED: &str = "\
+local existed = redis.call('EXISTS', KEYS[1])
+redis.call('SET', KEYS[1], unpack(ARGV))
+return existed";
+
+/// `Set`, also reporting whether the key existed, e.g. to tell new sessions
+/// from updated ones. `SET` does not tell, so it is run with `EXISTS` in a Lua
+/// script.
+#[derive(Debug, Clone)]
+pub struct SetReportExisted(pub Set);
+
+impl Message for SetReportExisted {
+    type Result = Result<bool, Error>;
+}
+
+impl Command for SetReportExisted {
+    /// whether the key existed, i.e. was overwritten
+    type Output = bool;
+
+    fn into_request(self) -> RespValue {
+        use self::Expiration::*;
+
+        let Set {
+            key,
+            value,
+            expiration,
+        } = self.0;
+        let mut v = vec![
+            "EVAL".into(),
+            SET_REPORT_EXISTED.into(),
+            "1".into(),
+            key.into(),
+            value.into(),
+        ];
+        match expiration {
+            Infinite => (),
+            Ex(ex) => ("EX", ex).write_redis_args(&mut v),
+            Px(px) => ("PX", px).write_redis_args(&mut v),
+            ExAt(t) => ("EXAT", t).write_redis_args(&mut v),
+            PxAt(t) => ("PXAT", t).write_redis_args(&mut v),
+        }
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "SET")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.0.key)
+    }
+}
+
+/// Get the value of a key and optionally update its expiration
+#[derive(Debug, Clone)]
+pub struct GetEx {
+    pub key: String,
+    /// `None` keeps the current TTL, `Some(Expiration::Infinite)` removes it (`PERSIST`)
+    pub expiration: Option<Expiration>,
+}
+
+impl Message for GetEx {
+    type Result = Result<Option<Vec<u8>>, Error>;
+}
+
+impl Command for GetEx {
+    type Output = Option<Vec<u8>>;
+
+    fn into_request(self) -> RespValue {
+        use self::Expiration::*;
+
+        match self.expiration {
+            None => resp_array!["GETEX", self.key],
+            Some(Infinite) => resp_array!["GETEX", self.key, "PERSIST"],
+            Some(Ex(ex)) => resp_array!["GETEX", self.key, "EX", ex],
+            Some(Px(px)) => resp_array!["GETEX", self.key, "PX", px],
+            Some(ExAt(t)) => resp_array!["GETEX", self.key, "EXAT", t],
+            Some(PxAt(t)) => resp_array!["GETEX", self.key, "PXAT", t],
+        }
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "GETEX")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Get the value of a key and delete it (`GETDEL`). Requires Redis 6.2 or
+/// later.
 #[derive(Debug)]
+pub struct GetDel {
+    pub key: String,
+}
+
+impl Message for GetDel {
+    type Result = Result<Option<Vec<u8>>, Error>;
+}
+
+impl Command for GetDel {
+    type Output = Option<Vec<u8>>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["GETDEL", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "GETDEL")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Expire {
     pub key: String,
     pub seconds: String,
@@ -130,14 +430,7 @@ impl Command for Expire {
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
-        match res {
-            RespValue::Integer(1) => Ok(true),
-            RespValue::Integer(0) => Ok(false),
-            _ => Err(RespError::RESP(
-                "invalid response for EXPIRE".into(),
-                Some(res),
-            )),
-        }
+        decode(res, "EXPIRE")
     }
 
     fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
@@ -145,7 +438,88 @@ impl Command for Expire {
     }
 }
 
-#[derive(Debug)]
+/// When `ExpireIf` may set the expiration of a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpireCondition {
+    /// Only if the key has no expiration (`NX`)
+    Nx,
+    /// Only if the key already has an expiration (`XX`)
+    Xx,
+    /// Only if the new expiration is later than the current one (`GT`)
+    Gt,
+    /// Only if the new expiration is sooner than the current one (`LT`)
+    Lt,
+}
+
+/// Make a key expire in `ttl` seconds, or milliseconds with `millis`, if
+/// `condition` holds (`EXPIRE` or `PEXPIRE` with an option).
+/// Requires Redis 7.0 or later.
+#[derive(Debug, Clone)]
+pub struct ExpireIf {
+    pub key: String,
+    pub ttl: i64,
+    pub millis: bool,
+    pub condition: ExpireCondition,
+}
+
+impl Message for ExpireIf {
+    type Result = Result<bool, Error>;
+}
+
+impl Command for ExpireIf {
+    /// true if the timeout was set, false if the key does not exist or the
+    /// condition does not hold
+    type Output = bool;
+
+    fn into_request(self) -> RespValue {
+        let condition = match self.condition {
+            ExpireCondition::Nx => "NX",
+            ExpireCondition::Xx => "XX",
+            ExpireCondition::Gt => "GT",
+            ExpireCondition::Lt => "LT",
+        };
+        let command = if self.millis { "PEXPIRE" } else { "EXPIRE" };
+        RespValue::Array((command, self.key, self.ttl, condition).to_redis_args())
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "EXPIRE")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Remove the expiration of a key (`PERSIST`)
+#[derive(Debug, Clone)]
+pub struct Persist {
+    pub key: String,
+}
+
+impl Message for Persist {
+    type Result = Result<bool, Error>;
+}
+
+impl Command for Persist {
+    /// true if the timeout was removed, false if the key does not exist or has
+    /// no timeout
+    type Output = bool;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["PERSIST", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "PERSIST")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Del {
     pub keys: Vec<String>,
 }
@@ -165,13 +539,7 @@ impl Command for Del {
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
-        match res {
-            RespValue::Integer(num) => Ok(num),
-            _ => Err(RespError::RESP(
-                "invalid response for DEL".into(),
-                Some(res),
-            )),
-        }
+        decode(res, "DEL")
     }
 
     fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
@@ -182,98 +550,3848 @@ impl Command for Del {
     }
 }
 
+/// Delete keys like `Del`, reclaiming their memory in the background
+/// (`UNLINK`)
 #[derive(Debug)]
-pub struct ClusterSlots;
-
-#[derive(Clone, Debug)]
-pub struct Slots {
-    pub start: u16,
-    pub end: u16,
-    /// IP address, port, id of nodes serving the slots.
-    /// The first entry corresponds to the master node.
-    pub nodes: Vec<(String, i64, Option<String>)>,
-}
-
-impl Slots {
-    pub fn master(&self) -> String {
-        format!("{}:{}", self.nodes[0].0, self.nodes[0].1)
-    }
+pub struct Unlink {
+    pub keys: Vec<String>,
 }
 
-impl Message for ClusterSlots {
-    type Result = Result<Vec<Slots>, Error>;
+impl Message for Unlink {
+    type Result = Result<i64, Error>;
 }
 
-impl Command for ClusterSlots {
-    type Output = Vec<Slots>;
+impl Command for Unlink {
+    /// the number of keys that were removed
+    type Output = i64;
 
     fn into_request(self) -> RespValue {
-        resp_array!["CLUSTER", "SLOTS"]
+        let mut v = vec![RespValue::BulkString(b"UNLINK".to_vec())];
+        v.extend(self.keys.into_iter().map(Into::into));
+        RespValue::Array(v)
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
-        use redis_async::resp::FromResp;
+        decode(res, "UNLINK")
+    }
 
-        fn parse_entry(res: RespValue) -> Result<Slots, RespError> {
-            match res {
-                RespValue::Array(values) => {
-                    if values.len() >= 3 {
-                        let mut it = values.into_iter();
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        for key in self.keys.iter() {
+            hasher.hash_str(key)?
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Touch {
+    pub keys: Vec<String>,
+}
+
+impl Message for Touch {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for Touch {
+    /// the number of keys that were touched
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec![RespValue::BulkString(b"TOUCH".to_vec())];
+        v.extend(self.keys.into_iter().map(Into::into));
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "TOUCH")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        for key in self.keys.iter() {
+            hasher.hash_str(key)?
+        }
+        Ok(())
+    }
+}
+
+/// Count how many of `keys` exist (`EXISTS`), a key given twice counting twice
+///
+/// In cluster mode all keys must be in the same slot.
+#[derive(Debug, Clone)]
+pub struct Exists {
+    pub keys: Vec<String>,
+}
+
+impl Message for Exists {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for Exists {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["EXISTS".into()];
+        self.keys.write_redis_args(&mut v);
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "EXISTS")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        for key in self.keys.iter() {
+            hasher.hash_str(key)?
+        }
+        Ok(())
+    }
+}
+
+/// The type of the value at `key` (`TYPE`), e.g. `string`, `list`, `set`,
+/// `zset`, `hash` or `stream`
+#[derive(Debug, Clone)]
+pub struct Type {
+    pub key: String,
+}
+
+impl Message for Type {
+    type Result = Result<Option<String>, Error>;
+}
+
+impl Command for Type {
+    /// `None` if the key does not exist
+    type Output = Option<String>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["TYPE", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::SimpleString(ref t) if t == "none" => Ok(None),
+            RespValue::SimpleString(t) => Ok(Some(t)),
+            _ => Err(RespError::RESP(
+                "invalid response for TYPE".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Set `field` of the hash at `key` to `value`, unless the field already
+/// exists (`HSETNX`)
+#[derive(Debug)]
+pub struct HSetNx {
+    pub key: String,
+    pub field: String,
+    pub value: Vec<u8>,
+}
+
+impl Message for HSetNx {
+    type Result = Result<bool, Error>;
+}
+
+impl Command for HSetNx {
+    /// true if the field was set, false if it already exists
+    type Output = bool;
+
+    fn into_request(self) -> RespValue {
+        RespValue::Array(vec![
+            "HSETNX".into(),
+            self.key.into(),
+            self.field.into(),
+            RespValue::BulkString(self.value),
+        ])
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "HSETNX")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Check whether `field` exists in the hash at `key` (`HEXISTS`)
+#[derive(Debug)]
+pub struct HExists {
+    pub key: String,
+    pub field: String,
+}
+
+impl Message for HExists {
+    type Result = Result<bool, Error>;
+}
+
+impl Command for HExists {
+    /// false if the field or the hash do not exist
+    type Output = bool;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["HEXISTS", self.key, self.field]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::Integer(1) => Ok(true),
+            RespValue::Integer(0) => Ok(false),
+            _ => Err(RespError::RESP(
+                "invalid response for HEXISTS".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The number of fields of the hash at `key` (`HLEN`)
+#[derive(Debug)]
+pub struct HLen {
+    pub key: String,
+}
+
+impl Message for HLen {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for HLen {
+    /// `0` if the hash does not exist
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["HLEN", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "HLEN")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Set `fields` of the hash at `key` to their values (`HSET`)
+#[derive(Debug, Clone)]
+pub struct HSet {
+    pub key: String,
+    pub fields: Vec<(String, Vec<u8>)>,
+}
+
+impl Message for HSet {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for HSet {
+    /// the number of fields added, not counting the ones updated
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        RespValue::Array(("HSET", self.key, self.fields).to_redis_args())
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "HSET")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Get the fields of the hash at `key` and their values (`HGETALL`)
+#[derive(Debug, Clone)]
+pub struct HGetAll {
+    pub key: String,
+}
+
+impl Message for HGetAll {
+    type Result = Result<HashMap<Vec<u8>, Vec<u8>>, Error>;
+}
+
+impl Command for HGetAll {
+    /// empty if the hash does not exist
+    type Output = HashMap<Vec<u8>, Vec<u8>>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["HGETALL", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "HGETALL")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Get the fields of the hash at `key` (`HGETALL`) as a `T` whose fields are
+/// named after them, e.g. a struct deriving `Deserialize`.
+///
+/// Each value is parsed as the type of its field: a number, a boolean (`true`,
+/// `false`, `1` or `0`), a string or a unit enum variant. `Option` fields are
+/// `None` if missing, while other missing fields fail with an error naming
+/// them; a missing hash has no field.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub struct HGetAllTyped<T> {
+    pub key: String,
+    _value: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> HGetAllTyped<T> {
+    pub fn new<S: Into<String>>(key: S) -> Self {
+        HGetAllTyped {
+            key: key.into(),
+            _value: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: DeserializeOwned + 'static> Message for HGetAllTyped<T> {
+    type Result = Result<T, Error>;
+}
+
+#[cfg(feature = "serde")]
+impl<T: DeserializeOwned> Command for HGetAllTyped<T> {
+    type Output = T;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["HGETALL", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        let fields = decode(res, "HGETALL")?;
+        crate::value::from_hash(fields).map_err(|e| {
+            RespError::RESP(format!("invalid response for HGETALL: {}", e), None)
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Add `members` to the set at `key` (`SADD`), returning how many were not
+/// members yet.
+#[derive(Debug, Clone)]
+pub struct SAdd {
+    pub key: String,
+    pub members: Vec<Vec<u8>>,
+}
+
+impl Message for SAdd {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for SAdd {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["SADD".into(), self.key.into()];
+        self.members.write_redis_args(&mut v);
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "SADD")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Return all the members of the set at `key` (`SMEMBERS`)
+#[derive(Debug, Clone)]
+pub struct SMembers {
+    pub key: String,
+}
+
+impl Message for SMembers {
+    type Result = Result<Vec<Vec<u8>>, Error>;
+}
+
+impl Command for SMembers {
+    /// the members, empty if the set does not exist
+    type Output = Vec<Vec<u8>>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["SMEMBERS", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "SMEMBERS")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Check whether each of `members` is in the set at `key` (`SMISMEMBER`).
+/// Requires Redis 6.2 or later.
+#[derive(Debug)]
+pub struct SMIsMember {
+    pub key: String,
+    pub members: Vec<Vec<u8>>,
+}
+
+impl Message for SMIsMember {
+    type Result = Result<Vec<bool>, Error>;
+}
+
+impl Command for SMIsMember {
+    /// the membership of each member, in the order of `members`
+    type Output = Vec<bool>;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["SMISMEMBER".into(), self.key.into()];
+        self.members.write_redis_args(&mut v);
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "SMISMEMBER")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Return the number of members in the intersection of the sets at `keys`
+/// without returning the intersection (`SINTERCARD`). Requires Redis 7.0 or
+/// later.
+///
+/// With `limit` the count stops at `limit`, so checking for an overlap of at
+/// least `limit` members does not compute the entire intersection. In cluster
+/// mode all keys must be in the same slot.
+#[derive(Debug)]
+pub struct SInterCard {
+    pub keys: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+impl Message for SInterCard {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for SInterCard {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        let limit = self.limit.map(|limit| ("LIMIT", limit));
+        RespValue::Array(
+            ("SINTERCARD", self.keys.len(), self.keys, limit).to_redis_args(),
+        )
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "SINTERCARD")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        for key in self.keys.iter() {
+            hasher.hash_str(key)?
+        }
+        Ok(())
+    }
+}
+
+/// Add `elements` to the HyperLogLog at `key` (`PFADD`)
+#[derive(Debug, Clone)]
+pub struct PfAdd {
+    pub key: String,
+    pub elements: Vec<Vec<u8>>,
+}
+
+impl Message for PfAdd {
+    type Result = Result<bool, Error>;
+}
+
+impl Command for PfAdd {
+    /// true if the estimated cardinality changed
+    type Output = bool;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["PFADD".into(), self.key.into()];
+        self.elements.write_redis_args(&mut v);
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "PFADD")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The estimated cardinality of the union of the HyperLogLogs at `keys`
+/// (`PFCOUNT`), with a standard error of 0.81%
+///
+/// In cluster mode the keys must share a slot.
+#[derive(Debug, Clone)]
+pub struct PfCount {
+    pub keys: Vec<String>,
+}
+
+impl Message for PfCount {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for PfCount {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["PFCOUNT".into()];
+        v.extend(self.keys.into_iter().map(Into::into));
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "PFCOUNT")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        for key in self.keys.iter() {
+            hasher.hash_str(key)?
+        }
+        Ok(())
+    }
+}
+
+/// Merge the HyperLogLogs at `sources` into the one at `dest` (`PFMERGE`)
+///
+/// In cluster mode the keys must share a slot.
+#[derive(Debug, Clone)]
+pub struct PfMerge {
+    pub dest: String,
+    pub sources: Vec<String>,
+}
+
+impl Message for PfMerge {
+    type Result = Result<(), Error>;
+}
+
+impl Command for PfMerge {
+    type Output = ();
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["PFMERGE".into(), self.dest.into()];
+        v.extend(self.sources.into_iter().map(Into::into));
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(()),
+            _ => Err(RespError::RESP(
+                "invalid response for PFMERGE".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.dest)?;
+        for key in self.sources.iter() {
+            hasher.hash_str(key)?
+        }
+        Ok(())
+    }
+}
+
+/// The random elements returned by `SRANDMEMBER`, `HRANDFIELD` and
+/// `ZRANDMEMBER`: a single one or nil without a count, an array otherwise
+fn random_elements(res: &RespValue) -> Option<Vec<Vec<u8>>> {
+    match res {
+        RespValue::Nil => Some(vec![]),
+        RespValue::BulkString(element) => Some(vec![element.clone()]),
+        RespValue::Array(elements) => elements
+            .iter()
+            .map(|e| match e {
+                RespValue::BulkString(e) => Some(e.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Request of the random element commands, asking for a count of 1 when
+/// `with` is needed without a count
+fn random_request(
+    command: &str,
+    key: String,
+    count: Option<i64>,
+    with: Option<&str>,
+) -> RespValue {
+    let count = match (count, with) {
+        (None, Some(_)) => Some(1),
+        (count, _) => count,
+    };
+    let mut v = vec![command.into(), key.into()];
+    if let Some(count) = count {
+        v.push(count.to_string().into());
+    }
+    if let Some(with) = with {
+        v.push(with.into());
+    }
+    RespValue::Array(v)
+}
+
+/// Return random members of the set at `key` (`SRANDMEMBER`)
+///
+/// Without `count` a single member is returned. A positive `count` returns up
+/// to `count` distinct members, a negative one exactly `-count` members which
+/// may repeat.
+#[derive(Debug)]
+pub struct SRandMember {
+    pub key: String,
+    pub count: Option<i64>,
+}
+
+impl Message for SRandMember {
+    type Result = Result<Vec<Vec<u8>>, Error>;
+}
+
+impl Command for SRandMember {
+    /// the members, empty if the set does not exist
+    type Output = Vec<Vec<u8>>;
+
+    fn into_request(self) -> RespValue {
+        random_request("SRANDMEMBER", self.key, self.count, None)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        random_elements(&res).ok_or_else(|| {
+            RespError::RESP("invalid response for SRANDMEMBER".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Return random fields of the hash at `key` (`HRANDFIELD`). Requires Redis 6.2
+/// or later.
+///
+/// `count` behaves as in `SRandMember`. With `with_values` each field is
+/// followed by its value.
+#[derive(Debug)]
+pub struct HRandField {
+    pub key: String,
+    pub count: Option<i64>,
+    pub with_values: bool,
+}
+
+impl Message for HRandField {
+    type Result = Result<Vec<Vec<u8>>, Error>;
+}
+
+impl Command for HRandField {
+    /// the fields, empty if the hash does not exist
+    type Output = Vec<Vec<u8>>;
+
+    fn into_request(self) -> RespValue {
+        let with = if self.with_values {
+            Some("WITHVALUES")
+        } else {
+            None
+        };
+        random_request("HRANDFIELD", self.key, self.count, with)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        random_elements(&res).ok_or_else(|| {
+            RespError::RESP("invalid response for HRANDFIELD".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Return random members of the sorted set at `key` (`ZRANDMEMBER`). Requires
+/// Redis 6.2 or later.
+///
+/// `count` behaves as in `SRandMember`. With `with_scores` each member is
+/// followed by its score.
+#[derive(Debug)]
+pub struct ZRandMember {
+    pub key: String,
+    pub count: Option<i64>,
+    pub with_scores: bool,
+}
+
+impl Message for ZRandMember {
+    type Result = Result<Vec<Vec<u8>>, Error>;
+}
+
+impl Command for ZRandMember {
+    /// the members, empty if the sorted set does not exist
+    type Output = Vec<Vec<u8>>;
+
+    fn into_request(self) -> RespValue {
+        let with = if self.with_scores {
+            Some("WITHSCORES")
+        } else {
+            None
+        };
+        random_request("ZRANDMEMBER", self.key, self.count, with)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        random_elements(&res).ok_or_else(|| {
+            RespError::RESP("invalid response for ZRANDMEMBER".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Which members `ZAdd` may touch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZAddCondition {
+    /// Only add new members (`NX`)
+    Nx,
+    /// Only update existing members (`XX`)
+    Xx,
+}
+
+/// How `ZAdd` may change the scores of existing members. Requires Redis 6.2 or
+/// later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZAddComparison {
+    /// Only raise scores (`GT`)
+    Gt,
+    /// Only lower scores (`LT`)
+    Lt,
+}
+
+/// Add members with their scores to the sorted set at `key`, or update their
+/// scores (`ZADD`)
+///
+/// With `incr` the score of the single member is incremented as with
+/// `ZINCRBY`.
+#[derive(Debug)]
+pub struct ZAdd {
+    pub key: String,
+    pub members: Vec<(f64, Vec<u8>)>,
+    pub condition: Option<ZAddCondition>,
+    pub comparison: Option<ZAddComparison>,
+    /// count the members whose score changed too (`CH`)
+    pub ch: bool,
+    pub incr: bool,
+}
+
+/// Reply of `ZAdd`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZAddReply {
+    /// the number of members added, or also changed with `ch`
+    Count(i64),
+    /// the new score with `incr`, or `None` if the condition or the comparison
+    /// refused the update
+    Score(Option<f64>),
+}
+
+impl Message for ZAdd {
+    type Result = Result<ZAddReply, Error>;
+}
+
+impl Command for ZAdd {
+    type Output = ZAddReply;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["ZADD".into(), self.key.into()];
+        match self.condition {
+            Some(ZAddCondition::Nx) => v.push("NX".into()),
+            Some(ZAddCondition::Xx) => v.push("XX".into()),
+            None => (),
+        }
+        match self.comparison {
+            Some(ZAddComparison::Gt) => v.push("GT".into()),
+            Some(ZAddComparison::Lt) => v.push("LT".into()),
+            None => (),
+        }
+        if self.ch {
+            v.push("CH".into());
+        }
+        if self.incr {
+            v.push("INCR".into());
+        }
+        self.members.write_redis_args(&mut v);
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        let reply = match res {
+            RespValue::Integer(count) => Some(ZAddReply::Count(count)),
+            RespValue::Nil => Some(ZAddReply::Score(None)),
+            RespValue::BulkString(ref score) => std::str::from_utf8(score)
+                .ok()
+                .and_then(|score| score.parse().ok())
+                .map(|score| ZAddReply::Score(Some(score))),
+            _ => None,
+        };
+        reply.ok_or_else(|| {
+            RespError::RESP("invalid response for ZADD".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// A bound of a lexicographical range of sorted set members
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexBound {
+    /// Before all members (`-`)
+    Min,
+    /// After all members (`+`)
+    Max,
+    /// The member and the ones after or before it (`[member`)
+    Inclusive(Vec<u8>),
+    /// Only the members after or before it (`(member`)
+    Exclusive(Vec<u8>),
+}
+
+impl LexBound {
+    /// Parse a bound in the syntax of Redis, e.g. from user input. `None` if it
+    /// is none of `-`, `+`, `[member` or `(member`.
+    pub fn parse(bound: &[u8]) -> Option<LexBound> {
+        match bound.split_first() {
+            Some((b'-', [])) => Some(LexBound::Min),
+            Some((b'+', [])) => Some(LexBound::Max),
+            Some((b'[', member)) => Some(LexBound::Inclusive(member.to_vec())),
+            Some((b'(', member)) => Some(LexBound::Exclusive(member.to_vec())),
+            _ => None,
+        }
+    }
+
+    fn into_arg(self) -> RespValue {
+        let bound = match self {
+            LexBound::Min => b"-".to_vec(),
+            LexBound::Max => b"+".to_vec(),
+            LexBound::Inclusive(member) => [&b"["[..], &member].concat(),
+            LexBound::Exclusive(member) => [&b"("[..], &member].concat(),
+        };
+        RespValue::BulkString(bound)
+    }
+}
+
+/// The members of the sorted set at `key` from the index `start` to `stop`,
+/// both included, and their scores (`ZRANGE` with `WITHSCORES`). Negative
+/// indices count from the highest score, e.g. `0` to `-1` for all.
+#[derive(Debug, Clone)]
+pub struct ZRangeWithScores {
+    pub key: String,
+    pub start: i64,
+    pub stop: i64,
+}
+
+impl Message for ZRangeWithScores {
+    type Result = Result<Vec<(Vec<u8>, f64)>, Error>;
+}
+
+impl Command for ZRangeWithScores {
+    /// the members and their scores, lowest first
+    type Output = Vec<(Vec<u8>, f64)>;
+
+    fn into_request(self) -> RespValue {
+        RespValue::Array(
+            ("ZRANGE", self.key, self.start, self.stop, "WITHSCORES").to_redis_args(),
+        )
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        scored_members(res, "ZRANGE")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The members of the sorted set at `key` between `min` and `max` in
+/// lexicographical order (`ZRANGEBYLEX`), e.g. from `[prefix` to
+/// `(prefix\xff` to complete a prefix
+///
+/// All the members must have the same score for the order to be meaningful.
+#[derive(Debug)]
+pub struct ZRangeByLex {
+    pub key: String,
+    pub min: LexBound,
+    pub max: LexBound,
+    /// the offset and the number of members to return, a negative number
+    /// returning all the members after the offset
+    pub limit: Option<(i64, i64)>,
+}
+
+impl Message for ZRangeByLex {
+    type Result = Result<Vec<Vec<u8>>, Error>;
+}
+
+impl Command for ZRangeByLex {
+    type Output = Vec<Vec<u8>>;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec![
+            "ZRANGEBYLEX".into(),
+            self.key.into(),
+            self.min.into_arg(),
+            self.max.into_arg(),
+        ];
+        self.limit
+            .map(|(offset, count)| ("LIMIT", offset, count))
+            .write_redis_args(&mut v);
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "ZRANGEBYLEX")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The number of members of the sorted set at `key` between `min` and `max`
+/// in lexicographical order (`ZLEXCOUNT`)
+#[derive(Debug)]
+pub struct ZLexCount {
+    pub key: String,
+    pub min: LexBound,
+    pub max: LexBound,
+}
+
+impl Message for ZLexCount {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for ZLexCount {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        RespValue::Array(vec![
+            "ZLEXCOUNT".into(),
+            self.key.into(),
+            self.min.into_arg(),
+            self.max.into_arg(),
+        ])
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "ZLEXCOUNT")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The order of `Sort`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// The elements of the list, set or sorted set at `key`, sorted (`SORT`)
+///
+/// In cluster mode, the keys matched by the `by` and `get` patterns must be in
+/// the slot of `key`, i.e. the patterns must have the hash tag of `key`, e.g.
+/// `weight_{user}_*` for `{user}:ids`; otherwise the command fails with
+/// `Error::MultipleSlot`.
+#[derive(Debug, Clone, Default)]
+pub struct Sort {
+    pub key: String,
+    /// sort by the values of the keys matching the pattern, `*` being replaced
+    /// by the element, or not at all if it has no `*`
+    pub by: Option<String>,
+    /// return the values of the keys matching the patterns instead of the
+    /// elements, `#` returning the element itself
+    pub get: Vec<String>,
+    /// the offset and the number of elements to return
+    pub limit: Option<(i64, i64)>,
+    pub order: Option<SortOrder>,
+    /// sort lexicographically instead of numerically
+    pub alpha: bool,
+}
+
+impl Sort {
+    fn request(self, command: &str) -> RespValue {
+        let mut v = vec![command.into(), self.key.into()];
+        self.by.map(|by| ("BY", by)).write_redis_args(&mut v);
+        self.limit
+            .map(|(offset, count)| ("LIMIT", offset, count))
+            .write_redis_args(&mut v);
+        for pattern in self.get {
+            ("GET", pattern).write_redis_args(&mut v);
+        }
+        match self.order {
+            Some(SortOrder::Asc) => "ASC".write_redis_args(&mut v),
+            Some(SortOrder::Desc) => "DESC".write_redis_args(&mut v),
+            None => (),
+        }
+        if self.alpha {
+            "ALPHA".write_redis_args(&mut v);
+        }
+        RespValue::Array(v)
+    }
+
+    fn hash_sort_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        // the key part of a pattern, before the field of a hash
+        fn pattern_key(pattern: &str) -> &str {
+            pattern.split("->").next().unwrap_or(pattern)
+        }
+
+        hasher.hash_str(&self.key)?;
+        if let Some(ref by) = self.by {
+            if by.contains('*') {
+                hasher.hash_str(pattern_key(by))?;
+            }
+        }
+        for pattern in self.get.iter().filter(|pattern| *pattern != "#") {
+            hasher.hash_str(pattern_key(pattern))?;
+        }
+        Ok(())
+    }
+}
+
+impl Message for Sort {
+    type Result = Result<Vec<Option<Vec<u8>>>, Error>;
+}
+
+impl Command for Sort {
+    /// the elements, or the values got for them, `None` for missing keys
+    type Output = Vec<Option<Vec<u8>>>;
+
+    fn into_request(self) -> RespValue {
+        self.request("SORT")
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "SORT")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        self.hash_sort_keys(hasher)
+    }
+}
+
+/// The read-only variant of `Sort` (`SORT_RO`), which can be sent to a replica.
+/// Requires Redis 7.0 or later.
+#[derive(Debug, Clone)]
+pub struct SortRo(pub Sort);
+
+impl Message for SortRo {
+    type Result = Result<Vec<Option<Vec<u8>>>, Error>;
+}
+
+impl Command for SortRo {
+    /// the elements, or the values got for them, `None` for missing keys
+    type Output = Vec<Option<Vec<u8>>>;
+
+    fn into_request(self) -> RespValue {
+        self.0.request("SORT_RO")
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "SORT_RO")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        self.0.hash_sort_keys(hasher)
+    }
+}
+
+/// Decode the members each followed by its score
+fn scored_members(
+    res: RespValue,
+    command: &str,
+) -> Result<Vec<(Vec<u8>, f64)>, RespError> {
+    let members = match res {
+        RespValue::Array(ref values) if values.len() % 2 == 0 => values
+            .chunks(2)
+            .map(|pair| match (&pair[0], &pair[1]) {
+                (RespValue::BulkString(member), RespValue::BulkString(score)) => {
+                    let score = std::str::from_utf8(score).ok()?.parse().ok()?;
+                    Some((member.clone(), score))
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>(),
+        _ => None,
+    };
+    members.ok_or_else(|| {
+        RespError::RESP(format!("invalid response for {}", command), Some(res))
+    })
+}
+
+/// Remove and return the `count` members with the lowest scores, or the one
+/// without `count`, of the sorted set at `key` (`ZPOPMIN`)
+#[derive(Debug, Clone)]
+pub struct ZPopMin {
+    pub key: String,
+    pub count: Option<usize>,
+}
+
+impl Message for ZPopMin {
+    type Result = Result<Vec<(Vec<u8>, f64)>, Error>;
+}
+
+impl Command for ZPopMin {
+    /// the members and their scores, lowest first
+    type Output = Vec<(Vec<u8>, f64)>;
+
+    fn into_request(self) -> RespValue {
+        RespValue::Array(("ZPOPMIN", self.key, self.count).to_redis_args())
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        scored_members(res, "ZPOPMIN")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Remove and return the `count` members with the highest scores, or the one
+/// without `count`, of the sorted set at `key` (`ZPOPMAX`)
+#[derive(Debug, Clone)]
+pub struct ZPopMax {
+    pub key: String,
+    pub count: Option<usize>,
+}
+
+impl Message for ZPopMax {
+    type Result = Result<Vec<(Vec<u8>, f64)>, Error>;
+}
+
+impl Command for ZPopMax {
+    /// the members and their scores, highest first
+    type Output = Vec<(Vec<u8>, f64)>;
+
+    fn into_request(self) -> RespValue {
+        RespValue::Array(("ZPOPMAX", self.key, self.count).to_redis_args())
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        scored_members(res, "ZPOPMAX")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Remove and return the member with the lowest score of the first non-empty
+/// sorted set of `keys` (`BZPOPMIN`), waiting up to `timeout` seconds (forever
+/// with 0) for one if they are all empty.
+///
+/// The connection is held meanwhile, as with `BLPop`, so send it through a
+/// `RedisActor` of its own, or one with a blocking lane.
+///
+/// In cluster mode all keys must be in the same slot.
+#[derive(Debug, Clone)]
+pub struct BZPopMin {
+    pub keys: Vec<String>,
+    pub timeout: f64,
+}
+
+impl Message for BZPopMin {
+    type Result = Result<Option<(String, Vec<u8>, f64)>, Error>;
+}
+
+impl Command for BZPopMin {
+    /// the key of the sorted set popped from, the member and its score, or
+    /// `None` if the timeout expired
+    type Output = Option<(String, Vec<u8>, f64)>;
+
+    fn into_request(self) -> RespValue {
+        RespValue::Array(("BZPOPMIN", self.keys, self.timeout).to_redis_args())
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        if let RespValue::Nil = res {
+            return Ok(None);
+        }
+        let popped = match res {
+            RespValue::Array(ref values) if values.len() == 3 => {
+                match (&values[0], &values[1], &values[2]) {
+                    (
+                        RespValue::BulkString(key),
+                        RespValue::BulkString(member),
+                        RespValue::BulkString(score),
+                    ) => String::from_utf8(key.clone()).ok().and_then(|key| {
+                        let score = std::str::from_utf8(score).ok()?.parse().ok()?;
+                        Some((key, member.clone(), score))
+                    }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        popped.map(Some).ok_or_else(|| {
+            RespError::RESP("invalid response for BZPOPMIN".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        for key in self.keys.iter() {
+            hasher.hash_str(key)?
+        }
+        Ok(())
+    }
+
+    fn is_blocking(&self) -> bool {
+        true
+    }
+}
+
+/// Add members at the given longitude and latitude to the geospatial index at
+/// `key` (`GEOADD`), or update their position. Returns the number of members
+/// added.
+#[derive(Debug, Clone)]
+pub struct GeoAdd {
+    pub key: String,
+    /// longitude, latitude and name of each member
+    pub members: Vec<(f64, f64, String)>,
+}
+
+impl Message for GeoAdd {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for GeoAdd {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["GEOADD".into(), self.key.into()];
+        self.members.write_redis_args(&mut v);
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "GEOADD")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The unit of a distance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeoUnit {
+    Meters,
+    Kilometers,
+    Feet,
+    Miles,
+}
+
+impl GeoUnit {
+    fn as_arg(self) -> &'static str {
+        match self {
+            GeoUnit::Meters => "m",
+            GeoUnit::Kilometers => "km",
+            GeoUnit::Feet => "ft",
+            GeoUnit::Miles => "mi",
+        }
+    }
+}
+
+/// The center of a `GeoSearch`
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoFrom {
+    /// The position of a member of the index (`FROMMEMBER`)
+    Member(String),
+    /// A longitude and a latitude (`FROMLONLAT`)
+    LonLat(f64, f64),
+}
+
+/// The area of a `GeoSearch`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoBy {
+    /// A circle of the radius (`BYRADIUS`)
+    Radius(f64, GeoUnit),
+    /// A rectangle of the width and the height (`BYBOX`)
+    Box(f64, f64, GeoUnit),
+}
+
+/// The order of the members found by a `GeoSearch`, by distance to the center
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeoOrder {
+    /// Nearest first (`ASC`)
+    Asc,
+    /// Farthest first (`DESC`)
+    Desc,
+}
+
+/// A member found by a `GeoSearch`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoMember {
+    pub member: String,
+    /// the distance to the center in the unit of the search, with `with_dist`
+    pub distance: Option<f64>,
+    /// the longitude and latitude, with `with_coord`
+    pub coordinates: Option<(f64, f64)>,
+}
+
+/// The members of the geospatial index at `key` within the area `by` around
+/// `from` (`GEOSEARCH`). Requires Redis 6.2 or later.
+///
+/// With `count`, only the first `count` members are returned, the nearest
+/// ones unless `any` is set, in which case the search stops as soon as enough
+/// members are found.
+#[derive(Debug, Clone)]
+pub struct GeoSearch {
+    pub key: String,
+    pub from: GeoFrom,
+    pub by: GeoBy,
+    pub order: Option<GeoOrder>,
+    pub count: Option<usize>,
+    pub any: bool,
+    pub with_coord: bool,
+    pub with_dist: bool,
+}
+
+impl Message for GeoSearch {
+    type Result = Result<Vec<GeoMember>, Error>;
+}
+
+impl Command for GeoSearch {
+    type Output = Vec<GeoMember>;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["GEOSEARCH".into(), self.key.into()];
+        match self.from {
+            GeoFrom::Member(member) => ("FROMMEMBER", member).write_redis_args(&mut v),
+            GeoFrom::LonLat(longitude, latitude) => {
+                ("FROMLONLAT", longitude, latitude).write_redis_args(&mut v)
+            }
+        }
+        match self.by {
+            GeoBy::Radius(radius, unit) => {
+                ("BYRADIUS", radius, unit.as_arg()).write_redis_args(&mut v)
+            }
+            GeoBy::Box(width, height, unit) => {
+                ("BYBOX", width, height, unit.as_arg()).write_redis_args(&mut v)
+            }
+        }
+        match self.order {
+            Some(GeoOrder::Asc) => v.push("ASC".into()),
+            Some(GeoOrder::Desc) => v.push("DESC".into()),
+            None => (),
+        }
+        if let Some(count) = self.count {
+            ("COUNT", count).write_redis_args(&mut v);
+            if self.any {
+                v.push("ANY".into());
+            }
+        }
+        if self.with_coord {
+            v.push("WITHCOORD".into());
+        }
+        if self.with_dist {
+            v.push("WITHDIST".into());
+        }
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        fn float(value: &RespValue) -> Option<f64> {
+            match value {
+                RespValue::BulkString(f) => std::str::from_utf8(f).ok()?.parse().ok(),
+                _ => None,
+            }
+        }
+
+        fn member(value: &RespValue) -> Option<GeoMember> {
+            let (member, extra) = match value {
+                RespValue::BulkString(member) => (member, &[][..]),
+                RespValue::Array(values) => match values.split_first()? {
+                    (RespValue::BulkString(member), extra) => (member, extra),
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            let mut member = GeoMember {
+                member: String::from_utf8(member.clone()).ok()?,
+                distance: None,
+                coordinates: None,
+            };
+            // the distance comes before the coordinates
+            for value in extra {
+                match value {
+                    RespValue::BulkString(_) if member.distance.is_none() => {
+                        member.distance = Some(float(value)?);
+                    }
+                    RespValue::Array(position) if position.len() == 2 => {
+                        member.coordinates =
+                            Some((float(&position[0])?, float(&position[1])?));
+                    }
+                    _ => return None,
+                }
+            }
+            Some(member)
+        }
+
+        let members = match res {
+            RespValue::Array(ref values) => {
+                values.iter().map(member).collect::<Option<Vec<_>>>()
+            }
+            _ => None,
+        };
+        members.ok_or_else(|| {
+            RespError::RESP("invalid response for GEOSEARCH".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Append an entry to the stream at `key` (`XADD`), creating the stream if
+/// needed. The id is generated by the server if `id` is `None`.
+///
+/// Fails with the error of the server if `id` is not greater than the id of
+/// the last entry.
+#[derive(Debug)]
+pub struct XAdd {
+    pub key: String,
+    pub id: Option<String>,
+    pub fields: Vec<(String, Vec<u8>)>,
+}
+
+impl Message for XAdd {
+    type Result = Result<String, Error>;
+}
+
+impl Command for XAdd {
+    /// the id of the entry
+    type Output = String;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec![
+            "XADD".into(),
+            self.key.into(),
+            self.id.unwrap_or_else(|| "*".into()).into(),
+        ];
+        for (field, value) in self.fields {
+            v.push(field.into());
+            v.push(RespValue::BulkString(value));
+        }
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::BulkString(ref id) => {
+                String::from_utf8(id.clone()).map_err(|_| {
+                    RespError::RESP(
+                        "invalid response for XADD".into(),
+                        Some(res.clone()),
+                    )
+                })
+            }
+            RespValue::Error(ref e) => Err(RespError::RESP(
+                format!("XADD failed: {}", e),
+                Some(res.clone()),
+            )),
+            _ => Err(RespError::RESP(
+                "invalid response for XADD".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// An entry of a stream
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamEntry {
+    pub id: String,
+    pub fields: Vec<(String, Vec<u8>)>,
+}
+
+/// Parse the reply of `XREAD` or `XREADGROUP`
+fn stream_entries(
+    res: RespValue,
+    command: &str,
+) -> Result<Vec<(String, Vec<StreamEntry>)>, RespError> {
+    fn string(value: &RespValue) -> Option<String> {
+        match value {
+            RespValue::BulkString(s) => String::from_utf8(s.clone()).ok(),
+            _ => None,
+        }
+    }
+
+    fn entry(value: &RespValue) -> Option<StreamEntry> {
+        match value {
+            RespValue::Array(entry) if entry.len() == 2 => match &entry[1] {
+                RespValue::Array(fields) if fields.len() % 2 == 0 => {
+                    let fields = fields
+                        .chunks(2)
+                        .map(|kv| match &kv[1] {
+                            RespValue::BulkString(value) => {
+                                Some((string(&kv[0])?, value.clone()))
+                            }
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    Some(StreamEntry {
+                        id: string(&entry[0])?,
+                        fields,
+                    })
+                }
+                // a pending entry deleted from the stream since it was read
+                RespValue::Nil => Some(StreamEntry {
+                    id: string(&entry[0])?,
+                    fields: vec![],
+                }),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn stream(value: &RespValue) -> Option<(String, Vec<StreamEntry>)> {
+        match value {
+            RespValue::Array(stream) if stream.len() == 2 => match &stream[1] {
+                RespValue::Array(entries) => Some((
+                    string(&stream[0])?,
+                    entries.iter().map(entry).collect::<Option<Vec<_>>>()?,
+                )),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    let streams = match res {
+        // timed out, or nothing new without `BLOCK`
+        RespValue::Nil => Some(vec![]),
+        RespValue::Array(ref streams) => {
+            streams.iter().map(stream).collect::<Option<Vec<_>>>()
+        }
+        _ => None,
+    };
+    streams.ok_or_else(|| {
+        RespError::RESP(format!("invalid response for {}", command), Some(res))
+    })
+}
+
+/// Append `COUNT`, `BLOCK` and `STREAMS` with the keys, then the ids
+fn stream_args(
+    v: &mut Vec<RespValue>,
+    streams: Vec<(String, String)>,
+    count: Option<usize>,
+    block_ms: Option<u64>,
+) {
+    if let Some(count) = count {
+        v.push("COUNT".into());
+        v.push(count.to_string().into());
+    }
+    if let Some(block_ms) = block_ms {
+        v.push("BLOCK".into());
+        v.push(block_ms.to_string().into());
+    }
+    v.push("STREAMS".into());
+    let (keys, ids): (Vec<_>, Vec<_>) = streams.into_iter().unzip();
+    v.extend(keys.into_iter().map(Into::into));
+    v.extend(ids.into_iter().map(Into::into));
+}
+
+/// Read the entries after the given ids from one or more streams (`XREAD`).
+/// `streams` holds the keys and the id to read after, e.g. `0-0` for the
+/// start of the stream or `$` for the entries added from now on.
+///
+/// With `block_ms`, the server waits that long (forever with 0) for an entry
+/// if there is none yet. The connection is held meanwhile, as with `BLPOP`:
+/// the commands sent after it are not answered before it is, so send blocking
+/// reads through a `RedisActor` of their own, or one with a blocking lane.
+///
+/// In cluster mode all keys must be in the same slot.
+#[derive(Debug)]
+pub struct XRead {
+    pub streams: Vec<(String, String)>,
+    pub count: Option<usize>,
+    pub block_ms: Option<u64>,
+}
+
+impl Message for XRead {
+    type Result = Result<Vec<(String, Vec<StreamEntry>)>, Error>;
+}
+
+impl Command for XRead {
+    /// the keys of the streams with new entries and their entries, empty if
+    /// there is none
+    type Output = Vec<(String, Vec<StreamEntry>)>;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["XREAD".into()];
+        stream_args(&mut v, self.streams, self.count, self.block_ms);
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        stream_entries(res, "XREAD")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        for (key, _) in self.streams.iter() {
+            hasher.hash_str(key)?;
+        }
+        Ok(())
+    }
+
+    fn is_blocking(&self) -> bool {
+        self.block_ms.is_some()
+    }
+}
+
+/// Create the consumer group `group` of the stream at `key` (`XGROUP CREATE`),
+/// starting after `id`: `$` to consume only the entries added from now on,
+/// `0` for the whole stream. With `mkstream`, a missing stream is created
+/// empty.
+///
+/// Fails with the error of the server, returned as `Ok(Err(_))`, if the group
+/// exists (`BUSYGROUP`) or the stream does not.
+#[derive(Debug)]
+pub struct XGroupCreate {
+    pub key: String,
+    pub group: String,
+    pub id: String,
+    pub mkstream: bool,
+}
+
+impl Message for XGroupCreate {
+    type Result = Result<Result<(), String>, Error>;
+}
+
+impl Command for XGroupCreate {
+    type Output = Result<(), String>;
+
+    fn into_request(self) -> RespValue {
+        if self.mkstream {
+            resp_array!["XGROUP", "CREATE", self.key, self.group, self.id, "MKSTREAM"]
+        } else {
+            resp_array!["XGROUP", "CREATE", self.key, self.group, self.id]
+        }
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(Ok(())),
+            RespValue::Error(e) => Ok(Err(e)),
+            res => Err(RespError::RESP(
+                "invalid response for XGROUP CREATE".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Read entries of one or more streams as `consumer` of `group`
+/// (`XREADGROUP`). `streams` holds the keys and the id to read after:
+///
+/// * `>` reads the entries never delivered to a consumer of the group. They
+///   are added to the pending entries of `consumer` until acknowledged with
+///   `XAck`.
+/// * an explicit id, e.g. `0`, reads the pending entries of `consumer` after
+///   it instead, e.g. to process again what was delivered before a crash.
+///   Pending entries deleted from the stream meanwhile have no fields.
+///
+/// `block_ms` holds the connection as for `XRead`.
+///
+/// In cluster mode all keys must be in the same slot.
+#[derive(Debug)]
+pub struct XReadGroup {
+    pub group: String,
+    pub consumer: String,
+    pub streams: Vec<(String, String)>,
+    pub count: Option<usize>,
+    pub block_ms: Option<u64>,
+}
+
+impl Message for XReadGroup {
+    type Result = Result<Vec<(String, Vec<StreamEntry>)>, Error>;
+}
+
+impl Command for XReadGroup {
+    /// the keys of the streams and their entries, empty if there is none
+    type Output = Vec<(String, Vec<StreamEntry>)>;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec![
+            "XREADGROUP".into(),
+            "GROUP".into(),
+            self.group.into(),
+            self.consumer.into(),
+        ];
+        stream_args(&mut v, self.streams, self.count, self.block_ms);
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        stream_entries(res, "XREADGROUP")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        for (key, _) in self.streams.iter() {
+            hasher.hash_str(key)?;
+        }
+        Ok(())
+    }
+
+    fn is_blocking(&self) -> bool {
+        self.block_ms.is_some()
+    }
+}
+
+/// Acknowledge entries of the stream at `key` read by a consumer of `group`
+/// (`XACK`), removing them from its pending entries. Returns how many were
+/// pending.
+#[derive(Debug)]
+pub struct XAck {
+    pub key: String,
+    pub group: String,
+    pub ids: Vec<String>,
+}
+
+impl Message for XAck {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for XAck {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["XACK".into(), self.key.into(), self.group.into()];
+        v.extend(self.ids.into_iter().map(Into::into));
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "XACK")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The end of a list to pop from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+/// Pop an element from the head of the first non-empty list of `keys`
+/// (`BLPOP`), waiting up to `timeout` seconds (forever with 0) for one if they
+/// are all empty.
+///
+/// The connection is held meanwhile: the commands sent after it are not
+/// answered before it is, so send it through a `RedisActor` of its own, or one
+/// with a blocking lane.
+///
+/// In cluster mode all keys must be in the same slot.
+#[derive(Debug, Clone)]
+pub struct BLPop {
+    pub keys: Vec<String>,
+    pub timeout: f64,
+}
+
+impl Message for BLPop {
+    type Result = Result<Option<(String, Vec<u8>)>, Error>;
+}
+
+impl Command for BLPop {
+    /// the key of the list popped from and the element, or `None` if the
+    /// timeout expired
+    type Output = Option<(String, Vec<u8>)>;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["BLPOP".into()];
+        self.keys.write_redis_args(&mut v);
+        self.timeout.write_redis_args(&mut v);
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        if let RespValue::Nil = res {
+            return Ok(None);
+        }
+        let popped = match res {
+            RespValue::Array(ref values) if values.len() == 2 => {
+                match (&values[0], &values[1]) {
+                    (RespValue::BulkString(key), RespValue::BulkString(element)) => {
+                        String::from_utf8(key.clone())
+                            .ok()
+                            .map(|key| (key, element.clone()))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        popped.map(Some).ok_or_else(|| {
+            RespError::RESP("invalid response for BLPOP".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        for key in self.keys.iter() {
+            hasher.hash_str(key)?
+        }
+        Ok(())
+    }
+
+    fn is_blocking(&self) -> bool {
+        true
+    }
+}
+
+/// Pop up to `count` elements from the first non-empty list of `keys`
+/// (`LMPOP`). Requires Redis 7.0 or later.
+///
+/// In cluster mode all keys must be in the same slot.
+#[derive(Debug)]
+pub struct LMPop {
+    pub keys: Vec<String>,
+    pub direction: Direction,
+    pub count: usize,
+}
+
+impl Message for LMPop {
+    type Result = Result<Option<(String, Vec<Vec<u8>>)>, Error>;
+}
+
+impl Command for LMPop {
+    /// the key of the list popped from and its elements, or `None` if all of
+    /// the lists are empty
+    type Output = Option<(String, Vec<Vec<u8>>)>;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["LMPOP".into(), self.keys.len().to_string().into()];
+        v.extend(self.keys.into_iter().map(Into::into));
+        v.push(
+            match self.direction {
+                Direction::Left => "LEFT",
+                Direction::Right => "RIGHT",
+            }
+            .into(),
+        );
+        ("COUNT", self.count).write_redis_args(&mut v);
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        if let RespValue::Nil = res {
+            return Ok(None);
+        }
+        let popped = match res {
+            RespValue::Array(ref values) if values.len() == 2 => {
+                match (&values[0], &values[1]) {
+                    (RespValue::BulkString(key), RespValue::Array(elements)) => {
+                        let elements = elements
+                            .iter()
+                            .map(|e| match e {
+                                RespValue::BulkString(e) => Some(e.clone()),
+                                _ => None,
+                            })
+                            .collect::<Option<Vec<_>>>();
+                        String::from_utf8(key.clone())
+                            .ok()
+                            .and_then(|key| elements.map(|elements| (key, elements)))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        popped.map(Some).ok_or_else(|| {
+            RespError::RESP("invalid response for LMPOP".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        for key in self.keys.iter() {
+            hasher.hash_str(key)?
+        }
+        Ok(())
+    }
+}
+
+/// The elements of the list at `key` from the index `start` to `stop`, both
+/// included (`LRANGE`). Negative indices count from the tail, e.g. `0` to `-1`
+/// for all.
+#[derive(Debug, Clone)]
+pub struct LRange {
+    pub key: String,
+    pub start: i64,
+    pub stop: i64,
+}
+
+impl Message for LRange {
+    type Result = Result<Vec<Vec<u8>>, Error>;
+}
+
+impl Command for LRange {
+    /// empty if the list does not exist
+    type Output = Vec<Vec<u8>>;
+
+    fn into_request(self) -> RespValue {
+        RespValue::Array(("LRANGE", self.key, self.start, self.stop).to_redis_args())
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "LRANGE")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Find the indices of `element` in the list at `key` (`LPOS`). Requires Redis
+/// 6.0.6 or later.
+///
+/// `rank` skips to the `rank`th match, a negative one searches from the tail.
+/// Without `count` the index of the first match is returned, with it up to
+/// `count` indices, all of them for `0`.
+#[derive(Debug)]
+pub struct LPos {
+    pub key: String,
+    pub element: Vec<u8>,
+    pub rank: Option<i64>,
+    pub count: Option<usize>,
+}
+
+/// Reply of `LPos`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LPosReply {
+    /// the index of the match without `count`, or `None` if there is none
+    Index(Option<i64>),
+    /// the indices of the matches with `count`
+    Indices(Vec<i64>),
+}
+
+impl Message for LPos {
+    type Result = Result<LPosReply, Error>;
+}
+
+impl Command for LPos {
+    type Output = LPosReply;
+
+    fn into_request(self) -> RespValue {
+        let rank = self.rank.map(|rank| ("RANK", rank));
+        let count = self.count.map(|count| ("COUNT", count));
+        RespValue::Array(("LPOS", self.key, self.element, rank, count).to_redis_args())
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        let reply = match res {
+            RespValue::Integer(index) => Some(LPosReply::Index(Some(index))),
+            RespValue::Nil => Some(LPosReply::Index(None)),
+            RespValue::Array(ref indices) => indices
+                .iter()
+                .map(|index| match index {
+                    RespValue::Integer(index) => Some(*index),
+                    _ => None,
+                })
+                .collect::<Option<_>>()
+                .map(LPosReply::Indices),
+            _ => None,
+        };
+        reply.ok_or_else(|| {
+            RespError::RESP("invalid response for LPOS".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Block until the preceding writes of this connection are acknowledged by
+/// `num_replicas` replicas, or `timeout_ms` milliseconds have passed (`WAIT`).
+/// A timeout of `0` blocks forever.
+///
+/// In cluster mode it is sent to the node the actor was started with.
+#[derive(Debug, Clone)]
+pub struct Wait {
+    pub num_replicas: i64,
+    pub timeout_ms: i64,
+}
+
+impl Message for Wait {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for Wait {
+    /// the number of replicas that acknowledged the writes
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        resp_array![
+            "WAIT",
+            self.num_replicas.to_string(),
+            self.timeout_ms.to_string()
+        ]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "WAIT")
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// Block until the preceding writes of this connection are fsynced to the AOF
+/// of the local server and of replicas (`WAITAOF`). Requires Redis 7.2 or later.
+///
+/// Servers without AOF enabled answer with an error if `num_local` is not `0`,
+/// which is returned as `Ok(Err(_))`. In cluster mode it is sent to the node the
+/// actor was started with.
+#[derive(Debug, Clone)]
+pub struct WaitAof {
+    pub num_local: i64,
+    pub num_replicas: i64,
+    pub timeout_ms: i64,
+}
+
+impl Message for WaitAof {
+    type Result = Result<Result<(i64, i64), String>, Error>;
+}
+
+impl Command for WaitAof {
+    /// the number of local servers and replicas that acknowledged the writes
+    type Output = Result<(i64, i64), String>;
+
+    fn into_request(self) -> RespValue {
+        resp_array![
+            "WAITAOF",
+            self.num_local.to_string(),
+            self.num_replicas.to_string(),
+            self.timeout_ms.to_string()
+        ]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::Array(ref acks) if acks.len() == 2 => {
+                match (&acks[0], &acks[1]) {
+                    (RespValue::Integer(local), RespValue::Integer(replicas)) => {
+                        Ok(Ok((*local, *replicas)))
+                    }
+                    _ => Err(RespError::RESP(
+                        "invalid response for WAITAOF".into(),
+                        Some(res.clone()),
+                    )),
+                }
+            }
+            RespValue::Error(e) => Ok(Err(e)),
+            res => Err(RespError::RESP(
+                "invalid response for WAITAOF".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// Return a random key. In cluster mode, a randomly chosen master is asked.
+#[derive(Debug)]
+pub struct RandomKey;
+
+impl Message for RandomKey {
+    type Result = Result<Option<Vec<u8>>, Error>;
+}
+
+impl Command for RandomKey {
+    type Output = Option<Vec<u8>>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["RANDOMKEY"]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "RANDOMKEY")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.set(random_slot())
+    }
+}
+
+/// Iterate over the keys of the server (`SCAN`), starting at `cursor`. The
+/// iteration is complete when the returned cursor is 0.
+///
+/// In cluster mode it is sent to the master of `slot`, see
+/// `scan::ClusterScan` to iterate over all masters.
+#[derive(Debug)]
+pub struct Scan {
+    pub cursor: u64,
+    /// only return the keys matching this glob-style pattern
+    pub pattern: Option<String>,
+    /// hint of the number of keys to return per call
+    pub count: Option<usize>,
+    pub slot: u16,
+}
+
+impl Message for Scan {
+    type Result = Result<(u64, Vec<Vec<u8>>), Error>;
+}
+
+impl Command for Scan {
+    /// the next cursor and the keys
+    type Output = (u64, Vec<Vec<u8>>);
+
+    fn into_request(self) -> RespValue {
+        let mut req = vec!["SCAN".into(), self.cursor.to_string().into()];
+        if let Some(pattern) = self.pattern {
+            req.push("MATCH".into());
+            req.push(pattern.into());
+        }
+        if let Some(count) = self.count {
+            req.push("COUNT".into());
+            req.push(count.to_string().into());
+        }
+        RespValue::Array(req)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        let page = match res {
+            RespValue::Array(ref page) if page.len() == 2 => {
+                let cursor = match page[0] {
+                    RespValue::BulkString(ref cursor) => std::str::from_utf8(cursor)
+                        .ok()
+                        .and_then(|cursor| cursor.parse().ok()),
+                    _ => None,
+                };
+                let keys = match page[1] {
+                    RespValue::Array(ref keys) => keys
+                        .iter()
+                        .map(|key| match key {
+                            RespValue::BulkString(key) => Some(key.clone()),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>(),
+                    _ => None,
+                };
+                cursor.and_then(|cursor| keys.map(|keys| (cursor, keys)))
+            }
+            _ => None,
+        };
+        page.ok_or_else(|| {
+            RespError::RESP("invalid response for SCAN".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.set(self.slot)
+    }
+}
+
+/// Copy the value of a key to another key (`COPY`). Requires Redis 6.2 or later.
+///
+/// In cluster mode both keys must belong to the same slot, and `db` must be
+/// `None`.
+#[derive(Debug)]
+pub struct Copy {
+    pub src: String,
+    pub dst: String,
+    /// overwrite `dst` if it already exists
+    pub replace: bool,
+    /// copy into another database
+    pub db: Option<u32>,
+}
+
+impl Message for Copy {
+    type Result = Result<bool, Error>;
+}
+
+impl Command for Copy {
+    /// true if the key was copied
+    type Output = bool;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec![
+            RespValue::BulkString(b"COPY".to_vec()),
+            self.src.into(),
+            self.dst.into(),
+        ];
+        if let Some(db) = self.db {
+            v.push(RespValue::BulkString(b"DB".to_vec()));
+            v.push(db.to_string().into());
+        }
+        if self.replace {
+            v.push(RespValue::BulkString(b"REPLACE".to_vec()));
+        }
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "COPY")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.src)?;
+        hasher.hash_str(&self.dst)
+    }
+}
+
+/// Move a key to another database (`MOVE`)
+///
+/// Not available in cluster mode, which has a single database: the nodes
+/// refuse it, and the error is returned as `Error::Redis`.
+#[derive(Debug, Clone)]
+pub struct Move {
+    pub key: String,
+    pub db: u32,
+}
+
+impl Message for Move {
+    type Result = Result<bool, Error>;
+}
+
+impl Command for Move {
+    /// true if the key was moved, false if it does not exist or already exists
+    /// in `db`
+    type Output = bool;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["MOVE", self.key, self.db.to_string()]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "MOVE")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The logarithmic access frequency counter of a key (`OBJECT FREQ`)
+///
+/// Requires an LFU `maxmemory-policy` such as `allkeys-lfu`. Otherwise the
+/// server answers with an error, returned as `Error::Redis`.
+#[derive(Debug)]
+pub struct ObjectFreq {
+    pub key: String,
+}
+
+impl Message for ObjectFreq {
+    type Result = Result<Option<i64>, Error>;
+}
+
+impl Command for ObjectFreq {
+    /// `None` if the key does not exist
+    type Output = Option<i64>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["OBJECT", "FREQ", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::Integer(freq) => Ok(Some(freq)),
+            RespValue::Nil => Ok(None),
+            RespValue::Error(ref e) => Err(RespError::RESP(
+                format!("OBJECT FREQ failed: {}", e),
+                Some(res.clone()),
+            )),
+            _ => Err(RespError::RESP(
+                "invalid response for OBJECT FREQ".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The internal encoding of the value of a key (`OBJECT ENCODING`), e.g.
+/// `listpack` or `hashtable` for a hash
+#[derive(Debug)]
+pub struct ObjectEncoding {
+    pub key: String,
+}
+
+impl Message for ObjectEncoding {
+    type Result = Result<Option<String>, Error>;
+}
+
+impl Command for ObjectEncoding {
+    /// `None` if the key does not exist
+    type Output = Option<String>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["OBJECT", "ENCODING", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::BulkString(ref encoding) => {
+                String::from_utf8(encoding.clone()).map(Some).map_err(|_| {
+                    RespError::RESP(
+                        "invalid response for OBJECT ENCODING".into(),
+                        Some(res.clone()),
+                    )
+                })
+            }
+            RespValue::Nil => Ok(None),
+            RespValue::Error(ref e) => Err(RespError::RESP(
+                format!("OBJECT ENCODING failed: {}", e),
+                Some(res.clone()),
+            )),
+            _ => Err(RespError::RESP(
+                "invalid response for OBJECT ENCODING".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The seconds since the value of a key was last accessed (`OBJECT IDLETIME`)
+///
+/// Not available with an LFU `maxmemory-policy` such as `allkeys-lfu`: the
+/// server answers with an error, returned as `Error::Redis`.
+#[derive(Debug)]
+pub struct ObjectIdleTime {
+    pub key: String,
+}
+
+impl Message for ObjectIdleTime {
+    type Result = Result<Option<i64>, Error>;
+}
+
+impl Command for ObjectIdleTime {
+    /// `None` if the key does not exist
+    type Output = Option<i64>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["OBJECT", "IDLETIME", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::Integer(idle) => Ok(Some(idle)),
+            RespValue::Nil => Ok(None),
+            RespValue::Error(ref e) => Err(RespError::RESP(
+                format!("OBJECT IDLETIME failed: {}", e),
+                Some(res.clone()),
+            )),
+            _ => Err(RespError::RESP(
+                "invalid response for OBJECT IDLETIME".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Serialize the value of a key (`DUMP`), to be recreated with `Restore`
+#[derive(Debug)]
+pub struct Dump {
+    pub key: String,
+}
+
+impl Message for Dump {
+    type Result = Result<Option<Vec<u8>>, Error>;
+}
+
+impl Command for Dump {
+    type Output = Option<Vec<u8>>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["DUMP", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "DUMP")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Create a key from a value serialized by `Dump` (`RESTORE`)
+#[derive(Debug)]
+pub struct Restore {
+    pub key: String,
+    /// time to live in milliseconds, `0` for no expiration
+    pub ttl_ms: i64,
+    pub payload: Vec<u8>,
+    /// overwrite the key if it already exists
+    pub replace: bool,
+}
+
+impl Message for Restore {
+    type Result = Result<(), Error>;
+}
+
+impl Command for Restore {
+    type Output = ();
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec![
+            RespValue::BulkString(b"RESTORE".to_vec()),
+            self.key.into(),
+            self.ttl_ms.to_string().into(),
+            RespValue::BulkString(self.payload),
+        ];
+        if self.replace {
+            v.push(RespValue::BulkString(b"REPLACE".to_vec()));
+        }
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(()),
+            _ => Err(RespError::RESP(
+                "invalid response for RESTORE".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Publish a message to a sharded channel (`SPUBLISH`)
+#[derive(Debug)]
+pub struct SPublish {
+    pub channel: String,
+    pub message: Vec<u8>,
+}
+
+impl Message for SPublish {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for SPublish {
+    /// the number of clients that received the message
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        resp_array![
+            "SPUBLISH",
+            self.channel,
+            RespValue::BulkString(self.message)
+        ]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "SPUBLISH")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.channel)
+    }
+}
+
+#[derive(Debug)]
+pub struct ClusterSlots;
+
+#[derive(Clone, Debug)]
+pub struct Slots {
+    pub start: u16,
+    pub end: u16,
+    /// IP address, port, id of nodes serving the slots.
+    /// The first entry corresponds to the master node.
+    pub nodes: Vec<(String, i64, Option<String>)>,
+}
+
+impl Slots {
+    pub fn master(&self) -> String {
+        Address::format(&self.nodes[0].0, self.nodes[0].1)
+    }
+}
+
+impl Message for ClusterSlots {
+    type Result = Result<Vec<Slots>, Error>;
+}
+
+impl Command for ClusterSlots {
+    type Output = Vec<Slots>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["CLUSTER", "SLOTS"]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        use redis_async::resp::FromResp;
+
+        fn parse_entry(res: RespValue) -> Result<Slots, RespError> {
+            match res {
+                RespValue::Array(values) => {
+                    if values.len() >= 3 {
+                        let mut it = values.into_iter();
                         let start = u32::from_resp(it.next().unwrap())? as u16;
                         let end = u32::from_resp(it.next().unwrap())? as u16;
 
-                        let mut nodes = vec![];
-                        for node in it {
-                            match node {
-                                RespValue::Array(node) => {
-                                    if node.len() >= 2 {
-                                        let mut it = node.into_iter();
-                                        let addr =
-                                            String::from_resp(it.next().unwrap())?;
-                                        let port = i64::from_resp(it.next().unwrap())?;
-                                        let id = it
-                                            .next()
-                                            .and_then(|x| String::from_resp(x).ok());
+                        let mut nodes = vec![];
+                        for node in it {
+                            match node {
+                                RespValue::Array(node) => {
+                                    if node.len() >= 2 {
+                                        let mut it = node.into_iter();
+                                        let addr =
+                                            String::from_resp(it.next().unwrap())?;
+                                        let port = i64::from_resp(it.next().unwrap())?;
+                                        let id = it
+                                            .next()
+                                            .and_then(|x| String::from_resp(x).ok());
+
+                                        nodes.push((addr, port, id));
+                                    } else {
+                                        return Err(RespError::RESP(
+                                            "invalid response for CLUSTER SLOTS".into(),
+                                            Some(RespValue::Array(node)),
+                                        ));
+                                    }
+                                }
+                                _ => {
+                                    return Err(RespError::RESP(
+                                        "invalid response for CLUSTER SLOTS".into(),
+                                        Some(node),
+                                    ));
+                                }
+                            }
+                        }
+
+                        Ok(Slots { start, end, nodes })
+                    } else {
+                        Err(RespError::RESP(
+                            "invalid response for CLUSTER SLOTS".into(),
+                            Some(RespValue::Array(values)),
+                        ))
+                    }
+                }
+                _ => Err(RespError::RESP(
+                    "invalid response for CLUSTER SLOTS".into(),
+                    Some(res),
+                )),
+            }
+        }
+
+        match res {
+            RespValue::Array(inner) => inner
+                .into_iter()
+                .map(parse_entry)
+                .collect::<Result<Vec<_>, _>>(),
+            _ => Err(RespError::RESP(
+                "invalid response for CLUSTER SLOTS".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// Server information and statistics (`INFO`), of one `section` or of the
+/// default ones. Send it with `Routed` and `Routing::ToNode` to pick the node
+/// in cluster mode.
+#[derive(Debug)]
+pub struct Info {
+    pub section: Option<String>,
+}
+
+impl Message for Info {
+    type Result = Result<HashMap<String, HashMap<String, String>>, Error>;
+}
+
+impl Command for Info {
+    /// the fields by section, e.g. `Memory` or `Replication`, then by name
+    type Output = HashMap<String, HashMap<String, String>>;
+
+    fn into_request(self) -> RespValue {
+        match self.section {
+            Some(section) => resp_array!["INFO", section],
+            None => resp_array!["INFO"],
+        }
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        fn parse(info: &str) -> HashMap<String, HashMap<String, String>> {
+            let mut sections = HashMap::new();
+            let mut section = String::new();
+            for line in info.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                if line.starts_with('#') {
+                    section = line.trim_start_matches('#').trim().to_owned();
+                    continue;
+                }
+                let mut kv = line.splitn(2, ':');
+                if let (Some(key), Some(value)) = (kv.next(), kv.next()) {
+                    sections
+                        .entry(section.clone())
+                        .or_insert_with(HashMap::new)
+                        .insert(key.to_owned(), value.to_owned());
+                }
+            }
+            sections
+        }
+
+        let sections = match res {
+            RespValue::BulkString(ref info) => std::str::from_utf8(info).ok().map(parse),
+            _ => None,
+        };
+        sections.ok_or_else(|| {
+            RespError::RESP("invalid response for INFO".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ClusterInfo;
+
+/// Cluster state reported by `CLUSTER INFO`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClusterState {
+    /// `ok` or `fail`
+    pub state: String,
+    pub slots_assigned: usize,
+    pub known_nodes: usize,
+    /// the number of masters serving at least one slot
+    pub size: usize,
+}
+
+impl Message for ClusterInfo {
+    type Result = Result<ClusterState, Error>;
+}
+
+impl Command for ClusterInfo {
+    type Output = ClusterState;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["CLUSTER", "INFO"]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        fn parse(info: &str) -> Option<ClusterState> {
+            let fields = info
+                .lines()
+                .filter_map(|line| {
+                    let mut kv = line.trim().splitn(2, ':');
+                    Some((kv.next()?, kv.next()?))
+                })
+                .collect::<HashMap<_, _>>();
+            let number = |key| fields.get(key)?.parse().ok();
+
+            Some(ClusterState {
+                state: fields.get("cluster_state")?.to_string(),
+                slots_assigned: number("cluster_slots_assigned")?,
+                known_nodes: number("cluster_known_nodes")?,
+                size: number("cluster_size")?,
+            })
+        }
+
+        let state = match res {
+            RespValue::BulkString(ref info) => {
+                std::str::from_utf8(info).ok().and_then(parse)
+            }
+            _ => None,
+        };
+        state.ok_or_else(|| {
+            RespError::RESP("invalid response for CLUSTER INFO".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ClusterNodes;
+
+/// A node reported by `CLUSTER NODES`
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeInfo {
+    pub id: String,
+    /// `ip:port` of the node, without the cluster bus port and hostname
+    pub addr: String,
+    /// e.g. `myself`, `master`, `slave`, `fail?`, `fail`, `handshake`, `noaddr`
+    pub flags: Vec<String>,
+    /// id of the master if this node is a replica
+    pub master: Option<String>,
+    /// `connected` or `disconnected`
+    pub link_state: String,
+    /// inclusive ranges of the slots served by this node
+    pub slots: Vec<(u16, u16)>,
+    /// slots being migrated to the node with the given id
+    pub migrating: Vec<(u16, String)>,
+    /// slots being imported from the node with the given id
+    pub importing: Vec<(u16, String)>,
+}
+
+impl NodeInfo {
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    fn parse(line: &str) -> Option<NodeInfo> {
+        let mut fields = line.split_whitespace();
+        let id = fields.next()?.to_string();
+        let addr = fields.next()?;
+        let addr = addr.split(&['@', ','][..]).next()?.to_string();
+        let flags = fields.next()?.split(',').map(ToString::to_string).collect();
+        let master = match fields.next()? {
+            "-" => None,
+            master => Some(master.to_string()),
+        };
+        // ping-sent, pong-recv, config-epoch
+        let mut fields = fields.skip(3);
+        let link_state = fields.next()?.to_string();
+
+        let mut node = NodeInfo {
+            id,
+            addr,
+            flags,
+            master,
+            link_state,
+            slots: vec![],
+            migrating: vec![],
+            importing: vec![],
+        };
+        for slot in fields {
+            if slot.starts_with('[') && slot.ends_with(']') {
+                let slot = &slot[1..slot.len() - 1];
+                if let Some(i) = slot.find("->-") {
+                    node.migrating
+                        .push((slot[..i].parse().ok()?, slot[i + 3..].to_string()));
+                } else if let Some(i) = slot.find("-<-") {
+                    node.importing
+                        .push((slot[..i].parse().ok()?, slot[i + 3..].to_string()));
+                } else {
+                    return None;
+                }
+            } else {
+                let mut range = slot.splitn(2, '-');
+                let start = range.next()?.parse().ok()?;
+                let end = match range.next() {
+                    Some(end) => end.parse().ok()?,
+                    None => start,
+                };
+                node.slots.push((start, end));
+            }
+        }
+
+        Some(node)
+    }
+}
+
+impl Message for ClusterNodes {
+    type Result = Result<Vec<NodeInfo>, Error>;
+}
+
+impl Command for ClusterNodes {
+    type Output = Vec<NodeInfo>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["CLUSTER", "NODES"]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        let nodes = match res {
+            RespValue::BulkString(ref nodes) => {
+                std::str::from_utf8(nodes).ok().and_then(|nodes| {
+                    nodes
+                        .lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .map(NodeInfo::parse)
+                        .collect::<Option<Vec<_>>>()
+                })
+            }
+            _ => None,
+        };
+        nodes.ok_or_else(|| {
+            RespError::RESP("invalid response for CLUSTER NODES".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// How `ClusterFailover` proceeds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailoverMode {
+    /// Without agreement of the master, e.g. when it is unreachable (`FORCE`)
+    Force,
+    /// Without agreement of the master nor of the other masters (`TAKEOVER`)
+    Takeover,
+}
+
+/// Start a manual failover of the master of the node to the node, a replica
+/// (`CLUSTER FAILOVER`). The failover completes after the reply.
+///
+/// In cluster mode, send it to the replica with `Routed` and
+/// `Routing::ToNode`, or with `cluster::Failover` to also route the following
+/// commands to the replica once it is promoted.
+#[derive(Debug, Clone)]
+pub struct ClusterFailover {
+    pub mode: Option<FailoverMode>,
+}
+
+impl Message for ClusterFailover {
+    type Result = Result<(), Error>;
+}
+
+impl Command for ClusterFailover {
+    type Output = ();
+
+    fn into_request(self) -> RespValue {
+        match self.mode {
+            None => resp_array!["CLUSTER", "FAILOVER"],
+            Some(FailoverMode::Force) => resp_array!["CLUSTER", "FAILOVER", "FORCE"],
+            Some(FailoverMode::Takeover) => {
+                resp_array!["CLUSTER", "FAILOVER", "TAKEOVER"]
+            }
+        }
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(()),
+            res => Err(RespError::RESP(
+                "invalid response for CLUSTER FAILOVER".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// Read the slow log of the server (`SLOWLOG GET`). Without `count`, the
+/// server returns its 10 latest entries.
+///
+/// In cluster mode it is sent to the master of `slot`.
+#[derive(Debug)]
+pub struct SlowlogGet {
+    pub count: Option<usize>,
+    pub slot: u16,
+}
+
+/// Entry of the slow log
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlowlogEntry {
+    pub id: i64,
+    /// Unix time in seconds at which the command was run
+    pub timestamp: i64,
+    pub duration_us: i64,
+    pub args: Vec<Vec<u8>>,
+    /// empty for servers before 4.0
+    pub client_addr: String,
+    /// empty for servers before 4.0 and unnamed clients
+    pub client_name: String,
+}
+
+impl SlowlogEntry {
+    fn parse(entry: &RespValue) -> Option<SlowlogEntry> {
+        let fields = match entry {
+            RespValue::Array(fields) if fields.len() >= 4 => fields,
+            _ => return None,
+        };
+        let integer = |i: usize| match fields[i] {
+            RespValue::Integer(n) => Some(n),
+            _ => None,
+        };
+        let string = |i: usize| match fields.get(i) {
+            Some(RespValue::BulkString(s)) => String::from_utf8(s.clone()).ok(),
+            None => Some(String::new()),
+            _ => None,
+        };
+        let args = match fields[3] {
+            RespValue::Array(ref args) => args
+                .iter()
+                .map(|arg| match arg {
+                    RespValue::BulkString(arg) => Some(arg.clone()),
+                    _ => None,
+                })
+                .collect::<Option<_>>()?,
+            _ => return None,
+        };
+
+        Some(SlowlogEntry {
+            id: integer(0)?,
+            timestamp: integer(1)?,
+            duration_us: integer(2)?,
+            args,
+            client_addr: string(4)?,
+            client_name: string(5)?,
+        })
+    }
+}
+
+impl Message for SlowlogGet {
+    type Result = Result<Vec<SlowlogEntry>, Error>;
+}
+
+impl Command for SlowlogGet {
+    /// the latest entries first
+    type Output = Vec<SlowlogEntry>;
+
+    fn into_request(self) -> RespValue {
+        match self.count {
+            Some(count) => resp_array!["SLOWLOG", "GET", count.to_string()],
+            None => resp_array!["SLOWLOG", "GET"],
+        }
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        let entries = match res {
+            RespValue::Array(ref entries) => entries
+                .iter()
+                .map(SlowlogEntry::parse)
+                .collect::<Option<Vec<_>>>(),
+            _ => None,
+        };
+        entries.ok_or_else(|| {
+            RespError::RESP("invalid response for SLOWLOG GET".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.set(self.slot)
+    }
+}
+
+/// Clear the slow log of the server (`SLOWLOG RESET`)
+///
+/// In cluster mode it is sent to the master of `slot`.
+#[derive(Debug)]
+pub struct SlowlogReset {
+    pub slot: u16,
+}
+
+impl Message for SlowlogReset {
+    type Result = Result<(), Error>;
+}
+
+impl Command for SlowlogReset {
+    type Output = ();
+
+    fn into_request(self) -> RespValue {
+        resp_array!["SLOWLOG", "RESET"]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        ok_response(res, "SLOWLOG RESET")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.set(self.slot)
+    }
+}
+
+#[derive(Debug)]
+pub struct Asking;
+
+impl Message for Asking {
+    type Result = Result<(), Error>;
+}
+
+impl Command for Asking {
+    type Output = ();
+
+    fn into_request(self) -> RespValue {
+        resp_array!["ASKING"]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(()),
+            res => Err(RespError::RESP(
+                "invalid response for ASKING".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum TtlError {
+    KeyNotExist,
+    NoExpire,
+    Unknown(i64),
+}
+
+impl std::fmt::Display for TtlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use self::TtlError::*;
+        match self {
+            KeyNotExist => write!(f, "key does not exist"),
+            NoExpire => write!(f, "key has no associated expire"),
+            Unknown(x) => write!(f, "unknown error: {}", x),
+        }
+    }
+}
+
+impl std::error::Error for TtlError {}
+
+#[derive(Debug)]
+pub struct Ttl {
+    pub key: String,
+}
+
+impl Message for Ttl {
+    type Result = Result<Result<i64, TtlError>, Error>;
+}
+
+impl Command for Ttl {
+    type Output = Result<i64, TtlError>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["TTL", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::Integer(-2) => Ok(Err(TtlError::NoExpire)),
+            RespValue::Integer(-1) => Ok(Err(TtlError::NoExpire)),
+            RespValue::Integer(x) if x < 0 => Ok(Err(TtlError::Unknown(x))),
+            RespValue::Integer(x) => Ok(Ok(x)),
+            res => Err(RespError::RESP(
+                "invalid response for TTL".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+#[derive(Debug)]
+pub struct Pttl {
+    pub key: String,
+}
+
+impl Message for Pttl {
+    type Result = Result<Result<i64, TtlError>, Error>;
+}
+
+impl Command for Pttl {
+    type Output = Result<i64, TtlError>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["PTTL", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::Integer(-2) => Ok(Err(TtlError::NoExpire)),
+            RespValue::Integer(-1) => Ok(Err(TtlError::NoExpire)),
+            RespValue::Integer(x) if x < 0 => Ok(Err(TtlError::Unknown(x))),
+            RespValue::Integer(x) => Ok(Ok(x)),
+            res => Err(RespError::RESP(
+                "invalid response for TTL".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The Unix time in seconds at which `key` expires (`EXPIRETIME`). Requires
+/// Redis 7.0 or later.
+///
+/// -1 if the key has no expiration, -2 if it does not exist.
+#[derive(Debug)]
+pub struct ExpireTime {
+    pub key: String,
+}
+
+impl Message for ExpireTime {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for ExpireTime {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["EXPIRETIME", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "EXPIRETIME")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The Unix time in milliseconds at which `key` expires (`PEXPIRETIME`).
+/// Requires Redis 7.0 or later.
+///
+/// -1 if the key has no expiration, -2 if it does not exist.
+#[derive(Debug)]
+pub struct PExpireTime {
+    pub key: String,
+}
+
+impl Message for PExpireTime {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for PExpireTime {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["PEXPIRETIME", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "PEXPIRETIME")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+#[derive(Debug)]
+pub struct Incr {
+    pub key: String,
+}
+
+impl Message for Incr {
+    type Result = Result<Result<i64, String>, Error>;
+}
+
+impl Command for Incr {
+    type Output = Result<i64, String>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["INCR", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::Integer(x) => Ok(Ok(x)),
+            RespValue::Error(e) => Ok(Err(e)),
+            res => Err(RespError::RESP(
+                "invalid response for INCR".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+#[derive(Debug)]
+pub struct IncrBy {
+    pub key: String,
+    pub increment: i64,
+}
+
+impl Message for IncrBy {
+    type Result = Result<Result<i64, String>, Error>;
+}
+
+impl Command for IncrBy {
+    type Output = Result<i64, String>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["INCRBY", self.key, self.increment.to_string()]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::Integer(x) => Ok(Ok(x)),
+            RespValue::Error(e) => Ok(Err(e)),
+            res => Err(RespError::RESP(
+                "invalid response for INCRBY".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+#[derive(Debug)]
+pub struct Decr {
+    pub key: String,
+}
+
+impl Message for Decr {
+    type Result = Result<Result<i64, String>, Error>;
+}
+
+impl Command for Decr {
+    type Output = Result<i64, String>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["DECR", self.key]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::Integer(x) => Ok(Ok(x)),
+            RespValue::Error(e) => Ok(Err(e)),
+            res => Err(RespError::RESP(
+                "invalid response for DECR".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+#[derive(Debug)]
+pub struct DecrBy {
+    pub key: String,
+    pub decrement: i64,
+}
+
+impl Message for DecrBy {
+    type Result = Result<Result<i64, String>, Error>;
+}
+
+impl Command for DecrBy {
+    type Output = Result<i64, String>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["DECRBY", self.key, self.decrement.to_string()]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::Integer(x) => Ok(Ok(x)),
+            RespValue::Error(e) => Ok(Err(e)),
+            res => Err(RespError::RESP(
+                "invalid response for DECRBY".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// The integer type of a `BitField` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitFieldType {
+    /// A signed integer of 1 to 64 bits, e.g. `i16`
+    Signed(u8),
+    /// An unsigned integer of 1 to 63 bits, e.g. `u8`
+    Unsigned(u8),
+}
+
+impl BitFieldType {
+    fn to_arg(self) -> String {
+        match self {
+            BitFieldType::Signed(bits) => format!("i{}", bits),
+            BitFieldType::Unsigned(bits) => format!("u{}", bits),
+        }
+    }
+}
+
+/// The offset of a `BitField` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitFieldOffset {
+    /// In bits
+    Bits(u64),
+    /// In multiples of the width of the type, e.g. `#3` for the fourth `u8`
+    Fields(u64),
+}
+
+impl BitFieldOffset {
+    fn to_arg(self) -> String {
+        match self {
+            BitFieldOffset::Bits(bits) => bits.to_string(),
+            BitFieldOffset::Fields(n) => format!("#{}", n),
+        }
+    }
+}
+
+/// How the `BitField` operations after it handle overflows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitFieldOverflow {
+    /// Wrap around, the default (`WRAP`)
+    Wrap,
+    /// Saturate at the minimum or maximum value of the type (`SAT`)
+    Sat,
+    /// Do nothing, the operation replies `None` (`FAIL`)
+    Fail,
+}
+
+/// An operation of `BitField`
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitFieldOp {
+    Get {
+        ty: BitFieldType,
+        offset: BitFieldOffset,
+    },
+    /// Set the field, replying its previous value
+    Set {
+        ty: BitFieldType,
+        offset: BitFieldOffset,
+        value: i64,
+    },
+    /// Increment the field, replying its new value
+    IncrBy {
+        ty: BitFieldType,
+        offset: BitFieldOffset,
+        increment: i64,
+    },
+    /// Set the overflow handling of the operations after it, replying nothing
+    Overflow(BitFieldOverflow),
+}
 
-                                        nodes.push((addr, port, id));
-                                    } else {
-                                        return Err(RespError::RESP(
-                                            "invalid response for CLUSTER SLOTS".into(),
-                                            Some(RespValue::Array(node)),
-                                        ));
-                                    }
-                                }
-                                _ => {
-                                    return Err(RespError::RESP(
-                                        "invalid response for CLUSTER SLOTS".into(),
-                                        Some(node),
-                                    ));
-                                }
-                            }
-                        }
+/// Operate on integer fields packed into the string at `key` (`BITFIELD`)
+///
+/// Replies one value per operation other than `Overflow`, `None` for those
+/// refused with `BitFieldOverflow::Fail`.
+#[derive(Debug, Clone)]
+pub struct BitField {
+    pub key: String,
+    pub ops: Vec<BitFieldOp>,
+}
 
-                        Ok(Slots { start, end, nodes })
-                    } else {
-                        Err(RespError::RESP(
-                            "invalid response for CLUSTER SLOTS".into(),
-                            Some(RespValue::Array(values)),
-                        ))
-                    }
+impl Message for BitField {
+    type Result = Result<Vec<Option<i64>>, Error>;
+}
+
+impl Command for BitField {
+    type Output = Vec<Option<i64>>;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["BITFIELD".into(), self.key.into()];
+        for op in self.ops {
+            match op {
+                BitFieldOp::Get { ty, offset } => {
+                    v.push("GET".into());
+                    v.push(ty.to_arg().into());
+                    v.push(offset.to_arg().into());
                 }
-                _ => Err(RespError::RESP(
-                    "invalid response for CLUSTER SLOTS".into(),
-                    Some(res),
-                )),
+                BitFieldOp::Set { ty, offset, value } => {
+                    v.push("SET".into());
+                    v.push(ty.to_arg().into());
+                    v.push(offset.to_arg().into());
+                    v.push(value.to_string().into());
+                }
+                BitFieldOp::IncrBy {
+                    ty,
+                    offset,
+                    increment,
+                } => {
+                    v.push("INCRBY".into());
+                    v.push(ty.to_arg().into());
+                    v.push(offset.to_arg().into());
+                    v.push(increment.to_string().into());
+                }
+                BitFieldOp::Overflow(overflow) => {
+                    v.push("OVERFLOW".into());
+                    v.push(
+                        match overflow {
+                            BitFieldOverflow::Wrap => "WRAP",
+                            BitFieldOverflow::Sat => "SAT",
+                            BitFieldOverflow::Fail => "FAIL",
+                        }
+                        .into(),
+                    );
+                }
+            }
+        }
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "BITFIELD")
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+#[derive(Debug)]
+pub struct Ping(pub Option<String>);
+
+impl Message for Ping {
+    type Result = Result<String, Error>;
+}
+
+impl Command for Ping {
+    type Output = String;
+
+    fn into_request(self) -> RespValue {
+        match self.0 {
+            Some(s) => resp_array!["PING", s],
+            None => resp_array!["PING"],
+        }
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "PING")
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// The number of keys of the server (`DBSIZE`). Send it with `Routed` and
+/// `Routing::ToAllMasters` to get the number of keys of each master in cluster
+/// mode.
+#[derive(Debug, Clone)]
+pub struct DbSize;
+
+impl Message for DbSize {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for DbSize {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["DBSIZE"]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "DBSIZE")
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// The current time of the server (`TIME`)
+///
+/// In cluster mode it is sent to the node the actor was started with.
+#[derive(Debug, Clone)]
+pub struct Time;
+
+impl Message for Time {
+    type Result = Result<SystemTime, Error>;
+}
+
+impl Command for Time {
+    type Output = SystemTime;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["TIME"]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        fn number(value: &RespValue) -> Option<u64> {
+            match value {
+                RespValue::BulkString(n) => std::str::from_utf8(n).ok()?.parse().ok(),
+                _ => None,
+            }
+        }
+
+        // seconds and microseconds
+        let time = match res {
+            RespValue::Array(ref values) if values.len() == 2 => number(&values[0])
+                .and_then(|secs| {
+                    let micros = number(&values[1])?;
+                    Some(
+                        UNIX_EPOCH
+                            + Duration::from_secs(secs)
+                            + Duration::from_micros(micros),
+                    )
+                }),
+            _ => None,
+        };
+        time.ok_or_else(|| {
+            RespError::RESP("invalid response for TIME".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Echo(String);
+
+impl Message for Echo {
+    type Result = Result<String, Error>;
+}
+
+impl Command for Echo {
+    type Output = String;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["ECHO", self.0]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "ECHO")
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// `CLIENT NO-EVICT ON|OFF`. Servers before 7.0 answer with an error, which is
+/// returned as `Ok(Err(_))`.
+#[derive(Debug)]
+pub struct ClientNoEvict(pub bool);
+
+impl Message for ClientNoEvict {
+    type Result = Result<Result<(), String>, Error>;
+}
+
+impl Command for ClientNoEvict {
+    type Output = Result<(), String>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["CLIENT", "NO-EVICT", if self.0 { "ON" } else { "OFF" }]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(Ok(())),
+            RespValue::Error(e) => Ok(Err(e)),
+            res => Err(RespError::RESP(
+                "invalid response for CLIENT NO-EVICT".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// Let a cluster replica serve reads of the slots of its master on this
+/// connection (`READONLY`)
+#[derive(Debug)]
+pub struct ReadOnly;
+
+impl Message for ReadOnly {
+    type Result = Result<(), Error>;
+}
+
+impl Command for ReadOnly {
+    type Output = ();
+
+    fn into_request(self) -> RespValue {
+        resp_array!["READONLY"]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        match res {
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(()),
+            res => Err(RespError::RESP(
+                "invalid response for READONLY".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// The kind of a client connection, to filter `CLIENT LIST` and `CLIENT KILL`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientType {
+    Normal,
+    Master,
+    Replica,
+    PubSub,
+}
+
+impl ClientType {
+    fn as_str(self) -> &'static str {
+        match self {
+            ClientType::Normal => "normal",
+            ClientType::Master => "master",
+            ClientType::Replica => "replica",
+            ClientType::PubSub => "pubsub",
+        }
+    }
+}
+
+/// The client connections of a server (`CLIENT LIST`), optionally of one
+/// type. Send it with `Routed` and `Routing::ToNode` to pick the node in
+/// cluster mode.
+#[derive(Debug)]
+pub struct ClientList {
+    pub type_filter: Option<ClientType>,
+}
+
+/// A connection reported by `CLIENT LIST`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientInfo {
+    pub id: u64,
+    /// `ip:port` of the client
+    pub addr: String,
+    /// set with `CLIENT SETNAME`, empty by default
+    pub name: String,
+    /// seconds since the connection was made
+    pub age: u64,
+    /// seconds since the last command
+    pub idle: u64,
+    /// e.g. `N` for a normal client, `S` for a replica, `P` for a subscriber
+    pub flags: String,
+    pub db: u64,
+    /// the last command run
+    pub cmd: String,
+    /// all the fields of the line, including those above
+    pub fields: HashMap<String, String>,
+}
+
+impl Message for ClientList {
+    type Result = Result<Vec<ClientInfo>, Error>;
+}
+
+impl Command for ClientList {
+    type Output = Vec<ClientInfo>;
+
+    fn into_request(self) -> RespValue {
+        match self.type_filter {
+            Some(t) => resp_array!["CLIENT", "LIST", "TYPE", t.as_str()],
+            None => resp_array!["CLIENT", "LIST"],
+        }
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        fn parse(line: &str) -> Option<ClientInfo> {
+            let fields = line
+                .split_whitespace()
+                .filter_map(|field| {
+                    let mut kv = field.splitn(2, '=');
+                    Some((kv.next()?.to_owned(), kv.next()?.to_owned()))
+                })
+                .collect::<HashMap<_, _>>();
+            let text = |key| fields.get(key).cloned();
+            let number = |key| fields.get(key)?.parse().ok();
+
+            Some(ClientInfo {
+                id: number("id")?,
+                addr: text("addr")?,
+                name: text("name")?,
+                age: number("age")?,
+                idle: number("idle")?,
+                flags: text("flags")?,
+                db: number("db")?,
+                cmd: text("cmd")?,
+                fields: fields.clone(),
+            })
+        }
+
+        let clients = match res {
+            RespValue::BulkString(ref list) => {
+                std::str::from_utf8(list).ok().and_then(|list| {
+                    list.lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .map(parse)
+                        .collect::<Option<Vec<_>>>()
+                })
+            }
+            _ => None,
+        };
+        clients.ok_or_else(|| {
+            RespError::RESP("invalid response for CLIENT LIST".into(), Some(res))
+        })
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// Which connections `CLIENT KILL` closes
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientKillFilter {
+    /// the connection with the id of `ClientInfo::id`
+    Id(u64),
+    /// the connection from the `ip:port` of `ClientInfo::addr`
+    Addr(String),
+    /// all the connections of the type
+    Type(ClientType),
+}
+
+/// Close client connections of a server (`CLIENT KILL`), returning how many
+/// were closed. Send it with `Routed` and `Routing::ToNode` to pick the node
+/// in cluster mode.
+#[derive(Debug)]
+pub struct ClientKill {
+    pub filter: ClientKillFilter,
+}
+
+impl Message for ClientKill {
+    type Result = Result<i64, Error>;
+}
+
+impl Command for ClientKill {
+    type Output = i64;
+
+    fn into_request(self) -> RespValue {
+        match self.filter {
+            ClientKillFilter::Id(id) => {
+                resp_array!["CLIENT", "KILL", "ID", id.to_string()]
+            }
+            ClientKillFilter::Addr(addr) => resp_array!["CLIENT", "KILL", "ADDR", addr],
+            ClientKillFilter::Type(t) => {
+                resp_array!["CLIENT", "KILL", "TYPE", t.as_str()]
             }
         }
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "CLIENT KILL")
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// Read the configuration parameters matching the glob-style `parameter`
+/// (`CONFIG GET`). Send it with `Routed` and `Routing::ToNode` to pick the
+/// node in cluster mode.
+#[derive(Debug)]
+pub struct ConfigGet {
+    pub parameter: String,
+}
+
+impl Message for ConfigGet {
+    type Result = Result<HashMap<String, String>, Error>;
+}
+
+impl Command for ConfigGet {
+    /// the matching parameters and their values, empty if none matches
+    type Output = HashMap<String, String>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["CONFIG", "GET", self.parameter]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        decode(res, "CONFIG GET")
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+/// Change a configuration parameter at runtime (`CONFIG SET`). Send it with
+/// `Routed` and `Routing::ToNode` to pick the node, or `Routing::ToAllMasters`
+/// to change every master, in cluster mode.
+///
+/// A parameter the server refuses, e.g. an unknown or protected one, fails
+/// with the error of the server, returned as `Ok(Err(_))`.
+#[derive(Debug)]
+pub struct ConfigSet {
+    pub parameter: String,
+    pub value: String,
+}
+
+impl Message for ConfigSet {
+    type Result = Result<Result<(), String>, Error>;
+}
+
+impl Command for ConfigSet {
+    type Output = Result<(), String>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["CONFIG", "SET", self.parameter, self.value]
+    }
 
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
         match res {
-            RespValue::Array(inner) => inner
-                .into_iter()
-                .map(parse_entry)
-                .collect::<Result<Vec<_>, _>>(),
-            _ => Err(RespError::RESP(
-                "invalid response for CLUSTER SLOTS".into(),
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(Ok(())),
+            RespValue::Error(e) => Ok(Err(e)),
+            res => Err(RespError::RESP(
+                "invalid response for CONFIG SET".into(),
                 Some(res),
             )),
         }
@@ -284,25 +4402,33 @@ impl Command for ClusterSlots {
     }
 }
 
+/// Block the server for `seconds` (`DEBUG SLEEP`), e.g. to test how an
+/// application copes with a slow node. Send it with `Routed` and
+/// `Routing::ToNode` to pick the node in cluster mode.
+///
+/// Fails with the error of the server if debug commands are disabled.
 #[derive(Debug)]
-pub struct Asking;
+pub struct DebugSleep {
+    pub seconds: f64,
+}
 
-impl Message for Asking {
-    type Result = Result<(), Error>;
+impl Message for DebugSleep {
+    type Result = Result<Result<(), String>, Error>;
 }
 
-impl Command for Asking {
-    type Output = ();
+impl Command for DebugSleep {
+    type Output = Result<(), String>;
 
     fn into_request(self) -> RespValue {
-        resp_array!["ASKING"]
+        resp_array!["DEBUG", "SLEEP", self.seconds.to_string()]
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
         match res {
-            RespValue::SimpleString(ref s) if s == "OK" => Ok(()),
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(Ok(())),
+            RespValue::Error(e) => Ok(Err(e)),
             res => Err(RespError::RESP(
-                "invalid response for ASKING".into(),
+                "invalid response for DEBUG SLEEP".into(),
                 Some(res),
             )),
         }
@@ -313,50 +4439,85 @@ impl Command for Asking {
     }
 }
 
+/// The internals of the value of a key (`DEBUG OBJECT`), e.g. to check its
+/// encoding before restructuring the data.
+///
+/// Fails with the error of the server if debug commands are disabled.
 #[derive(Debug)]
-pub enum TtlError {
-    KeyNotExist,
-    NoExpire,
-    Unknown(i64),
+pub struct DebugObject {
+    pub key: String,
 }
 
-impl std::fmt::Display for TtlError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        use self::TtlError::*;
-        match self {
-            KeyNotExist => write!(f, "key does not exist"),
-            NoExpire => write!(f, "key has no associated expire"),
-            Unknown(x) => write!(f, "unknown error: {}", x),
-        }
-    }
+/// The value reported by `DEBUG OBJECT`
+///
+/// The fields vary between versions of the server, so they are all kept as
+/// text, with accessors for the common ones.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectInfo {
+    pub fields: HashMap<String, String>,
 }
 
-impl std::error::Error for TtlError {}
+impl ObjectInfo {
+    fn number(&self, field: &str) -> Option<i64> {
+        self.fields.get(field)?.parse().ok()
+    }
 
-#[derive(Debug)]
-pub struct Ttl {
-    pub key: String,
+    pub fn refcount(&self) -> Option<i64> {
+        self.number("refcount")
+    }
+
+    /// e.g. `listpack` (`ziplist` before Redis 7), `hashtable` or `quicklist`
+    pub fn encoding(&self) -> Option<&str> {
+        self.fields.get("encoding").map(String::as_str)
+    }
+
+    /// The length of the value serialized by `DUMP`, in bytes
+    pub fn serialized_length(&self) -> Option<i64> {
+        self.number("serializedlength")
+    }
+
+    /// The LRU clock of the last access
+    pub fn lru(&self) -> Option<i64> {
+        self.number("lru")
+    }
+
+    /// Seconds since the last access
+    pub fn lru_seconds_idle(&self) -> Option<i64> {
+        self.number("lru_seconds_idle")
+    }
 }
 
-impl Message for Ttl {
-    type Result = Result<Result<i64, TtlError>, Error>;
+impl Message for DebugObject {
+    type Result = Result<Option<ObjectInfo>, Error>;
 }
 
-impl Command for Ttl {
-    type Output = Result<i64, TtlError>;
+impl Command for DebugObject {
+    /// `None` if the key does not exist
+    type Output = Option<ObjectInfo>;
 
     fn into_request(self) -> RespValue {
-        resp_array!["TTL", self.key]
+        resp_array!["DEBUG", "OBJECT", self.key]
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
         match res {
-            RespValue::Integer(-2) => Ok(Err(TtlError::NoExpire)),
-            RespValue::Integer(-1) => Ok(Err(TtlError::NoExpire)),
-            RespValue::Integer(x) if x < 0 => Ok(Err(TtlError::Unknown(x))),
-            RespValue::Integer(x) => Ok(Ok(x)),
-            res => Err(RespError::RESP(
-                "invalid response for TTL".into(),
+            // e.g. `Value at:0x7f0f4c0a2f00 refcount:1 encoding:listpack ...`
+            RespValue::SimpleString(ref info) => Ok(Some(ObjectInfo {
+                fields: info
+                    .split_whitespace()
+                    .filter_map(|field| {
+                        let mut kv = field.splitn(2, ':');
+                        Some((kv.next()?.to_owned(), kv.next()?.to_owned()))
+                    })
+                    .collect(),
+            })),
+            RespValue::Error(ref e) if e.ends_with("no such key") => Ok(None),
+            RespValue::Error(ref e) => Err(RespError::RESP(
+                format!("DEBUG OBJECT failed: {}", e),
+                Some(res.clone()),
+            )),
+            _ => Err(RespError::RESP(
+                "invalid response for DEBUG OBJECT".into(),
                 Some(res),
             )),
         }
@@ -367,192 +4528,190 @@ impl Command for Ttl {
     }
 }
 
-#[derive(Debug)]
-pub struct Pttl {
-    pub key: String,
+/// The clients suspended by `ClientPause`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PauseMode {
+    /// every command
+    All,
+    /// the commands which may write, while reads go on
+    Write,
 }
 
-impl Message for Pttl {
-    type Result = Result<Result<i64, TtlError>, Error>;
+/// Suspend the commands of the clients for `timeout_ms` milliseconds
+/// (`CLIENT PAUSE`), e.g. for a maintenance window. The pause is global to the
+/// server: it affects every client, not only this connection. Send it with
+/// `Routed` and `Routing::ToAllMasters` to pause the whole cluster, or
+/// `Routing::ToNode` to pick the node.
+#[derive(Debug, Clone)]
+pub struct ClientPause {
+    pub timeout_ms: u64,
+    pub mode: PauseMode,
 }
 
-impl Command for Pttl {
-    type Output = Result<i64, TtlError>;
+impl Message for ClientPause {
+    type Result = Result<(), Error>;
+}
+
+impl Command for ClientPause {
+    type Output = ();
 
     fn into_request(self) -> RespValue {
-        resp_array!["TTL", self.key]
+        let mode = match self.mode {
+            PauseMode::All => "ALL",
+            PauseMode::Write => "WRITE",
+        };
+        resp_array!["CLIENT", "PAUSE", self.timeout_ms.to_string(), mode]
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
         match res {
-            RespValue::Integer(-2) => Ok(Err(TtlError::NoExpire)),
-            RespValue::Integer(-1) => Ok(Err(TtlError::NoExpire)),
-            RespValue::Integer(x) if x < 0 => Ok(Err(TtlError::Unknown(x))),
-            RespValue::Integer(x) => Ok(Ok(x)),
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(()),
             res => Err(RespError::RESP(
-                "invalid response for TTL".into(),
+                "invalid response for CLIENT PAUSE".into(),
                 Some(res),
             )),
         }
     }
 
-    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
-        hasher.hash_str(&self.key)
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
     }
 }
 
-#[derive(Debug)]
-pub struct Incr {
-    pub key: String,
-}
+/// Resume the clients suspended by `ClientPause` before its timeout
+/// (`CLIENT UNPAUSE`). Route it as the `ClientPause`.
+#[derive(Debug, Clone)]
+pub struct ClientUnpause;
 
-impl Message for Incr {
-    type Result = Result<Result<i64, String>, Error>;
+impl Message for ClientUnpause {
+    type Result = Result<(), Error>;
 }
 
-impl Command for Incr {
-    type Output = Result<i64, String>;
+impl Command for ClientUnpause {
+    type Output = ();
 
     fn into_request(self) -> RespValue {
-        resp_array!["INCR", self.key]
+        resp_array!["CLIENT", "UNPAUSE"]
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
         match res {
-            RespValue::Integer(x) => Ok(Ok(x)),
-            RespValue::Error(e) => Ok(Err(e)),
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(()),
             res => Err(RespError::RESP(
-                "invalid response for INCR".into(),
+                "invalid response for CLIENT UNPAUSE".into(),
                 Some(res),
             )),
         }
     }
 
-    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
-        hasher.hash_str(&self.key)
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
     }
 }
 
+/// Watch keys for a `MULTI` transaction. Send it on a `Lease`, as the watch
+/// belongs to the connection.
 #[derive(Debug)]
-pub struct IncrBy {
-    pub key: String,
-    pub increment: i64,
+pub struct Watch {
+    pub keys: Vec<String>,
 }
 
-impl Message for IncrBy {
-    type Result = Result<Result<i64, String>, Error>;
+impl Message for Watch {
+    type Result = Result<(), Error>;
 }
 
-impl Command for IncrBy {
-    type Output = Result<i64, String>;
+impl Command for Watch {
+    type Output = ();
 
     fn into_request(self) -> RespValue {
-        resp_array!["INCRBY", self.key, RespValue::Integer(self.increment)]
+        let mut v = vec![RespValue::BulkString(b"WATCH".to_vec())];
+        v.extend(self.keys.into_iter().map(Into::into));
+        RespValue::Array(v)
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
-        match res {
-            RespValue::Integer(x) => Ok(Ok(x)),
-            RespValue::Error(e) => Ok(Err(e)),
-            res => Err(RespError::RESP(
-                "invalid response for INCRBY".into(),
-                Some(res),
-            )),
-        }
+        ok_response(res, "WATCH")
     }
 
     fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
-        hasher.hash_str(&self.key)
+        for key in self.keys.iter() {
+            hasher.hash_str(key)?
+        }
+        Ok(())
     }
 }
 
 #[derive(Debug)]
-pub struct Decr {
-    pub key: String,
-}
+pub struct Unwatch;
 
-impl Message for Decr {
-    type Result = Result<Result<i64, String>, Error>;
+impl Message for Unwatch {
+    type Result = Result<(), Error>;
 }
 
-impl Command for Decr {
-    type Output = Result<i64, String>;
+impl Command for Unwatch {
+    type Output = ();
 
     fn into_request(self) -> RespValue {
-        resp_array!["DECR", self.key]
+        resp_array!["UNWATCH"]
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
-        match res {
-            RespValue::Integer(x) => Ok(Ok(x)),
-            RespValue::Error(e) => Ok(Err(e)),
-            res => Err(RespError::RESP(
-                "invalid response for DECR".into(),
-                Some(res),
-            )),
-        }
+        ok_response(res, "UNWATCH")
     }
 
-    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
-        hasher.hash_str(&self.key)
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
     }
 }
 
+/// Start a transaction. Send it on a `Lease`, and queue the commands with
+/// `Lease::queue`.
 #[derive(Debug)]
-pub struct DecrBy {
-    pub key: String,
-    pub decrement: i64,
-}
+pub struct Multi;
 
-impl Message for DecrBy {
-    type Result = Result<Result<i64, String>, Error>;
+impl Message for Multi {
+    type Result = Result<(), Error>;
 }
 
-impl Command for DecrBy {
-    type Output = Result<i64, String>;
+impl Command for Multi {
+    type Output = ();
 
     fn into_request(self) -> RespValue {
-        resp_array!["DECRBY", self.key, RespValue::Integer(self.decrement)]
+        resp_array!["MULTI"]
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
-        match res {
-            RespValue::Integer(x) => Ok(Ok(x)),
-            RespValue::Error(e) => Ok(Err(e)),
-            res => Err(RespError::RESP(
-                "invalid response for DECRBY".into(),
-                Some(res),
-            )),
-        }
+        ok_response(res, "MULTI")
     }
 
-    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
-        hasher.hash_str(&self.key)
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
     }
 }
 
+/// Run the queued commands of a transaction
 #[derive(Debug)]
-pub struct Ping(pub Option<String>);
+pub struct Exec;
 
-impl Message for Ping {
-    type Result = Result<String, Error>;
+impl Message for Exec {
+    type Result = Result<Option<Vec<RespValue>>, Error>;
 }
 
-impl Command for Ping {
-    type Output = String;
+impl Command for Exec {
+    /// the replies of the queued commands, or `None` if a watched key was
+    /// modified and the transaction aborted
+    type Output = Option<Vec<RespValue>>;
 
     fn into_request(self) -> RespValue {
-        match self.0 {
-            Some(s) => resp_array!["PING", s],
-            None => resp_array!["PING"],
-        }
+        resp_array!["EXEC"]
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
         match res {
-            RespValue::SimpleString(s) => Ok(s),
-            res => Err(RespError::RESP(
-                "invalid response for PING".into(),
+            RespValue::Array(replies) => Ok(Some(replies)),
+            RespValue::Nil => Ok(None),
+            _ => Err(RespError::RESP(
+                "invalid response for EXEC".into(),
                 Some(res),
             )),
         }
@@ -563,28 +4722,23 @@ impl Command for Ping {
     }
 }
 
+/// Abort a transaction
 #[derive(Debug)]
-pub struct Echo(String);
+pub struct Discard;
 
-impl Message for Echo {
-    type Result = Result<String, Error>;
+impl Message for Discard {
+    type Result = Result<(), Error>;
 }
 
-impl Command for Echo {
-    type Output = String;
+impl Command for Discard {
+    type Output = ();
 
     fn into_request(self) -> RespValue {
-        resp_array!["ECHO", self.0]
+        resp_array!["DISCARD"]
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
-        match res {
-            RespValue::SimpleString(s) => Ok(s),
-            res => Err(RespError::RESP(
-                "invalid response for ECHO".into(),
-                Some(res),
-            )),
-        }
+        ok_response(res, "DISCARD")
     }
 
     fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
@@ -592,6 +4746,16 @@ impl Command for Echo {
     }
 }
 
+fn ok_response(res: RespValue, command: &str) -> Result<(), RespError> {
+    match res {
+        RespValue::SimpleString(ref s) if s == "OK" => Ok(()),
+        _ => Err(RespError::RESP(
+            format!("invalid response for {}", command),
+            Some(res),
+        )),
+    }
+}
+
 pub struct ScriptExists {
     pub hash: Vec<Vec<u8>>,
     pub slot: u16,
@@ -612,23 +4776,7 @@ impl Command for ScriptExists {
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
-        if let RespValue::Array(values) = &res {
-            let bool_values = values
-                .iter()
-                .map(|v| match v {
-                    RespValue::Integer(0) => Some(false),
-                    RespValue::Integer(1) => Some(true),
-                    _ => None,
-                })
-                .collect();
-            if let Some(results) = bool_values {
-                return Ok(results);
-            }
-        }
-        Err(RespError::RESP(
-            "invalid response for SCRIPT EXISTS".into(),
-            Some(res),
-        ))
+        decode(res, "SCRIPT EXISTS")
     }
 
     fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
@@ -683,13 +4831,7 @@ impl Command for ScriptFlush {
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
-        match res {
-            RespValue::SimpleString(str) => Ok(str),
-            res => Err(RespError::RESP(
-                "invalid response for SCRIPT FLUSH".into(),
-                Some(res),
-            )),
-        }
+        decode(res, "SCRIPT FLUSH")
     }
 
     fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
@@ -697,6 +4839,7 @@ impl Command for ScriptFlush {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct Eval<'a> {
     pub script: &'a str,
     pub keys: Vec<String>,
@@ -787,7 +4930,7 @@ impl Command for ClusterAddSlots {
 
     fn into_request(self) -> RespValue {
         let mut v = vec!["CLUSTER".into(), "ADDSLOTS".into()];
-        v.extend(self.slots.into_iter().map(|x| x.to_string().into()));
+        self.slots.write_redis_args(&mut v);
         RespValue::Array(v)
     }
 
@@ -820,7 +4963,7 @@ impl Command for ClusterDelSlots {
 
     fn into_request(self) -> RespValue {
         let mut v = vec!["CLUSTER".into(), "DELSLOTS".into()];
-        v.extend(self.slots.into_iter().map(|x| x.to_string().into()));
+        self.slots.write_redis_args(&mut v);
         RespValue::Array(v)
     }
 
@@ -931,17 +5074,22 @@ impl Command for ClusterSetSlot {
     }
 }
 
+/// The number of keys in `slot` (`CLUSTER COUNTKEYSINSLOT`)
+///
+/// In cluster mode it is sent to the master currently serving `slot`. Send it
+/// with `Routed` and `Routing::ToNode` to count the keys already imported by
+/// another node during a migration.
+#[derive(Debug, Clone)]
 pub struct ClusterCountKeysInSlot {
     pub slot: u16,
-    pub target_node_slot: u16,
 }
 
 impl Message for ClusterCountKeysInSlot {
-    type Result = Result<usize, Error>;
+    type Result = Result<i64, Error>;
 }
 
 impl Command for ClusterCountKeysInSlot {
-    type Output = usize;
+    type Output = i64;
 
     fn into_request(self) -> RespValue {
         resp_array!["CLUSTER", "COUNTKEYSINSLOT", self.slot.to_string()]
@@ -949,7 +5097,7 @@ impl Command for ClusterCountKeysInSlot {
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
         match res {
-            RespValue::Integer(v) if v >= 0 => Ok(v as usize),
+            RespValue::Integer(v) if v >= 0 => Ok(v),
             _ => Err(RespError::RESP(
                 "invalid response for CLUSTER COUNTKEYSINSLOT".into(),
                 Some(res),
@@ -958,22 +5106,26 @@ impl Command for ClusterCountKeysInSlot {
     }
 
     fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
-        hasher.set(self.target_node_slot)
+        hasher.set(self.slot)
     }
 }
 
+/// Up to `count` keys in `slot` (`CLUSTER GETKEYSINSLOT`)
+///
+/// In cluster mode it is sent to the master currently serving `slot`, see
+/// `ClusterCountKeysInSlot`.
+#[derive(Debug, Clone)]
 pub struct ClusterGetKeysInSlot {
     pub slot: u16,
     pub count: usize,
-    pub target_node_slot: u16,
 }
 
 impl Message for ClusterGetKeysInSlot {
-    type Result = Result<Vec<String>, Error>;
+    type Result = Result<Vec<Vec<u8>>, Error>;
 }
 
 impl Command for ClusterGetKeysInSlot {
-    type Output = Vec<String>;
+    type Output = Vec<Vec<u8>>;
 
     fn into_request(self) -> RespValue {
         resp_array![
@@ -985,19 +5137,11 @@ impl Command for ClusterGetKeysInSlot {
     }
 
     fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
-        use redis_async::resp::FromResp;
-
-        match res {
-            RespValue::Array(v) => v.into_iter().map(String::from_resp).collect(),
-            _ => Err(RespError::RESP(
-                "invalid response for CLUSTER GETKEYSINSLOT".into(),
-                Some(res),
-            )),
-        }
+        decode(res, "CLUSTER GETKEYSINSLOT")
     }
 
     fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
-        hasher.set(self.target_node_slot)
+        hasher.set(self.slot)
     }
 }
 
@@ -1043,3 +5187,43 @@ impl Command for Migrate {
         hasher.set(self.target_node_slot)
     }
 }
+
+/// Ask a Sentinel for the `ip:port` of the master it monitors under
+/// `master_name`. `None` if the name is unknown.
+#[derive(Debug)]
+pub struct SentinelGetMasterAddr {
+    pub master_name: String,
+}
+
+impl Message for SentinelGetMasterAddr {
+    type Result = Result<Option<String>, Error>;
+}
+
+impl Command for SentinelGetMasterAddr {
+    type Output = Option<String>;
+
+    fn into_request(self) -> RespValue {
+        resp_array!["SENTINEL", "get-master-addr-by-name", self.master_name]
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        use redis_async::resp::FromResp;
+
+        match res {
+            RespValue::Nil => Ok(None),
+            RespValue::Array(ref v) if v.len() == 2 => {
+                let ip = String::from_resp(v[0].clone())?;
+                let port = String::from_resp(v[1].clone())?;
+                Ok(Some(format!("{}:{}", ip, port)))
+            }
+            _ => Err(RespError::RESP(
+                "invalid response for SENTINEL get-master-addr-by-name".into(),
+                Some(res),
+            )),
+        }
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+//! Configurations to read from a file, e.g. in TOML or YAML with serde
+//!
+//! Durations are written with a unit, e.g. `"500ms"`, `"30s"`, `"5m"`, `"2h"`
+//! or `"7d"`, and the `SameSite` of a cookie as `"strict"`, `"lax"` or
+//! `"none"`. Requires the `serde` feature.
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cluster::{RedisClusterActor, RedisClusterActorBuilder};
+use crate::redis::{RedisActor, RedisActorBuilder};
+
+/// The options of the connection to a Redis server or cluster
+///
+/// The missing fields take the defaults of `RedisActor::builder`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedisConnectionConfig {
+    /// The address of the server, or of the node of the cluster the slots are
+    /// discovered from
+    pub addr: String,
+    /// Whether `addr` is a node of a cluster
+    pub cluster: bool,
+    #[serde(with = "duration")]
+    pub connect_timeout: Duration,
+    pub strict: bool,
+    #[serde(with = "opt_duration")]
+    pub slow_log: Option<Duration>,
+    pub max_reply_bytes: Option<usize>,
+    pub mailbox_capacity: usize,
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+}
+
+/// The options of `RedisActorBuilder::circuit_breaker`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CircuitBreakerConfig {
+    pub threshold: usize,
+    #[serde(with = "duration")]
+    pub cooldown: Duration,
+}
+
+impl Default for RedisConnectionConfig {
+    fn default() -> RedisConnectionConfig {
+        RedisConnectionConfig {
+            addr: "127.0.0.1:6379".to_owned(),
+            cluster: false,
+            connect_timeout: Duration::from_secs(1),
+            strict: false,
+            slow_log: None,
+            max_reply_bytes: None,
+            mailbox_capacity: 16,
+            circuit_breaker: None,
+        }
+    }
+}
+
+impl RedisConnectionConfig {
+    /// A `RedisActorBuilder` configured with the options, whether `cluster` is
+    /// set or not
+    pub fn builder(&self) -> RedisActorBuilder {
+        let mut builder = RedisActor::builder(self.addr.as_str())
+            .connect_timeout(self.connect_timeout)
+            .strict(self.strict)
+            .mailbox_capacity(self.mailbox_capacity);
+        if let Some(threshold) = self.slow_log {
+            builder = builder.slow_log(threshold);
+        }
+        if let Some(max) = self.max_reply_bytes {
+            builder = builder.max_reply_bytes(max);
+        }
+        if let Some(ref breaker) = self.circuit_breaker {
+            builder = builder.circuit_breaker(breaker.threshold, breaker.cooldown);
+        }
+        builder
+    }
+
+    /// A `RedisClusterActorBuilder` configured with the options, whether
+    /// `cluster` is set or not
+    pub fn cluster_builder(&self) -> RedisClusterActorBuilder {
+        let mut builder = RedisClusterActor::builder(self.addr.as_str())
+            .connect_timeout(self.connect_timeout)
+            .strict(self.strict)
+            .mailbox_capacity(self.mailbox_capacity);
+        if let Some(threshold) = self.slow_log {
+            builder = builder.slow_log(threshold);
+        }
+        if let Some(max) = self.max_reply_bytes {
+            builder = builder.max_reply_bytes(max);
+        }
+        if let Some(ref breaker) = self.circuit_breaker {
+            builder = builder.circuit_breaker(breaker.threshold, breaker.cooldown);
+        }
+        builder
+    }
+}
+
+/// The options of a `RedisSession`
+///
+/// The missing fields take the defaults of `RedisSession::new`. The key
+/// signing the cookies is not part of it, so it can be kept apart from the
+/// rest of the configuration.
+#[cfg(feature = "session")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedisSessionConfig {
+    #[serde(with = "duration")]
+    pub ttl: Duration,
+    #[serde(with = "duration")]
+    pub max_ttl: Duration,
+    pub cookie_name: String,
+    pub cookie_path: String,
+    pub cookie_domain: Option<String>,
+    pub cookie_secure: bool,
+    #[serde(with = "opt_duration")]
+    pub cookie_max_age: Option<Duration>,
+    #[serde(with = "same_site")]
+    pub cookie_same_site: Option<actix_web::cookie::SameSite>,
+    pub refresh_on_load: bool,
+    pub connection: RedisConnectionConfig,
+}
+
+#[cfg(feature = "session")]
+impl Default for RedisSessionConfig {
+    fn default() -> RedisSessionConfig {
+        RedisSessionConfig {
+            ttl: Duration::from_secs(7200),
+            max_ttl: Duration::from_secs(30 * 24 * 3600),
+            cookie_name: "actix-session".to_owned(),
+            cookie_path: "/".to_owned(),
+            cookie_domain: None,
+            cookie_secure: false,
+            cookie_max_age: Some(Duration::from_secs(7 * 24 * 3600)),
+            cookie_same_site: None,
+            refresh_on_load: false,
+            connection: RedisConnectionConfig::default(),
+        }
+    }
+}
+
+const UNITS: &[(&str, u64)] = &[
+    ("d", 24 * 3600 * 1000),
+    ("h", 3600 * 1000),
+    ("m", 60 * 1000),
+    ("s", 1000),
+    ("ms", 1),
+];
+
+/// Parse a duration with a unit, e.g. `500ms` or `30s`
+fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit())?;
+    let (value, unit) = s.split_at(split);
+    let value: u64 = value.parse().ok()?;
+    let (_, millis) = UNITS.iter().find(|&&(name, _)| name == unit.trim())?;
+    Some(Duration::from_millis(value.checked_mul(*millis)?))
+}
+
+/// Format a duration in the largest unit it is a whole number of
+fn format_duration(d: Duration) -> String {
+    let ms = d.as_millis() as u64;
+    for &(name, millis) in UNITS.iter() {
+        if ms.checked_rem(millis) == Some(0) && (ms > 0 || name == "s") {
+            return format!("{}{}", ms / millis, name);
+        }
+    }
+    format!("{}ms", ms)
+}
+
+mod duration {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        d: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::format_duration(*d))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        super::parse_duration(&s)
+            .ok_or_else(|| de::Error::custom(format!("invalid duration: {}", s)))
+    }
+}
+
+mod opt_duration {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        d: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match *d {
+            Some(ref d) => super::duration::serialize(d, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super::duration")] Duration);
+
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(d)| d))
+    }
+}
+
+#[cfg(feature = "session")]
+mod same_site {
+    use actix_web::cookie::SameSite;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        same_site: &Option<SameSite>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match *same_site {
+            Some(SameSite::Strict) => serializer.serialize_str("strict"),
+            Some(SameSite::Lax) => serializer.serialize_str("lax"),
+            Some(SameSite::None) => serializer.serialize_str("none"),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SameSite>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(s) => match s.to_ascii_lowercase().as_str() {
+                "strict" => Ok(Some(SameSite::Strict)),
+                "lax" => Ok(Some(SameSite::Lax)),
+                "none" => Ok(Some(SameSite::None)),
+                _ => Err(de::Error::custom(format!("invalid SameSite: {}", s))),
+            },
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Advisory on the encodings of values, e.g. to tune
+//! `hash-max-listpack-entries` so the hashes of an application stay in the
+//! compact encoding
+use std::collections::BTreeMap;
+
+use ::actix::dev::ToEnvelope;
+use ::actix::prelude::*;
+use futures::future::{self, Either};
+use futures::Future;
+
+use crate::cluster::RedisClusterActor;
+use crate::command::{ConfigGet, HLen, ObjectEncoding};
+use crate::{reply, Error, RedisActor};
+
+/// The largest `hash-max-listpack-entries` suggested: beyond it, the linear
+/// scans of a listpack cost more CPU than the memory they save is worth
+pub const MAX_SUGGESTED_ENTRIES: i64 = 1024;
+
+/// Report the encodings of a sample of keys (`OBJECT ENCODING`), e.g. of
+/// sessions, and the hashes that grew past the compact encoding. Read-only:
+/// nothing is changed on the server.
+///
+/// In cluster mode the thresholds are read from the node the actor was
+/// started with.
+#[derive(Debug, Clone)]
+pub struct EncodingAdvisory {
+    pub keys: Vec<String>,
+}
+
+/// The encoding of a sampled key
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyEncoding {
+    pub key: String,
+    pub encoding: String,
+    /// the number of fields if the value is a hash
+    pub fields: Option<i64>,
+}
+
+/// The encodings of the keys sampled by an `EncodingAdvisory`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodingReport {
+    /// `hash-max-listpack-entries` of the server, `None` if unknown
+    pub max_entries: Option<i64>,
+    /// the number of sampled keys with each encoding
+    pub encodings: BTreeMap<String, usize>,
+    /// the hashes encoded as hash tables and their number of fields, the
+    /// largest first
+    pub grown: Vec<(String, i64)>,
+    /// a `hash-max-listpack-entries` keeping the grown hashes compact, if some
+    /// grew past it by their number of fields, rather than by the size of a
+    /// value, and have at most `MAX_SUGGESTED_ENTRIES` fields
+    pub suggested_max_entries: Option<i64>,
+}
+
+impl EncodingReport {
+    /// Build the report from the encodings of the sampled keys and the
+    /// `hash-max-listpack-entries` of the server
+    pub fn new(max_entries: Option<i64>, keys: Vec<KeyEncoding>) -> EncodingReport {
+        let mut encodings = BTreeMap::new();
+        let mut grown = vec![];
+        for key in keys {
+            *encodings.entry(key.encoding.clone()).or_insert(0) += 1;
+            if key.encoding == "hashtable" {
+                if let Some(fields) = key.fields {
+                    grown.push((key.key, fields));
+                }
+            }
+        }
+        grown.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let suggested_max_entries = grown
+            .iter()
+            .map(|&(_, fields)| fields)
+            .filter(|&fields| match max_entries {
+                Some(max) => fields > max,
+                None => true,
+            })
+            .filter(|&fields| fields <= MAX_SUGGESTED_ENTRIES)
+            .max()
+            .map(|fields| (fields as u64).next_power_of_two() as i64);
+
+        EncodingReport {
+            max_entries,
+            encodings,
+            grown,
+            suggested_max_entries,
+        }
+    }
+}
+
+impl Message for EncodingAdvisory {
+    type Result = Result<EncodingReport, Error>;
+}
+
+impl Handler<EncodingAdvisory> for RedisActor {
+    type Result = ResponseFuture<EncodingReport, Error>;
+
+    fn handle(
+        &mut self,
+        msg: EncodingAdvisory,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        Box::new(msg.run(ctx.address()))
+    }
+}
+
+impl Handler<EncodingAdvisory> for RedisClusterActor {
+    type Result = ResponseFuture<EncodingReport, Error>;
+
+    fn handle(
+        &mut self,
+        msg: EncodingAdvisory,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        Box::new(msg.run(ctx.address()))
+    }
+}
+
+impl EncodingAdvisory {
+    fn run<A>(self, addr: Addr<A>) -> impl Future<Item = EncodingReport, Error = Error>
+    where
+        A: Handler<ConfigGet> + Handler<ObjectEncoding> + Handler<HLen>,
+        A::Context: ToEnvelope<A, ConfigGet>
+            + ToEnvelope<A, ObjectEncoding>
+            + ToEnvelope<A, HLen>,
+    {
+        // named `hash-max-ziplist-entries` before Redis 7.0, and still
+        // answering to it since. Unknown if `CONFIG` is disabled.
+        let max_entries = reply(addr.send(ConfigGet {
+            parameter: "hash-max-ziplist-entries".into(),
+        }))
+        .then(|config| {
+            Ok(config.ok().and_then(|config| {
+                config.values().filter_map(|v| v.parse().ok()).next()
+            }))
+        });
+
+        let keys = self.keys.into_iter().map(move |key| {
+            let addr = addr.clone();
+            reply(addr.send(ObjectEncoding { key: key.clone() })).and_then(
+                move |encoding| {
+                    let encoding = match encoding {
+                        Some(encoding) => encoding,
+                        None => return Either::A(future::ok(None)),
+                    };
+                    // only hashes have fields, other types fail with `WRONGTYPE`
+                    Either::B(reply(addr.send(HLen { key: key.clone() })).then(
+                        move |fields| {
+                            Ok(Some(KeyEncoding {
+                                key,
+                                encoding,
+                                fields: fields.ok(),
+                            }))
+                        },
+                    ))
+                },
+            )
+        });
+
+        max_entries
+            .join(future::join_all(keys.collect::<Vec<_>>()))
+            .map(|(max_entries, keys)| {
+                EncodingReport::new(max_entries, keys.into_iter().flatten().collect())
+            })
+    }
+}
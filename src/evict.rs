@@ -0,0 +1,125 @@
+//! Client-side eviction of the least recently used keys, e.g. for a cache
+//! kept below a memory budget regardless of the `maxmemory-policy` of the
+//! server
+use std::cmp::Reverse;
+
+use ::actix::dev::ToEnvelope;
+use ::actix::prelude::*;
+use futures::future::{self, Either};
+use futures::Future;
+
+use crate::cluster::RedisClusterActor;
+use crate::command::{ObjectIdleTime, RandomKey, Ttl, Unlink};
+use crate::{reply, Error, RedisActor};
+
+/// Sample `sample` random keys (`RANDOMKEY`) and unlink the `evict` of them
+/// idle for the longest time (`OBJECT IDLETIME`), approximating LRU like the
+/// server does. Send it on a timer, or when the memory used exceeds a budget.
+///
+/// If `volatile`, only the keys with an expiration are evicted, like the
+/// `volatile-lru` policy. Not available with an LFU `maxmemory-policy`.
+///
+/// Resolves to the keys unlinked and their idle time in seconds, the idlest
+/// first. Keys which are not valid UTF-8 are never evicted.
+#[derive(Debug, Clone)]
+pub struct EvictIdlest {
+    pub sample: usize,
+    pub evict: usize,
+    pub volatile: bool,
+}
+
+impl Message for EvictIdlest {
+    type Result = Result<Vec<(String, i64)>, Error>;
+}
+
+impl Handler<EvictIdlest> for RedisActor {
+    type Result = ResponseFuture<Vec<(String, i64)>, Error>;
+
+    fn handle(&mut self, msg: EvictIdlest, ctx: &mut Self::Context) -> Self::Result {
+        Box::new(msg.run(ctx.address()))
+    }
+}
+
+impl Handler<EvictIdlest> for RedisClusterActor {
+    type Result = ResponseFuture<Vec<(String, i64)>, Error>;
+
+    fn handle(&mut self, msg: EvictIdlest, ctx: &mut Self::Context) -> Self::Result {
+        Box::new(msg.run(ctx.address()))
+    }
+}
+
+impl EvictIdlest {
+    fn run<A>(
+        self,
+        addr: Addr<A>,
+    ) -> impl Future<Item = Vec<(String, i64)>, Error = Error>
+    where
+        A: Handler<RandomKey> + Handler<ObjectIdleTime> + Handler<Ttl> + Handler<Unlink>,
+        A::Context: ToEnvelope<A, RandomKey>
+            + ToEnvelope<A, ObjectIdleTime>
+            + ToEnvelope<A, Ttl>
+            + ToEnvelope<A, Unlink>,
+    {
+        let EvictIdlest {
+            sample,
+            evict,
+            volatile,
+        } = self;
+
+        let keys = (0..sample)
+            .map(|_| reply(addr.send(RandomKey)))
+            .collect::<Vec<_>>();
+
+        future::join_all(keys)
+            .and_then({
+                let addr = addr.clone();
+                move |keys| {
+                    let mut sampled = Vec::<String>::new();
+                    for key in keys.into_iter().flatten() {
+                        if let Ok(key) = String::from_utf8(key) {
+                            if !sampled.contains(&key) {
+                                sampled.push(key);
+                            }
+                        }
+                    }
+                    let idle = sampled.into_iter().map(move |key| {
+                        let idle = reply(addr.send(ObjectIdleTime { key: key.clone() }));
+                        let expires = if volatile {
+                            Either::A(
+                                reply(addr.send(Ttl { key: key.clone() }))
+                                    .map(|ttl| ttl.is_ok()),
+                            )
+                        } else {
+                            Either::B(future::ok(true))
+                        };
+                        idle.join(expires).map(move |(idle, expires)| match idle {
+                            Some(idle) if expires => Some((key, idle)),
+                            _ => None,
+                        })
+                    });
+                    future::join_all(idle.collect::<Vec<_>>())
+                }
+            })
+            .and_then(move |candidates| {
+                let mut candidates =
+                    candidates.into_iter().flatten().collect::<Vec<_>>();
+                candidates.sort_by_key(|&(_, idle)| Reverse(idle));
+                candidates.truncate(evict);
+                // one by one, as the keys may be in different slots
+                let unlinked = candidates.into_iter().map(move |(key, idle)| {
+                    reply(addr.send(Unlink {
+                        keys: vec![key.clone()],
+                    }))
+                    .map(move |n| {
+                        if n > 0 {
+                            Some((key, idle))
+                        } else {
+                            None
+                        }
+                    })
+                });
+                future::join_all(unlinked.collect::<Vec<_>>())
+            })
+            .map(|unlinked| unlinked.into_iter().flatten().collect())
+    }
+}
@@ -0,0 +1,55 @@
+//! Client-side fault injection for resilience tests, enabled by the
+//! `test-util` feature
+use std::time::Duration;
+
+use rand::Rng;
+
+/// Faults a `RedisActor` injects into a fraction of its commands, see
+/// `RedisActorBuilder::faults`
+///
+/// Each command is failed with probability `fail`, otherwise its reply is
+/// delayed with the probability given to `delay`.
+#[derive(Debug, Clone, Default)]
+pub struct Faults {
+    delay: Option<(f64, Duration)>,
+    fail: f64,
+}
+
+/// What happens to a command
+pub(crate) enum Fault {
+    Fail,
+    Delay(Duration),
+}
+
+impl Faults {
+    pub fn new() -> Faults {
+        Faults::default()
+    }
+
+    /// Delay the replies of a `fraction` of the commands by `delay`, as if the
+    /// server were slow. The commands are sent right away.
+    pub fn delay(mut self, fraction: f64, delay: Duration) -> Self {
+        self.delay = Some((fraction, delay));
+        self
+    }
+
+    /// Fail a `fraction` of the commands with `Error::Disconnected` without
+    /// sending them
+    pub fn fail(mut self, fraction: f64) -> Self {
+        self.fail = fraction;
+        self
+    }
+
+    pub(crate) fn pick(&self) -> Option<Fault> {
+        let mut rng = rand::thread_rng();
+        if self.fail > 0.0 && rng.gen::<f64>() < self.fail {
+            return Some(Fault::Fail);
+        }
+        match self.delay {
+            Some((fraction, delay)) if fraction > 0.0 && rng.gen::<f64>() < fraction => {
+                Some(Fault::Delay(delay))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Idempotency keys, e.g. so a `POST` retried by a client is processed once
+//!
+//! The first request with a key reserves it, computes its response and stores
+//! it at the key; the duplicates get the stored response instead. The key holds
+//! a marker meanwhile, and the duplicates wait for the response. Both expire
+//! after the time to live, so a key whose first request crashed is reserved
+//! again by a duplicate once its marker expired.
+use std::time::{Duration, Instant};
+
+use ::actix::prelude::*;
+use futures::{future, Future, IntoFuture};
+use redis_async::error::Error as RespError;
+use redis_async::resp::RespValue;
+use tokio_timer::Delay;
+
+use crate::command::Eval;
+use crate::{reply, Error};
+
+/// The value of a reserved key, until the response is stored. Stored responses
+/// are prefixed with `DONE`, so they never look like it.
+const PENDING: &str = "P";
+const DONE: &[u8] = b"D";
+
+const RESERVE: &str = "\
+if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+    return false
+end
+return redis.call('GET', KEYS[1])";
+
+const COMPLETE: &str = "return redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])";
+
+const ABANDON: &str = "\
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0";
+
+/// How often a duplicate checks whether the response was stored
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The state of an idempotency key
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reservation {
+    /// The key was free and is now reserved: compute the response, then
+    /// `complete` it, or `abandon` it if it fails
+    First,
+    /// The response of the first request is being computed
+    Pending,
+    /// The response stored by the first request
+    Done(Vec<u8>),
+}
+
+/// Reserve the idempotency key `key` for `ttl`, if it is free. `redis` is the
+/// recipient of a `RedisActor` or `RedisClusterActor`.
+pub fn reserve<S: Into<String>>(
+    redis: &Recipient<Eval<'static>>,
+    key: S,
+    ttl: Duration,
+) -> impl Future<Item = Reservation, Error = Error> {
+    eval(redis, RESERVE, key.into(), PENDING.into(), Some(ttl)).and_then(reservation)
+}
+
+fn reservation(res: RespValue) -> Result<Reservation, Error> {
+    match res {
+        RespValue::Nil => Ok(Reservation::First),
+        RespValue::BulkString(ref value) if value == PENDING.as_bytes() => {
+            Ok(Reservation::Pending)
+        }
+        RespValue::BulkString(ref value) if value.starts_with(DONE) => {
+            Ok(Reservation::Done(value[DONE.len()..].to_vec()))
+        }
+        res => Err(Error::Redis(RespError::RESP(
+            "invalid response for idempotency key reservation".into(),
+            Some(res),
+        ))),
+    }
+}
+
+/// Store `response` at the idempotency key `key` reserved with `reserve`,
+/// for `ttl`
+pub fn complete<S: Into<String>>(
+    redis: &Recipient<Eval<'static>>,
+    key: S,
+    response: &[u8],
+    ttl: Duration,
+) -> impl Future<Item = (), Error = Error> {
+    let value = [DONE, response].concat();
+    eval(redis, COMPLETE, key.into(), value.into(), Some(ttl)).and_then(
+        |res| match res {
+            RespValue::SimpleString(ref s) if s == "OK" => Ok(()),
+            res => Err(Error::Redis(RespError::RESP(
+                "invalid response for idempotency key completion".into(),
+                Some(res),
+            ))),
+        },
+    )
+}
+
+/// Free the idempotency key `key` reserved with `reserve`, e.g. when computing
+/// the response failed, so a retry computes it again. A stored response is
+/// kept.
+pub fn abandon<S: Into<String>>(
+    redis: &Recipient<Eval<'static>>,
+    key: S,
+) -> impl Future<Item = (), Error = Error> {
+    eval(redis, ABANDON, key.into(), PENDING.into(), None).and_then(|res| match res {
+        RespValue::Integer(_) => Ok(()),
+        res => Err(Error::Redis(RespError::RESP(
+            "invalid response for idempotency key abandon".into(),
+            Some(res),
+        ))),
+    })
+}
+
+/// Compute the response for the idempotency key `key` with `compute`, unless
+/// another request did or is doing it, and keep it for `ttl`.
+///
+/// Resolves to the response computed here, or the one stored by the first
+/// request, waiting for it if needed. If `compute` fails, the key is freed and
+/// the error returned.
+pub fn run<S, C, F, E>(
+    redis: &Recipient<Eval<'static>>,
+    key: S,
+    ttl: Duration,
+    compute: C,
+) -> impl Future<Item = Vec<u8>, Error = E>
+where
+    S: Into<String>,
+    C: FnOnce() -> F,
+    F: IntoFuture<Item = Vec<u8>, Error = E>,
+    E: From<Error>,
+{
+    let redis = redis.clone();
+    let key = key.into();
+    let reserved = future::loop_fn((), {
+        let (redis, key) = (redis.clone(), key.clone());
+        move |()| {
+            let (redis, key) = (redis.clone(), key.clone());
+            reserve(&redis, key, ttl).and_then(|reservation| match reservation {
+                Reservation::Pending => future::Either::A(
+                    Delay::new(Instant::now() + POLL_INTERVAL)
+                        .then(|_| Ok(future::Loop::Continue(()))),
+                ),
+                reservation => {
+                    future::Either::B(future::ok(future::Loop::Break(reservation)))
+                }
+            })
+        }
+    });
+
+    reserved
+        .map_err(E::from)
+        .and_then(move |reservation| match reservation {
+            Reservation::Done(response) => future::Either::A(future::ok(response)),
+            _ => future::Either::B(compute().into_future().then(move |res| {
+                match res {
+                    Ok(response) => future::Either::A(
+                        complete(&redis, key, &response, ttl)
+                            .map(|()| response)
+                            .map_err(E::from),
+                    ),
+                    Err(e) => future::Either::B(abandon(&redis, key).then(|_| Err(e))),
+                }
+            })),
+        })
+}
+
+fn eval(
+    redis: &Recipient<Eval<'static>>,
+    script: &'static str,
+    key: String,
+    value: RespValue,
+    ttl: Option<Duration>,
+) -> impl Future<Item = RespValue, Error = Error> {
+    let mut args = vec![value];
+    if let Some(ttl) = ttl {
+        args.push(ttl.as_millis().to_string().into());
+    }
+    reply(redis.send(Eval {
+        script,
+        keys: vec![key],
+        args,
+    }))
+}
@@ -0,0 +1,120 @@
+//! Reading a key of any type, e.g. for an admin inspector
+use std::collections::HashMap;
+
+use ::actix::dev::ToEnvelope;
+use ::actix::prelude::*;
+use futures::future::{self, Either};
+use futures::Future;
+
+use crate::cluster::RedisClusterActor;
+use crate::command::{Get, HGetAll, LRange, SMembers, Type, ZRangeWithScores};
+use crate::{reply, Error, RedisActor};
+
+/// The value of a key, of any type
+#[derive(Debug, Clone, PartialEq)]
+pub enum RedisValue {
+    String(Vec<u8>),
+    /// the elements, from the head
+    List(Vec<Vec<u8>>),
+    Set(Vec<Vec<u8>>),
+    Hash(HashMap<Vec<u8>, Vec<u8>>),
+    /// the members and their scores, lowest first
+    ZSet(Vec<(Vec<u8>, f64)>),
+    /// A value of another type, e.g. a stream, by the name of its type. Its
+    /// content is not read.
+    Other(String),
+}
+
+/// Read the value at `key` whatever its type: the type is read first
+/// (`TYPE`), then the value with the command for it, e.g. `HGETALL` for a
+/// hash. In cluster mode both are sent to the master of the slot of `key`.
+///
+/// Resolves to `None` if the key does not exist. A key deleted between the two
+/// commands is returned empty, and one whose type changed in between fails
+/// with `Error::Redis`. The whole value is read at once: mind large values.
+#[derive(Debug, Clone)]
+pub struct GetAny {
+    pub key: String,
+}
+
+impl Message for GetAny {
+    type Result = Result<Option<RedisValue>, Error>;
+}
+
+impl Handler<GetAny> for RedisActor {
+    type Result = ResponseFuture<Option<RedisValue>, Error>;
+
+    fn handle(&mut self, msg: GetAny, ctx: &mut Self::Context) -> Self::Result {
+        Box::new(msg.run(ctx.address()))
+    }
+}
+
+impl Handler<GetAny> for RedisClusterActor {
+    type Result = ResponseFuture<Option<RedisValue>, Error>;
+
+    fn handle(&mut self, msg: GetAny, ctx: &mut Self::Context) -> Self::Result {
+        Box::new(msg.run(ctx.address()))
+    }
+}
+
+impl GetAny {
+    fn run<A>(
+        self,
+        addr: Addr<A>,
+    ) -> impl Future<Item = Option<RedisValue>, Error = Error>
+    where
+        A: Handler<Type>
+            + Handler<Get>
+            + Handler<LRange>
+            + Handler<SMembers>
+            + Handler<HGetAll>
+            + Handler<ZRangeWithScores>,
+        A::Context: ToEnvelope<A, Type>
+            + ToEnvelope<A, Get>
+            + ToEnvelope<A, LRange>
+            + ToEnvelope<A, SMembers>
+            + ToEnvelope<A, HGetAll>
+            + ToEnvelope<A, ZRangeWithScores>,
+    {
+        let key = self.key;
+        reply(addr.send(Type { key: key.clone() })).and_then(move |t| {
+            let t = match t {
+                Some(t) => t,
+                None => return Either::A(future::ok(None)),
+            };
+            let value: Box<dyn Future<Item = Option<RedisValue>, Error = Error>> =
+                match t.as_str() {
+                    "string" => Box::new(
+                        reply(addr.send(Get { key }))
+                            .map(|value| value.map(RedisValue::String)),
+                    ),
+                    "list" => Box::new(
+                        reply(addr.send(LRange {
+                            key,
+                            start: 0,
+                            stop: -1,
+                        }))
+                        .map(|elements| Some(RedisValue::List(elements))),
+                    ),
+                    "set" => Box::new(
+                        reply(addr.send(SMembers { key }))
+                            .map(|members| Some(RedisValue::Set(members))),
+                    ),
+                    "hash" => Box::new(
+                        reply(addr.send(HGetAll { key }))
+                            .map(|fields| Some(RedisValue::Hash(fields))),
+                    ),
+                    "zset" => Box::new(
+                        reply(addr.send(ZRangeWithScores {
+                            key,
+                            start: 0,
+                            stop: -1,
+                        }))
+                        .map(|members| Some(RedisValue::ZSet(members))),
+                    ),
+                    _ => Box::new(future::ok(Some(RedisValue::Other(t)))),
+                };
+            Either::B(value)
+        })
+    }
+}
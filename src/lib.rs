@@ -14,19 +14,51 @@ extern crate redis_async;
 #[macro_use]
 extern crate derive_more;
 
+pub mod addr;
 pub mod cluster;
 pub mod command;
+#[cfg(feature = "serde")]
+pub mod config;
+pub mod encoding;
+pub mod evict;
+#[cfg(feature = "test-util")]
+pub mod fault;
+pub mod idempotency;
+pub mod inspect;
+#[cfg(feature = "lock")]
+pub mod lock;
+pub mod pipe;
+pub mod pubsub;
+pub mod range;
 pub mod redis;
+pub mod resp;
+pub mod rolling;
+#[cfg(feature = "sample")]
+pub mod sample;
+pub mod scan;
+pub mod sentinel;
 pub mod slot;
-pub use crate::cluster::RedisClusterActor;
-pub use crate::redis::RedisActor;
+pub mod token;
+pub mod value;
+pub use crate::cluster::{
+    ClusterHealth, ClusterHealthReport, ClusterPing, ClusterStats, Failover, NodeStats,
+    ReadReplica, RedisClusterActor, RedisClusterActorBuilder, Routed, Routing,
+    Transaction,
+};
+pub use crate::redis::{
+    Checkout, ConnectionState, Drain, Health, HealthReport, Lease, RateLimitPolicy,
+    Readiness, RedactionPolicy, RedisActor, RedisActorBuilder, ServerNow,
+};
 
-#[cfg(feature = "web")]
+#[cfg(feature = "session")]
 mod session;
-#[cfg(feature = "web")]
+#[cfg(feature = "session")]
 pub use actix_web::cookie::SameSite;
-#[cfg(feature = "web")]
-pub use session::RedisSession;
+#[cfg(feature = "session")]
+pub use session::{
+    CookieEncoding, InvalidatedSessions, KeyHash, MaintenanceMode, RedisSession,
+    SessionConsistency, TypedSession, PERSIST_KEY, TTL_OVERRIDE_KEY,
+};
 
 /// General purpose actix redis error
 #[derive(Debug, Display, From)]
@@ -45,8 +77,44 @@ pub enum Error {
     /// I/O Error
     #[display(fmt = "Redis: I/O error {}", _0)]
     IoError(std::io::Error),
+    /// The server sent a malformed reply, the connection is dropped
+    #[display(fmt = "Redis: Parse error {}", _0)]
+    Parse(String),
+    /// The server sent a reply larger than the configured `max_reply_bytes`,
+    /// the connection is dropped
+    #[display(fmt = "Redis: Reply too large")]
+    ReplyTooLarge,
+    /// The circuit breaker is open after repeated failures, the command was
+    /// not sent
+    #[display(fmt = "Redis: Circuit open")]
+    CircuitOpen,
+    /// The commands of a cluster transaction touch keys in different slots,
+    /// the transaction was not sent
+    #[display(fmt = "Redis: Cross slot transaction {:?}", slots)]
+    CrossSlot { slots: Vec<u16> },
+    /// The server refused a write with `OOM`, it reached `maxmemory` and
+    /// cannot evict keys, e.g. with the `noeviction` policy
+    #[display(fmt = "Redis: Out of memory")]
+    OutOfMemory,
+    /// The command is beyond the rate limit of the connection, it was not sent
+    #[display(fmt = "Redis: Rate limited")]
+    RateLimited,
+}
+
+/// The reply to a message sent to an actor, failing with `Error::Disconnected`
+/// if the actor stopped before replying
+pub(crate) fn reply<F, T>(req: F) -> impl futures::Future<Item = T, Error = Error>
+where
+    F: futures::Future<Item = Result<T, Error>, Error = actix::MailboxError>,
+{
+    req.then(|res| match res {
+        Ok(res) => res,
+        Err(_) => Err(Error::Disconnected),
+    })
 }
 
 // re-export
 pub use redis_async::error::Error as RespError;
 pub use redis_async::resp::RespValue;
+
+pub use bytes::Bytes;
@@ -0,0 +1,114 @@
+//! Distributed locks, e.g. to run a periodic job on a single worker
+//!
+//! A lock is a key holding a random token, set only if the key does not exist
+//! and with a time to live, so a lock whose holder crashed is not held
+//! forever. Renewing and releasing check the token first, so a holder whose
+//! lock expired and was acquired by someone else never extends or releases
+//! the lock of the new holder.
+use std::time::Duration;
+
+use ::actix::prelude::*;
+use futures::Future;
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
+use redis_async::error::Error as RespError;
+use redis_async::resp::RespValue;
+
+use crate::command::Eval;
+use crate::{reply, Error};
+
+const ACQUIRE: &str = "return redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2])";
+
+const RENEW: &str = "\
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0";
+
+const RELEASE: &str = "\
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0";
+
+/// A held lock
+///
+/// Dropping the guard does not release the lock, it is then held until it
+/// expires.
+pub struct LockGuard {
+    redis: Recipient<Eval<'static>>,
+    key: String,
+    token: String,
+}
+
+/// Acquire the lock at `key` for `ttl`. `redis` is the recipient of a
+/// `RedisActor` or `RedisClusterActor`.
+///
+/// Resolves to `None` if the lock is already held.
+pub fn acquire<S: Into<String>>(
+    redis: &Recipient<Eval<'static>>,
+    key: S,
+    ttl: Duration,
+) -> impl Future<Item = Option<LockGuard>, Error = Error> {
+    let guard = LockGuard {
+        redis: redis.clone(),
+        key: key.into(),
+        token: OsRng.sample_iter(&Alphanumeric).take(32).collect(),
+    };
+    guard.eval(ACQUIRE, Some(ttl)).and_then(|res| match res {
+        RespValue::SimpleString(ref s) if s == "OK" => Ok(Some(guard)),
+        RespValue::Nil => Ok(None),
+        res => Err(Error::Redis(RespError::RESP(
+            "invalid response for lock acquisition".into(),
+            Some(res),
+        ))),
+    })
+}
+
+impl LockGuard {
+    /// The key of the lock
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Make the lock expire `ttl` from now, if it is still held
+    ///
+    /// Resolves to `false` if the lock was lost, i.e. it expired and may have
+    /// been acquired by someone else meanwhile.
+    pub fn renew(&self, ttl: Duration) -> impl Future<Item = bool, Error = Error> {
+        self.eval(RENEW, Some(ttl)).and_then(held)
+    }
+
+    /// Release the lock, if it is still held
+    ///
+    /// Resolves to `false` if the lock was already lost.
+    pub fn release(self) -> impl Future<Item = bool, Error = Error> {
+        self.eval(RELEASE, None).and_then(held)
+    }
+
+    fn eval(
+        &self,
+        script: &'static str,
+        ttl: Option<Duration>,
+    ) -> impl Future<Item = RespValue, Error = Error> {
+        let mut args = vec![self.token.as_str().into()];
+        if let Some(ttl) = ttl {
+            args.push(ttl.as_millis().to_string().into());
+        }
+        reply(self.redis.send(Eval {
+            script,
+            keys: vec![self.key.clone()],
+            args,
+        }))
+    }
+}
+
+/// Whether a renewal or release script found the lock held
+fn held(res: RespValue) -> Result<bool, Error> {
+    match res {
+        RespValue::Integer(n) => Ok(n == 1),
+        res => Err(Error::Redis(RespError::RESP(
+            "invalid response for a lock held check".into(),
+            Some(res),
+        ))),
+    }
+}
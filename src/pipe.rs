@@ -0,0 +1,159 @@
+//! Pipelines of commands with typed results
+use ::actix::dev::ToEnvelope;
+use ::actix::prelude::*;
+use futures::Future;
+
+use crate::cluster::RedisClusterActor;
+use crate::command::{Command, Expiration, FromRedisValue, Get, Incr, Set};
+use crate::slot::{HashError, Hasher};
+use crate::{reply, Error, RedisActor, RespError, RespValue};
+
+/// Commands sent back to back through a `RedisActor` or a `RedisClusterActor`,
+/// without waiting for the reply of one before sending the next.
+///
+/// In cluster mode each command is sent to the master serving the slot of its
+/// keys, so the commands for different nodes run concurrently. Unlike a
+/// `Transaction`, the commands are not atomic.
+///
+/// Resolves to the replies of the commands, in order.
+#[derive(Debug, Default)]
+pub struct Pipe {
+    steps: Vec<Step>,
+    /// the first command whose keys are in several slots
+    invalid: Option<HashError>,
+}
+
+impl Pipe {
+    pub fn new() -> Pipe {
+        Pipe::default()
+    }
+
+    /// Add a command to the pipe
+    pub fn command<M: Command>(mut self, command: M) -> Self {
+        let slot = match command.key_slot() {
+            Ok(slot) => slot,
+            Err(e) => {
+                if self.invalid.is_none() {
+                    self.invalid = Some(e);
+                }
+                None
+            }
+        };
+        self.steps.push(Step {
+            req: command.into_request(),
+            slot,
+        });
+        self
+    }
+
+    /// Add a `GET`, replying with the value or nil
+    pub fn get<K: Into<String>>(self, key: K) -> Self {
+        self.command(Get { key: key.into() })
+    }
+
+    /// Add a `SET` without expiration, replying with `OK`
+    pub fn set<K: Into<String>, V: Into<String>>(self, key: K, value: V) -> Self {
+        self.command(Set {
+            key: key.into(),
+            value: value.into(),
+            expiration: Expiration::Infinite,
+        })
+    }
+
+    /// Add an `INCR`, replying with the incremented value
+    pub fn incr<K: Into<String>>(self, key: K) -> Self {
+        self.command(Incr { key: key.into() })
+    }
+
+    /// Send the commands through `redis` and decode their replies as `T`, e.g.
+    /// a tuple with an element per command, or a `Vec`.
+    ///
+    /// Fails with the first error replied to a command, or if the replies do
+    /// not decode as `T`.
+    pub fn execute<T: FromRedisValue>(
+        self,
+        redis: &Recipient<Pipe>,
+    ) -> impl Future<Item = T, Error = Error> {
+        reply(redis.send(self)).and_then(|replies| {
+            for reply in replies.iter() {
+                if let RespValue::Error(ref e) = reply {
+                    return Err(Error::Redis(RespError::Remote(e.clone())));
+                }
+            }
+            T::from_redis_value(RespValue::Array(replies)).map_err(|res| {
+                Error::Redis(RespError::RESP(
+                    "invalid response for pipe".into(),
+                    Some(res),
+                ))
+            })
+        })
+    }
+
+    fn send<A>(self, addr: Addr<A>) -> ResponseFuture<Vec<RespValue>, Error>
+    where
+        A: Handler<Step>,
+        A::Context: ToEnvelope<A, Step>,
+    {
+        if let Some(e) = self.invalid {
+            return Box::new(futures::future::err(Error::MultipleSlot(e)));
+        }
+        // queued in the mailbox in order, so written in order; those deferred
+        // while it is full are queued again in order as the replies are polled
+        let replies = self
+            .steps
+            .into_iter()
+            .map(|step| reply(addr.send(step)))
+            .collect::<Vec<_>>();
+        Box::new(futures::future::join_all(replies))
+    }
+}
+
+impl Message for Pipe {
+    type Result = Result<Vec<RespValue>, Error>;
+}
+
+impl Handler<Pipe> for RedisActor {
+    type Result = ResponseFuture<Vec<RespValue>, Error>;
+
+    fn handle(&mut self, msg: Pipe, ctx: &mut Self::Context) -> Self::Result {
+        msg.send(ctx.address())
+    }
+}
+
+impl Handler<Pipe> for RedisClusterActor {
+    type Result = ResponseFuture<Vec<RespValue>, Error>;
+
+    fn handle(&mut self, msg: Pipe, ctx: &mut Self::Context) -> Self::Result {
+        msg.send(ctx.address())
+    }
+}
+
+/// A command of a `Pipe`, and the slot of its keys
+#[derive(Debug)]
+struct Step {
+    req: RespValue,
+    slot: Option<u16>,
+}
+
+impl Message for Step {
+    type Result = Result<RespValue, Error>;
+}
+
+impl Command for Step {
+    type Output = RespValue;
+
+    fn into_request(self) -> RespValue {
+        self.req
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        Ok(res)
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        match self.slot {
+            Some(slot) => hasher.set(slot),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,507 @@
+//! Pub/Sub support
+use ::actix::actors::resolver::{Connect, Resolver};
+use ::actix::prelude::*;
+use backoff::backoff::Backoff;
+use backoff::ExponentialBackoff;
+use futures::future::{self, Either};
+use futures::sync::{mpsc, oneshot};
+use futures::{Future, Poll, Stream};
+use redis_async::error::Error as RespError;
+use redis_async::resp::{RespCodec, RespValue};
+use std::io;
+use std::time::Duration;
+use tokio_codec::FramedRead;
+use tokio_io::io::WriteHalf;
+use tokio_io::AsyncRead;
+use tokio_tcp::TcpStream;
+use tokio_timer::Timeout;
+
+use crate::cluster::{MasterOf, RedisClusterActor};
+use crate::command::Exists;
+use crate::slot::{hash_slot, Hasher};
+use crate::{reply, Error};
+
+/// A message published to a subscribed channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct PubSubMessage {
+    pub channel: String,
+    pub payload: Vec<u8>,
+}
+
+/// A message published to a channel matching a subscribed pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternMessage {
+    pub pattern: String,
+    pub channel: String,
+    pub payload: Vec<u8>,
+}
+
+/// An event of a `Subscription`
+#[derive(Debug, Clone, PartialEq)]
+pub enum PubSubEvent {
+    /// A message published to a subscribed channel
+    Message(PubSubMessage),
+    /// A message published to a channel matching a subscribed pattern
+    PatternMessage(PatternMessage),
+    /// The subscription was issued again on a new connection, after the
+    /// previous one dropped or the slot moved. Messages published meanwhile
+    /// may have been missed.
+    Resubscribed,
+}
+
+/// Stream of the messages published to the subscribed channels.
+///
+/// The subscription is cancelled when the stream is dropped.
+pub struct Subscription {
+    rx: mpsc::UnboundedReceiver<PubSubEvent>,
+}
+
+impl Stream for Subscription {
+    type Item = PubSubEvent;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        self.rx.poll().map_err(|()| Error::Disconnected)
+    }
+}
+
+/// Subscribe to sharded channels of a Redis 7 cluster (`SSUBSCRIBE`)
+///
+/// The subscription connects to the master serving the slot of the channels,
+/// and resubscribes to the new master when the slot moves or the connection
+/// drops, emitting `PubSubEvent::Resubscribed`.
+#[derive(Debug)]
+pub struct SSubscribe {
+    pub channels: Vec<String>,
+}
+
+impl SSubscribe {
+    /// Start the subscription. Must be called within a running actix system.
+    ///
+    /// # Failures
+    /// If the channels fall into different slots, an error is reported
+    ///
+    /// # Panics
+    /// Panics if no channel is given
+    pub fn start(self, cluster: Addr<RedisClusterActor>) -> Result<Subscription, Error> {
+        let mut hasher = Hasher::new();
+        for channel in self.channels.iter() {
+            hasher.hash_str(channel)?;
+        }
+        let slot = hasher.get().expect("no channel to subscribe");
+
+        Ok(Subscriber::start(
+            Target::Cluster { cluster, slot },
+            Kind::Sharded,
+            self.channels,
+            None,
+        ))
+    }
+}
+
+/// Subscribe to channels of a Redis server (`SUBSCRIBE`)
+///
+/// When the connection drops, the subscription reconnects with a backoff time
+/// and subscribes to the channels again, then emits
+/// `PubSubEvent::Resubscribed`.
+#[derive(Debug)]
+pub struct Subscribe {
+    pub channels: Vec<String>,
+}
+
+impl Subscribe {
+    /// Start the subscription on the server at `addr`. Must be called within a
+    /// running actix system.
+    ///
+    /// # Panics
+    /// Panics if no channel is given
+    pub fn start<S: Into<String>>(self, addr: S) -> Subscription {
+        assert!(!self.channels.is_empty(), "no channel to subscribe");
+
+        Subscriber::start(Target::Node(addr.into()), Kind::Plain, self.channels, None)
+    }
+}
+
+/// Subscribe to the channels matching patterns on a Redis server
+/// (`PSUBSCRIBE`), e.g. `user.*`
+///
+/// When the connection drops, the subscription reconnects with a backoff time
+/// and subscribes to the patterns again, then emits
+/// `PubSubEvent::Resubscribed`.
+#[derive(Debug)]
+pub struct PSubscribe {
+    pub patterns: Vec<String>,
+}
+
+impl PSubscribe {
+    /// Start the subscription on the server at `addr`. Must be called within a
+    /// running actix system.
+    ///
+    /// # Panics
+    /// Panics if no pattern is given
+    pub fn start<S: Into<String>>(self, addr: S) -> Subscription {
+        assert!(!self.patterns.is_empty(), "no pattern to subscribe");
+
+        Subscriber::start(
+            Target::Node(addr.into()),
+            Kind::Pattern,
+            self.patterns,
+            None,
+        )
+    }
+}
+
+/// Wait until `key` is set by another client, through keyspace notifications
+///
+/// The notifications must be enabled on the server, e.g. with
+/// `notify-keyspace-events K$`, and only keys of the database 0 are waited
+/// for. Once subscribed, and again after resubscribing, the key is checked for,
+/// so a key set before is not missed.
+///
+/// Resolves to `true` once the key exists, or `false` if it does not after
+/// `timeout`.
+#[derive(Debug)]
+pub struct WaitForKey {
+    pub key: String,
+    pub timeout: Duration,
+}
+
+impl WaitForKey {
+    /// Wait on the server at `addr`, checking for the key through `redis`, e.g.
+    /// a `RedisActor` connected to the same server. Must be called within a
+    /// running actix system.
+    pub fn start<S: Into<String>>(
+        self,
+        addr: S,
+        redis: Recipient<Exists>,
+    ) -> impl Future<Item = bool, Error = Error> {
+        self.wait(Target::Node(addr.into()), redis)
+    }
+
+    /// Wait on the master serving the slot of the key in a cluster. Must be
+    /// called within a running actix system.
+    pub fn start_cluster(
+        self,
+        cluster: Addr<RedisClusterActor>,
+    ) -> impl Future<Item = bool, Error = Error> {
+        let slot = hash_slot(self.key.as_bytes());
+        let redis = cluster.clone().recipient();
+        self.wait(Target::Cluster { cluster, slot }, redis)
+    }
+
+    fn wait(
+        self,
+        target: Target,
+        redis: Recipient<Exists>,
+    ) -> impl Future<Item = bool, Error = Error> {
+        let WaitForKey { key, timeout } = self;
+        let (subscribed, on_subscribed) = oneshot::channel();
+        let channel = format!("__keyspace@0__:{}", key);
+        let events =
+            Subscriber::start(target, Kind::Plain, vec![channel], Some(subscribed));
+
+        let exists = move || {
+            reply(redis.send(Exists {
+                keys: vec![key.clone()],
+            }))
+            .map(|n| n > 0)
+        };
+        let found = on_subscribed
+            .map_err(|_| Error::Disconnected)
+            .and_then({
+                let exists = exists.clone();
+                move |()| exists()
+            })
+            .and_then(move |found| {
+                let set = events
+                    .and_then(move |event| match event {
+                        PubSubEvent::Message(message) => {
+                            Either::A(future::ok(message.payload == b"set"))
+                        }
+                        // set while resubscribing
+                        PubSubEvent::Resubscribed => Either::B(exists()),
+                        // not subscribed to patterns
+                        PubSubEvent::PatternMessage(_) => Either::A(future::ok(false)),
+                    })
+                    .filter(|set| *set)
+                    .into_future()
+                    .map(|(set, _)| set.is_some())
+                    .map_err(|(e, _)| e);
+                if found {
+                    Either::A(future::ok(true))
+                } else {
+                    Either::B(set)
+                }
+            });
+
+        Timeout::new(found, timeout).or_else(|e| {
+            if e.is_elapsed() {
+                Ok(false)
+            } else {
+                Err(e.into_inner().unwrap_or(Error::Disconnected))
+            }
+        })
+    }
+}
+
+/// Where the subscriber connects to
+enum Target {
+    /// The server at the address
+    Node(String),
+    /// The master serving the slot
+    Cluster {
+        cluster: Addr<RedisClusterActor>,
+        slot: u16,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Plain,
+    Sharded,
+    Pattern,
+}
+
+impl Kind {
+    fn subscribe(self) -> &'static str {
+        match self {
+            Kind::Plain => "SUBSCRIBE",
+            Kind::Sharded => "SSUBSCRIBE",
+            Kind::Pattern => "PSUBSCRIBE",
+        }
+    }
+}
+
+/// Actor owning a connection in the subscribed state
+struct Subscriber {
+    target: Target,
+    kind: Kind,
+    channels: Vec<String>,
+    tx: mpsc::UnboundedSender<PubSubEvent>,
+    backoff: ExponentialBackoff,
+    cell: Option<actix::io::FramedWrite<WriteHalf<TcpStream>, RespCodec>>,
+    stream: Option<SpawnHandle>,
+    reconnecting: bool,
+    /// whether a connection has subscribed before, and the current one
+    subscribed_before: bool,
+    subscribed: bool,
+    /// notified once first subscribed
+    on_subscribed: Option<oneshot::Sender<()>>,
+}
+
+impl Subscriber {
+    fn start(
+        target: Target,
+        kind: Kind,
+        channels: Vec<String>,
+        on_subscribed: Option<oneshot::Sender<()>>,
+    ) -> Subscription {
+        let (tx, rx) = mpsc::unbounded();
+        let backoff = ExponentialBackoff {
+            max_elapsed_time: None,
+            ..Default::default()
+        };
+
+        Subscriber {
+            target,
+            kind,
+            channels,
+            tx,
+            backoff,
+            cell: None,
+            stream: None,
+            reconnecting: false,
+            subscribed_before: false,
+            subscribed: false,
+            on_subscribed,
+        }
+        .start();
+
+        Subscription { rx }
+    }
+
+    fn connect(&mut self, ctx: &mut Context<Self>) {
+        let addr = match self.target {
+            Target::Node(ref addr) => Either::A(future::ok(addr.clone())),
+            Target::Cluster { ref cluster, slot } => {
+                Either::B(reply(cluster.send(MasterOf(slot))))
+            }
+        };
+
+        addr.and_then(|addr| {
+            Resolver::from_registry()
+                .send(Connect::host(addr.as_str()))
+                .then(move |res| match res {
+                    Ok(Ok(stream)) => Ok((addr, stream)),
+                    Ok(Err(e)) => {
+                        error!("Can not connect to redis server: {}", e);
+                        Err(Error::NotConnected)
+                    }
+                    Err(_) => Err(Error::Disconnected),
+                })
+        })
+        .into_actor(self)
+        .map(|(addr, stream), act, ctx| {
+            info!("Subscribing to {:?} on {}", act.channels, addr);
+
+            let (r, w) = stream.split();
+
+            let mut framed = actix::io::FramedWrite::new(w, RespCodec, ctx);
+            let mut req = vec![act.kind.subscribe().into()];
+            req.extend(act.channels.iter().map(|c| c.as_str().into()));
+            framed.write(RespValue::Array(req));
+            act.cell = Some(framed);
+            act.subscribed = false;
+
+            act.stream = Some(ctx.add_stream(FramedRead::new(r, RespCodec)));
+            act.backoff.reset();
+        })
+        .map_err(|e, act, ctx| {
+            error!("Can not subscribe to {:?}: {}", act.channels, e);
+            act.reconnect(ctx);
+        })
+        .spawn(ctx);
+    }
+
+    /// Drop the current connection and connect again after a backoff time
+    fn reconnect(&mut self, ctx: &mut Context<Self>) {
+        if let Some(stream) = self.stream.take() {
+            ctx.cancel_future(stream);
+        }
+        if let Some(mut cell) = self.cell.take() {
+            cell.close();
+        }
+
+        if !self.reconnecting {
+            self.reconnecting = true;
+            let timeout = self
+                .backoff
+                .next_backoff()
+                .unwrap_or_else(|| Duration::from_secs(1));
+            ctx.run_later(timeout, |act, ctx| {
+                act.reconnecting = false;
+                act.connect(ctx);
+            });
+        }
+    }
+
+    /// Pass an event to the subscription, stopping once it is dropped
+    fn emit(&mut self, event: PubSubEvent, ctx: &mut Context<Self>) {
+        if self.tx.unbounded_send(event).is_err() {
+            ctx.stop();
+        }
+    }
+}
+
+impl Actor for Subscriber {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        self.connect(ctx);
+
+        // stop once the subscription is dropped, even if nothing is published
+        ctx.run_interval(Duration::from_secs(1), |act, ctx| {
+            if act.tx.is_closed() {
+                ctx.stop();
+            }
+        });
+    }
+}
+
+impl actix::io::WriteHandler<io::Error> for Subscriber {
+    fn error(&mut self, err: io::Error, ctx: &mut Self::Context) -> Running {
+        warn!(
+            "Redis subscription dropped: {:?} error: {}",
+            self.channels, err
+        );
+        self.reconnect(ctx);
+        Running::Stop
+    }
+
+    fn finished(&mut self, _: &mut Self::Context) {}
+}
+
+impl StreamHandler<RespValue, RespError> for Subscriber {
+    fn error(&mut self, err: RespError, _: &mut Self::Context) -> Running {
+        warn!(
+            "Redis subscription dropped: {:?} error: {}",
+            self.channels, err
+        );
+        Running::Stop
+    }
+
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        self.reconnect(ctx);
+    }
+
+    fn handle(&mut self, msg: RespValue, ctx: &mut Self::Context) {
+        use redis_async::resp::FromResp;
+
+        let mut values = match msg {
+            RespValue::Array(values) => values.into_iter(),
+            RespValue::Error(e) => {
+                warn!("Redis subscription {:?} failed: {}", self.channels, e);
+                return self.reconnect(ctx);
+            }
+            msg => {
+                warn!("Unexpected message in subscription: {:?}", msg);
+                return;
+            }
+        };
+
+        let kind = values
+            .next()
+            .and_then(|v| String::from_resp(v).ok())
+            .unwrap_or_default();
+        match kind.as_str() {
+            "message" | "smessage" => {
+                let channel = values.next().and_then(|v| String::from_resp(v).ok());
+                let payload = values.next().and_then(|v| Vec::<u8>::from_resp(v).ok());
+                if let (Some(channel), Some(payload)) = (channel, payload) {
+                    self.emit(
+                        PubSubEvent::Message(PubSubMessage { channel, payload }),
+                        ctx,
+                    );
+                }
+            }
+            "pmessage" => {
+                let pattern = values.next().and_then(|v| String::from_resp(v).ok());
+                let channel = values.next().and_then(|v| String::from_resp(v).ok());
+                let payload = values.next().and_then(|v| Vec::<u8>::from_resp(v).ok());
+                if let (Some(pattern), Some(channel), Some(payload)) =
+                    (pattern, channel, payload)
+                {
+                    self.emit(
+                        PubSubEvent::PatternMessage(PatternMessage {
+                            pattern,
+                            channel,
+                            payload,
+                        }),
+                        ctx,
+                    );
+                }
+            }
+            "subscribe" | "ssubscribe" | "psubscribe" => {
+                debug!("subscribed: {:?}", values.next());
+                // confirmed once per channel or pattern
+                if !self.subscribed {
+                    self.subscribed = true;
+                    if self.subscribed_before {
+                        self.emit(PubSubEvent::Resubscribed, ctx);
+                    }
+                    self.subscribed_before = true;
+                    if let Some(on_subscribed) = self.on_subscribed.take() {
+                        let _ = on_subscribed.send(());
+                    }
+                }
+            }
+            // the server unsubscribes us when the slot has moved
+            "sunsubscribe" => {
+                info!("Resubscribing to {:?}", self.channels);
+                self.backoff.reset();
+                self.reconnect(ctx);
+            }
+            kind => warn!("Unexpected message in subscription: {:?}", kind),
+        }
+    }
+}
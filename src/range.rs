@@ -0,0 +1,118 @@
+//! Streaming of large string values
+use ::actix::prelude::*;
+use bytes::Bytes;
+use futures::{Async, Future, Poll, Stream};
+
+use crate::command::GetRange;
+use crate::{reply, Error};
+
+/// Stream of the chunks of a string value, read with `GETRANGE`
+///
+/// Only one chunk is held in memory at a time, so a large value can be sent as
+/// a streaming response body, or a byte range of it for HTTP range requests.
+///
+/// The chunks are read with separate commands, so writes to the value while
+/// it is streamed may be partially visible. A missing key streams as empty.
+pub struct RangeStream {
+    redis: Recipient<GetRange>,
+    key: String,
+    chunk_size: usize,
+    /// offset of the next chunk
+    pos: u64,
+    /// offset of the last byte to stream
+    end: Option<u64>,
+    pending: Option<Box<dyn Future<Item = Bytes, Error = Error>>>,
+    done: bool,
+}
+
+impl RangeStream {
+    /// Stream the value at `key` in chunks of `chunk_size` bytes. `redis` is
+    /// the recipient of a `RedisActor` or `RedisClusterActor`.
+    ///
+    /// # Panics
+    /// Panics if `chunk_size` is 0
+    pub fn new<S: Into<String>>(
+        redis: Recipient<GetRange>,
+        key: S,
+        chunk_size: usize,
+    ) -> RangeStream {
+        assert!(chunk_size > 0, "chunk size must not be 0");
+        RangeStream {
+            redis,
+            key: key.into(),
+            chunk_size,
+            pos: 0,
+            end: None,
+            pending: None,
+            done: false,
+        }
+    }
+
+    /// Only stream the bytes from `start` to `end` inclusive, or to the end of
+    /// the value without `end`.
+    pub fn range(mut self, start: u64, end: Option<u64>) -> Self {
+        self.pos = start;
+        self.end = end;
+        self
+    }
+
+    /// The offset of the last byte of the next chunk
+    fn chunk_end(&self) -> u64 {
+        let end = self.pos + self.chunk_size as u64 - 1;
+        match self.end {
+            Some(last) if last < end => last,
+            _ => end,
+        }
+    }
+}
+
+impl Stream for RangeStream {
+    type Item = Bytes;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Bytes>, Error> {
+        if self.done {
+            return Ok(Async::Ready(None));
+        }
+        if let Some(end) = self.end {
+            if self.pos > end {
+                self.done = true;
+                return Ok(Async::Ready(None));
+            }
+        }
+
+        let end = self.chunk_end();
+        if self.pending.is_none() {
+            self.pending = Some(Box::new(reply(self.redis.send(GetRange {
+                key: self.key.clone(),
+                start: self.pos as i64,
+                end: end as i64,
+            }))));
+        }
+        let res = match self.pending.as_mut().unwrap().poll() {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(chunk)) => Ok(chunk),
+            Err(e) => Err(e),
+        };
+        self.pending = None;
+
+        let chunk = match res {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                self.done = true;
+                return Err(e);
+            }
+        };
+        // a short chunk ends the value
+        if (chunk.len() as u64) < end - self.pos + 1 {
+            self.done = true;
+        }
+        self.pos += chunk.len() as u64;
+
+        if chunk.is_empty() {
+            Ok(Async::Ready(None))
+        } else {
+            Ok(Async::Ready(Some(chunk)))
+        }
+    }
+}
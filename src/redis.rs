@@ -1,154 +1,1660 @@
-use std::collections::VecDeque;
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher as _};
 use std::io;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
-use ::actix::actors::resolver::{Connect, Resolver};
+use ::actix::actors::resolver::{Connect, Resolver, ResolverError};
 use ::actix::prelude::*;
 use backoff::backoff::Backoff;
 use backoff::ExponentialBackoff;
-use futures::unsync::oneshot;
-use futures::Future;
+use futures::future::{self, Either};
+use futures::task::{self, Task};
+use futures::unsync::{mpsc, oneshot};
+use futures::{Async, Future, Stream};
 use redis_async::error::Error as RespError;
 use redis_async::resp::{RespCodec, RespValue};
-use tokio_codec::FramedRead;
-use tokio_io::io::WriteHalf;
+use tokio_codec::{FramedRead, FramedWrite};
 use tokio_io::AsyncRead;
 use tokio_tcp::TcpStream;
+use tokio_timer::{Delay, Timeout};
 
+use crate::addr::Address;
 use crate::command;
-use crate::Error;
+#[cfg(feature = "test-util")]
+use crate::fault::{Fault, Faults};
+use crate::pubsub::PubSubEvent;
+use crate::resp::{Frame, FrameCodec};
+use crate::sentinel::{self, Sentinels};
+use crate::slot::{HashError, Hasher};
+use crate::value::ToRedisArgs;
+use crate::{reply, Error};
 
 /// Command for send data to Redis
 #[derive(Debug)]
 pub struct Command(pub RespValue);
 
+impl Command {
+    /// The command of `args`, its name first, e.g.
+    /// `Command::new(("SET", "key", 1))`
+    pub fn new<A: ToRedisArgs>(args: A) -> Command {
+        Command(RespValue::Array(args.to_redis_args()))
+    }
+}
+
 impl Message for Command {
     type Result = Result<RespValue, Error>;
 }
 
+impl command::Command for Command {
+    type Output = RespValue;
+
+    fn into_request(self) -> RespValue {
+        self.0
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        Ok(res)
+    }
+
+    /// Its keys are unknown, in cluster mode it is sent to any node
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
 /// Redis comminucation actor
+///
+/// Commands are pipelined: a command is written without waiting for the reply
+/// of the previous ones, and the commands sent to the actor in the same turn of
+/// the event loop, e.g. with `join_all`, are coalesced into a single write.
 pub struct RedisActor {
     addr: String,
+    connect_timeout: Duration,
+    no_evict: bool,
+    read_only: bool,
+    strict: bool,
+    slow_log: Option<Duration>,
+    redaction: RedactionPolicy,
+    max_reply_bytes: Option<usize>,
+    /// the names the server knows the commands under, by upper case name
+    renames: HashMap<String, String>,
+    breaker: Option<CircuitBreaker>,
+    rate_limiter: Option<RateLimiter>,
+    /// the commands waiting for the rate limit, and whether they are scheduled
+    /// to be sent
+    throttled: VecDeque<(RespValue, oneshot::Sender<Result<Frame, Error>>)>,
+    unthrottle_scheduled: bool,
+    /// the readiness to report to, and whether this actor is counted as ready
+    readiness: Option<(Readiness, bool)>,
+    /// the Sentinels to discover `addr` from, if any
+    sentinels: Option<Sentinels>,
+    #[cfg(feature = "test-util")]
+    faults: Option<Faults>,
     backoff: ExponentialBackoff,
-    cell: Option<actix::io::FramedWrite<WriteHalf<TcpStream>, RespCodec>>,
-    queue: VecDeque<oneshot::Sender<Result<RespValue, Error>>>,
+    cell: Option<mpsc::UnboundedSender<RespValue>>,
+    /// incremented on every restart, so events of a previous connection are
+    /// ignored
+    connection: u64,
+    /// the number of connections established
+    connects: u64,
+    /// the last error failing to connect or dropping the connection
+    last_error: Option<String>,
+    /// dropping these cancels the detached tasks of the connection
+    tasks: Vec<oneshot::Sender<()>>,
+    /// keeps the actor alive while replies are in flight
+    busy: Option<oneshot::Sender<()>>,
+    queue: VecDeque<InFlight>,
+    lease: Option<usize>,
+    next_lease: usize,
+    checkouts: VecDeque<oneshot::Sender<Lease>>,
+    deferred: VecDeque<(RespValue, oneshot::Sender<Result<Frame, Error>>)>,
+    /// number of commands written, and of those completed
+    written: u64,
+    completed: u64,
+    /// `Drain`s waiting for `completed` to reach their target
+    drains: VecDeque<(u64, oneshot::Sender<()>)>,
+    /// the connections blocking commands are sent on, and how many of them
+    /// each has in flight
+    blocking_lane: Vec<(Addr<RedisActor>, Rc<Cell<usize>>)>,
+    /// the time of the server and when it was current, for `ServerNow`
+    server_clock: Option<(SystemTime, Instant)>,
+}
+
+/// Connect to `addr`. Host names are resolved by the `Resolver` actor, IP
+/// literals are connected to directly.
+fn connect(
+    addr: &str,
+    timeout: Duration,
+) -> Box<dyn Future<Item = TcpStream, Error = ResolverError>> {
+    match Address::parse(addr) {
+        Some(Address::Ip(addr)) => Box::new(
+            Timeout::new(TcpStream::connect(&addr), timeout).map_err(|e| {
+                if e.is_elapsed() {
+                    ResolverError::Timeout
+                } else {
+                    match e.into_inner() {
+                        Some(e) => ResolverError::IoError(e),
+                        None => ResolverError::Resolver("timer is gone".into()),
+                    }
+                }
+            }),
+        ),
+        Some(Address::Host(host, port)) => Box::new(
+            Resolver::from_registry()
+                .send(Connect::host_and_port(host, port).timeout(timeout))
+                .then(|res| match res {
+                    Ok(res) => res,
+                    Err(_) => Err(ResolverError::Resolver("resolver is gone".into())),
+                }),
+        ),
+        None => Box::new(futures::future::err(ResolverError::InvalidInput(
+            "invalid redis address",
+        ))),
+    }
+}
+
+/// Fails commands fast while the server is persistently unreachable
+///
+/// The circuit opens after `threshold` consecutive failures, and half-opens
+/// after `cooldown` to let a connection attempt probe the server. A reply
+/// closes it again, another failure reopens it.
+struct CircuitBreaker {
+    threshold: usize,
+    cooldown: Duration,
+    failures: usize,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: usize, cooldown: Duration) -> CircuitBreaker {
+        CircuitBreaker {
+            threshold,
+            cooldown,
+            failures: 0,
+            open_until: None,
+        }
+    }
+
+    /// How long the circuit is still open
+    fn open_for(&self) -> Option<Duration> {
+        let now = Instant::now();
+        match self.open_until {
+            Some(until) if now < until => Some(until - now),
+            _ => None,
+        }
+    }
+
+    fn failure(&mut self) {
+        self.failures += 1;
+        if self.failures >= self.threshold && self.open_for().is_none() {
+            warn!("Opening the circuit for {:?}", self.cooldown);
+            self.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+
+    fn success(&mut self) {
+        if self.open_until.take().is_some() {
+            info!("Closing the circuit");
+        }
+        self.failures = 0;
+    }
+}
+
+/// What a `RedisActor` with a rate limit does with the commands beyond it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitPolicy {
+    /// Hold them until the rate allows them, in order
+    Queue,
+    /// Fail them with `Error::RateLimited`
+    Reject,
+}
+
+/// How the commands are written to the logs, e.g. the slow log, as their keys
+/// and values may hold personal data or secrets such as session ids
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RedactionPolicy {
+    /// The name of the command and the length of its key. The default.
+    KeyLength,
+    /// The name of the command and a hash of its key, telling the records of a
+    /// key apart without revealing it. The hash is not cryptographic: it does
+    /// not hide keys that can be guessed.
+    KeyHash,
+    /// The name of the command and its key, the other arguments, which hold
+    /// the values, being left out. Session ids are logged in clear.
+    RedactValues,
+    /// The whole commands and their replies, e.g. to debug against a test
+    /// server
+    Off,
+}
+
+impl RedactionPolicy {
+    /// The key of a command as logged
+    pub(crate) fn key(self, key: &[u8]) -> String {
+        match self {
+            RedactionPolicy::KeyLength => format!("<{} bytes>", key.len()),
+            RedactionPolicy::KeyHash => {
+                let mut hasher = DefaultHasher::new();
+                key.hash(&mut hasher);
+                format!("#{:016x}", hasher.finish())
+            }
+            RedactionPolicy::RedactValues | RedactionPolicy::Off => {
+                String::from_utf8_lossy(key).into_owned()
+            }
+        }
+    }
+
+    /// The name and the key of `command` as logged. The key is the first
+    /// argument, which is the key of most commands, or `-`.
+    pub(crate) fn name_and_key(self, command: &RespValue) -> (String, String) {
+        match command {
+            RespValue::Array(args) if !args.is_empty() => {
+                let key = args
+                    .get(1)
+                    .map_or_else(|| "-".to_owned(), |key| self.key(&arg_bytes(key)));
+                (
+                    String::from_utf8_lossy(&arg_bytes(&args[0])).into_owned(),
+                    key,
+                )
+            }
+            command => (
+                String::from_utf8_lossy(&arg_bytes(command)).into_owned(),
+                "-".to_owned(),
+            ),
+        }
+    }
+
+    /// `command` as logged: its name, its key, and how many arguments were
+    /// left out
+    pub(crate) fn command(
+        self,
+        command: &RespValue,
+        full: impl FnOnce() -> String,
+    ) -> String {
+        if self == RedactionPolicy::Off {
+            return full();
+        }
+        let (name, key) = self.name_and_key(command);
+        match command {
+            RespValue::Array(args) if args.len() > 2 => {
+                format!("{} {} <{} arguments redacted>", name, key, args.len() - 2)
+            }
+            _ => format!("{} {}", name, key),
+        }
+    }
+}
+
+fn arg_bytes(value: &RespValue) -> Vec<u8> {
+    match value {
+        RespValue::BulkString(s) => s.clone(),
+        RespValue::SimpleString(s) => s.clone().into_bytes(),
+        value => format!("{:?}", value).into_bytes(),
+    }
+}
+
+/// A token bucket pacing the commands to `per_sec`, letting bursts of up to
+/// `burst` through
+#[derive(Clone)]
+struct RateLimiter {
+    per_sec: f64,
+    burst: f64,
+    policy: RateLimitPolicy,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    fn new(per_sec: u32, burst: u32, policy: RateLimitPolicy) -> RateLimiter {
+        RateLimiter {
+            per_sec: f64::from(per_sec),
+            burst: f64::from(burst.max(1)),
+            policy,
+            tokens: f64::from(burst.max(1)),
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Take a token, if one is available
+    fn take(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now - self.refilled;
+        let elapsed = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9;
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.burst);
+        self.refilled = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// How long until the next token is available
+    fn next_token(&self) -> Duration {
+        let secs = (1.0 - self.tokens).max(0.0) / self.per_sec;
+        Duration::from_nanos((secs * 1e9).ceil() as u64)
+    }
+}
+
+/// A command waiting for its reply
+struct InFlight {
+    tx: oneshot::Sender<Result<Frame, Error>>,
+    /// when the command was written, its name and key, if slow commands are
+    /// logged
+    sent: Option<(Instant, String, String)>,
 }
 
 impl RedisActor {
     /// Start new `Supervisor` with `RedisActor`.
     pub fn start<S: Into<String>>(addr: S) -> Addr<RedisActor> {
-        let addr = addr.into();
+        RedisActor::builder(addr).start()
+    }
+
+    /// Start new `Supervisor` with `RedisActor` and wait until the connection to
+    /// the server is established.
+    ///
+    /// Fails with `Error::NotConnected` if the first connection attempt fails.
+    pub fn start_eagerly<S: Into<String>>(
+        addr: S,
+    ) -> impl Future<Item = Addr<RedisActor>, Error = Error> {
+        RedisActor::builder(addr).start_eagerly()
+    }
+
+    /// Start new `Supervisor` with `RedisActor` connected to the master named
+    /// `master_name`, as reported by the first of `sentinels` that knows it.
+    ///
+    /// The master is looked up again on every reconnect. The actor also
+    /// subscribes to `+switch-master` on that Sentinel, and reconnects when the
+    /// master fails over. Commands in flight then fail with
+    /// `Error::Disconnected`.
+    pub fn start_sentinel<S: Into<String>>(
+        sentinels: Vec<String>,
+        master_name: S,
+    ) -> Addr<RedisActor> {
+        RedisActor::sentinel_builder(sentinels, master_name).start()
+    }
+
+    /// Configure a `RedisActor` discovering its master through `sentinels`
+    /// before starting it. See `start_sentinel`.
+    pub fn sentinel_builder<S: Into<String>>(
+        sentinels: Vec<String>,
+        master_name: S,
+    ) -> RedisActorBuilder {
+        let master_name = master_name.into();
+        RedisActorBuilder {
+            sentinel: Some((sentinels, master_name.clone())),
+            ..RedisActor::builder(master_name)
+        }
+    }
+
+    /// Configure a `RedisActor` before starting it.
+    pub fn builder<S: Into<String>>(addr: S) -> RedisActorBuilder {
+        RedisActorBuilder {
+            addr: addr.into(),
+            sentinel: None,
+            connect_timeout: Duration::from_secs(1),
+            no_evict: false,
+            read_only: false,
+            strict: false,
+            slow_log: None,
+            redaction: RedactionPolicy::KeyLength,
+            max_reply_bytes: None,
+            renames: HashMap::new(),
+            circuit_breaker: None,
+            rate_limit: None,
+            mailbox_capacity: 16,
+            readiness: None,
+            blocking_lane: 0,
+            #[cfg(feature = "test-util")]
+            faults: None,
+        }
+    }
+}
+
+/// Builder for `RedisActor`
+pub struct RedisActorBuilder {
+    addr: String,
+    /// the Sentinels and the name of the master
+    sentinel: Option<(Vec<String>, String)>,
+    connect_timeout: Duration,
+    no_evict: bool,
+    read_only: bool,
+    strict: bool,
+    slow_log: Option<Duration>,
+    redaction: RedactionPolicy,
+    max_reply_bytes: Option<usize>,
+    renames: HashMap<String, String>,
+    circuit_breaker: Option<(usize, Duration)>,
+    rate_limit: Option<(u32, u32, RateLimitPolicy)>,
+    mailbox_capacity: usize,
+    readiness: Option<Readiness>,
+    blocking_lane: usize,
+    #[cfg(feature = "test-util")]
+    faults: Option<Faults>,
+}
+
+impl RedisActorBuilder {
+    /// Set the timeout of a connection attempt. Defaults to 1 second.
+    ///
+    /// Commands sent while connecting wait at most this long before failing
+    /// with `Error::NotConnected`.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Send `CLIENT NO-EVICT ON` after every (re)connect, so the server does not
+    /// evict this connection under `maxmemory-clients` pressure.
+    ///
+    /// Servers that do not support the command are ignored.
+    pub fn no_evict(mut self, no_evict: bool) -> Self {
+        self.no_evict = no_evict;
+        self
+    }
+
+    /// Send `READONLY` after every (re)connect, so a cluster replica serves
+    /// the reads of the slots of its master instead of redirecting them.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Refuse to send requests other than arrays of bulk strings, which is the
+    /// only form of command the server is guaranteed to parse as intended.
+    ///
+    /// These requests fail with `Error::Redis` without being sent.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Log the commands whose reply takes longer than `threshold` to arrive,
+    /// with their name, key and the node they were sent to.
+    ///
+    /// The records are logged as warnings with the target
+    /// `actix_redis::slow_log`. Keys are redacted as set with `redaction`.
+    pub fn slow_log(mut self, threshold: Duration) -> Self {
+        self.slow_log = Some(threshold);
+        self
+    }
+
+    /// Set how the keys and values of commands are redacted in the logs.
+    /// Defaults to `RedactionPolicy::KeyLength`, logging neither keys, e.g.
+    /// session ids, nor values.
+    pub fn redaction(mut self, policy: RedactionPolicy) -> Self {
+        self.redaction = policy;
+        self
+    }
+
+    /// Drop the connection when a reply larger than `max` bytes arrives, before
+    /// it is buffered entirely. The pending command fails with
+    /// `Error::ReplyTooLarge`, the following ones with `Error::Disconnected`.
+    ///
+    /// Unlimited by default.
+    pub fn max_reply_bytes(mut self, max: usize) -> Self {
+        self.max_reply_bytes = Some(max);
+        self
+    }
+
+    /// Send the commands named by the keys of `renames` under the names they
+    /// map to, for servers where commands were renamed with `rename-command`,
+    /// e.g. `CONFIG` to a secret name.
+    ///
+    /// Names are matched case-insensitively. Only the name of a command is
+    /// renamed, not its subcommand, as `rename-command` does.
+    pub fn command_rename_map(mut self, renames: HashMap<&str, &str>) -> Self {
+        self.renames = renames
+            .into_iter()
+            .map(|(name, alias)| (name.to_ascii_uppercase(), alias.to_owned()))
+            .collect();
+        self
+    }
+
+    /// Fail commands with `Error::CircuitOpen` for `cooldown` after `threshold`
+    /// consecutive failures, without attempting to connect meanwhile.
+    ///
+    /// Failed connection attempts and commands failing with
+    /// `Error::NotConnected` or `Error::Disconnected` count as failures, any
+    /// reply as a success. After `cooldown` the next connection attempt probes
+    /// the server: the circuit closes on the first reply, and opens again on the
+    /// next failure.
+    pub fn circuit_breaker(mut self, threshold: usize, cooldown: Duration) -> Self {
+        self.circuit_breaker = Some((threshold, cooldown));
+        self
+    }
 
-        let mut backoff = ExponentialBackoff::default();
-        backoff.max_elapsed_time = None;
+    /// Send at most `per_sec` commands per second, letting bursts of up to
+    /// `burst` through, e.g. to keep a traffic spike from overwhelming a small
+    /// server. The commands beyond the rate are held until it allows them, or
+    /// fail with `Error::RateLimited`, depending on `policy`.
+    ///
+    /// The commands of a `Lease` are not counted.
+    ///
+    /// # Panics
+    /// Panics if `per_sec` is 0
+    pub fn rate_limit(
+        mut self,
+        per_sec: u32,
+        burst: u32,
+        policy: RateLimitPolicy,
+    ) -> Self {
+        assert!(per_sec > 0, "rate limit must not be 0");
+        self.rate_limit = Some((per_sec, burst, policy));
+        self
+    }
+
+    /// Set the number of messages the mailbox of the actor holds before
+    /// `Addr::try_send` fails with `SendError::Full` and `Addr::send` waits for
+    /// room. Defaults to 16, the actix default.
+    ///
+    /// A larger mailbox absorbs bursts of commands, but holds them in memory
+    /// before they are written, and senders notice an overloaded connection
+    /// later.
+    pub fn mailbox_capacity(mut self, capacity: usize) -> Self {
+        self.mailbox_capacity = capacity;
+        self
+    }
 
-        Supervisor::start(|_| RedisActor {
+    /// Report to `readiness` whether the actor is ready to take more commands,
+    /// e.g. to apply backpressure upstream.
+    ///
+    /// The actor is not ready while it is not connected, which includes while
+    /// its circuit is open, and while `Readiness::set_max_in_flight` commands
+    /// wait for their reply. Commands sent meanwhile are still accepted.
+    pub fn readiness(mut self, readiness: Readiness) -> Self {
+        self.readiness = Some(readiness);
+        self
+    }
+
+    /// Send blocking commands, e.g. `BLPOP`, on `size` connections of their own,
+    /// so the commands sent after them are not held until they are answered.
+    ///
+    /// Each blocking command goes to the connection of the lane with the fewest
+    /// of them in flight. More than `size` of them at once still wait for each
+    /// other. The connections of the lane share the configuration of the actor,
+    /// but do not report to its `readiness`.
+    pub fn blocking_lane(mut self, size: usize) -> Self {
+        self.blocking_lane = size;
+        self
+    }
+
+    /// Inject `faults` into the commands, to test how an application copes
+    /// with a slow or failing server. Requires the `test-util` feature.
+    #[cfg(feature = "test-util")]
+    pub fn faults(mut self, faults: Faults) -> Self {
+        self.faults = Some(faults);
+        self
+    }
+
+    /// Start new `Supervisor` with `RedisActor`.
+    pub fn start(self) -> Addr<RedisActor> {
+        let RedisActorBuilder {
             addr,
-            cell: None,
-            backoff,
-            queue: VecDeque::new(),
+            sentinel,
+            connect_timeout,
+            no_evict,
+            read_only,
+            strict,
+            slow_log,
+            redaction,
+            max_reply_bytes,
+            renames,
+            circuit_breaker,
+            rate_limit,
+            mailbox_capacity,
+            readiness,
+            blocking_lane,
+            #[cfg(feature = "test-util")]
+            faults,
+        } = self;
+
+        let blocking_lane = (0..blocking_lane)
+            .map(|_| {
+                let lane = RedisActorBuilder {
+                    addr: addr.clone(),
+                    sentinel: sentinel.clone(),
+                    connect_timeout,
+                    no_evict,
+                    read_only,
+                    strict,
+                    slow_log,
+                    redaction,
+                    max_reply_bytes,
+                    renames: renames.clone(),
+                    circuit_breaker,
+                    rate_limit,
+                    mailbox_capacity,
+                    readiness: None,
+                    blocking_lane: 0,
+                    #[cfg(feature = "test-util")]
+                    faults: None,
+                };
+                (lane.start(), Rc::new(Cell::new(0)))
+            })
+            .collect();
+
+        let backoff = ExponentialBackoff {
+            max_elapsed_time: None,
+            ..Default::default()
+        };
+
+        let sentinels = sentinel
+            .map(|(sentinels, master_name)| Sentinels::new(sentinels, master_name));
+
+        // not ready until connected
+        if let Some(ref readiness) = readiness {
+            readiness.set_ready(false);
+        }
+
+        Supervisor::start(move |ctx: &mut Context<RedisActor>| {
+            ctx.set_mailbox_capacity(mailbox_capacity);
+            RedisActor {
+                addr,
+                connect_timeout,
+                no_evict,
+                read_only,
+                strict,
+                slow_log,
+                redaction,
+                max_reply_bytes,
+                renames,
+                breaker: circuit_breaker.map(|(threshold, cooldown)| {
+                    CircuitBreaker::new(threshold, cooldown)
+                }),
+                rate_limiter: rate_limit.map(|(per_sec, burst, policy)| {
+                    RateLimiter::new(per_sec, burst, policy)
+                }),
+                throttled: VecDeque::new(),
+                unthrottle_scheduled: false,
+                readiness: readiness.map(|readiness| (readiness, false)),
+                sentinels,
+                #[cfg(feature = "test-util")]
+                faults,
+                cell: None,
+                connection: 0,
+                connects: 0,
+                last_error: None,
+                tasks: Vec::new(),
+                busy: None,
+                backoff,
+                queue: VecDeque::new(),
+                lease: None,
+                next_lease: 0,
+                checkouts: VecDeque::new(),
+                deferred: VecDeque::new(),
+                written: 0,
+                completed: 0,
+                drains: VecDeque::new(),
+                blocking_lane,
+                server_clock: None,
+            }
         })
     }
+
+    /// Start new `Supervisor` with `RedisActor` and wait until the connection to
+    /// the server is established.
+    ///
+    /// Fails with `Error::NotConnected` if the first connection attempt fails.
+    pub fn start_eagerly(self) -> impl Future<Item = Addr<RedisActor>, Error = Error> {
+        let addr = self.start();
+
+        // messages are not processed until the connection attempt completes
+        reply(addr.send(command::Ping(None))).map(move |_| addr)
+    }
 }
 
 impl Actor for RedisActor {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Context<Self>) {
-        Resolver::from_registry()
-            .send(Connect::host(self.addr.as_str()))
+        if let Some(open) = self.breaker.as_ref().and_then(CircuitBreaker::open_for) {
+            // retry once the circuit half-opens
+            self.reconnect_later(open, ctx);
+            return;
+        }
+
+        let master = match self.sentinels {
+            Some(ref sentinels) => Either::A(
+                sentinels
+                    .master()
+                    .map(|(master, sentinel)| (master, Some(sentinel))),
+            ),
+            None => Either::B(future::ok((self.addr.clone(), None))),
+        };
+        let timeout = self.connect_timeout;
+
+        master
+            .and_then(move |(addr, sentinel)| {
+                connect(&addr, timeout).map(|stream| (addr, sentinel, stream))
+            })
             .into_actor(self)
-            .map(|res, act, ctx| match res {
-                Ok(stream) => {
-                    info!("Connected to redis server: {}", act.addr);
+            .map(|(addr, sentinel, stream), act, ctx| {
+                act.addr = addr;
+                act.connects += 1;
+                info!("Connected to redis server: {}", act.addr);
 
-                    let (r, w) = stream.split();
+                let (r, w) = stream.split();
+                let connection = act.connection;
 
-                    // configure write side of the connection
-                    let framed = actix::io::FramedWrite::new(w, RespCodec, ctx);
-                    act.cell = Some(framed);
+                // write side of the connection. `forward` only flushes once the
+                // queue is empty, so the commands queued together share a write
+                let (tx, rx) = mpsc::unbounded();
+                act.cell = Some(tx);
+                act.update_readiness();
+                let weak = ctx.address().downgrade();
+                act.detach(
+                    // an unbounded receiver never fails
+                    rx.map_err(|()| -> io::Error { unreachable!() })
+                        .forward(FramedWrite::new(w, RespCodec))
+                        .then(move |res| {
+                            if let (Err(err), Some(addr)) = (res, weak.upgrade()) {
+                                addr.do_send(Io {
+                                    connection,
+                                    event: IoEvent::WriteError(err),
+                                });
+                            }
+                            Ok(())
+                        }),
+                );
 
-                    // read side of the connection
-                    ctx.add_stream(FramedRead::new(r, RespCodec));
+                // read side of the connection, up to the first error
+                let mut codec = FrameCodec::default();
+                if let Some(max) = act.max_reply_bytes {
+                    codec = codec.max_reply_bytes(max);
+                }
+                let mut failed = false;
+                // `matches!` is not available on the minimum supported Rust version
+                #[allow(clippy::match_like_matches_macro)]
+                let frames = FramedRead::new(r, codec)
+                    .then(|res| {
+                        Ok(match res {
+                            Ok(frame) => IoEvent::Frame(frame),
+                            Err(err) => IoEvent::ReadError(err),
+                        })
+                    })
+                    .take_while(move |event| {
+                        let more = !failed;
+                        failed = match *event {
+                            IoEvent::ReadError(_) => true,
+                            _ => false,
+                        };
+                        Ok(more)
+                    })
+                    .chain(futures::stream::once(Ok(IoEvent::Closed)));
+                act.forward(frames, ctx);
 
-                    act.backoff.reset();
+                act.backoff.reset();
+
+                if act.no_evict {
+                    act.set_no_evict(ctx);
                 }
-                Err(err) => {
-                    error!("Can not connect to redis server: {}", err);
-                    // re-connect with backoff time.
-                    // we stop current context, supervisor will restart it.
-                    if let Some(timeout) = act.backoff.next_backoff() {
-                        ctx.run_later(timeout, |_, ctx| ctx.stop());
-                    }
+                if act.read_only {
+                    act.set_read_only(ctx);
+                }
+
+                // reconnect to the new master on failover
+                if let Some(sentinel) = sentinel {
+                    let events = sentinel::switch_master(&sentinel)
+                        .map(IoEvent::Sentinel)
+                        .map_err(|err| warn!("Sentinel subscription failed: {}", err));
+                    act.forward(events, ctx);
                 }
             })
             .map_err(|err, act, ctx| {
                 error!("Can not connect to redis server: {}", err);
+                act.last_error = Some(err.to_string());
+                act.failure();
                 // re-connect with backoff time.
                 // we stop current context, supervisor will restart it.
                 if let Some(timeout) = act.backoff.next_backoff() {
-                    ctx.run_later(timeout, |_, ctx| ctx.stop());
+                    act.reconnect_later(timeout, ctx);
                 }
             })
             .wait(ctx);
     }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        // close the connection and cancel the reconnect timer
+        self.disconnect();
+    }
 }
 
 impl Supervised for RedisActor {
-    fn restarting(&mut self, _: &mut Self::Context) {
-        self.cell.take();
-        for tx in self.queue.drain(..) {
+    fn restarting(&mut self, ctx: &mut Self::Context) {
+        self.disconnect();
+        for InFlight { tx, .. } in self.queue.split_off(0) {
             let _ = tx.send(Err(Error::Disconnected));
+            self.failure();
+            self.complete();
+        }
+        for (_, tx) in self.deferred.drain(..).chain(self.throttled.drain(..)) {
+            let _ = tx.send(Err(Error::Disconnected));
+        }
+        // the timer is cancelled with the context
+        self.unthrottle_scheduled = false;
+        // nothing sent before is pending anymore
+        for (_, tx) in self.drains.drain(..) {
+            let _ = tx.send(());
+        }
+        // the state of the leased connection is lost
+        if self.lease.take().is_some() {
+            self.next_checkout(ctx);
         }
     }
 }
 
-impl actix::io::WriteHandler<io::Error> for RedisActor {
-    fn error(&mut self, err: io::Error, _: &mut Self::Context) -> Running {
-        warn!("Redis connection dropped: {} error: {}", self.addr, err);
-        Running::Stop
-    }
+/// An event of the connection, reported by its detached tasks
+struct Io {
+    connection: u64,
+    event: IoEvent,
 }
 
-impl StreamHandler<RespValue, RespError> for RedisActor {
-    fn error(&mut self, err: RespError, _: &mut Self::Context) -> Running {
-        if let Some(tx) = self.queue.pop_front() {
-            let _ = tx.send(Err(err.into()));
+enum IoEvent {
+    Frame(Frame),
+    ReadError(Error),
+    Closed,
+    WriteError(io::Error),
+    Sentinel(PubSubEvent),
+}
+
+impl Message for Io {
+    type Result = ();
+}
+
+impl Handler<Io> for RedisActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: Io, ctx: &mut Self::Context) {
+        if msg.connection != self.connection {
+            return;
+        }
+        match msg.event {
+            IoEvent::Frame(frame) => self.frame(frame),
+            IoEvent::ReadError(err) => {
+                self.read_error(err);
+                ctx.stop();
+            }
+            IoEvent::Closed => ctx.stop(),
+            IoEvent::WriteError(err) => {
+                warn!("Redis connection dropped: {} error: {}", self.addr, err);
+                self.last_error = Some(err.to_string());
+                ctx.stop();
+            }
+            IoEvent::Sentinel(event) => {
+                let master = self.sentinels.as_ref().and_then(|sentinels| {
+                    sentinel::switched_to(&event, sentinels.master_name())
+                });
+                if let Some(master) = master {
+                    info!("Redis master switched from {} to {}", self.addr, master);
+                    // the supervisor restarts the actor, which asks the sentinels
+                    // again
+                    ctx.stop();
+                }
+            }
         }
-        Running::Stop
     }
+}
 
-    fn handle(&mut self, msg: RespValue, _: &mut Self::Context) {
-        if let Some(tx) = self.queue.pop_front() {
-            let _ = tx.send(Ok(msg));
+/// Stop the actor, so the supervisor reconnects
+struct Reconnect {
+    connection: u64,
+}
+
+impl Message for Reconnect {
+    type Result = ();
+}
+
+impl Handler<Reconnect> for RedisActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: Reconnect, ctx: &mut Self::Context) {
+        if msg.connection == self.connection {
+            ctx.stop();
         }
     }
 }
 
 impl RedisActor {
-    fn send(&mut self, command: RespValue) -> ResponseFuture<RespValue, Error> {
+    /// Run `task` until the connection closes. Unlike the futures of the
+    /// context, it does not keep the actor alive once every address is
+    /// dropped.
+    fn detach<F>(&mut self, task: F)
+    where
+        F: Future<Item = (), Error = ()> + 'static,
+    {
+        let (cancel, cancelled) = oneshot::channel();
+        self.tasks.push(cancel);
+        Arbiter::spawn(task.select2(cancelled).then(|_| Ok(())));
+    }
+
+    /// Deliver the `events` of the current connection to the actor
+    fn forward<S>(&mut self, events: S, ctx: &mut Context<Self>)
+    where
+        S: Stream<Item = IoEvent, Error = ()> + 'static,
+    {
+        let connection = self.connection;
+        let addr = ctx.address().downgrade();
+        self.detach(events.for_each(move |event| {
+            // stop reading once the actor is gone
+            let addr = addr.upgrade().ok_or(())?;
+            addr.do_send(Io { connection, event });
+            Ok(())
+        }));
+    }
+
+    fn reconnect_later(&mut self, delay: Duration, ctx: &mut Context<Self>) {
+        let connection = self.connection;
+        let addr = ctx.address().downgrade();
+        self.detach(Delay::new(Instant::now() + delay).then(move |_| {
+            if let Some(addr) = addr.upgrade() {
+                addr.do_send(Reconnect { connection });
+            }
+            Ok(())
+        }));
+    }
+
+    /// Keep the actor alive until the replies in flight arrive, even if every
+    /// address is dropped meanwhile
+    fn keep_alive(&mut self, ctx: &mut Context<Self>) {
+        if !self.queue.is_empty() && self.busy.is_none() {
+            let (busy, idle) = oneshot::channel();
+            self.busy = Some(busy);
+            ctx.spawn(actix::fut::wrap_future(idle.then(|_| Ok(()))));
+        }
+    }
+
+    /// Close the connection and cancel its tasks
+    fn disconnect(&mut self) {
+        self.cell.take();
+        self.tasks.clear();
+        self.busy.take();
+        self.connection += 1;
+        self.update_readiness();
+    }
+
+    /// Report to the `Readiness`, if any, whether the actor is connected and
+    /// below the limit of commands in flight
+    fn update_readiness(&mut self) {
+        if let Some((ref readiness, ref mut counted)) = self.readiness {
+            let ready =
+                self.cell.is_some() && self.queue.len() < readiness.max_in_flight();
+            if ready != *counted {
+                *counted = ready;
+                readiness.set_ready(ready);
+            }
+        }
+    }
+
+    fn read_error(&mut self, err: Error) {
+        match err {
+            Error::Parse(ref msg) => warn!(
+                "Malformed reply from redis server: {} error: {}",
+                self.addr, msg
+            ),
+            Error::ReplyTooLarge => {
+                warn!("Too large reply from redis server: {}", self.addr)
+            }
+            ref err => warn!("Redis connection dropped: {} error: {}", self.addr, err),
+        }
+        self.last_error = Some(err.to_string());
+        if let Some(InFlight { tx, .. }) = self.queue.pop_front() {
+            let _ = tx.send(Err(err));
+            self.complete();
+        }
+    }
+
+    fn frame(&mut self, msg: Frame) {
+        if let Some(ref mut breaker) = self.breaker {
+            breaker.success();
+        }
+        if let Some(InFlight { tx, sent }) = self.queue.pop_front() {
+            if let (Some(threshold), Some((at, name, key))) = (self.slow_log, sent) {
+                let elapsed = at.elapsed();
+                if elapsed > threshold {
+                    log_slow(&name, &key, &self.addr, elapsed);
+                }
+            }
+            let _ = tx.send(Ok(msg));
+            self.complete();
+        }
+    }
+
+    // written before anything else on the new connection
+    fn set_no_evict(&mut self, ctx: &mut Context<Self>) {
+        use crate::command::Command as _;
+
+        let addr = self.addr.clone();
+        let res = self
+            .write(command::ClientNoEvict(true).into_request())
+            .and_then(|res| {
+                command::ClientNoEvict::from_frame(res).map_err(Error::Redis)
+            })
+            .then(move |res| {
+                match res {
+                    Ok(Ok(())) => debug!("CLIENT NO-EVICT enabled: {}", addr),
+                    Ok(Err(e)) => {
+                        info!("CLIENT NO-EVICT is not supported by {}: {}", addr, e)
+                    }
+                    Err(e) => warn!("CLIENT NO-EVICT failed: {} error: {}", addr, e),
+                }
+                Ok(())
+            });
+        ctx.spawn(actix::fut::wrap_future(res));
+        self.keep_alive(ctx);
+    }
+
+    // written before anything else on the new connection
+    fn set_read_only(&mut self, ctx: &mut Context<Self>) {
+        use crate::command::Command as _;
+
+        let addr = self.addr.clone();
+        let res = self
+            .write(command::ReadOnly.into_request())
+            .and_then(|res| command::ReadOnly::from_frame(res).map_err(Error::Redis))
+            .then(move |res| {
+                match res {
+                    Ok(()) => debug!("READONLY enabled: {}", addr),
+                    Err(e) => warn!("READONLY failed: {} error: {}", addr, e),
+                }
+                Ok(())
+            });
+        ctx.spawn(actix::fut::wrap_future(res));
+        self.keep_alive(ctx);
+    }
+
+    /// Send the blocking command on the connection of the lane with the fewest
+    /// blocking commands in flight
+    fn send_blocking(&mut self, command: RespValue) -> ResponseFuture<Frame, Error> {
+        let (lane, in_flight) = self
+            .blocking_lane
+            .iter()
+            .min_by_key(|(_, in_flight)| in_flight.get())
+            .expect("blocking lane");
+        let in_flight = in_flight.clone();
+        in_flight.set(in_flight.get() + 1);
+        Box::new(
+            reply(lane.send(RespValueWrapper(command))).then(move |res| {
+                in_flight.set(in_flight.get() - 1);
+                res
+            }),
+        )
+    }
+
+    /// Send the command, unless a fault is injected instead
+    #[cfg(feature = "test-util")]
+    fn send(
+        &mut self,
+        command: RespValue,
+        ctx: &mut Context<Self>,
+    ) -> ResponseFuture<Frame, Error> {
+        match self.faults.as_ref().and_then(Faults::pick) {
+            Some(Fault::Fail) => Box::new(futures::future::err(Error::Disconnected)),
+            Some(Fault::Delay(delay)) => {
+                let res = self.send_now(command, ctx);
+                Box::new(
+                    tokio_timer::Delay::new(Instant::now() + delay).then(move |_| res),
+                )
+            }
+            None => self.send_now(command, ctx),
+        }
+    }
+
+    #[cfg(not(feature = "test-util"))]
+    fn send(
+        &mut self,
+        command: RespValue,
+        ctx: &mut Context<Self>,
+    ) -> ResponseFuture<Frame, Error> {
+        self.send_now(command, ctx)
+    }
+
+    /// Send the command, or hold it until the rate limit allows it
+    fn send_now(
+        &mut self,
+        command: RespValue,
+        ctx: &mut Context<Self>,
+    ) -> ResponseFuture<Frame, Error> {
         let (tx, rx) = oneshot::channel();
-        if let Some(ref mut cell) = self.cell {
-            self.queue.push_back(tx);
-            cell.write(command);
+        let res = Box::new(rx.map_err(|_| Error::Disconnected).and_then(|res| res));
+        let limited = match self.rate_limiter {
+            // the commands held before go first
+            Some(ref mut limiter) => {
+                if !self.throttled.is_empty() || !limiter.take() {
+                    Some(limiter.policy)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+        match limited {
+            None => self.dispatch(command, tx),
+            Some(RateLimitPolicy::Reject) => {
+                let _ = tx.send(Err(Error::RateLimited));
+            }
+            Some(RateLimitPolicy::Queue) => {
+                self.throttled.push_back((command, tx));
+                self.unthrottle_later(ctx);
+            }
+        }
+        res
+    }
+
+    /// Send the commands held by the rate limit as it allows them
+    fn unthrottle(&mut self, ctx: &mut Context<Self>) {
+        self.unthrottle_scheduled = false;
+        while !self.throttled.is_empty() {
+            if let Some(ref mut limiter) = self.rate_limiter {
+                if !limiter.take() {
+                    break;
+                }
+            }
+            if let Some((command, tx)) = self.throttled.pop_front() {
+                self.dispatch(command, tx);
+            }
+        }
+        self.keep_alive(ctx);
+        self.unthrottle_later(ctx);
+    }
+
+    fn unthrottle_later(&mut self, ctx: &mut Context<Self>) {
+        if self.unthrottle_scheduled || self.throttled.is_empty() {
+            return;
+        }
+        if let Some(ref limiter) = self.rate_limiter {
+            self.unthrottle_scheduled = true;
+            ctx.run_later(limiter.next_token(), |act, ctx| act.unthrottle(ctx));
+        }
+    }
+
+    /// Write the command, or defer it while the connection is leased
+    fn dispatch(
+        &mut self,
+        command: RespValue,
+        tx: oneshot::Sender<Result<Frame, Error>>,
+    ) {
+        if self.lease.is_none() {
+            self.write_with(command, tx);
         } else {
-            let _ = tx.send(Err(Error::NotConnected));
+            self.deferred.push_back((command, tx));
         }
+    }
 
+    fn write(&mut self, command: RespValue) -> ResponseFuture<Frame, Error> {
+        let (tx, rx) = oneshot::channel();
+        self.write_with(command, tx);
         Box::new(rx.map_err(|_| Error::Disconnected).and_then(|res| res))
     }
+
+    fn write_with(
+        &mut self,
+        mut command: RespValue,
+        tx: oneshot::Sender<Result<Frame, Error>>,
+    ) {
+        self.written += 1;
+        if self.strict && !is_command(&command) {
+            let err = RespError::RESP("not a command".into(), Some(command));
+            let _ = tx.send(Err(Error::Redis(err)));
+            self.complete();
+        } else if let Some(ref mut cell) = self.cell {
+            let redaction = self.redaction;
+            let sent = self.slow_log.map(|_| {
+                let (name, key) = redaction.name_and_key(&command);
+                (Instant::now(), name, key)
+            });
+            self.queue.push_back(InFlight { tx, sent });
+            rename(&self.renames, &mut command);
+            // the writer task is gone only after the connection dropped, which
+            // fails the queue
+            let _ = cell.unbounded_send(command);
+            self.update_readiness();
+        } else if self.circuit_open() {
+            let _ = tx.send(Err(Error::CircuitOpen));
+            self.complete();
+        } else {
+            let _ = tx.send(Err(Error::NotConnected));
+            self.failure();
+            self.complete();
+        }
+    }
+
+    /// Count a written command as completed, and resolve the `Drain`s waiting
+    /// for it
+    fn complete(&mut self) {
+        self.completed += 1;
+        if self.queue.is_empty() {
+            self.busy.take();
+        }
+        self.update_readiness();
+        while let Some(&(target, _)) = self.drains.front() {
+            if target > self.completed {
+                break;
+            }
+            if let Some((_, tx)) = self.drains.pop_front() {
+                let _ = tx.send(());
+            }
+        }
+    }
+
+    fn circuit_open(&self) -> bool {
+        self.breaker
+            .as_ref()
+            .and_then(CircuitBreaker::open_for)
+            .is_some()
+    }
+
+    fn failure(&mut self) {
+        if let Some(ref mut breaker) = self.breaker {
+            breaker.failure();
+        }
+    }
+
+    /// Lease the connection to the next waiting `Checkout`, if any
+    fn next_checkout(&mut self, ctx: &mut Context<Self>) {
+        while let Some(tx) = self.checkouts.pop_front() {
+            let id = self.next_lease;
+            self.next_lease = self.next_lease.wrapping_add(1);
+            self.lease = Some(id);
+            let lease = Lease {
+                id,
+                addr: ctx.address(),
+            };
+            // a dropped lease is released through the mailbox
+            if tx.send(lease).is_ok() {
+                return;
+            }
+        }
+    }
+}
+
+fn log_slow(name: &str, key: &str, node: &str, elapsed: Duration) {
+    warn!(
+        target: "actix_redis::slow_log",
+        "Slow command: {} key: {} node: {} duration: {:?}",
+        name,
+        key,
+        node,
+        elapsed
+    );
+}
+
+/// Replace the name of `command` with the name the server knows it under, if
+/// it was renamed
+fn rename(renames: &HashMap<String, String>, command: &mut RespValue) {
+    if renames.is_empty() {
+        return;
+    }
+    if let RespValue::Array(ref mut args) = command {
+        if let Some(RespValue::BulkString(ref mut name)) = args.first_mut() {
+            let upper = String::from_utf8_lossy(name).to_ascii_uppercase();
+            if let Some(alias) = renames.get(&upper) {
+                *name = alias.as_bytes().to_vec();
+            }
+        }
+    }
+}
+
+impl Drop for RedisActor {
+    fn drop(&mut self) {
+        // a stopped actor does not hold back the others
+        if let Some((ref readiness, false)) = self.readiness {
+            readiness.set_ready(true);
+        }
+    }
+}
+
+/// Whether the `RedisActor`s started with it are ready to take more commands,
+/// see `RedisActorBuilder::readiness`
+///
+/// It is ready once every actor is.
+#[derive(Clone)]
+pub struct Readiness(Arc<ReadinessInner>);
+
+struct ReadinessInner {
+    max_in_flight: AtomicUsize,
+    /// the number of actors not ready, and the tasks to notify once none is
+    state: Mutex<(usize, Vec<Task>)>,
+}
+
+impl Default for Readiness {
+    fn default() -> Readiness {
+        Readiness::new()
+    }
+}
+
+impl Readiness {
+    /// Create a readiness without a limit of commands in flight
+    // `usize::MAX` is not available on the minimum supported Rust version
+    #[allow(clippy::legacy_numeric_constants)]
+    pub fn new() -> Readiness {
+        Readiness(Arc::new(ReadinessInner {
+            max_in_flight: AtomicUsize::new(usize::max_value()),
+            state: Mutex::new((0, Vec::new())),
+        }))
+    }
+
+    /// Consider an actor not ready while `max` commands sent to it wait for
+    /// their reply. Applies from the next command sent or reply received.
+    pub fn set_max_in_flight(&self, max: usize) {
+        self.0.max_in_flight.store(max, Ordering::Relaxed);
+    }
+
+    /// `Async::Ready` if every actor is ready. Otherwise the current task is
+    /// notified once they are.
+    ///
+    /// Panics if called outside of a task.
+    pub fn poll_ready(&self) -> Async<()> {
+        let mut state = self.0.state.lock().unwrap();
+        if state.0 == 0 {
+            return Async::Ready(());
+        }
+        if !state.1.iter().any(Task::will_notify_current) {
+            state.1.push(task::current());
+        }
+        Async::NotReady
+    }
+
+    fn max_in_flight(&self) -> usize {
+        self.0.max_in_flight.load(Ordering::Relaxed)
+    }
+
+    fn set_ready(&self, ready: bool) {
+        let mut state = self.0.state.lock().unwrap();
+        if !ready {
+            state.0 += 1;
+            return;
+        }
+        state.0 -= 1;
+        if state.0 == 0 {
+            for task in state.1.drain(..) {
+                task.notify();
+            }
+        }
+    }
+}
+
+/// Wait until the commands sent to a `RedisActor` before this message have
+/// completed, successfully or not, e.g. to stop the system only once they
+/// are.
+///
+/// Commands deferred by a `Lease` or held by the rate limit are waited for too,
+/// so this does not resolve before the lease is released.
+pub struct Drain;
+
+impl Message for Drain {
+    type Result = Result<(), Error>;
+}
+
+impl Handler<Drain> for RedisActor {
+    type Result = ResponseFuture<(), Error>;
+
+    fn handle(&mut self, _: Drain, _: &mut Self::Context) -> Self::Result {
+        let target = self.written + (self.deferred.len() + self.throttled.len()) as u64;
+        if self.completed >= target {
+            return Box::new(futures::future::ok(()));
+        }
+        let (tx, rx) = oneshot::channel();
+        self.drains.push_back((target, tx));
+        Box::new(rx.map_err(|_| Error::Disconnected))
+    }
+}
+
+/// Whether a `RedisActor` is connected
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ConnectionState {
+    Connected,
+    /// Connecting, or waiting to reconnect
+    Disconnected,
+    /// Disconnected, and failing the commands fast, see
+    /// `RedisActorBuilder::circuit_breaker`
+    CircuitOpen,
+}
+
+/// The health of a `RedisActor`, see `Health`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HealthReport {
+    /// the address of the server
+    pub addr: String,
+    pub state: ConnectionState,
+    /// the last error failing to connect or dropping the connection
+    pub last_error: Option<String>,
+    /// the number of times the connection was established again
+    pub reconnections: u64,
+    /// the number of commands written and waiting for their reply
+    pub in_flight: usize,
+}
+
+/// Report the health of a `RedisActor`, e.g. to expose it on a `/healthz`
+/// endpoint. The report is serializable with the `serde` feature.
+pub struct Health;
+
+impl Message for Health {
+    type Result = HealthReport;
+}
+
+impl Handler<Health> for RedisActor {
+    type Result = MessageResult<Health>;
+
+    fn handle(&mut self, _: Health, _: &mut Self::Context) -> Self::Result {
+        let state = if self.cell.is_some() {
+            ConnectionState::Connected
+        } else if self.circuit_open() {
+            ConnectionState::CircuitOpen
+        } else {
+            ConnectionState::Disconnected
+        };
+        MessageResult(HealthReport {
+            addr: self.addr.clone(),
+            state,
+            last_error: self.last_error.clone(),
+            reconnections: self.connects.saturating_sub(1),
+            in_flight: self.queue.len(),
+        })
+    }
+}
+
+/// How long the time of the server fetched by `ServerNow` is used, advanced by
+/// the local monotonic clock, before it is fetched again
+const SERVER_CLOCK_REFRESH: Duration = Duration::from_secs(10);
+
+/// The current time of the server of a `RedisActor`, e.g. for rate limits
+/// shared by several instances whose clocks disagree.
+///
+/// The time is fetched with `TIME`, assumed current halfway through the round
+/// trip, and advanced by the local monotonic clock for the following 10
+/// seconds, so most `ServerNow` are answered without a command.
+pub struct ServerNow;
+
+impl Message for ServerNow {
+    type Result = Result<SystemTime, Error>;
+}
+
+impl Handler<ServerNow> for RedisActor {
+    type Result = ResponseActFuture<RedisActor, SystemTime, Error>;
+
+    fn handle(&mut self, _: ServerNow, ctx: &mut Self::Context) -> Self::Result {
+        use crate::command::Command as _;
+
+        if let Some((time, at)) = self.server_clock {
+            if at.elapsed() < SERVER_CLOCK_REFRESH {
+                return Box::new(actix::fut::ok(time + at.elapsed()));
+            }
+        }
+        let sent = Instant::now();
+        let res = self.send(command::Time.into_request(), ctx);
+        self.keep_alive(ctx);
+        Box::new(
+            res.and_then(|res| command::Time::from_frame(res).map_err(Error::Redis))
+                .into_actor(self)
+                .map(move |time, this, _ctx| {
+                    let at = sent + sent.elapsed() / 2;
+                    this.server_clock = Some((time, at));
+                    time + at.elapsed()
+                }),
+        )
+    }
+}
+
+/// Lease the connection of a `RedisActor` for exclusive use, e.g. for
+/// `WATCH`/`MULTI`/`EXEC` transactions.
+///
+/// Resolves once earlier leases are released. Commands other actors send while
+/// the connection is leased are deferred until the `Lease` is dropped.
+pub struct Checkout;
+
+impl Message for Checkout {
+    type Result = Result<Lease, Error>;
+}
+
+impl Handler<Checkout> for RedisActor {
+    type Result = ResponseFuture<Lease, Error>;
+
+    fn handle(&mut self, _: Checkout, ctx: &mut Self::Context) -> Self::Result {
+        let (tx, rx) = oneshot::channel();
+        self.checkouts.push_back(tx);
+        if self.lease.is_none() {
+            self.next_checkout(ctx);
+        }
+        Box::new(rx.map_err(|_| Error::Disconnected))
+    }
+}
+
+/// Exclusive use of the connection of a `RedisActor`, released when dropped
+///
+/// If the connection is dropped, the lease ends and its commands fail with
+/// `Error::Disconnected`.
+pub struct Lease {
+    id: usize,
+    addr: Addr<RedisActor>,
+}
+
+impl Lease {
+    /// Send a command on the leased connection
+    pub fn send<M>(&self, msg: M) -> impl Future<Item = M::Output, Error = Error>
+    where
+        M: command::Command,
+    {
+        self.send_raw(msg.into_request())
+            .and_then(|res| M::from_frame(res).map_err(Error::Redis))
+    }
+
+    /// Queue a command in a `MULTI` transaction. Its result is part of the
+    /// reply of `Exec`.
+    pub fn queue<M>(&self, msg: M) -> impl Future<Item = (), Error = Error>
+    where
+        M: command::Command,
+    {
+        self.queue_raw(msg.into_request())
+    }
+
+    pub(crate) fn queue_raw(
+        &self,
+        req: RespValue,
+    ) -> impl Future<Item = (), Error = Error> {
+        self.send_raw(req).and_then(|res| match res {
+            Frame::SimpleString(ref s) if s == "QUEUED" => Ok(()),
+            res => Err(Error::Redis(RespError::RESP(
+                "command was not queued".into(),
+                Some(res.into()),
+            ))),
+        })
+    }
+
+    fn send_raw(&self, req: RespValue) -> impl Future<Item = Frame, Error = Error> {
+        reply(self.addr.send(Leased { id: self.id, req }))
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        self.addr.do_send(Release(self.id));
+    }
+}
+
+struct Leased {
+    id: usize,
+    req: RespValue,
+}
+
+impl Message for Leased {
+    type Result = Result<Frame, Error>;
+}
+
+impl Handler<Leased> for RedisActor {
+    type Result = ResponseFuture<Frame, Error>;
+
+    fn handle(&mut self, msg: Leased, ctx: &mut Self::Context) -> Self::Result {
+        if self.lease != Some(msg.id) {
+            return Box::new(futures::future::err(Error::Disconnected));
+        }
+        let res = self.write(msg.req);
+        self.keep_alive(ctx);
+        res
+    }
+}
+
+struct Release(usize);
+
+impl Message for Release {
+    type Result = ();
+}
+
+impl Handler<Release> for RedisActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: Release, ctx: &mut Self::Context) {
+        if self.lease != Some(msg.0) {
+            return;
+        }
+        self.lease = None;
+        for (command, tx) in self.deferred.split_off(0) {
+            self.write_with(command, tx);
+        }
+        self.keep_alive(ctx);
+        self.next_checkout(ctx);
+    }
+}
+
+/// Whether `value` is a non-empty array of bulk strings
+// `matches!` is not available on the minimum supported Rust version
+#[allow(clippy::match_like_matches_macro)]
+fn is_command(value: &RespValue) -> bool {
+    match value {
+        RespValue::Array(args) => {
+            !args.is_empty()
+                && args.iter().all(|arg| match arg {
+                    RespValue::BulkString(_) => true,
+                    _ => false,
+                })
+        }
+        _ => false,
+    }
 }
 
 pub(crate) struct RespValueWrapper(pub RespValue);
 
 impl Message for RespValueWrapper {
-    type Result = Result<RespValue, Error>;
+    type Result = Result<Frame, Error>;
 }
 
 impl Handler<RespValueWrapper> for RedisActor {
-    type Result = ResponseFuture<RespValue, Error>;
+    type Result = ResponseFuture<Frame, Error>;
 
-    fn handle(&mut self, msg: RespValueWrapper, _: &mut Self::Context) -> Self::Result {
-        self.send(msg.0)
+    fn handle(
+        &mut self,
+        msg: RespValueWrapper,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let res = self.send(msg.0, ctx);
+        self.keep_alive(ctx);
+        res
     }
 }
 
@@ -160,10 +1666,15 @@ where
 {
     type Result = ResponseFuture<M::Output, Error>;
 
-    fn handle(&mut self, msg: M, _: &mut Self::Context) -> Self::Result {
-        Box::new(
-            self.send(msg.into_request())
-                .and_then(|res| M::from_response(res).map_err(Error::Redis)),
-        )
+    fn handle(&mut self, msg: M, ctx: &mut Self::Context) -> Self::Result {
+        if msg.is_blocking() && !self.blocking_lane.is_empty() {
+            let res = self.send_blocking(msg.into_request());
+            return Box::new(
+                res.and_then(|res| M::from_frame(res).map_err(Error::Redis)),
+            );
+        }
+        let res = self.send(msg.into_request(), ctx);
+        self.keep_alive(ctx);
+        Box::new(res.and_then(|res| M::from_frame(res).map_err(Error::Redis)))
     }
 }
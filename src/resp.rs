@@ -0,0 +1,244 @@
+//! Zero-copy RESP decoding
+use std::str;
+
+use bytes::{Bytes, BytesMut};
+use redis_async::resp::RespValue;
+use tokio_codec::Decoder;
+
+use crate::Error;
+
+/// A reply whose bulk strings share the buffer they were read into
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Frame {
+    Nil,
+    Array(Vec<Frame>),
+    BulkString(Bytes),
+    Error(String),
+    Integer(i64),
+    SimpleString(String),
+}
+
+/// Copies the bulk strings
+impl From<Frame> for RespValue {
+    fn from(frame: Frame) -> RespValue {
+        match frame {
+            Frame::Nil => RespValue::Nil,
+            Frame::Array(frames) => {
+                RespValue::Array(frames.into_iter().map(Into::into).collect())
+            }
+            Frame::BulkString(s) => RespValue::BulkString(s.to_vec()),
+            Frame::Error(e) => RespValue::Error(e),
+            Frame::Integer(i) => RespValue::Integer(i),
+            Frame::SimpleString(s) => RespValue::SimpleString(s),
+        }
+    }
+}
+
+/// Decoder of `Frame`s
+///
+/// A reply is split off the read buffer once it is complete, and its bulk
+/// strings are handed out as slices of it rather than copies.
+///
+/// Replies arriving in several reads are buffered. The decoder remembers how
+/// far it has scanned the reply, and how many bytes are at least missing, so
+/// each read only scans what it adds.
+///
+/// Malformed replies fail with `Error::Parse`, as do replies with arrays nested
+/// more than 128 levels deep, rather than overflowing the stack.
+#[derive(Debug, Default)]
+pub struct FrameCodec {
+    needed: usize,
+    /// the start of the next element of the reply to scan
+    pos: usize,
+    /// the elements left to scan of each array the next element is nested in,
+    /// outermost first
+    arrays: Vec<i64>,
+    max_reply_bytes: Option<usize>,
+}
+
+impl FrameCodec {
+    /// Fail with `Error::ReplyTooLarge` as soon as a reply is known to be larger
+    /// than `max` bytes, before it is buffered entirely.
+    ///
+    /// The declared length of a bulk string is checked when its header is read,
+    /// and the size of arrays as their elements arrive.
+    pub fn max_reply_bytes(mut self, max: usize) -> Self {
+        self.max_reply_bytes = Some(max);
+        self
+    }
+
+    /// Validate the reply at the start of `buf`, from where the previous call
+    /// stopped
+    fn scan(&mut self, buf: &[u8]) -> Result<Scan, Error> {
+        loop {
+            match element(buf, self.pos)? {
+                Element::Incomplete(needed) => return Ok(Scan::Incomplete(needed)),
+                Element::Array(pos, size) if size > 0 => {
+                    if self.arrays.len() == MAX_DEPTH {
+                        return Err(parse_error("Arrays are nested too deep".into()));
+                    }
+                    self.pos = pos;
+                    self.arrays.push(size);
+                    continue;
+                }
+                Element::Array(pos, _) | Element::Complete(pos) => self.pos = pos,
+            }
+            // the arrays completed by the element
+            loop {
+                match self.arrays.last_mut() {
+                    Some(left) => {
+                        *left -= 1;
+                        if *left > 0 {
+                            break;
+                        }
+                    }
+                    None => return Ok(Scan::Complete(self.pos)),
+                }
+                self.arrays.pop();
+            }
+        }
+    }
+}
+
+impl Decoder for FrameCodec {
+    type Item = Frame;
+    type Error = Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Frame>, Error> {
+        if buf.len() < self.needed {
+            return Ok(None);
+        }
+        let scan = self.scan(buf)?;
+        if let Some(max) = self.max_reply_bytes {
+            let len = match scan {
+                Scan::Complete(len) | Scan::Incomplete(len) => len,
+            };
+            if len > max {
+                return Err(Error::ReplyTooLarge);
+            }
+        }
+        match scan {
+            Scan::Complete(end) => {
+                self.needed = 0;
+                self.pos = 0;
+                let buf = buf.split_to(end).freeze();
+                Ok(Some(build(&buf, 0).1))
+            }
+            Scan::Incomplete(needed) => {
+                self.needed = needed;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// The levels of nested arrays a reply may have
+const MAX_DEPTH: usize = 128;
+
+enum Scan {
+    /// the position after the reply
+    Complete(usize),
+    /// the buffer length the reply needs at least
+    Incomplete(usize),
+}
+
+enum Element {
+    /// the position after the element
+    Complete(usize),
+    /// the buffer length the element needs at least
+    Incomplete(usize),
+    /// the position after the header of the array, and its size
+    Array(usize, i64),
+}
+
+fn parse_error(message: String) -> Error {
+    Error::Parse(message)
+}
+
+/// Find the line starting at `idx`, returning it without the terminator and
+/// the position after it
+fn line(buf: &[u8], idx: usize) -> Option<(&[u8], usize)> {
+    buf[idx..]
+        .windows(2)
+        .position(|w| w == b"\r\n")
+        .map(|n| (&buf[idx..idx + n], idx + n + 2))
+}
+
+fn integer(line: &[u8]) -> Result<i64, Error> {
+    str::from_utf8(line)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| parse_error(format!("Not an integer: {:?}", line)))
+}
+
+/// Validate the element starting at `idx`, or the header of the array
+/// starting there
+fn element(buf: &[u8], idx: usize) -> Result<Element, Error> {
+    if buf.len() <= idx {
+        return Ok(Element::Incomplete(idx + 1));
+    }
+    let (header, pos) = match line(buf, idx + 1) {
+        Some(line) => line,
+        // no terminator yet, at least one more byte is needed
+        None => return Ok(Element::Incomplete(buf.len() + 1)),
+    };
+
+    match buf[idx] {
+        b'+' | b'-' => Ok(Element::Complete(pos)),
+        b':' => integer(header).map(|_| Element::Complete(pos)),
+        b'$' => match integer(header)? {
+            -1 => Ok(Element::Complete(pos)),
+            size if size >= 0 => {
+                let end = pos.saturating_add(size as usize);
+                if buf.len() < end.saturating_add(2) {
+                    Ok(Element::Incomplete(end.saturating_add(2)))
+                } else if &buf[end..end + 2] != b"\r\n" {
+                    Err(parse_error("Bulk string is not terminated".into()))
+                } else {
+                    Ok(Element::Complete(end + 2))
+                }
+            }
+            size => Err(parse_error(format!("Invalid string size: {}", size))),
+        },
+        b'*' => match integer(header)? {
+            -1 => Ok(Element::Complete(pos)),
+            size if size >= 0 => Ok(Element::Array(pos, size)),
+            size => Err(parse_error(format!("Invalid array size: {}", size))),
+        },
+        byte => Err(parse_error(format!("Unexpected byte: {}", byte))),
+    }
+}
+
+/// Build the reply starting at `idx`, which `scan` has validated, nesting
+/// included
+fn build(buf: &Bytes, idx: usize) -> (usize, Frame) {
+    let (header, pos) = line(buf, idx + 1).unwrap();
+    let string = || String::from_utf8_lossy(header).into_owned();
+    let int = || integer(header).unwrap();
+
+    match buf[idx] {
+        b'+' => (pos, Frame::SimpleString(string())),
+        b'-' => (pos, Frame::Error(string())),
+        b':' => (pos, Frame::Integer(int())),
+        b'$' => match int() {
+            -1 => (pos, Frame::Nil),
+            size => {
+                let end = pos + size as usize;
+                (end + 2, Frame::BulkString(buf.slice(pos, end)))
+            }
+        },
+        _ => match int() {
+            -1 => (pos, Frame::Nil),
+            size => {
+                let mut pos = pos;
+                let mut frames = Vec::with_capacity(size as usize);
+                for _ in 0..size {
+                    let (next, frame) = build(buf, pos);
+                    frames.push(frame);
+                    pos = next;
+                }
+                (pos, Frame::Array(frames))
+            }
+        },
+    }
+}
@@ -0,0 +1,76 @@
+//! Bounded rolling buffers in string values
+use ::actix::prelude::*;
+use futures::Future;
+use redis_async::resp::RespValue;
+
+use crate::command::Eval;
+use crate::{reply, Error};
+
+/// Appends, then drops the bytes before the last `ARGV[2]`, keeping the TTL
+const APPEND_TRIM: &str = "\
+local len = redis.call('APPEND', KEYS[1], ARGV[1])
+local max = tonumber(ARGV[2])
+if len > max then
+    local ttl = redis.call('PTTL', KEYS[1])
+    redis.call('SET', KEYS[1], redis.call('GETRANGE', KEYS[1], len - max, -1))
+    if ttl > 0 then
+        redis.call('PEXPIRE', KEYS[1], ttl)
+    end
+    len = max
+end
+return len";
+
+/// A string value holding the last `max_len` bytes appended to it, e.g. to
+/// capture the recent activity of a session
+///
+/// Each append trims the front of the value in the same script, so concurrent
+/// appends are not lost and the value never exceeds `max_len`. The TTL of the
+/// key is kept. Read it with `GetBytes` or `GetRange`.
+pub struct RollingBuffer {
+    redis: Recipient<Eval<'static>>,
+    key: String,
+    max_len: usize,
+}
+
+impl RollingBuffer {
+    /// Keep the last `max_len` bytes at `key`. `redis` is the recipient of a
+    /// `RedisActor` or `RedisClusterActor`.
+    ///
+    /// # Panics
+    /// Panics if `max_len` is 0
+    pub fn new<S: Into<String>>(
+        redis: Recipient<Eval<'static>>,
+        key: S,
+        max_len: usize,
+    ) -> RollingBuffer {
+        assert!(max_len > 0, "max length must not be 0");
+        RollingBuffer {
+            redis,
+            key: key.into(),
+            max_len,
+        }
+    }
+
+    /// Append `value`, dropping the oldest bytes beyond the max length.
+    /// Resolves to the length of the value afterwards.
+    pub fn append<V: Into<Vec<u8>>>(
+        &self,
+        value: V,
+    ) -> impl Future<Item = usize, Error = Error> {
+        reply(self.redis.send(Eval {
+            script: APPEND_TRIM,
+            keys: vec![self.key.clone()],
+            args: vec![
+                RespValue::BulkString(value.into()),
+                self.max_len.to_string().into(),
+            ],
+        }))
+        .and_then(|res| match res {
+            RespValue::Integer(len) => Ok(len as usize),
+            res => Err(Error::Redis(redis_async::error::Error::RESP(
+                "invalid response for rolling buffer append".into(),
+                Some(res),
+            ))),
+        })
+    }
+}
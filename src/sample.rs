@@ -0,0 +1,201 @@
+//! Sampling the keys of a cluster
+use ::actix::prelude::*;
+use futures::future::{self, Either, Loop};
+use futures::Future;
+use rand::Rng;
+use std::collections::HashSet;
+
+use crate::cluster::{RedisClusterActor, Routed, Routing};
+use crate::command::{ClusterSlots, DbSize, Scan, Ttl};
+use crate::{reply, Error};
+
+/// Sample keys of all masters of a cluster uniformly at random, e.g. to show
+/// random active sessions in an admin UI
+///
+/// Unlike `RANDOMKEY` on a master chosen at random, which favours the keys of
+/// the smaller masters, the sample is spread over the masters in proportion to
+/// their number of keys (`DBSIZE`), then taken uniformly from the keys each of
+/// them returns to a full `SCAN`. As `DBSIZE` counts all the keys, the sample
+/// is only approximately uniform when `pattern` matches a different share of
+/// the keys of each master.
+#[derive(Debug, Default)]
+pub struct ClusterSample {
+    /// only sample the keys matching this glob-style pattern
+    pub pattern: Option<String>,
+    /// the number of keys to sample
+    pub size: usize,
+    /// hint of the number of keys to return per `SCAN` call
+    pub count: Option<usize>,
+}
+
+/// A key sampled by a `ClusterSample`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampledKey {
+    pub key: Vec<u8>,
+    /// the address of the master holding the key
+    pub node: String,
+    /// the time to live of the key in seconds, `None` if it does not expire,
+    /// no longer exists, or is not valid UTF-8
+    pub ttl: Option<i64>,
+}
+
+impl ClusterSample {
+    /// Take the sample. Must be called within a running actix system.
+    ///
+    /// # Failures
+    /// Fails if the slots cannot be discovered, or a master fails or replies
+    /// with an error
+    pub fn start(
+        self,
+        cluster: Addr<RedisClusterActor>,
+    ) -> impl Future<Item = Vec<SampledKey>, Error = Error> {
+        let ClusterSample {
+            pattern,
+            size,
+            count,
+        } = self;
+
+        let sizes = reply(cluster.send(Routed {
+            routing: Routing::ToAllMasters,
+            command: DbSize,
+        }));
+
+        reply(cluster.send(ClusterSlots))
+            .join(sizes)
+            .and_then({
+                let cluster = cluster.clone();
+                move |(slots, sizes)| {
+                    // a slot served by each master, to route the scans to it
+                    let nodes = sizes
+                        .into_iter()
+                        .filter_map(|(addr, keys)| {
+                            let slot =
+                                slots.iter().find(|slots| slots.master() == addr)?;
+                            Some((addr, slot.start, keys.max(0) as u64))
+                        })
+                        .collect::<Vec<_>>();
+                    let shares = allocate(
+                        &nodes.iter().map(|node| node.2).collect::<Vec<_>>(),
+                        size,
+                    );
+
+                    let samples = nodes
+                        .into_iter()
+                        .zip(shares)
+                        .filter(|&(_, share)| share > 0)
+                        .map(|((addr, slot, _), share)| {
+                            sample_node(
+                                &cluster,
+                                addr,
+                                slot,
+                                pattern.clone(),
+                                count,
+                                share,
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    future::join_all(samples)
+                }
+            })
+            .and_then(move |samples| {
+                let keys = samples
+                    .into_iter()
+                    .flatten()
+                    .map(|(node, key)| {
+                        let ttl = match String::from_utf8(key.clone()) {
+                            Ok(name) => Either::A(
+                                reply(cluster.send(Ttl { key: name }))
+                                    .map(|ttl| ttl.ok()),
+                            ),
+                            Err(_) => Either::B(future::ok(None)),
+                        };
+                        ttl.map(move |ttl| SampledKey { key, node, ttl })
+                    })
+                    .collect::<Vec<_>>();
+                future::join_all(keys)
+            })
+    }
+}
+
+/// Sample `share` keys of the master at `addr` with a reservoir over a full
+/// `SCAN`
+fn sample_node(
+    cluster: &Addr<RedisClusterActor>,
+    addr: String,
+    slot: u16,
+    pattern: Option<String>,
+    count: Option<usize>,
+    share: usize,
+) -> impl Future<Item = Vec<(String, Vec<u8>)>, Error = Error> {
+    let cluster = cluster.clone();
+    future::loop_fn(
+        (0, Vec::with_capacity(share), 0),
+        move |(cursor, mut reservoir, mut seen): (u64, Vec<Vec<u8>>, u64)| {
+            let scan = Scan {
+                cursor,
+                pattern: pattern.clone(),
+                count,
+                slot,
+            };
+            reply(cluster.send(scan)).map(move |(cursor, keys)| {
+                let mut rng = rand::thread_rng();
+                for key in keys {
+                    seen += 1;
+                    if reservoir.len() < share {
+                        reservoir.push(key);
+                    } else {
+                        let i = rng.gen_range(0, seen) as usize;
+                        if i < share {
+                            reservoir[i] = key;
+                        }
+                    }
+                }
+                if cursor == 0 {
+                    Loop::Break(reservoir)
+                } else {
+                    Loop::Continue((cursor, reservoir, seen))
+                }
+            })
+        },
+    )
+    .map(move |reservoir| {
+        // `SCAN` may return a key more than once
+        let mut keys = Vec::<(String, Vec<u8>)>::new();
+        for key in reservoir {
+            if keys.iter().all(|(_, k)| *k != key) {
+                keys.push((addr.clone(), key));
+            }
+        }
+        keys
+    })
+}
+
+/// Spread `size` distinct keys drawn uniformly from all the keys over the
+/// masters holding `keys` each
+fn allocate(keys: &[u64], size: usize) -> Vec<usize> {
+    let total = keys.iter().sum::<u64>();
+    let size = (size as u64).min(total);
+
+    // Floyd's sampling of `size` distinct indices below `total`
+    let mut rng = rand::thread_rng();
+    let mut picked = HashSet::new();
+    for j in total - size..total {
+        let i = rng.gen_range(0, j + 1);
+        if !picked.insert(i) {
+            picked.insert(j);
+        }
+    }
+
+    let mut shares = vec![0; keys.len()];
+    for i in picked {
+        let mut start = 0;
+        for (node, &keys) in keys.iter().enumerate() {
+            if i < start + keys {
+                shares[node] += 1;
+                break;
+            }
+            start += keys;
+        }
+    }
+    shares
+}
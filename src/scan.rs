@@ -0,0 +1,102 @@
+//! Scanning the keys of a cluster
+use ::actix::prelude::*;
+use futures::future::{self, Either, Loop};
+use futures::Future;
+use std::collections::VecDeque;
+
+use crate::cluster::RedisClusterActor;
+use crate::command::{ClusterSlots, Scan};
+use crate::{reply, Error};
+
+/// Iterate over the keys of all masters of a cluster with `SCAN`
+///
+/// The masters are scanned one after another, each with its own cursor. When
+/// a master fails mid-scan, the scan goes on with the next one instead of
+/// aborting, and the master is reported in `ScanResult::skipped`.
+#[derive(Debug, Default)]
+pub struct ClusterScan {
+    /// only return the keys matching this glob-style pattern
+    pub pattern: Option<String>,
+    /// hint of the number of keys to return per call
+    pub count: Option<usize>,
+}
+
+/// Keys found by a `ClusterScan`
+#[derive(Debug, Default, PartialEq)]
+pub struct ScanResult {
+    /// Keys of all masters. A key may be returned more than once.
+    pub keys: Vec<Vec<u8>>,
+    /// Addresses of the masters that failed before their scan completed. The
+    /// keys they returned until then are kept in `keys`.
+    pub skipped: Vec<String>,
+}
+
+/// Master being scanned
+struct Node {
+    addr: String,
+    /// a slot served by the master, to route the commands to it
+    slot: u16,
+    cursor: u64,
+}
+
+impl ClusterScan {
+    /// Start the scan. Must be called within a running actix system.
+    ///
+    /// # Failures
+    /// Fails if the slots cannot be discovered, or a master replies with an
+    /// error
+    pub fn start(
+        self,
+        cluster: Addr<RedisClusterActor>,
+    ) -> impl Future<Item = ScanResult, Error = Error> {
+        let ClusterScan { pattern, count } = self;
+
+        reply(cluster.send(ClusterSlots)).and_then(move |slots| {
+            let mut nodes = VecDeque::<Node>::new();
+            for slots in slots.iter() {
+                let addr = slots.master();
+                if nodes.iter().all(|node| node.addr != addr) {
+                    nodes.push_back(Node {
+                        addr,
+                        slot: slots.start,
+                        cursor: 0,
+                    });
+                }
+            }
+
+            future::loop_fn(
+                (nodes, ScanResult::default()),
+                move |(mut nodes, mut result)| {
+                    let mut node = match nodes.pop_front() {
+                        Some(node) => node,
+                        None => return Either::A(future::ok(Loop::Break(result))),
+                    };
+                    let scan = Scan {
+                        cursor: node.cursor,
+                        pattern: pattern.clone(),
+                        count,
+                        slot: node.slot,
+                    };
+
+                    Either::B(reply(cluster.send(scan)).then(move |res| {
+                        match res {
+                            Ok((cursor, keys)) => {
+                                result.keys.extend(keys);
+                                if cursor != 0 {
+                                    node.cursor = cursor;
+                                    nodes.push_front(node);
+                                }
+                            }
+                            Err(Error::NotConnected) | Err(Error::Disconnected) => {
+                                warn!("skipping {} in the scan", node.addr);
+                                result.skipped.push(node.addr);
+                            }
+                            Err(e) => return Err(e),
+                        }
+                        Ok(Loop::Continue((nodes, result)))
+                    }))
+                },
+            )
+        })
+    }
+}
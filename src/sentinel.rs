@@ -0,0 +1,105 @@
+//! Master discovery through Redis Sentinel
+use ::actix::actors::resolver::ResolverError;
+use ::actix::prelude::*;
+use futures::future::{self, Either, Loop};
+use futures::Future;
+
+use crate::command::SentinelGetMasterAddr;
+use crate::pubsub::{PubSubEvent, Subscribe, Subscription};
+use crate::redis::RedisActor;
+
+/// The channel on which Sentinels announce a failover
+const SWITCH_MASTER: &str = "+switch-master";
+
+/// The Sentinels monitoring a master, asked in turn for its address
+pub(crate) struct Sentinels {
+    master_name: String,
+    sentinels: Vec<(String, Addr<RedisActor>)>,
+}
+
+impl Sentinels {
+    /// Connect to the `sentinels`. Must be called within a running actix
+    /// system.
+    pub(crate) fn new(sentinels: Vec<String>, master_name: String) -> Sentinels {
+        Sentinels {
+            master_name,
+            sentinels: sentinels
+                .into_iter()
+                .map(|addr| {
+                    let sentinel = RedisActor::start(addr.clone());
+                    (addr, sentinel)
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn master_name(&self) -> &str {
+        &self.master_name
+    }
+
+    /// The address of the master according to the first Sentinel that knows
+    /// it, and the address of that Sentinel
+    pub(crate) fn master(
+        &self,
+    ) -> impl Future<Item = (String, String), Error = ResolverError> {
+        let master_name = self.master_name.clone();
+        let sentinels = self.sentinels.clone();
+
+        future::loop_fn(0, move |i| match sentinels.get(i) {
+            None => Either::A(future::err(ResolverError::Resolver(format!(
+                "no sentinel knows the master {}",
+                master_name
+            )))),
+            Some((addr, sentinel)) => {
+                let addr = addr.clone();
+                let master_name = master_name.clone();
+                Either::B(
+                    sentinel
+                        .send(SentinelGetMasterAddr {
+                            master_name: master_name.clone(),
+                        })
+                        .then(move |res| match res {
+                            Ok(Ok(Some(master))) => Ok(Loop::Break((master, addr))),
+                            Ok(Ok(None)) => {
+                                warn!("Sentinel {} does not know {}", addr, master_name);
+                                Ok(Loop::Continue(i + 1))
+                            }
+                            Ok(Err(e)) => {
+                                warn!("Can not ask sentinel {}: {}", addr, e);
+                                Ok(Loop::Continue(i + 1))
+                            }
+                            Err(_) => Ok(Loop::Continue(i + 1)),
+                        }),
+                )
+            }
+        })
+    }
+}
+
+/// Subscribe to the failovers announced by the Sentinel at `addr`
+pub(crate) fn switch_master(addr: &str) -> Subscription {
+    Subscribe {
+        channels: vec![SWITCH_MASTER.into()],
+    }
+    .start(addr)
+}
+
+/// The address of the new master if `event` announces a failover of
+/// `master_name`
+///
+/// The payload is `<master name> <old ip> <old port> <new ip> <new port>`.
+pub(crate) fn switched_to(event: &PubSubEvent, master_name: &str) -> Option<String> {
+    match *event {
+        PubSubEvent::Message(ref msg) if msg.channel == SWITCH_MASTER => {
+            let payload = String::from_utf8_lossy(&msg.payload);
+            let fields = payload.split(' ').collect::<Vec<_>>();
+            match fields[..] {
+                [name, _, _, ip, port] if name == master_name => {
+                    Some(format!("{}:{}", ip, port))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
@@ -1,30 +1,191 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::iter;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
 use actix::prelude::*;
-use actix_session::Session;
+use actix_session::{Session, SessionStatus};
 use actix_web::cookie::{Cookie, CookieJar, Key, SameSite};
-use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::dev::{
+    Extensions, Payload, Service, ServiceRequest, ServiceResponse, Transform,
+};
 use actix_web::http::header::{self, HeaderValue};
-use actix_web::{error, Error, HttpMessage};
+use actix_web::{error, Error, FromRequest, HttpMessage, HttpRequest};
 use futures::future::{err, ok, Either, Future, FutureResult};
-use futures::Poll;
+use futures::{Async, IntoFuture, Poll};
 use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
+use redis_async::error::Error as RespError;
+use redis_async::resp::RespValue;
+use ring::digest;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
 use time::Duration;
 
-use crate::command::{self, Expiration, Get, Set};
-use crate::redis::RedisActor;
+use crate::cluster::{Routed, Routing};
+use crate::command::{self, Expiration, Get, GetEx, Set, Wait, WaitAof};
+use crate::config::RedisSessionConfig;
+use crate::redis::{Readiness, RedisActor};
+use crate::slot::{hash_slot, HashError, Hasher};
 use crate::RedisClusterActor;
 
+/// Session key whose value, if set by a handler, overrides the time to live
+/// in seconds of this particular session.
+///
+/// The value is clamped to `RedisSession::max_ttl` and kept with the session
+/// data, so it applies to every later write and load of the session. It is
+/// also used as the max-age of the session cookie. Handlers never see it when
+/// the session is loaded.
+pub const TTL_OVERRIDE_KEY: &str = "__ttl_override";
+
+/// Session key which, if set to `true` by a handler, removes the expiration of
+/// this particular session, e.g. for a "keep me signed in" option. Setting it
+/// to `false` restores the default time to live.
+///
+/// The marker itself is not stored. A persisted session is kept with the
+/// session data as an override of `0` seconds, written without expiration,
+/// and its cookie gets `RedisSession::max_ttl` as max-age.
+pub const PERSIST_KEY: &str = "__persist";
+
+/// The ttl override of persisted sessions
+const PERSISTED: i64 = 0;
+
+/// The version of the format sessions are stored in, `{"v":1,"values":{...}}`
+const FORMAT_VERSION: u64 = 1;
+
+/// How the session id is turned into the Redis key of the session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyHash {
+    /// Use the session id as is
+    None,
+    /// Use the hex encoded SHA-256 of the session id, so the keys in Redis do
+    /// not reveal live cookie values
+    Sha256,
+}
+
+/// How the session id is written in the session cookie
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CookieEncoding {
+    /// Write the session id as is
+    Plain,
+    /// Write the base64url encoding of the session id, without padding
+    Base64Url,
+}
+
+impl CookieEncoding {
+    fn encode(self, id: &str) -> String {
+        match self {
+            CookieEncoding::Plain => id.to_owned(),
+            CookieEncoding::Base64Url => {
+                base64::encode_config(id, base64::URL_SAFE_NO_PAD)
+            }
+        }
+    }
+
+    /// The session id, or `None` if `value` is not encoded this way
+    fn decode(self, value: &str) -> Option<String> {
+        match self {
+            CookieEncoding::Plain => Some(value.to_owned()),
+            CookieEncoding::Base64Url => {
+                base64::decode_config(value, base64::URL_SAFE_NO_PAD)
+                    .ok()
+                    .and_then(|id| String::from_utf8(id).ok())
+            }
+        }
+    }
+}
+
+impl KeyHash {
+    fn apply(self, id: &str) -> String {
+        match self {
+            KeyHash::None => id.to_owned(),
+            KeyHash::Sha256 => digest::digest(&digest::SHA256, id.as_bytes())
+                .as_ref()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+        }
+    }
+}
+
+/// How far a session write is replicated before the response is sent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionConsistency {
+    /// Only write to the master
+    Master,
+    /// Also wait until the given number of replicas have acknowledged the
+    /// write, for at most the given time in milliseconds (`WAIT`).
+    ///
+    /// This keeps a session written right before a failover, e.g. at login.
+    /// Fewer acknowledgements than requested are only logged.
+    WaitReplica(i64, i64),
+}
+
+/// The sessions of a user deleted by `RedisSession::invalidate_all`
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidatedSessions {
+    /// The number of sessions deleted
+    pub deleted: i64,
+    /// The number of replicas that acknowledged the deletions, the fewest of
+    /// any master in cluster mode. `None` if they were not waited for.
+    pub replicas: Option<i64>,
+    /// The number of replicas asked to acknowledge the deletions
+    pub requested_replicas: i64,
+}
+
+impl InvalidatedSessions {
+    /// Whether the deletions were acknowledged by the replicas asked to, or
+    /// not waited for
+    pub fn is_replicated(&self) -> bool {
+        match self.replicas {
+            Some(replicas) => replicas >= self.requested_replicas,
+            None => true,
+        }
+    }
+}
+
+/// The switch of the maintenance mode of `RedisSession`, e.g. for the
+/// duration of a Redis maintenance. Clones share the switch, so one can be
+/// given to the `RedisSession` of every worker and flipped from anywhere.
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceMode(Arc<AtomicBool>);
+
+impl MaintenanceMode {
+    pub fn new() -> MaintenanceMode {
+        MaintenanceMode::default()
+    }
+
+    /// Enter the maintenance mode
+    pub fn enable(&self) {
+        self.0.store(true, Ordering::SeqCst)
+    }
+
+    /// Leave the maintenance mode. The writes buffered by a worker are flushed
+    /// by its next request, or by `RedisSession::resume`.
+    pub fn disable(&self) {
+        self.0.store(false, Ordering::SeqCst)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
 /// Use redis as session storage.
 ///
 /// You need to pass an address of the redis server and random value to the
 /// constructor of `RedisSessionBackend`. This is private key for cookie
-/// session, When this value is changed, all session data is lost.
+/// session, When this value is changed, all session data is lost, unless the
+/// previous value is passed to `with_fallback_keys`.
 ///
 /// Constructor panics if key length is less than 32 bytes.
+///
+/// Sessions a request did not modify are not written back, only their time to
+/// live is reset.
 #[derive(Clone)]
 pub struct RedisSession(Rc<Inner>);
 
@@ -33,33 +194,82 @@ impl RedisSession {
     ///
     /// * `addr` - address of the redis server
     pub fn new<S: Into<String>>(addr: S, key: &[u8]) -> RedisSession {
-        RedisSession(Rc::new(Inner {
-            key: Key::from_master(key),
-            ttl: "7200".to_owned(),
-            addr: Redis::Redis(RedisActor::start(addr)),
-            name: "actix-session".to_owned(),
-            path: "/".to_owned(),
-            domain: None,
-            secure: false,
-            max_age: Some(Duration::days(7)),
-            same_site: None,
-        }))
+        let readiness = Readiness::new();
+        let addr = RedisActor::builder(addr)
+            .readiness(readiness.clone())
+            .start();
+        RedisSession::with_redis(Redis::Redis(addr), readiness, key)
     }
 
     /// Create new redis session backend with redis cluster
     ///
     /// * `addrs` - addresses of the redis masters
     pub fn new_cluster<S: Into<String>>(addr: S, key: &[u8]) -> RedisSession {
+        let readiness = Readiness::new();
+        let addr = RedisClusterActor::builder(addr)
+            .readiness(readiness.clone())
+            .start();
+        RedisSession::with_redis(Redis::RedisCluster(addr), readiness, key)
+    }
+
+    /// Create new redis session backend configured with `config`, e.g. read
+    /// from a file
+    pub fn from_config(config: &RedisSessionConfig, key: &[u8]) -> RedisSession {
+        let readiness = Readiness::new();
+        let connection = &config.connection;
+        let addr = if connection.cluster {
+            Redis::RedisCluster(
+                connection
+                    .cluster_builder()
+                    .readiness(readiness.clone())
+                    .start(),
+            )
+        } else {
+            Redis::Redis(connection.builder().readiness(readiness.clone()).start())
+        };
+
+        let mut session = RedisSession::with_redis(addr, readiness, key)
+            .ttl(config.ttl.as_secs() as i64)
+            .max_ttl(config.max_ttl.as_secs() as i64)
+            .cookie_name(&config.cookie_name)
+            .cookie_path(&config.cookie_path)
+            .cookie_secure(config.cookie_secure)
+            .refresh_on_load(config.refresh_on_load);
+        if let Some(ref domain) = config.cookie_domain {
+            session = session.cookie_domain(domain);
+        }
+        let inner = Rc::get_mut(&mut session.0).unwrap();
+        inner.max_age = config
+            .cookie_max_age
+            .map(|max_age| Duration::seconds(max_age.as_secs() as i64));
+        inner.same_site = config.cookie_same_site;
+        session
+    }
+
+    fn with_redis(addr: Redis, readiness: Readiness, key: &[u8]) -> RedisSession {
         RedisSession(Rc::new(Inner {
             key: Key::from_master(key),
+            fallback_keys: Vec::new(),
             ttl: "7200".to_owned(),
-            addr: Redis::RedisCluster(RedisClusterActor::start(addr)),
+            ttl_jitter: 0,
+            addr,
+            readiness: vec![readiness],
+            backpressure: false,
             name: "actix-session".to_owned(),
             path: "/".to_owned(),
             domain: None,
             secure: false,
             max_age: Some(Duration::days(7)),
             same_site: None,
+            refresh_on_load: false,
+            max_ttl: Duration::days(30).num_seconds(),
+            key_hash: KeyHash::None,
+            cookie_encoding: CookieEncoding::Plain,
+            client_state: false,
+            wait_aof: None,
+            consistency: SessionConsistency::Master,
+            user_index: None,
+            maintenance: None,
         }))
     }
 
@@ -69,6 +279,23 @@ impl RedisSession {
         self
     }
 
+    /// Add a random offset of up to `jitter` to the time to live each time it is
+    /// reset, so sessions created in the same burst do not all expire at once.
+    /// The time to live is never shorter than the one set with `ttl`.
+    ///
+    /// Times to live set via `TTL_OVERRIDE_KEY` are kept exact.
+    pub fn ttl_jitter(mut self, jitter: Duration) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().ttl_jitter = cmp::max(jitter.num_seconds(), 0);
+        self
+    }
+
+    /// Set the upper bound in seconds for a per-session time to live set via
+    /// `TTL_OVERRIDE_KEY`. Defaults to 30 days.
+    pub fn max_ttl(mut self, max_ttl: i64) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().max_ttl = max_ttl;
+        self
+    }
+
     /// Set custom cookie name for session id
     pub fn cookie_name(mut self, name: &str) -> Self {
         Rc::get_mut(&mut self.0).unwrap().name = name.to_owned();
@@ -106,6 +333,351 @@ impl RedisSession {
         Rc::get_mut(&mut self.0).unwrap().same_site = Some(same_site);
         self
     }
+
+    /// Reset the time to live of the session value whenever it is loaded
+    ///
+    /// The value is read with `GETEX`, so the expiration is refreshed in the
+    /// same round trip. Requires Redis 6.2 or later.
+    pub fn refresh_on_load(mut self, refresh: bool) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().refresh_on_load = refresh;
+        self
+    }
+
+    /// Set how the session id is hashed into the Redis key. The cookie always
+    /// carries the original session id. Defaults to `KeyHash::None`.
+    ///
+    /// Changing this makes existing sessions unreachable.
+    pub fn key_hash(mut self, key_hash: KeyHash) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().key_hash = key_hash;
+        self
+    }
+
+    /// Set how the session id is written in the session cookie, e.g.
+    /// `CookieEncoding::Base64Url` behind proxies mangling some characters.
+    /// The Redis key is derived from the decoded session id. Defaults to
+    /// `CookieEncoding::Plain`.
+    ///
+    /// Changing this makes existing sessions unreachable.
+    pub fn cookie_encoding(mut self, encoding: CookieEncoding) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().cookie_encoding = encoding;
+        self
+    }
+
+    /// Issue `WAITAOF num_local num_replicas timeout_ms` after every session
+    /// write, see `command::WaitAof`.
+    ///
+    /// The request fails if the server rejects the command, e.g. because AOF
+    /// is disabled. Fewer acknowledgements than requested are only logged.
+    pub fn wait_aof(
+        mut self,
+        num_local: i64,
+        num_replicas: i64,
+        timeout_ms: i64,
+    ) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().wait_aof =
+            Some((num_local, num_replicas, timeout_ms));
+        self
+    }
+
+    /// Also accept cookies signed with any of `keys`, e.g. the previous keys
+    /// while rotating the key. New cookies are signed with the key given to the
+    /// constructor, and a session whose cookie is signed with a fallback key
+    /// gets a new cookie in the response.
+    ///
+    /// Panics if a key is shorter than 32 bytes.
+    pub fn with_fallback_keys(mut self, keys: Vec<&[u8]>) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().fallback_keys =
+            keys.into_iter().map(Key::from_master).collect();
+        self
+    }
+
+    /// Fall back to the standalone server at `secondary` while the primary is
+    /// unreachable, e.g. in a two node setup without Sentinel.
+    ///
+    /// Every command is sent to the primary first, and to the secondary if the
+    /// primary fails with `NotConnected`, `Disconnected` or `CircuitOpen`, so
+    /// the primary is used again as soon as it is back. A session missing on
+    /// the primary is looked up on the secondary, and written to the primary
+    /// if it is found there.
+    pub fn with_failover<S: Into<String>>(mut self, secondary: S) -> Self {
+        let inner = Rc::get_mut(&mut self.0).unwrap();
+        let primary = inner.addr.clone();
+        let readiness = Readiness::new();
+        let secondary = RedisActor::builder(secondary)
+            .readiness(readiness.clone())
+            .start();
+        inner.addr = Redis::Failover(Box::new(primary), secondary);
+        inner.readiness.push(readiness);
+        self
+    }
+
+    /// Report the middleware as not ready, so actix-web stops accepting
+    /// requests, while Redis is not connected or `max` commands sent to it
+    /// wait for their reply. Off by default: the middleware is ready whenever
+    /// the wrapped service is.
+    ///
+    /// With a failover the middleware is ready while either server is.
+    pub fn backpressure(mut self, max: usize) -> Self {
+        let inner = Rc::get_mut(&mut self.0).unwrap();
+        for readiness in &inner.readiness {
+            readiness.set_max_in_flight(max);
+        }
+        inner.backpressure = true;
+        self
+    }
+
+    /// Set how far session writes are replicated before the response is
+    /// sent. Defaults to `SessionConsistency::Master`.
+    pub fn consistency(mut self, consistency: SessionConsistency) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().consistency = consistency;
+        self
+    }
+
+    /// Keep the session state in the cookie, encrypted, and only a random
+    /// version of it in Redis, so loading a session reads a short value.
+    ///
+    /// Every write stores a new version. A cookie is rejected unless it
+    /// carries the stored version, so deleting the Redis key of a session
+    /// revokes it, and an older cookie of the session is not accepted. The
+    /// state must fit in a cookie, about 4 KB. `cookie_encoding` does not
+    /// apply.
+    ///
+    /// Changing this makes existing sessions unreachable.
+    pub fn client_state(mut self, client_state: bool) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().client_state = client_state;
+        self
+    }
+
+    /// Track the sessions of each user, e.g. to sign a user out everywhere.
+    ///
+    /// When a session with the key `field` set, e.g. by the login handler, is
+    /// written, its Redis key is added to the set at `user_index_key`, whose
+    /// time to live is extended to the one of the session, so the set outlives
+    /// all of them. Keys are not removed from the set when the session expires
+    /// or `field` is removed.
+    ///
+    /// New sessions of a user get an id with a hash tag derived from the user,
+    /// so the session and the set are in the same slot, and the write costs a
+    /// single round trip in cluster mode too. Sessions created before `field`
+    /// is set, or hashed with `KeyHash::Sha256`, are in another slot: the set
+    /// is then written after the session.
+    pub fn user_index(mut self, field: &str, prefix: &str) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().user_index =
+            Some((field.to_owned(), prefix.to_owned()));
+        self
+    }
+
+    /// The key of the set tracking the sessions of `user`, the value of the
+    /// field passed to `user_index`. `None` if sessions are not tracked.
+    pub fn user_index_key(&self, user: &str) -> Option<String> {
+        self.0
+            .user_index
+            .as_ref()
+            .map(|(_, prefix)| index_key(prefix, user))
+    }
+
+    /// Sign `user` out everywhere: delete the sessions tracked for `user` by
+    /// `user_index`, and the set tracking them.
+    ///
+    /// With `SessionConsistency::WaitReplica`, then wait until the deletions
+    /// are acknowledged by replicas (`WAIT`), on every master in cluster mode,
+    /// so a failover does not bring the sessions back. Fewer acknowledgements
+    /// than requested do not fail, but are reported in the result, see
+    /// `InvalidatedSessions::is_replicated`.
+    ///
+    /// Fails if sessions are not tracked with `user_index`.
+    pub fn invalidate_all(
+        &self,
+        user: &str,
+        consistency: SessionConsistency,
+    ) -> impl Future<Item = InvalidatedSessions, Error = Error> {
+        let index = match self.user_index_key(user) {
+            Some(index) => index,
+            None => {
+                return Either::A(err(error::ErrorInternalServerError(
+                    "sessions are not tracked by user",
+                )))
+            }
+        };
+        let inner = self.0.clone();
+        let addr = inner.addr.clone();
+        Either::B(
+            addr.send(command::SMembers { key: index.clone() })
+                .map_err(Error::from)
+                .and_then(|res| res.map_err(error::ErrorInternalServerError))
+                .and_then({
+                    let addr = addr.clone();
+                    move |members| {
+                        let keys = members
+                            .into_iter()
+                            .filter_map(|key| String::from_utf8(key).ok())
+                            .collect::<Vec<_>>();
+                        if let Some(ref maintenance) = inner.maintenance {
+                            maintenance.forget(&keys);
+                        }
+                        // one by one, as the sessions may be in different slots
+                        let deleted = keys
+                            .into_iter()
+                            .map(|key| delete(&addr, key))
+                            .collect::<Vec<_>>();
+                        futures::future::join_all(deleted)
+                            .join(delete(&addr, index))
+                            .map(|(deleted, _)| deleted.into_iter().sum::<i64>())
+                    }
+                })
+                .and_then(move |deleted| {
+                    let (num_replicas, timeout_ms) = match consistency {
+                        SessionConsistency::Master => {
+                            return Either::A(ok(InvalidatedSessions {
+                                deleted,
+                                replicas: None,
+                                requested_replicas: 0,
+                            }))
+                        }
+                        SessionConsistency::WaitReplica(num_replicas, timeout_ms) => {
+                            (num_replicas, timeout_ms)
+                        }
+                    };
+                    let wait = Wait {
+                        num_replicas,
+                        timeout_ms,
+                    };
+                    Either::B(
+                        addr.wait_all(wait)
+                            .map_err(Error::from)
+                            .and_then(|res| res.map_err(error::ErrorInternalServerError))
+                            .map(move |replicas| {
+                                if replicas < num_replicas {
+                                    warn!(
+                                        "session deletions are acknowledged by {} replicas",
+                                        replicas
+                                    );
+                                }
+                                InvalidatedSessions {
+                                    deleted,
+                                    replicas: Some(replicas),
+                                    requested_replicas: num_replicas,
+                                }
+                            }),
+                    )
+                }),
+        )
+    }
+
+    /// Keep serving requests with degraded guarantees while `mode` is enabled,
+    /// e.g. during a Redis maintenance, instead of failing them.
+    ///
+    /// Sessions are then loaded from a local cache of the sessions this worker
+    /// loaded or wrote in the last minute, sessions missing from it being
+    /// treated as new, and their times to live are not reset. Writes are
+    /// buffered, the last one of each session, until the mode is disabled;
+    /// a write of a session beyond the `max_buffered` ones fails with
+    /// `503 Service Unavailable`. Replicas and the AOF are not waited for.
+    pub fn maintenance_mode(
+        mut self,
+        mode: &MaintenanceMode,
+        max_buffered: usize,
+    ) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().maintenance = Some(Rc::new(Maintenance {
+            mode: mode.clone(),
+            max_buffered,
+            cache: RefCell::new(HashMap::new()),
+            last_purge: Cell::new(Instant::now()),
+            buffered: RefCell::new(HashMap::new()),
+        }));
+        self
+    }
+
+    /// Reset the time to live of the session of `req` if it still exists,
+    /// without writing it, e.g. in a heartbeat endpoint keeping the session of
+    /// an idle page alive. Resolves to whether the session exists. The endpoint
+    /// is not wrapped by the middleware, which would load the session.
+    ///
+    /// A session with a time to live override gets it back, and a persisted
+    /// session keeps no expiration: the session is read for its override,
+    /// unless the cookie carries it. A heartbeat never shortens the time to
+    /// live of a session. Requires Redis 7.0 or later.
+    pub fn heartbeat(
+        &self,
+        req: &HttpRequest,
+    ) -> impl Future<Item = bool, Error = Error> {
+        let inner = &self.0;
+        let (key, carried) = match inner.session_cookie(req) {
+            Some((value, carried, _)) => (inner.key_hash.apply(&value), carried),
+            None => return Either::A(ok(false)),
+        };
+        let state = match carried {
+            Some((_, state)) => Either::A(ok(Some(state))),
+            None => Either::B(
+                get_session(&inner.addr, key.clone(), None)
+                    .map(|res| res.and_then(|stored| decode(&stored))),
+            ),
+        };
+        let max_ttl = inner.max_ttl;
+        let default_ttl = inner.jittered_ttl();
+        let addr = inner.addr.clone();
+        Either::B(state.and_then(move |state| {
+            let mut state = match state {
+                Some(state) => state,
+                None => return Either::A(ok(false)),
+            };
+            let ttl = match state
+                .remove(TTL_OVERRIDE_KEY)
+                .and_then(|ttl| parse_stored_override(&ttl, max_ttl))
+            {
+                Some(PERSISTED) => None,
+                Some(ttl) => Some(ttl),
+                None => match default_ttl.parse() {
+                    Ok(ttl) => Some(ttl),
+                    Err(_) => {
+                        return Either::A(err(error::ErrorInternalServerError(
+                            "invalid ttl",
+                        )))
+                    }
+                },
+            };
+            // `GT` leaves a longer time to live, or no expiration, unchanged
+            let expire = match ttl {
+                Some(ttl) => Either::A(
+                    addr.send(command::ExpireIf {
+                        key: key.clone(),
+                        ttl,
+                        millis: false,
+                        condition: command::ExpireCondition::Gt,
+                    })
+                    .map_err(Error::from)
+                    .and_then(|res| res.map_err(error::ErrorInternalServerError)),
+                ),
+                None => Either::B(ok(false)),
+            };
+            Either::B(expire.and_then(move |expired| {
+                if expired {
+                    return Either::A(ok(true));
+                }
+                Either::B(
+                    addr.send(command::Touch { keys: vec![key] })
+                        .map_err(Error::from)
+                        .and_then(|res| res.map_err(error::ErrorInternalServerError))
+                        .map(|touched| touched == 1),
+                )
+            }))
+        }))
+    }
+
+    /// Leave the maintenance mode, and flush the writes buffered by this
+    /// worker. The other workers flush theirs on their next request.
+    ///
+    /// Fails with the first write Redis refused, the failed writes staying
+    /// buffered.
+    pub fn resume(&self) -> impl Future<Item = (), Error = Error> {
+        match self.0.maintenance {
+            Some(ref maintenance) => {
+                maintenance.mode.disable();
+                Either::A(flush(maintenance, &self.0.addr))
+            }
+            None => Either::B(ok(())),
+        }
+    }
 }
 
 impl<S, B> Transform<S> for RedisSession
@@ -150,6 +722,16 @@ where
     type Future = Box<dyn Future<Item = Self::Response, Error = Self::Error>>;
 
     fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        if self.inner.backpressure {
+            // poll every server, to be notified once any is ready
+            let mut ready = false;
+            for readiness in &self.inner.readiness {
+                ready |= readiness.poll_ready().is_ready();
+            }
+            if !ready {
+                return Ok(Async::NotReady);
+            }
+        }
         self.service.poll_ready()
     }
 
@@ -157,20 +739,45 @@ where
         let mut srv = self.service.clone();
         let inner = self.inner.clone();
 
-        Box::new(self.inner.load(&req).and_then(move |state| {
-            let value = if let Some((state, value)) = state {
-                Session::set_session(state.into_iter(), &mut req);
-                Some(value)
-            } else {
-                None
-            };
+        // the writes buffered until the end of a maintenance first
+        let flushed = match self.inner.maintenance {
+            Some(ref maintenance)
+                if !maintenance.mode.is_enabled()
+                    && !maintenance.buffered.borrow().is_empty() =>
+            {
+                Either::A(flush(maintenance, &self.inner.addr).then(|res| {
+                    if let Err(e) = res {
+                        warn!("failed to flush the buffered session writes: {}", e);
+                    }
+                    Ok(())
+                }))
+            }
+            _ => Either::B(ok(())),
+        };
 
-            srv.call(req).and_then(move |mut res| {
-                if let (_status, Some(state)) = Session::get_changes(&mut res) {
-                    Either::A(inner.update(res, state, value))
+        Box::new(flushed.and_then(move |()| {
+            inner.load(&req).and_then(move |state| {
+                let value = if let Some((state, value, ttl_override, stale)) = state {
+                    Session::set_session(
+                        state
+                            .iter()
+                            .map(|(key, value)| (key.clone(), value.to_string())),
+                        &mut req,
+                    );
+                    typed_state(&mut req.extensions_mut()).borrow_mut().values =
+                        state.clone();
+                    Some((value, ttl_override, state, stale))
                 } else {
-                    Either::B(ok(res))
-                }
+                    None
+                };
+
+                srv.call(req).and_then(move |mut res| {
+                    if let Some(state) = changes(&mut res) {
+                        Either::A(inner.update(res, state, value))
+                    } else {
+                        Either::B(ok(res))
+                    }
+                })
             })
         }))
     }
@@ -178,19 +785,39 @@ where
 
 struct Inner {
     key: Key,
+    fallback_keys: Vec<Key>,
     ttl: String,
+    ttl_jitter: i64,
     addr: Redis,
+    /// of the primary, and of the secondary if any
+    readiness: Vec<Readiness>,
+    backpressure: bool,
     name: String,
     path: String,
     domain: Option<String>,
     secure: bool,
     max_age: Option<Duration>,
     same_site: Option<SameSite>,
+    refresh_on_load: bool,
+    max_ttl: i64,
+    key_hash: KeyHash,
+    cookie_encoding: CookieEncoding,
+    /// the state is in the cookie, Redis only holds its version
+    client_state: bool,
+    wait_aof: Option<(i64, i64, i64)>,
+    consistency: SessionConsistency,
+    /// the session key holding the user, and the prefix of the index keys
+    user_index: Option<(String, String)>,
+    maintenance: Option<Rc<Maintenance>>,
 }
 
+#[derive(Clone)]
+#[allow(clippy::enum_variant_names)]
 enum Redis {
     Redis(Addr<RedisActor>),
     RedisCluster(Addr<RedisClusterActor>),
+    /// The primary, and the secondary used while the primary is unreachable
+    Failover(Box<Redis>, Addr<RedisActor>),
 }
 
 impl Redis {
@@ -201,6 +828,7 @@ impl Redis {
     where
         M: command::Command
             + Message<Result = Result<<M as command::Command>::Output, super::Error>>
+            + Clone
             + Send
             + 'static,
         <M as command::Command>::Output: Send + 'static,
@@ -210,117 +838,1036 @@ impl Redis {
         match self {
             Redis(addr) => Box::new(addr.send(msg)),
             RedisCluster(addr) => Box::new(addr.send(msg)),
+            Failover(primary, secondary) => {
+                let secondary = secondary.clone();
+                let retry = msg.clone();
+                Box::new(primary.send(msg).then(move |res| match res {
+                    Ok(Err(ref e)) if is_unreachable(e) => {
+                        Either::A(secondary.send(retry))
+                    }
+                    Err(_) => Either::A(secondary.send(retry)),
+                    res => Either::B(futures::future::result(res)),
+                }))
+            }
+        }
+    }
+
+    /// Wait until the preceding writes are acknowledged by replicas, on every
+    /// master in cluster mode. Resolves to the fewest acknowledgements of a
+    /// master.
+    fn wait_all(
+        &self,
+        wait: Wait,
+    ) -> ResponseFuture<Result<i64, super::Error>, MailboxError> {
+        match self {
+            Redis::RedisCluster(addr) => Box::new(
+                addr.send(Routed {
+                    routing: Routing::ToAllMasters,
+                    command: wait,
+                })
+                .map(|res| {
+                    res.map(|acks| {
+                        acks.into_iter()
+                            .map(|(_, replicas)| replicas)
+                            .min()
+                            .unwrap_or(0)
+                    })
+                }),
+            ),
+            _ => self.send(wait),
+        }
+    }
+}
+
+/// Whether the error means the server could not be reached, rather than the
+/// command failing
+#[allow(clippy::match_like_matches_macro)]
+fn is_unreachable(e: &super::Error) -> bool {
+    match e {
+        super::Error::NotConnected
+        | super::Error::Disconnected
+        | super::Error::CircuitOpen => true,
+        _ => false,
+    }
+}
+
+/// How long a session loaded or written is served from the cache during a
+/// maintenance
+const MAINTENANCE_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// The maintenance mode of a worker
+struct Maintenance {
+    mode: MaintenanceMode,
+    max_buffered: usize,
+    /// the sessions loaded or written recently, and when
+    cache: RefCell<HashMap<String, (Vec<u8>, Instant)>>,
+    last_purge: Cell<Instant>,
+    /// the last write of each session deferred until the maintenance ends
+    buffered: RefCell<HashMap<String, BufferedWrite>>,
+}
+
+#[derive(Clone)]
+struct BufferedWrite {
+    body: String,
+    expiration: Expiration,
+    /// the user index to add the session to
+    index: Option<String>,
+}
+
+impl Maintenance {
+    /// The session at `key` buffered, or cached if the maintenance mode is
+    /// enabled
+    fn load(&self, key: &str) -> Option<Vec<u8>> {
+        if let Some(write) = self.buffered.borrow().get(key) {
+            return Some(write.body.clone().into_bytes());
+        }
+        if !self.mode.is_enabled() {
+            return None;
+        }
+        match self.cache.borrow().get(key) {
+            Some((stored, at)) if at.elapsed() < MAINTENANCE_CACHE_TTL => {
+                Some(stored.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Cache the session `stored` at `key`
+    fn remember(&self, key: String, stored: Vec<u8>) {
+        let mut cache = self.cache.borrow_mut();
+        if self.last_purge.get().elapsed() >= MAINTENANCE_CACHE_TTL {
+            cache.retain(|_, (_, at)| at.elapsed() < MAINTENANCE_CACHE_TTL);
+            self.last_purge.set(Instant::now());
+        }
+        cache.insert(key, (stored, Instant::now()));
+    }
+
+    /// Drop the sessions at `keys` from the cache and the buffer, once deleted
+    fn forget(&self, keys: &[String]) {
+        let (mut cache, mut buffered) =
+            (self.cache.borrow_mut(), self.buffered.borrow_mut());
+        for key in keys {
+            cache.remove(key);
+            buffered.remove(key);
+        }
+    }
+
+    /// Buffer the write of the session at `key`, unless too many sessions are
+    /// buffered already
+    fn buffer(&self, key: String, write: BufferedWrite) -> Result<(), Error> {
+        let mut buffered = self.buffered.borrow_mut();
+        if buffered.len() >= self.max_buffered && !buffered.contains_key(&key) {
+            warn!("{} session writes are buffered already", buffered.len());
+            return Err(error::ErrorServiceUnavailable(
+                "session writes are suspended for a maintenance",
+            ));
+        }
+        buffered.insert(key, write);
+        Ok(())
+    }
+}
+
+/// Write the sessions buffered during a maintenance to `addr`. Fails with the
+/// first write refused, the failed writes staying buffered unless the session
+/// was written again meanwhile.
+fn flush(
+    maintenance: &Rc<Maintenance>,
+    addr: &Redis,
+) -> impl Future<Item = (), Error = Error> {
+    let buffered = maintenance.buffered.replace(HashMap::new());
+    let writes = buffered
+        .into_iter()
+        .map(|(key, write)| {
+            let set = addr
+                .send(Set {
+                    key: key.clone(),
+                    value: write.body.clone(),
+                    expiration: write.expiration.clone(),
+                })
+                .map_err(Error::from)
+                .and_then(|res| res.map_err(write_error));
+            let index = write.index.clone();
+            let add_to_index = {
+                let (addr, key, expiration) =
+                    (addr.clone(), key.clone(), write.expiration.clone());
+                move |()| match index {
+                    Some(index) => {
+                        Either::A(add_to_index(&addr, index, key, expiration))
+                    }
+                    None => Either::B(ok(())),
+                }
+            };
+            let maintenance = maintenance.clone();
+            set.and_then(add_to_index).then(move |res| {
+                if res.is_err() {
+                    maintenance
+                        .buffered
+                        .borrow_mut()
+                        .entry(key)
+                        .or_insert(write);
+                }
+                Ok::<_, Error>(res)
+            })
+        })
+        .collect::<Vec<_>>();
+    futures::future::join_all(writes)
+        .and_then(|results| results.into_iter().collect::<Result<Vec<_>, _>>())
+        .map(|_| ())
+}
+
+/// Load the session at `key`, resetting its expiration if given
+fn get_session(
+    addr: &Redis,
+    key: String,
+    expiration: Option<Expiration>,
+) -> impl Future<Item = Option<Vec<u8>>, Error = Error> {
+    let get = match expiration {
+        Some(expiration) => Either::A(addr.send(GetEx {
+            key: key.clone(),
+            expiration: Some(expiration),
+        })),
+        None => Either::B(addr.send(Get { key: key.clone() })),
+    };
+    get.map_err(Error::from).and_then(move |res| match res {
+        Ok(res) => Ok(res),
+        // another application wrote a value of another type at the key
+        Err(ref e) if is_wrong_type(e) => {
+            warn!("session key {} does not hold a string: {}", key, e);
+            Ok(None)
+        }
+        Err(e) => Err(error::ErrorInternalServerError(e)),
+    })
+}
+
+/// Whether Redis refused the command with `WRONGTYPE`, the key holding a
+/// value of another type
+fn is_wrong_type(e: &super::Error) -> bool {
+    match e {
+        super::Error::Redis(RespError::RESP(_, Some(RespValue::Error(ref msg))))
+        | super::Error::Redis(RespError::Remote(ref msg)) => {
+            msg.starts_with("WRONGTYPE ")
+        }
+        _ => false,
+    }
+}
+
+/// A command sent to the node holding the session
+#[derive(Clone)]
+struct OnSessionNode<C> {
+    key: String,
+    command: C,
+}
+
+impl<C> Message for OnSessionNode<C>
+where
+    C: command::Command,
+    C::Output: 'static,
+{
+    type Result = Result<C::Output, super::Error>;
+}
+
+impl<C: command::Command> command::Command for OnSessionNode<C> {
+    type Output = C::Output;
+
+    fn into_request(self) -> RespValue {
+        self.command.into_request()
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        C::from_response(res)
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+/// Delete the session at `key`, resolving to the number of keys deleted
+fn delete(addr: &Redis, key: String) -> impl Future<Item = i64, Error = Error> {
+    addr.send(command::Del { keys: vec![key] })
+        .map_err(Error::from)
+        .and_then(|res| res.map_err(error::ErrorInternalServerError))
+}
+
+/// Wait until the session write is acknowledged by replicas
+fn session_wait(
+    addr: &Redis,
+    key: String,
+    consistency: SessionConsistency,
+) -> impl Future<Item = (), Error = Error> {
+    let (num_replicas, timeout_ms) = match consistency {
+        SessionConsistency::Master => return Either::A(ok(())),
+        SessionConsistency::WaitReplica(num_replicas, timeout_ms) => {
+            (num_replicas, timeout_ms)
+        }
+    };
+    let command = Wait {
+        num_replicas,
+        timeout_ms,
+    };
+    Either::B(
+        addr.send(OnSessionNode { key, command })
+            .map_err(Error::from)
+            .and_then(|res| res.map_err(error::ErrorInternalServerError))
+            .map(move |replicas| {
+                if replicas < num_replicas {
+                    warn!("session write is acknowledged by {} replicas", replicas);
+                }
+            }),
+    )
+}
+
+/// Wait until the session write is fsynced to the AOF
+fn session_wait_aof(
+    addr: &Redis,
+    key: String,
+    (num_local, num_replicas, timeout_ms): (i64, i64, i64),
+) -> impl Future<Item = (), Error = Error> {
+    let command = WaitAof {
+        num_local,
+        num_replicas,
+        timeout_ms,
+    };
+    addr.send(OnSessionNode { key, command })
+        .map_err(Error::from)
+        .and_then(|res| res.map_err(error::ErrorInternalServerError))
+        .and_then(move |res| match res {
+            Ok((local, replicas)) => {
+                if local < num_local || replicas < num_replicas {
+                    warn!(
+                        "session write is fsynced by {} local and {} replicas",
+                        local, replicas
+                    );
+                }
+                Ok(())
+            }
+            Err(e) => Err(error::ErrorInternalServerError(e)),
+        })
+}
+
+/// The error of a failed session write: `503 Service Unavailable` with
+/// `Error::OutOfMemory` if Redis refused it for lack of memory, so the
+/// application can tell it apart from other failures
+fn write_error(e: super::Error) -> Error {
+    let out_of_memory = match e {
+        super::Error::Redis(RespError::RESP(_, Some(RespValue::Error(ref msg))))
+        | super::Error::Redis(RespError::Remote(ref msg)) => msg.starts_with("OOM "),
+        _ => false,
+    };
+    if out_of_memory {
+        error::ErrorServiceUnavailable(super::Error::OutOfMemory)
+    } else {
+        error::ErrorInternalServerError(e)
+    }
+}
+
+/// Only ever extend the expiration of the set: `GT` never shortens it, and a
+/// set without expiration is only given one when just created
+const ADD_TO_INDEX: &str = "\
+local ttl = redis.call('TTL', KEYS[1])
+redis.call('SADD', KEYS[1], ARGV[1])
+if ARGV[2] == '' then
+    return redis.call('PERSIST', KEYS[1])
+elseif ttl == -2 then
+    return redis.call('EXPIRE', KEYS[1], ARGV[2])
+elseif ttl >= 0 then
+    return redis.call('EXPIRE', KEYS[1], ARGV[2], 'GT')
+end
+return 0";
+
+/// Add the session at `key` to the set at `index`, which then expires no
+/// sooner than any of its sessions
+fn add_to_index(
+    addr: &Redis,
+    index: String,
+    key: String,
+    expiration: Expiration,
+) -> impl Future<Item = (), Error = Error> {
+    let seconds = match expiration {
+        Expiration::Ex(seconds) => seconds,
+        _ => String::new(),
+    };
+    addr.send(command::Eval {
+        script: ADD_TO_INDEX,
+        keys: vec![index],
+        args: vec![key.into(), seconds.into()],
+    })
+    .map_err(Error::from)
+    .and_then(|res| res.map_err(write_error))
+    .map(|_| ())
+}
+
+/// Reset the expiration of the session at `key` to `seconds`, or remove it
+fn expire(
+    addr: &Redis,
+    key: String,
+    seconds: Option<String>,
+) -> impl Future<Item = (), Error = Error> {
+    let res = match seconds {
+        Some(seconds) => Either::A(addr.send(command::Expire { key, seconds })),
+        None => Either::B(addr.send(command::Persist { key })),
+    };
+    res.map_err(Error::from)
+        .and_then(|res| res.map_err(error::ErrorInternalServerError))
+        .map(|_| ())
+}
+
+/// The hash tag of the sessions of `user`, which does not reveal the user
+fn user_tag(user: &str) -> String {
+    digest::digest(&digest::SHA256, user.as_bytes()).as_ref()[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// The key of the set tracking the sessions of `user`
+fn index_key(prefix: &str, user: &str) -> String {
+    format!("{}{{{}}}", prefix, user_tag(user))
+}
+
+/// The session `state` of `Session`, whose values are JSON, as JSON values
+fn json_values(state: impl Iterator<Item = (String, String)>) -> HashMap<String, Value> {
+    state
+        .map(|(key, value)| {
+            let value = serde_json::from_str(&value).unwrap_or(Value::String(value));
+            (key, value)
+        })
+        .collect()
+}
+
+/// A stored session
+#[derive(Serialize)]
+struct Stored<'a> {
+    v: u64,
+    values: &'a HashMap<String, Value>,
+}
+
+/// Encode the session `state` to be stored
+fn encode(state: &HashMap<String, Value>) -> serde_json::Result<String> {
+    serde_json::to_string(&Stored {
+        v: FORMAT_VERSION,
+        values: state,
+    })
+}
+
+/// The session id, the version and the state carried by the cookie of a
+/// client side session
+#[derive(Serialize)]
+struct Carried<'a> {
+    id: &'a str,
+    version: &'a str,
+    state: &'a HashMap<String, Value>,
+}
+
+/// Encode the session id, the version and the `state` carried by the cookie
+/// of a client side session
+fn encode_carried(
+    id: &str,
+    version: &str,
+    state: &HashMap<String, Value>,
+) -> serde_json::Result<String> {
+    serde_json::to_string(&Carried { id, version, state })
+}
+
+/// Decode the cookie of a client side session into the session id, the
+/// version and the state
+fn decode_carried(carried: &str) -> Option<(String, String, HashMap<String, Value>)> {
+    let mut carried: serde_json::Map<String, Value> =
+        serde_json::from_str(carried).ok()?;
+    match (
+        carried.remove("id"),
+        carried.remove("version"),
+        carried.remove("state"),
+    ) {
+        (
+            Some(Value::String(id)),
+            Some(Value::String(version)),
+            Some(Value::Object(state)),
+        ) => Some((id, version, state.into_iter().collect())),
+        _ => None,
+    }
+}
+
+/// Decode a stored session into its state
+fn decode(stored: &[u8]) -> Option<HashMap<String, Value>> {
+    let mut stored: serde_json::Map<String, Value> =
+        serde_json::from_slice(stored).ok()?;
+    if stored.len() == 2 && stored.get("v") == Some(&Value::from(FORMAT_VERSION)) {
+        return match stored.remove("values") {
+            Some(Value::Object(values)) => Some(values.into_iter().collect()),
+            _ => None,
+        };
+    }
+    // written by an earlier version, as strings holding JSON
+    let state = stored
+        .into_iter()
+        .map(|(key, value)| match value {
+            Value::String(value) => Some((key, value)),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(json_values(state.into_iter()))
+}
+
+/// The user of a session, from the value of its user field
+fn parse_user(user: &Value) -> String {
+    match user {
+        Value::String(user) => user.clone(),
+        user => user.to_string(),
+    }
+}
+
+/// Parse a stored session ttl override, which may mark the session as
+/// persisted
+fn parse_stored_override(ttl: &Value, max_ttl: i64) -> Option<i64> {
+    if ttl.as_i64() == Some(PERSISTED) {
+        Some(PERSISTED)
+    } else {
+        parse_ttl_override(ttl, max_ttl)
+    }
+}
+
+/// Parse a session persist marker
+fn parse_persist(persist: &Value) -> Option<bool> {
+    match persist.as_bool() {
+        Some(persist) => Some(persist),
+        None => {
+            warn!("invalid session persist marker {:?}", persist);
+            None
+        }
+    }
+}
+
+/// Parse a session ttl override, clamping it to `max_ttl`
+fn parse_ttl_override(ttl: &Value, max_ttl: i64) -> Option<i64> {
+    match ttl.as_i64() {
+        Some(ttl) if ttl > 0 => Some(cmp::min(ttl, max_ttl)),
+        _ => {
+            warn!("invalid session ttl override {:?}", ttl);
+            None
         }
     }
 }
 
 impl Inner {
+    #[allow(clippy::type_complexity)]
     fn load(
         &self,
         req: &ServiceRequest,
-    ) -> impl Future<Item = Option<(HashMap<String, String>, String)>, Error = Error>
-    {
-        if let Ok(cookies) = req.cookies() {
-            for cookie in cookies.iter() {
-                if cookie.name() == self.name {
-                    let mut jar = CookieJar::new();
-                    jar.add_original(cookie.clone());
-                    if let Some(cookie) = jar.signed(&self.key).get(&self.name) {
-                        let value = cookie.value().to_owned();
-                        return Either::A(
-                            self.addr
-                                .send(Get {
-                                    key: cookie.value().into(),
-                                })
-                                .map_err(From::from)
-                                .and_then(move |res| match res {
-                                    Ok(Some(s)) => {
-                                        if let Ok(val) = serde_json::from_slice(&s) {
-                                            Ok(Some((val, value)))
-                                        } else {
-                                            Ok(None)
-                                        }
-                                    }
-                                    Ok(None) => Ok(None),
-                                    Err(err) => {
-                                        Err(error::ErrorInternalServerError(err))
-                                    }
-                                }),
-                        );
-                    } else {
-                        return Either::B(ok(None));
+    ) -> impl Future<
+        Item = Option<(HashMap<String, Value>, String, Option<i64>, bool)>,
+        Error = Error,
+    > {
+        if let Some((value, carried, stale)) = self.session_cookie(req) {
+            let key = self.key_hash.apply(&value);
+            let expiration = if self.refresh_on_load {
+                Some(Expiration::Ex(self.jittered_ttl()))
+            } else {
+                None
+            };
+            let in_maintenance = self.in_maintenance();
+            let get = self.get_session(key.clone(), expiration.clone());
+            // a session missing on the primary may have been written to
+            // the secondary, it is then moved to the primary
+            let get = match self.addr {
+                Redis::Failover(_, ref secondary) if !in_maintenance => {
+                    let secondary = Redis::Redis(secondary.clone());
+                    let key = key.clone();
+                    Either::A(get.and_then(move |res| {
+                        match res {
+                            Some(res) => Either::A(ok((Some(res), stale))),
+                            None => Either::B(
+                                get_session(&secondary, key, expiration)
+                                    .map(|res| (res, true)),
+                            ),
+                        }
+                    }))
+                }
+                _ => Either::B(get.map(move |res| (res, stale))),
+            };
+            let addr = self.addr.clone();
+            let ttl = self.ttl.clone();
+            let max_ttl = self.max_ttl;
+            let refresh = self.refresh_on_load;
+            return Either::A(get.and_then(move |(res, stale)| {
+                let val = res.and_then(|s| match carried {
+                    // unless revoked or superseded
+                    Some((version, state)) => {
+                        Some(state).filter(|_| s == version.as_bytes())
                     }
+                    None => decode(&s),
+                });
+                let mut val = match val {
+                    Some(val) => val,
+                    None => return Either::B(ok(None)),
+                };
+                let ttl_override = val
+                    .remove(TTL_OVERRIDE_KEY)
+                    .and_then(|t| parse_stored_override(&t, max_ttl));
+
+                match ttl_override {
+                    // GETEX has reset the session to the default ttl
+                    Some(t) if refresh && !in_maintenance && t.to_string() != ttl => {
+                        let seconds = if t == PERSISTED {
+                            None
+                        } else {
+                            Some(t.to_string())
+                        };
+                        Either::A(
+                            expire(&addr, key, seconds)
+                                .map(move |()| Some((val, value, ttl_override, stale))),
+                        )
+                    }
+                    _ => Either::B(ok(Some((val, value, ttl_override, stale)))),
                 }
-            }
+            }));
         }
         Either::B(ok(None))
     }
 
+    fn in_maintenance(&self) -> bool {
+        match self.maintenance {
+            Some(ref maintenance) => maintenance.mode.is_enabled(),
+            None => false,
+        }
+    }
+
+    /// Load the session at `key`, from the buffered writes or the cache of the
+    /// maintenance mode if it has it
+    fn get_session(
+        &self,
+        key: String,
+        expiration: Option<Expiration>,
+    ) -> impl Future<Item = Option<Vec<u8>>, Error = Error> {
+        let maintenance = match self.maintenance {
+            Some(ref maintenance) => maintenance.clone(),
+            None => return Either::A(get_session(&self.addr, key, expiration)),
+        };
+        if let Some(stored) = maintenance.load(&key) {
+            return Either::B(Either::A(ok(Some(stored))));
+        }
+        if maintenance.mode.is_enabled() {
+            return Either::B(Either::A(ok(None)));
+        }
+        Either::B(Either::B(
+            get_session(&self.addr, key.clone(), expiration).map(move |res| {
+                if let Some(ref stored) = res {
+                    maintenance.remember(key, stored.clone());
+                }
+                res
+            }),
+        ))
+    }
+
+    /// The session id in the session cookie of `req`, the state the cookie
+    /// carries with `client_state`, and whether the cookie is stale
+    #[allow(clippy::type_complexity)]
+    fn session_cookie<R: HttpMessage>(
+        &self,
+        req: &R,
+    ) -> Option<(String, Option<(String, HashMap<String, Value>)>, bool)> {
+        let cookies = req.cookies().ok()?;
+        let cookie = cookies.iter().find(|cookie| cookie.name() == self.name)?;
+        let mut jar = CookieJar::new();
+        jar.add_original(cookie.clone());
+        // stale if signed with a fallback key, written back even if unmodified
+        let (cookie, stale) = iter::once(&self.key)
+            .chain(self.fallback_keys.iter())
+            .enumerate()
+            .filter_map(|(i, key)| {
+                let cookie = if self.client_state {
+                    jar.private(key).get(&self.name)
+                } else {
+                    jar.signed(key).get(&self.name)
+                };
+                cookie.map(|c| (c, i > 0))
+            })
+            .next()?;
+        if self.client_state {
+            let (id, version, state) = decode_carried(cookie.value())?;
+            Some((id, Some((version, state)), stale))
+        } else {
+            let id = self.cookie_encoding.decode(cookie.value())?;
+            Some((id, None, stale))
+        }
+    }
+
+    /// The default ttl with a random jitter added
+    fn jittered_ttl(&self) -> String {
+        if self.ttl_jitter == 0 {
+            return self.ttl.clone();
+        }
+        match self.ttl.parse::<i64>() {
+            Ok(ttl) => (ttl + OsRng.gen_range(0, self.ttl_jitter + 1)).to_string(),
+            Err(_) => self.ttl.clone(),
+        }
+    }
+
+    /// Reset the ttl of a session that is not written back, unless loading it
+    /// has done so
+    fn refresh(
+        &self,
+        value: &str,
+        ttl_override: Option<i64>,
+    ) -> impl Future<Item = (), Error = Error> {
+        if self.refresh_on_load || self.in_maintenance() {
+            return Either::A(ok(()));
+        }
+        let seconds = match ttl_override {
+            Some(PERSISTED) => None,
+            Some(ttl) => Some(ttl.to_string()),
+            None => Some(self.jittered_ttl()),
+        };
+        Either::B(expire(&self.addr, self.key_hash.apply(value), seconds))
+    }
+
+    #[allow(clippy::type_complexity)]
     fn update<B>(
         &self,
-        mut res: ServiceResponse<B>,
-        state: impl Iterator<Item = (String, String)>,
-        value: Option<String>,
+        res: ServiceResponse<B>,
+        mut state: HashMap<String, Value>,
+        value: Option<(String, Option<i64>, HashMap<String, Value>, bool)>,
     ) -> impl Future<Item = ServiceResponse<B>, Error = Error> {
-        let (value, jar) = if let Some(value) = value {
-            (value.clone(), None)
-        } else {
-            let value: String = iter::repeat(())
-                .map(|()| OsRng.sample(Alphanumeric))
-                .take(32)
-                .collect();
+        // an override set during this request replaces the stored one
+        let new_override = state
+            .remove(TTL_OVERRIDE_KEY)
+            .and_then(|ttl| parse_ttl_override(&ttl, self.max_ttl));
+        let persist = state.remove(PERSIST_KEY).and_then(|p| parse_persist(&p));
+        let new_override = match persist {
+            Some(true) => Some(PERSISTED),
+            _ => new_override,
+        };
 
-            // prepare session id cookie
-            let mut cookie = Cookie::new(self.name.clone(), value.clone());
-            cookie.set_path(self.path.clone());
-            cookie.set_secure(self.secure);
-            cookie.set_http_only(true);
+        let stale = match value {
+            Some((_, _, _, stale)) => stale,
+            None => false,
+        };
 
-            if let Some(ref domain) = self.domain {
-                cookie.set_domain(domain.clone());
+        // the handler did not modify the loaded session
+        if let Some((ref value, stored, ref loaded, _)) = value {
+            if new_override.is_none() && persist.is_none() && !stale && state == *loaded
+            {
+                return Either::A(self.refresh(value, stored).map(move |()| res));
             }
+        }
 
-            if let Some(max_age) = self.max_age {
-                cookie.set_max_age(max_age);
+        let (value, ttl_override) = match value {
+            // restore the default ttl, or the one set during this request
+            Some((value, Some(PERSISTED), _, _)) if persist == Some(false) => {
+                (Some(value), new_override)
             }
+            Some((value, stored, _, _)) => (Some(value), new_override.or(stored)),
+            None => (None, new_override),
+        };
+
+        let user = self
+            .user_index
+            .as_ref()
+            .and_then(|(field, _)| state.get(field).map(parse_user));
 
-            if let Some(same_site) = self.same_site {
-                cookie.set_same_site(same_site);
+        let (value, new_id) = match value {
+            Some(value) if new_override.is_none() && persist.is_none() && !stale => {
+                (value, false)
+            }
+            value => {
+                let value = value.unwrap_or_else(|| {
+                    let id: String = iter::repeat(())
+                        .map(|()| OsRng.sample(Alphanumeric))
+                        .take(32)
+                        .collect();
+                    // in the slot of the index of the user
+                    match user {
+                        Some(ref user) => format!("{{{}}}{}", user_tag(user), id),
+                        None => id,
+                    }
+                });
+                (value, true)
             }
+        };
 
-            // set cookie
-            let mut jar = CookieJar::new();
+        if let Some(ttl) = ttl_override {
+            state.insert(TTL_OVERRIDE_KEY.to_owned(), Value::from(ttl));
+        }
+        let expiration = match ttl_override {
+            Some(PERSISTED) => Expiration::Infinite,
+            Some(ttl) => Expiration::Ex(ttl.to_string()),
+            None => Expiration::Ex(self.jittered_ttl()),
+        };
+
+        let written = if self.client_state {
+            // the cookie carries the state, it is set on every write
+            let version: String = iter::repeat(())
+                .map(|()| OsRng.sample(Alphanumeric))
+                .take(16)
+                .collect();
+            encode_carried(&value, &version, &state)
+                .map(|carried| (version, Some(self.cookie(carried, ttl_override))))
+        } else {
+            encode(&state).map(|body| {
+                let jar = if new_id {
+                    let cookie = self.cookie_encoding.encode(&value);
+                    Some(self.cookie(cookie, ttl_override))
+                } else {
+                    None
+                };
+                (body, jar)
+            })
+        };
+        let (body, jar) = match written {
+            Ok(written) => written,
+            Err(e) => return Either::B(Either::A(err(e.into()))),
+        };
+
+        let key = self.key_hash.apply(&value);
+        let index = match (&self.user_index, user) {
+            (Some((_, prefix)), Some(user)) => Some(index_key(prefix, &user)),
+            _ => None,
+        };
+        Either::B(Either::B(
+            self.write(res, key, body, expiration, jar, index),
+        ))
+    }
+
+    /// The session cookie carrying `value`, signed, or encrypted for client side
+    /// sessions
+    fn cookie(&self, value: String, ttl_override: Option<i64>) -> CookieJar {
+        let mut cookie = Cookie::new(self.name.clone(), value);
+        cookie.set_path(self.path.clone());
+        cookie.set_secure(self.secure);
+        cookie.set_http_only(true);
+
+        if let Some(ref domain) = self.domain {
+            cookie.set_domain(domain.clone());
+        }
+
+        let max_age = match ttl_override {
+            Some(PERSISTED) => Some(Duration::seconds(self.max_ttl)),
+            Some(ttl) => Some(Duration::seconds(ttl)),
+            None => self.max_age,
+        };
+        if let Some(max_age) = max_age {
+            cookie.set_max_age(max_age);
+        }
+
+        if let Some(same_site) = self.same_site {
+            cookie.set_same_site(same_site);
+        }
+
+        let mut jar = CookieJar::new();
+        if self.client_state {
+            jar.private(&self.key).add(cookie);
+        } else {
             jar.signed(&self.key).add(cookie);
+        }
+        jar
+    }
+
+    /// Write the session `body` at `key`, and add it to the user `index` if any
+    ///
+    /// The writes and `WAIT`/`WAITAOF` are pipelined if they are sent to the
+    /// same node, since `WAIT` covers the writes sent before it on the
+    /// connection. Otherwise they are sent one after another.
+    fn write<B>(
+        &self,
+        res: ServiceResponse<B>,
+        key: String,
+        body: String,
+        expiration: Expiration,
+        jar: Option<CookieJar>,
+        index: Option<String>,
+    ) -> impl Future<Item = ServiceResponse<B>, Error = Error> {
+        if let Some(ref maintenance) = self.maintenance {
+            if maintenance.mode.is_enabled() {
+                let write = BufferedWrite {
+                    body,
+                    expiration,
+                    index,
+                };
+                return Either::A(
+                    maintenance
+                        .buffer(key, write)
+                        .and_then(move |()| set_cookies(res, jar))
+                        .into_future(),
+                );
+            }
+        }
+
+        let addr = self.addr.clone();
+        let wait_aof = self.wait_aof;
+        let consistency = self.consistency;
+        let aligned = match (&self.addr, &index) {
+            (Redis::RedisCluster(_), Some(index)) => {
+                hash_slot(key.as_bytes()) == hash_slot(index.as_bytes())
+            }
+            _ => true,
+        };
+
+        let set = {
+            let addr = addr.clone();
+            let key = key.clone();
+            let expiration = expiration.clone();
+            // cached for a maintenance
+            let cached = self
+                .maintenance
+                .clone()
+                .map(|maintenance| (maintenance, body.clone().into_bytes()));
+            move || {
+                addr.send(Set {
+                    key: key.clone(),
+                    value: body,
+                    expiration,
+                })
+                .map_err(Error::from)
+                .and_then(|res| res.map_err(write_error))
+                .map(move |()| {
+                    if let Some((maintenance, stored)) = cached {
+                        maintenance.remember(key, stored);
+                    }
+                })
+            }
+        };
+        let add_to_index = {
+            let addr = addr.clone();
+            let key = key.clone();
+            move || match index {
+                Some(index) => Either::A(add_to_index(&addr, index, key, expiration)),
+                None => Either::B(ok(())),
+            }
+        };
+        let wait = move || {
+            session_wait(&addr, key.clone(), consistency).join(match wait_aof {
+                Some(wait) => Either::A(session_wait_aof(&addr, key, wait)),
+                None => Either::B(ok(())),
+            })
+        };
 
-            (value, Some(jar))
+        let written = if aligned {
+            // all written before any reply is awaited, in this order
+            Either::A(set().join3(add_to_index(), wait()).map(|_| ()))
+        } else {
+            Either::B(
+                set()
+                    .and_then(move |()| add_to_index())
+                    .and_then(move |()| wait())
+                    .map(|_| ()),
+            )
         };
 
-        let state: HashMap<_, _> = state.collect();
+        Either::B(written.and_then(move |()| set_cookies(res, jar)))
+    }
+}
 
-        match serde_json::to_string(&state) {
-            Err(e) => Either::A(err(e.into())),
-            Ok(body) => Either::B(
-                self.addr
-                    .send(Set {
-                        key: value,
-                        value: body,
-                        expiration: Expiration::Ex(self.ttl.clone()),
-                    })
-                    .map_err(Error::from)
-                    .and_then(move |redis_result| match redis_result {
-                        Ok(_) => {
-                            if let Some(jar) = jar {
-                                for cookie in jar.delta() {
-                                    let val =
-                                        HeaderValue::from_str(&cookie.to_string())?;
-                                    res.headers_mut().append(header::SET_COOKIE, val);
-                                }
-                            }
-                            Ok(res)
-                        }
-                        Err(err) => Err(error::ErrorInternalServerError(err)),
-                    }),
-            ),
+/// Add the cookies of `jar`, if any, to the response
+fn set_cookies<B>(
+    mut res: ServiceResponse<B>,
+    jar: Option<CookieJar>,
+) -> Result<ServiceResponse<B>, Error> {
+    if let Some(jar) = jar {
+        for cookie in jar.delta() {
+            let val = HeaderValue::from_str(&cookie.to_string())?;
+            res.headers_mut().append(header::SET_COOKIE, val);
         }
     }
+    Ok(res)
+}
+
+/// Typed access to the session of a request
+///
+/// Values are kept as JSON values, serialized once when inserted, and the
+/// session is encoded once when written. `actix_session::Session` can be used
+/// alongside: changes made through `TypedSession` are applied after the ones
+/// made through `Session`.
+pub struct TypedSession(Rc<RefCell<TypedState>>);
+
+#[derive(Default)]
+struct TypedState {
+    values: HashMap<String, Value>,
+    /// the keys inserted or removed
+    changed: HashSet<String>,
+    cleared: bool,
+}
+
+impl TypedSession {
+    /// Get the value of `key`, if any
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Error> {
+        match self.0.borrow().values.get(key) {
+            Some(value) => Ok(Some(T::deserialize(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Set the value of `key`
+    pub fn insert<T: Serialize>(&self, key: &str, value: T) -> Result<(), Error> {
+        let value = serde_json::to_value(value)?;
+        let mut state = self.0.borrow_mut();
+        state.values.insert(key.to_owned(), value);
+        state.changed.insert(key.to_owned());
+        Ok(())
+    }
+
+    /// Remove the value of `key`
+    pub fn remove(&self, key: &str) {
+        let mut state = self.0.borrow_mut();
+        state.values.remove(key);
+        state.changed.insert(key.to_owned());
+    }
+
+    /// Remove every value
+    pub fn clear(&self) {
+        let mut state = self.0.borrow_mut();
+        state.values.clear();
+        state.changed.clear();
+        state.cleared = true;
+    }
+}
+
+impl FromRequest for TypedSession {
+    type Error = Error;
+    type Future = Result<TypedSession, Error>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        Ok(TypedSession(typed_state(&mut req.extensions_mut())))
+    }
+}
+
+/// The state of the `TypedSession` of a request, created if missing
+fn typed_state(extensions: &mut Extensions) -> Rc<RefCell<TypedState>> {
+    if let Some(state) = extensions.get::<Rc<RefCell<TypedState>>>() {
+        return state.clone();
+    }
+    let state = Rc::new(RefCell::new(TypedState::default()));
+    extensions.insert(state.clone());
+    state
+}
+
+/// The session state after the request: the loaded one with the changes made
+/// through `Session`, then through `TypedSession`. `None` without session.
+fn changes<B>(res: &mut ServiceResponse<B>) -> Option<HashMap<String, Value>> {
+    let session = match Session::get_changes(res) {
+        (SessionStatus::Unchanged, _) => None,
+        (_, state) => state.map(json_values),
+    };
+    let typed = res
+        .request()
+        .extensions_mut()
+        .remove::<Rc<RefCell<TypedState>>>();
+    let mut typed = match typed {
+        Some(typed) => typed.replace(TypedState::default()),
+        None => return session,
+    };
+    let mut state = match session {
+        Some(state) => state,
+        None => return Some(typed.values),
+    };
+    if typed.cleared {
+        state.clear();
+    }
+    for key in typed.changed {
+        match typed.values.remove(&key) {
+            Some(value) => state.insert(key, value),
+            None => state.remove(&key),
+        };
+    }
+    Some(state)
 }
@@ -96,6 +96,15 @@ pub fn hash_slot(key: &[u8]) -> u16 {
     crc & 0x3FFF
 }
 
+/// Pick a slot uniformly at random
+pub fn random_slot() -> u16 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    // every `RandomState` hashes with distinct random keys
+    (RandomState::new().build_hasher().finish() & 0x3FFF) as u16
+}
+
 #[derive(Debug)]
 pub struct HashError {
     expected: u16,
@@ -0,0 +1,20 @@
+//! Single-use tokens, e.g. for password resets or email confirmations
+use ::actix::prelude::*;
+use futures::Future;
+
+use crate::command::GetDel;
+use crate::{reply, Error};
+
+/// Consume the token stored at `key`: its payload is read and the key deleted
+/// in one `GETDEL`, so a token is never accepted twice, even by concurrent
+/// requests. `redis` is the recipient of a `RedisActor` or
+/// `RedisClusterActor`.
+///
+/// Resolves to the payload, or `None` if the token does not exist, has expired
+/// or was already consumed. Requires Redis 6.2 or later.
+pub fn consume<S: Into<String>>(
+    redis: &Recipient<GetDel>,
+    key: S,
+) -> impl Future<Item = Option<Vec<u8>>, Error = Error> {
+    reply(redis.send(GetDel { key: key.into() }))
+}
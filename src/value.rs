@@ -0,0 +1,393 @@
+//! Conversions between Redis values and Rust types
+//!
+//! The built-in commands decode their replies through `FromRedisValue` and
+//! encode their arguments through `ToRedisArgs`, and a custom command can do
+//! the same.
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use bytes::Bytes;
+use redis_async::resp::RespValue;
+
+/// Decode a reply of Redis
+pub trait FromRedisValue: Sized {
+    /// Decode `value`, or return the part of it that has an unexpected type
+    fn from_redis_value(value: RespValue) -> Result<Self, RespValue>;
+}
+
+impl FromRedisValue for i64 {
+    fn from_redis_value(value: RespValue) -> Result<Self, RespValue> {
+        match value {
+            RespValue::Integer(n) => Ok(n),
+            value => Err(value),
+        }
+    }
+}
+
+/// `0` or `1`
+impl FromRedisValue for bool {
+    fn from_redis_value(value: RespValue) -> Result<Self, RespValue> {
+        match value {
+            RespValue::Integer(0) => Ok(false),
+            RespValue::Integer(1) => Ok(true),
+            value => Err(value),
+        }
+    }
+}
+
+/// A simple string, or a bulk string in UTF-8
+impl FromRedisValue for String {
+    fn from_redis_value(value: RespValue) -> Result<Self, RespValue> {
+        match value {
+            RespValue::SimpleString(s) => Ok(s),
+            RespValue::BulkString(s) => {
+                String::from_utf8(s).map_err(|e| RespValue::BulkString(e.into_bytes()))
+            }
+            value => Err(value),
+        }
+    }
+}
+
+/// A bulk string
+impl FromRedisValue for Vec<u8> {
+    fn from_redis_value(value: RespValue) -> Result<Self, RespValue> {
+        match value {
+            RespValue::BulkString(s) => Ok(s),
+            value => Err(value),
+        }
+    }
+}
+
+/// `None` for a nil reply
+impl<T: FromRedisValue> FromRedisValue for Option<T> {
+    fn from_redis_value(value: RespValue) -> Result<Self, RespValue> {
+        match value {
+            RespValue::Nil => Ok(None),
+            value => T::from_redis_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: FromRedisValue> FromRedisValue for Vec<T> {
+    fn from_redis_value(value: RespValue) -> Result<Self, RespValue> {
+        match value {
+            RespValue::Array(values) => {
+                values.into_iter().map(T::from_redis_value).collect()
+            }
+            value => Err(value),
+        }
+    }
+}
+
+/// An array of keys each followed by its value, as returned by `HGETALL`
+impl<K, V> FromRedisValue for HashMap<K, V>
+where
+    K: FromRedisValue + Eq + Hash,
+    V: FromRedisValue,
+{
+    fn from_redis_value(value: RespValue) -> Result<Self, RespValue> {
+        match value {
+            RespValue::Array(values) if values.len() % 2 == 0 => {
+                let mut map = HashMap::with_capacity(values.len() / 2);
+                let mut values = values.into_iter();
+                while let (Some(key), Some(value)) = (values.next(), values.next()) {
+                    map.insert(K::from_redis_value(key)?, V::from_redis_value(value)?);
+                }
+                Ok(map)
+            }
+            value => Err(value),
+        }
+    }
+}
+
+/// Any reply but an error, ignored, e.g. the `OK` of `SET`
+impl FromRedisValue for () {
+    fn from_redis_value(value: RespValue) -> Result<Self, RespValue> {
+        match value {
+            RespValue::Error(_) => Err(value),
+            _ => Ok(()),
+        }
+    }
+}
+
+macro_rules! tuple_values {
+    ($len:expr, $($name:ident),*) => {
+        /// An array of as many elements, each decoded in order
+        impl<$($name: FromRedisValue),*> FromRedisValue for ($($name,)*) {
+            fn from_redis_value(value: RespValue) -> Result<Self, RespValue> {
+                match value {
+                    RespValue::Array(values) if values.len() == $len => {
+                        let mut values = values.into_iter();
+                        Ok(($($name::from_redis_value(values.next().unwrap())?,)*))
+                    }
+                    value => Err(value),
+                }
+            }
+        }
+    };
+}
+
+tuple_values!(1, A);
+tuple_values!(2, A, B);
+tuple_values!(3, A, B, C);
+tuple_values!(4, A, B, C, D);
+tuple_values!(5, A, B, C, D, E);
+tuple_values!(6, A, B, C, D, E, F);
+tuple_values!(7, A, B, C, D, E, F, G);
+tuple_values!(8, A, B, C, D, E, F, G, H);
+
+/// Encode the arguments of a command, each as a bulk string
+///
+/// Numbers are written in decimal, which is how Redis parses them. `u8` is not
+/// an argument on its own: `Vec<u8>` and `&[u8]` are bytes, while the vectors
+/// and slices of other arguments are their arguments in order.
+pub trait ToRedisArgs {
+    /// Append the arguments to `args`
+    fn write_redis_args(self, args: &mut Vec<RespValue>);
+
+    /// The arguments
+    fn to_redis_args(self) -> Vec<RespValue>
+    where
+        Self: Sized,
+    {
+        let mut args = Vec::new();
+        self.write_redis_args(&mut args);
+        args
+    }
+}
+
+macro_rules! display_args {
+    ($($t:ty),*) => {
+        $(
+            impl ToRedisArgs for $t {
+                fn write_redis_args(self, args: &mut Vec<RespValue>) {
+                    args.push(RespValue::BulkString(self.to_string().into_bytes()));
+                }
+            }
+        )*
+    };
+}
+
+// `Display` of floats never uses exponents nor locale dependent separators,
+// and writes the shortest representation parsing back to the same value,
+// e.g. `1` for `1.0` and `inf` for infinity, all of which Redis accepts.
+display_args!(i16, i32, i64, isize, u16, u32, u64, usize, f32, f64);
+
+impl ToRedisArgs for String {
+    fn write_redis_args(self, args: &mut Vec<RespValue>) {
+        args.push(RespValue::BulkString(self.into_bytes()));
+    }
+}
+
+impl ToRedisArgs for &str {
+    fn write_redis_args(self, args: &mut Vec<RespValue>) {
+        args.push(RespValue::BulkString(self.as_bytes().to_vec()));
+    }
+}
+
+impl ToRedisArgs for &String {
+    fn write_redis_args(self, args: &mut Vec<RespValue>) {
+        self.as_str().write_redis_args(args)
+    }
+}
+
+impl ToRedisArgs for Vec<u8> {
+    fn write_redis_args(self, args: &mut Vec<RespValue>) {
+        args.push(RespValue::BulkString(self));
+    }
+}
+
+impl ToRedisArgs for &[u8] {
+    fn write_redis_args(self, args: &mut Vec<RespValue>) {
+        args.push(RespValue::BulkString(self.to_vec()));
+    }
+}
+
+impl ToRedisArgs for Bytes {
+    fn write_redis_args(self, args: &mut Vec<RespValue>) {
+        args.push(RespValue::BulkString(self.to_vec()));
+    }
+}
+
+/// No argument for `None`
+impl<T: ToRedisArgs> ToRedisArgs for Option<T> {
+    fn write_redis_args(self, args: &mut Vec<RespValue>) {
+        if let Some(value) = self {
+            value.write_redis_args(args)
+        }
+    }
+}
+
+impl<T: ToRedisArgs> ToRedisArgs for Vec<T> {
+    fn write_redis_args(self, args: &mut Vec<RespValue>) {
+        for value in self {
+            value.write_redis_args(args)
+        }
+    }
+}
+
+impl<T: ToRedisArgs + Clone> ToRedisArgs for &[T] {
+    fn write_redis_args(self, args: &mut Vec<RespValue>) {
+        for value in self {
+            value.clone().write_redis_args(args)
+        }
+    }
+}
+
+/// The arguments of each item in order, e.g. pairs of fields and values
+pub struct Iter<I>(pub I);
+
+impl<I> ToRedisArgs for Iter<I>
+where
+    I: IntoIterator,
+    I::Item: ToRedisArgs,
+{
+    fn write_redis_args(self, args: &mut Vec<RespValue>) {
+        for value in self.0 {
+            value.write_redis_args(args)
+        }
+    }
+}
+
+macro_rules! tuple_args {
+    ($($name:ident),*) => {
+        /// The arguments of each element in order
+        impl<$($name: ToRedisArgs),*> ToRedisArgs for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn write_redis_args(self, args: &mut Vec<RespValue>) {
+                let ($($name,)*) = self;
+                $($name.write_redis_args(args);)*
+            }
+        }
+    };
+}
+
+tuple_args!(A);
+tuple_args!(A, B);
+tuple_args!(A, B, C);
+tuple_args!(A, B, C, D);
+tuple_args!(A, B, C, D, E);
+tuple_args!(A, B, C, D, E, F);
+tuple_args!(A, B, C, D, E, F, G);
+tuple_args!(A, B, C, D, E, F, G, H);
+
+/// Deserialize the `fields` of a hash into `T`, each value being the string
+/// form of a scalar of the type of its field
+#[cfg(feature = "serde")]
+pub(crate) fn from_hash<T: serde::de::DeserializeOwned>(
+    fields: HashMap<String, String>,
+) -> Result<T, serde::de::value::Error> {
+    use serde::de::value::MapDeserializer;
+
+    let fields = fields
+        .into_iter()
+        .map(|(field, value)| (field, Scalar(value)));
+    T::deserialize(MapDeserializer::new(fields))
+}
+
+/// The value of a hash field: a number, a boolean (`true`, `false`, `1` or
+/// `0`), a string or a unit enum variant
+#[cfg(feature = "serde")]
+struct Scalar(String);
+
+#[cfg(feature = "serde")]
+macro_rules! parse_scalar {
+    ($($deserialize:ident => $visit:ident,)*) => {
+        $(
+            fn $deserialize<V: serde::de::Visitor<'de>>(
+                self,
+                visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(&self.0),
+                        &visitor,
+                    )),
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Deserializer<'de> for Scalar {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_bool<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.0.as_str() {
+            "true" | "1" => visitor.visit_bool(true),
+            "false" | "0" => visitor.visit_bool(false),
+            _ => Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(&self.0),
+                &visitor,
+            )),
+        }
+    }
+
+    parse_scalar! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    /// A field present in the hash is `Some`
+    fn deserialize_option<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: serde::de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: serde::de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        use serde::de::IntoDeserializer;
+
+        let variant: serde::de::value::StringDeserializer<Self::Error> =
+            self.0.into_deserializer();
+        variant.deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::IntoDeserializer<'de> for Scalar {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
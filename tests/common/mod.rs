@@ -0,0 +1,76 @@
+//! Mock servers shared by the tests
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+
+/// Read the next command of a client as its arguments, `None` once the client
+/// closed the connection
+pub fn read_command<R: BufRead>(reader: &mut R) -> Option<Vec<String>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    let n: usize = line[1..].trim().parse().unwrap();
+    let mut args = Vec::with_capacity(n);
+    for _ in 0..n {
+        let mut len = String::new();
+        reader.read_line(&mut len).unwrap();
+        let len: usize = len[1..].trim().parse().unwrap();
+        // the argument may span lines, e.g. a script
+        let mut arg = vec![0; len + 2];
+        reader.read_exact(&mut arg).unwrap();
+        arg.truncate(len);
+        args.push(String::from_utf8(arg).unwrap());
+    }
+    Some(args)
+}
+
+/// Serve each connection accepted by `listener` on a thread of its own, with
+/// the handler `connect` returns for it. The handler answers each command, or
+/// drops the connection by returning `None`.
+pub fn serve<C, H>(listener: TcpListener, mut connect: C)
+where
+    C: FnMut() -> H + Send + 'static,
+    H: FnMut(&[String]) -> Option<String> + Send + 'static,
+{
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut handler = connect();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                while let Some(args) = read_command(&mut reader) {
+                    match handler(&args) {
+                        Some(reply) => stream.write_all(reply.as_bytes()).unwrap(),
+                        None => return,
+                    }
+                }
+            });
+        }
+    });
+}
+
+/// Answer each command received by `listener` with `reply`
+pub fn mock_node<F>(listener: TcpListener, reply: F)
+where
+    F: Fn(&[String]) -> String + Clone + Send + 'static,
+{
+    serve(listener, move || {
+        let reply = reply.clone();
+        move |args: &[String]| Some(reply(args))
+    });
+}
+
+/// Start a server on a free port answering each command with `reply`.
+/// Returns its address.
+pub fn mock_server<F>(reply: F) -> String
+where
+    F: Fn(&[String]) -> String + Clone + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    mock_node(listener, reply);
+    addr
+}
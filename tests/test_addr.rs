@@ -0,0 +1,103 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::thread;
+
+use actix::prelude::*;
+use actix_redis::addr::Address;
+use actix_redis::{command::*, RedisActor, RespValue};
+use futures::Future;
+
+#[test]
+fn test_parse_address() {
+    let ip = |addr: &str| Some(Address::Ip(addr.parse::<SocketAddr>().unwrap()));
+
+    assert_eq!(
+        Address::parse("[2001:db8::1]:6379"),
+        ip("[2001:db8::1]:6379")
+    );
+    assert_eq!(Address::parse("[::1]:6379"), ip("[::1]:6379"));
+    assert_eq!(Address::parse("127.0.0.1:6379"), ip("127.0.0.1:6379"));
+    assert_eq!(
+        Address::parse("redis.example.com:6379"),
+        Some(Address::Host("redis.example.com".into(), 6379))
+    );
+    // as announced in redirections
+    assert_eq!(Address::parse("2001:db8::1:7000"), ip("[2001:db8::1]:7000"));
+
+    match Address::parse("[fe80::1%2]:6379") {
+        Some(Address::Ip(SocketAddr::V6(addr))) => {
+            assert_eq!(addr.ip(), &"fe80::1".parse::<std::net::Ipv6Addr>().unwrap());
+            assert_eq!(addr.scope_id(), 2);
+            assert_eq!(addr.port(), 6379);
+        }
+        addr => panic!("Should not happen {:?}", addr),
+    }
+    assert_eq!(
+        Address::parse("[fe80::1%2]:6379").unwrap().to_string(),
+        "[fe80::1%2]:6379"
+    );
+
+    for addr in &[
+        "[::1]",
+        "[::1]6379",
+        "[::1]:",
+        "[::1]:port",
+        "[not-an-ip]:6379",
+        "[fe80::1%eth0]:6379",
+        "localhost",
+        ":6379",
+    ] {
+        assert_eq!(Address::parse(addr), None, "{}", addr);
+    }
+}
+
+#[test]
+fn test_cluster_slots_ipv6() {
+    let node = |ip: &str, port| {
+        RespValue::Array(vec![
+            RespValue::BulkString(ip.into()),
+            RespValue::Integer(port),
+            RespValue::BulkString(b"09dbe9720cda62f7865eabc5fd8857c5d2678366".to_vec()),
+        ])
+    };
+    let res = RespValue::Array(vec![RespValue::Array(vec![
+        RespValue::Integer(0),
+        RespValue::Integer(16383),
+        node("2001:db8:0::1", 7000),
+        node("2001:db8::2", 7001),
+    ])]);
+
+    let slots = ClusterSlots::from_response(res).unwrap();
+    assert_eq!(slots[0].master(), "[2001:db8::1]:7000");
+    // the node announces itself the same way when redirecting
+    assert_eq!(Address::normalize("2001:db8::1:7000"), slots[0].master());
+}
+
+#[test]
+fn test_connect_ipv6() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-connect-ipv6");
+
+    let listener = TcpListener::bind("[::1]:0").unwrap();
+    let server = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 64];
+        let _ = stream.read(&mut buf).unwrap();
+        stream.write_all(b"+PONG\r\n").unwrap();
+        let _ = stream.read(&mut buf);
+    });
+
+    Arbiter::spawn_fn(move || {
+        assert!(server.starts_with("[::1]:"));
+        RedisActor::start(server)
+            .send(Ping(None))
+            .map(|res| {
+                assert_eq!(res.unwrap(), "PONG");
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,69 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+
+use actix::prelude::*;
+use actix_redis::{command::*, RedisActor};
+use futures::{future, Future};
+
+/// Minimal server reporting the number of commands of each read, and answering
+/// every `GET` with a nil
+fn mock_server() -> (u16, mpsc::Receiver<usize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 4096];
+        loop {
+            let n = match stream.read(&mut buf) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => n,
+            };
+            let commands = buf[..n].windows(5).filter(|w| w == b"\r\nGET").count();
+            tx.send(commands).unwrap();
+            for _ in 0..commands {
+                stream.write_all(b"$-1\r\n").unwrap();
+            }
+        }
+    });
+    (port, rx)
+}
+
+#[test]
+fn test_auto_pipelining() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-auto-pipelining");
+    let (port, writes) = mock_server();
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisActor::start(format!("127.0.0.1:{}", port));
+        let gets = move || {
+            let gets = (0..8).map(|i| {
+                addr.send(Get {
+                    key: format!("test-auto-pipelining-{}", i),
+                })
+            });
+            future::join_all(gets.collect::<Vec<_>>()).map(|res| {
+                for get in res {
+                    assert!(get.unwrap().is_none());
+                }
+            })
+        };
+
+        // queued while connecting, then sent once connected
+        gets()
+            .and_then(move |()| {
+                assert_eq!(writes.recv().unwrap(), 8);
+                gets().map(move |()| writes)
+            })
+            .map(|writes| {
+                assert_eq!(writes.recv().unwrap(), 8);
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,112 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::{RedisClusterActor, RespValue};
+use futures::Future;
+
+const KEY: &str = "test-bitfield";
+
+const COUNTER: BitFieldType = BitFieldType::Unsigned(8);
+const BALANCE: BitFieldType = BitFieldType::Signed(16);
+
+fn bitfield(ops: Vec<BitFieldOp>) -> BitField {
+    BitField {
+        key: KEY.into(),
+        ops,
+    }
+}
+
+#[test]
+fn test_bitfield_request() {
+    let request = bitfield(vec![
+        BitFieldOp::Overflow(BitFieldOverflow::Sat),
+        BitFieldOp::IncrBy {
+            ty: COUNTER,
+            offset: BitFieldOffset::Fields(3),
+            increment: -1,
+        },
+        BitFieldOp::Get {
+            ty: BALANCE,
+            offset: BitFieldOffset::Bits(100),
+        },
+    ])
+    .into_request();
+    let args = [
+        "BITFIELD", KEY, "OVERFLOW", "SAT", "INCRBY", "u8", "#3", "-1",
+    ]
+    .iter()
+    .chain(&["GET", "i16", "100"])
+    .map(|&arg| RespValue::BulkString(arg.into()))
+    .collect();
+    assert_eq!(request, RespValue::Array(args));
+}
+
+#[test]
+fn test_bitfield() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-bitfield");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(bitfield(vec![
+                    BitFieldOp::Set {
+                        ty: COUNTER,
+                        offset: BitFieldOffset::Fields(0),
+                        value: 250,
+                    },
+                    BitFieldOp::Set {
+                        ty: BALANCE,
+                        offset: BitFieldOffset::Bits(8),
+                        value: -2,
+                    },
+                ]))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                // the previous values
+                assert_eq!(res.unwrap(), vec![Some(0), Some(0)]);
+                addr.send(bitfield(vec![
+                    BitFieldOp::Overflow(BitFieldOverflow::Wrap),
+                    BitFieldOp::IncrBy {
+                        ty: COUNTER,
+                        offset: BitFieldOffset::Fields(0),
+                        increment: 10,
+                    },
+                    BitFieldOp::Get {
+                        ty: BALANCE,
+                        offset: BitFieldOffset::Bits(8),
+                    },
+                    BitFieldOp::Overflow(BitFieldOverflow::Fail),
+                    BitFieldOp::IncrBy {
+                        ty: COUNTER,
+                        offset: BitFieldOffset::Fields(0),
+                        increment: 300,
+                    },
+                    BitFieldOp::Overflow(BitFieldOverflow::Sat),
+                    BitFieldOp::IncrBy {
+                        ty: BALANCE,
+                        offset: BitFieldOffset::Bits(8),
+                        increment: -40000,
+                    },
+                ]))
+            }
+        })
+        .map(|res| {
+            // 260 wrapped around, the failed increment left the counter as is
+            assert_eq!(res.unwrap(), vec![Some(4), Some(-2), None, Some(-32768)]);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
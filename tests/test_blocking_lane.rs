@@ -0,0 +1,50 @@
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::{command::*, RedisActor};
+use futures::Future;
+
+const KEY: &str = "test-blocking-lane";
+
+#[test]
+fn test_blocking_lane() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-blocking-lane");
+
+    let addr = RedisActor::builder("127.0.0.1:6379")
+        .blocking_lane(1)
+        .start();
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .and_then(move |res| {
+            res.unwrap();
+
+            let start = Instant::now();
+            let blpop = addr
+                .send(BLPop {
+                    keys: vec![KEY.into()],
+                    timeout: 1.0,
+                })
+                .map(move |res| {
+                    assert_eq!(res.unwrap(), None);
+                    assert!(start.elapsed() >= Duration::from_secs(1));
+                });
+            // sent after the BLPOP, but answered while it blocks
+            let gets: Vec<_> = (0..10)
+                .map(|_| addr.send(Get { key: KEY.into() }))
+                .collect();
+            let gets = futures::future::join_all(gets).map(move |res| {
+                assert!(res.into_iter().all(|res| res.unwrap().is_none()));
+                assert!(start.elapsed() < Duration::from_millis(500));
+            });
+            gets.join(blpop)
+        })
+        .map(|_| System::current().stop())
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,129 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use actix::prelude::*;
+use actix_redis::{command::*, Checkout, RedisActor, RespValue};
+use futures::Future;
+
+fn set(key: &str, value: &str) -> Set {
+    Set {
+        key: key.into(),
+        value: value.into(),
+        expiration: Expiration::Infinite,
+    }
+}
+
+#[test]
+fn test_checkout_transaction() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-checkout-transaction");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+    let key = "test-checkout";
+
+    Arbiter::spawn_fn(move || {
+        addr.send(set(key, "init"))
+            .and_then({
+                let addr = addr.clone();
+                move |res| {
+                    res.unwrap();
+                    addr.send(Checkout)
+                }
+            })
+            .and_then(move |lease| {
+                let lease = lease.unwrap();
+                lease
+                    .send(Watch {
+                        keys: vec![key.into()],
+                    })
+                    .map(move |()| lease)
+                    .map_err(|e| panic!("Should not happen {:?}", e))
+            })
+            .and_then({
+                let addr = addr.clone();
+                move |lease| {
+                    // other users of the actor wait for the lease, so the
+                    // watched key is not touched
+                    let done = Rc::new(Cell::new(false));
+                    Arbiter::spawn(addr.send(set(key, "other")).then({
+                        let done = done.clone();
+                        move |res| {
+                            res.unwrap().unwrap();
+                            done.set(true);
+                            Ok(())
+                        }
+                    }));
+
+                    // pipelined on the leased connection
+                    lease
+                        .send(Multi)
+                        .join3(lease.queue(set(key, "transaction")), lease.send(Exec))
+                        .map(move |((), (), res)| {
+                            assert_eq!(
+                                res,
+                                Some(vec![RespValue::SimpleString("OK".into())])
+                            );
+                            assert!(!done.get());
+                            drop(lease);
+                        })
+                        .map_err(|e| panic!("Should not happen {:?}", e))
+                }
+            })
+            .and_then(move |()| addr.send(Get { key: key.into() }))
+            .map(|res| {
+                // the deferred command runs after the transaction
+                assert_eq!(res.unwrap(), Some(b"other".to_vec()));
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_checkout_watch_abort() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-checkout-watch-abort");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+    let other = RedisActor::start("127.0.0.1:6379");
+    let key = "test-checkout-abort";
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Checkout)
+            .and_then(move |lease| {
+                let lease = lease.unwrap();
+                lease
+                    .send(Watch {
+                        keys: vec![key.into()],
+                    })
+                    // another connection modifies the watched key
+                    .and_then(move |()| {
+                        other.send(set(key, "other")).then(|res| match res {
+                            Ok(res) => res,
+                            Err(e) => panic!("Should not happen {:?}", e),
+                        })
+                    })
+                    .and_then(move |()| {
+                        lease.send(Multi).join3(
+                            lease.queue(set(key, "transaction")),
+                            lease.send(Exec),
+                        )
+                    })
+                    .map(|((), (), res)| res)
+                    .map_err(|e| panic!("Should not happen {:?}", e))
+            })
+            .and_then(move |res| {
+                assert_eq!(res, None);
+                addr.send(Get { key: key.into() })
+            })
+            .map(|res| {
+                assert_eq!(res.unwrap(), Some(b"other".to_vec()));
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::{command::*, Error, RedisActor};
+use futures::future::{self, Loop};
+use futures::Future;
+use tokio_timer::Delay;
+
+use common::mock_node;
+
+/// Minimal server answering `PING`, started on `port`
+fn mock_server(port: u16) {
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+    mock_node(listener, |_| "+PONG\r\n".to_owned());
+}
+
+fn ping(
+    addr: &Addr<RedisActor>,
+) -> impl Future<Item = Result<String, Error>, Error = ()> {
+    addr.send(Ping(None))
+        .map_err(|e| panic!("Should not happen {:?}", e))
+}
+
+#[test]
+fn test_circuit_breaker() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-circuit-breaker");
+
+    // nothing listens on the port yet
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let cooldown = Duration::from_millis(300);
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisActor::builder(format!("127.0.0.1:{}", port))
+            .circuit_breaker(2, cooldown)
+            .start();
+
+        // the failed connection attempt and this command trip the breaker
+        ping(&addr)
+            .and_then({
+                let addr = addr.clone();
+                move |res| {
+                    match res {
+                        Err(Error::NotConnected) => (),
+                        res => panic!("Should not happen {:?}", res),
+                    }
+                    let start = Instant::now();
+                    ping(&addr).map(move |res| (res, start))
+                }
+            })
+            .and_then({
+                let addr = addr.clone();
+                move |(res, start)| {
+                    match res {
+                        Err(Error::CircuitOpen) => (),
+                        res => panic!("Should not happen {:?}", res),
+                    }
+                    assert!(start.elapsed() < Duration::from_millis(50));
+
+                    // the server recovers while the circuit is open
+                    mock_server(port);
+                    let start = Instant::now();
+                    future::loop_fn((), move |()| {
+                        ping(&addr).and_then(|res| match res {
+                            Ok(pong) => future::Either::A(future::ok(Loop::Break(pong))),
+                            Err(Error::CircuitOpen) | Err(Error::NotConnected) => {
+                                future::Either::B(
+                                    Delay::new(
+                                        Instant::now() + Duration::from_millis(50),
+                                    )
+                                    .map(|()| Loop::Continue(()))
+                                    .map_err(|e| panic!("Should not happen {:?}", e)),
+                                )
+                            }
+                            Err(e) => panic!("Should not happen {:?}", e),
+                        })
+                    })
+                    .map(move |pong| (pong, start))
+                }
+            })
+            .map(move |(pong, start)| {
+                assert_eq!(pong, "PONG");
+                // the circuit half-opened after the cooldown
+                assert!(start.elapsed() < cooldown * 10);
+                System::current().stop();
+            })
+    });
+
+    sys.run()
+}
@@ -0,0 +1,63 @@
+use actix_redis::command::*;
+use actix_redis::RespValue;
+
+/// Captured from `CLIENT LIST` of a Redis 7.2 server, shortened
+const CLIENT_LIST: &str = "\
+id=3 addr=127.0.0.1:52555 laddr=127.0.0.1:6379 fd=8 name= age=855 idle=0 flags=N db=0 sub=0 psub=0 ssub=0 multi=-1 qbuf=26 qbuf-free=20448 argv-mem=10 multi-mem=0 rbs=1024 rbp=0 obl=0 oll=0 omem=0 tot-mem=22426 events=r cmd=client|list user=default redir=-1 resp=2 lib-name= lib-ver=
+id=4 addr=10.0.0.2:41234 laddr=127.0.0.1:6379 fd=9 name=worker-1 age=12 idle=3 flags=P db=2 sub=1 psub=0 ssub=0 multi=-1 qbuf=0 qbuf-free=0 argv-mem=0 multi-mem=0 rbs=1024 rbp=0 obl=0 oll=0 omem=0 tot-mem=1928 events=r cmd=subscribe user=default redir=-1 resp=2 lib-name= lib-ver=
+";
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(s.into())
+}
+
+#[test]
+fn test_client_list_parse() {
+    let clients = ClientList::from_response(bulk(CLIENT_LIST)).unwrap();
+
+    assert_eq!(clients.len(), 2);
+    assert_eq!(clients[0].id, 3);
+    assert_eq!(clients[0].addr, "127.0.0.1:52555");
+    assert_eq!(clients[0].name, "");
+    assert_eq!(clients[0].cmd, "client|list");
+    assert_eq!(
+        (clients[1].name.as_str(), clients[1].age, clients[1].idle),
+        ("worker-1", 12, 3)
+    );
+    assert_eq!((clients[1].flags.as_str(), clients[1].db), ("P", 2));
+    // fields without a dedicated member are kept
+    assert_eq!(clients[1].fields["sub"], "1");
+    assert_eq!(clients[1].fields["lib-name"], "");
+
+    assert_eq!(ClientList::from_response(bulk("")).unwrap(), vec![]);
+    assert!(ClientList::from_response(bulk("id=x addr=a")).is_err());
+    assert!(ClientList::from_response(RespValue::Integer(1)).is_err());
+}
+
+#[test]
+fn test_client_kill_request() {
+    let request = |filter| ClientKill { filter }.into_request();
+    let args =
+        |args: &[&str]| RespValue::Array(args.iter().map(|&arg| bulk(arg)).collect());
+
+    assert_eq!(
+        request(ClientKillFilter::Id(4)),
+        args(&["CLIENT", "KILL", "ID", "4"])
+    );
+    assert_eq!(
+        request(ClientKillFilter::Addr("10.0.0.2:41234".into())),
+        args(&["CLIENT", "KILL", "ADDR", "10.0.0.2:41234"])
+    );
+    assert_eq!(
+        request(ClientKillFilter::Type(ClientType::PubSub)),
+        args(&["CLIENT", "KILL", "TYPE", "pubsub"])
+    );
+    assert_eq!(ClientKill::from_response(RespValue::Integer(2)).unwrap(), 2);
+    assert_eq!(
+        ClientList {
+            type_filter: Some(ClientType::Replica)
+        }
+        .into_request(),
+        args(&["CLIENT", "LIST", "TYPE", "replica"])
+    );
+}
@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::{command::*, RedisClusterActor, Routed, Routing};
+use futures::Future;
+use tokio_timer::Delay;
+
+fn set() -> Set {
+    Set {
+        key: "test-client-pause".into(),
+        value: "value".into(),
+        expiration: Expiration::Infinite,
+    }
+}
+
+#[test]
+fn test_client_pause() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-client-pause");
+
+    // pause from one client, write from another
+    let admin = RedisClusterActor::start("127.0.0.1:7000");
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        let start = Instant::now();
+        admin
+            .send(Routed {
+                routing: Routing::ToAllMasters,
+                command: ClientPause {
+                    timeout_ms: 200,
+                    mode: PauseMode::Write,
+                },
+            })
+            .and_then({
+                let addr = addr.clone();
+                move |res| {
+                    assert!(!res.unwrap().is_empty());
+                    addr.send(set())
+                }
+            })
+            .and_then({
+                let admin = admin.clone();
+                move |res| {
+                    res.unwrap();
+                    // delayed until the pause elapsed
+                    assert!(start.elapsed() >= Duration::from_millis(200));
+                    admin.send(Routed {
+                        routing: Routing::ToAllMasters,
+                        command: ClientPause {
+                            timeout_ms: 10_000,
+                            mode: PauseMode::Write,
+                        },
+                    })
+                }
+            })
+            .and_then(move |res| {
+                res.unwrap();
+                let start = Instant::now();
+                let unpause = Delay::new(Instant::now() + Duration::from_millis(100))
+                    .map_err(|e| panic!("Should not happen {:?}", e))
+                    .and_then(move |()| {
+                        admin.send(Routed {
+                            routing: Routing::ToAllMasters,
+                            command: ClientUnpause,
+                        })
+                    })
+                    .map(|res| {
+                        res.unwrap();
+                    });
+                addr.send(set())
+                    .join(unpause)
+                    .map(move |(res, ())| (res, start.elapsed()))
+            })
+            .map(|(res, elapsed)| {
+                res.unwrap();
+                // delayed until unpaused, not for the whole pause
+                assert!(elapsed >= Duration::from_millis(100));
+                assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::{command::*, slot::hash_slot, Error, RedisClusterActor};
+use futures::Future;
+
+/// A listener whose accept queue is full, so new connection attempts stall
+/// like those to a partitioned node
+fn stalled_node() -> (TcpListener, Vec<TcpStream>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut backlog = vec![];
+    while let Ok(stream) = TcpStream::connect_timeout(&addr, Duration::from_millis(100))
+    {
+        backlog.push(stream);
+    }
+    (listener, backlog)
+}
+
+/// Minimal cluster node serving slots 0-8191, and announcing `other` as the
+/// master of slots 8192-16383
+fn mock_node(other: SocketAddr) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    common::mock_node(listener, move |args| match args[0].as_str() {
+        "CLUSTER" => format!(
+            "*2\r\n\
+             *3\r\n:0\r\n:8191\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n\
+             *3\r\n:8192\r\n:16383\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n",
+            addr.port(),
+            other.port()
+        ),
+        "GET" => "$-1\r\n".to_owned(),
+        _ => "-ERR unknown command\r\n".to_owned(),
+    });
+
+    addr.to_string()
+}
+
+/// A key whose slot satisfies `pred`
+fn key(pred: impl Fn(u16) -> bool) -> String {
+    (0..)
+        .map(|i| format!("test-connect-timeout{}", i))
+        .find(|key| pred(hash_slot(key.as_bytes())))
+        .unwrap()
+}
+
+#[test]
+fn test_cluster_connect_timeout() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-connect-timeout");
+
+    let (stalled, _backlog) = stalled_node();
+    let node = mock_node(stalled.local_addr().unwrap());
+    let timeout = Duration::from_millis(200);
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisClusterActor::builder(node)
+            .connect_timeout(timeout)
+            .start();
+        let start = Instant::now();
+
+        // the reachable node is not held up by the unreachable one
+        addr.send(Get {
+            key: key(|slot| slot < 8192),
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), None);
+                assert!(start.elapsed() < timeout);
+
+                addr.send(Get {
+                    key: key(|slot| slot >= 8192),
+                })
+            }
+        })
+        .map(move |res| {
+            match res {
+                Err(Error::NotConnected) => (),
+                res => panic!("Should not happen {:?}", res),
+            }
+            let elapsed = start.elapsed();
+            assert!(elapsed >= timeout, "failed too early {:?}", elapsed);
+            assert!(elapsed < timeout * 2, "failed too late {:?}", elapsed);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,26 @@
+use actix::prelude::*;
+use actix_redis::{command::*, RedisClusterActor};
+use futures::Future;
+
+#[test]
+fn test_cluster_eager_connect() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-eager-connect");
+
+    Arbiter::spawn_fn(move || {
+        RedisClusterActor::start_eagerly("127.0.0.1:7000")
+            .map_err(|e| panic!("Should not happen {:?}", e))
+            .and_then(|addr| {
+                addr.send(Get {
+                    key: "test-eager".into(),
+                })
+            })
+            .map(|res| {
+                res.unwrap();
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
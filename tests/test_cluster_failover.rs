@@ -0,0 +1,90 @@
+use actix::prelude::*;
+use actix_redis::{
+    command::*, slot::hash_slot, Failover, RedisClusterActor, Routed, Routing,
+};
+use futures::Future;
+
+/// A key whose slot satisfies `pred`
+fn key(pred: impl Fn(u16) -> bool) -> String {
+    (0..)
+        .map(|i| format!("test-failover{}", i))
+        .find(|key| pred(hash_slot(key.as_bytes())))
+        .unwrap()
+}
+
+#[test]
+fn test_cluster_failover() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-failover");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        let nodes = addr
+            .send(Routed {
+                routing: Routing::ToNode("127.0.0.1:7000".into()),
+                command: ClusterNodes,
+            })
+            .map(|res| res.unwrap().pop().unwrap().1);
+
+        nodes
+            .and_then(move |nodes| {
+                let replica = nodes
+                    .iter()
+                    .find(|node| node.has_flag("slave"))
+                    .expect("a replica")
+                    .clone();
+                let master = nodes
+                    .iter()
+                    .find(|node| Some(&node.id) == replica.master.as_ref())
+                    .expect("the master of the replica")
+                    .clone();
+                let (start, end) = master.slots[0];
+                let key = key(|slot| start <= slot && slot <= end);
+
+                addr.send(Failover {
+                    node: replica.addr.clone(),
+                    mode: None,
+                })
+                .and_then({
+                    let (addr, key) = (addr.clone(), key.clone());
+                    move |res| {
+                        res.unwrap();
+                        addr.send(Set {
+                            key,
+                            value: "promoted".into(),
+                            expiration: Expiration::Infinite,
+                        })
+                    }
+                })
+                .and_then({
+                    let addr = addr.clone();
+                    move |res| {
+                        res.unwrap();
+                        // not redirected, served by the promoted replica itself
+                        addr.send(Routed {
+                            routing: Routing::ToNode(replica.addr),
+                            command: Get { key },
+                        })
+                    }
+                })
+                .and_then(move |res| {
+                    let (_, value) = res.unwrap().pop().unwrap();
+                    assert_eq!(value, Some(b"promoted".to_vec()));
+
+                    // restore the topology for the other tests
+                    addr.send(Failover {
+                        node: master.addr,
+                        mode: None,
+                    })
+                })
+            })
+            .map(|res| {
+                res.unwrap();
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
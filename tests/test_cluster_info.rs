@@ -0,0 +1,120 @@
+use actix::prelude::*;
+use actix_redis::{command::*, RedisClusterActor, RespValue};
+use futures::Future;
+
+const CLUSTER_INFO: &str = "cluster_state:ok\r
+cluster_slots_assigned:16384\r
+cluster_slots_ok:16384\r
+cluster_slots_pfail:0\r
+cluster_slots_fail:0\r
+cluster_known_nodes:6\r
+cluster_size:3\r
+cluster_current_epoch:6\r
+cluster_my_epoch:2\r
+cluster_stats_messages_sent:1483972\r
+cluster_stats_messages_received:1483968\r
+";
+
+// captured from a cluster with a failing replica and a slot being resharded
+const CLUSTER_NODES: &str = "\
+07c37dfeb235213a872192d90877d0cd55635b91 127.0.0.1:30004@31004 slave e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca 0 1426238317239 4 connected
+67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1 127.0.0.1:30002@31002 master - 0 1426238316232 2 connected 5461-10922
+292f8b365bb7edb5e285caf0b7e6ddc7265d2f4f 127.0.0.1:30003@31003 master - 0 1426238318243 3 connected 10923-16383 [10922-<-67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1]
+6ec23923021cf3ffec47632106199cb7f496ce01 127.0.0.1:30005@31005,node5.example.com slave,fail? 67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1 1426238316000 1426238316232 5 disconnected
+824fe116063bc5fcf9f4ffd895bc17aee7731ac3 127.0.0.1:30006@31006 slave 292f8b365bb7edb5e285caf0b7e6ddc7265d2f4f 0 1426238317741 6 connected
+e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca 127.0.0.1:30001@31001 myself,master - 0 0 1 connected 0-5460 5462 [5461->-292f8b365bb7edb5e285caf0b7e6ddc7265d2f4f]
+";
+
+#[test]
+fn test_cluster_info_response() {
+    let res = RespValue::BulkString(CLUSTER_INFO.into());
+    assert_eq!(
+        ClusterInfo::from_response(res).unwrap(),
+        ClusterState {
+            state: "ok".into(),
+            slots_assigned: 16384,
+            known_nodes: 6,
+            size: 3,
+        }
+    );
+
+    let res = RespValue::BulkString(b"cluster_state:fail\r\n".to_vec());
+    assert!(ClusterInfo::from_response(res).is_err());
+}
+
+#[test]
+fn test_cluster_nodes_response() {
+    let res = RespValue::BulkString(CLUSTER_NODES.into());
+    let nodes = ClusterNodes::from_response(res).unwrap();
+    assert_eq!(nodes.len(), 6);
+
+    let replica = &nodes[0];
+    assert_eq!(replica.addr, "127.0.0.1:30004");
+    assert_eq!(replica.flags, vec!["slave"]);
+    assert_eq!(
+        replica.master,
+        Some("e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca".to_string())
+    );
+    assert!(replica.slots.is_empty());
+
+    let importing = &nodes[2];
+    assert_eq!(importing.master, None);
+    assert_eq!(importing.slots, vec![(10923, 16383)]);
+    assert_eq!(
+        importing.importing,
+        vec![(
+            10922,
+            "67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1".to_string()
+        )]
+    );
+
+    let failing = &nodes[3];
+    assert_eq!(failing.addr, "127.0.0.1:30005");
+    assert!(failing.has_flag("fail?"));
+    assert_eq!(failing.link_state, "disconnected");
+
+    let myself = &nodes[5];
+    assert_eq!(myself.id, "e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca");
+    assert!(myself.has_flag("myself") && myself.has_flag("master"));
+    assert_eq!(myself.slots, vec![(0, 5460), (5462, 5462)]);
+    assert_eq!(
+        myself.migrating,
+        vec![(5461, "292f8b365bb7edb5e285caf0b7e6ddc7265d2f4f".to_string())]
+    );
+    assert!(myself.importing.is_empty());
+
+    let res = RespValue::BulkString(b"07c37dfe 127.0.0.1:30004@31004 slave".to_vec());
+    assert!(ClusterNodes::from_response(res).is_err());
+}
+
+#[test]
+fn test_cluster_info() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-info");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(ClusterInfo)
+            .join(addr.send(ClusterNodes))
+            .map(|(info, nodes)| {
+                let info = info.unwrap();
+                assert_eq!(info.state, "ok");
+                assert_eq!(info.slots_assigned, 16384);
+
+                let nodes = nodes.unwrap();
+                assert_eq!(nodes.len(), info.known_nodes);
+                let slots: usize = nodes
+                    .iter()
+                    .flat_map(|node| node.slots.iter())
+                    .map(|&(start, end)| (end - start) as usize + 1)
+                    .sum();
+                assert_eq!(slots, 16384);
+                assert_eq!(nodes.iter().filter(|n| n.has_flag("myself")).count(), 1);
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
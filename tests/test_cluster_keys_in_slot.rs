@@ -0,0 +1,107 @@
+mod common;
+
+use std::net::TcpListener;
+
+use actix::prelude::*;
+use actix_redis::slot::hash_slot;
+use actix_redis::{command::*, RedisClusterActor};
+use futures::{future, Future};
+
+use common::mock_node;
+
+/// Minimal cluster of two masters, the second one holding the keys `a` and `b`
+/// in the slot 12000. Returns the address of the first master.
+fn mock_cluster() -> String {
+    let (first, second) = (
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+    );
+    let (first_port, second_port) = (
+        first.local_addr().unwrap().port(),
+        second.local_addr().unwrap().port(),
+    );
+    let slots = format!(
+        "*2\r\n\
+         *3\r\n:0\r\n:8191\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n\
+         *3\r\n:8192\r\n:16383\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n",
+        first_port, second_port
+    );
+
+    let first_slots = slots.clone();
+    mock_node(first, move |args| match args[1].as_str() {
+        "SLOTS" => first_slots.clone(),
+        _ => "-ERR not the owner\r\n".to_owned(),
+    });
+    mock_node(second, move |args| match (args[1].as_str(), args.get(2)) {
+        ("SLOTS", _) => slots.clone(),
+        ("COUNTKEYSINSLOT", Some(slot)) if slot == "12000" => ":2\r\n".to_owned(),
+        ("GETKEYSINSLOT", Some(slot)) if slot == "12000" && args[3] == "10" => {
+            "*2\r\n$1\r\na\r\n$1\r\nb\r\n".to_owned()
+        }
+        _ => "-ERR unknown command\r\n".to_owned(),
+    });
+
+    format!("127.0.0.1:{}", first_port)
+}
+
+#[test]
+fn test_cluster_keys_in_slot_routing() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-keys-in-slot-routing");
+    let seed = mock_cluster();
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisClusterActor::start(seed);
+        addr.send(ClusterCountKeysInSlot { slot: 12000 })
+            .join(addr.send(ClusterGetKeysInSlot {
+                slot: 12000,
+                count: 10,
+            }))
+            .map(|(count, keys)| {
+                assert_eq!(count.unwrap(), 2);
+                assert_eq!(keys.unwrap(), [b"a".to_vec(), b"b".to_vec()]);
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_cluster_keys_in_slot() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-keys-in-slot");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+    let slot = hash_slot(b"{test-keys-in-slot}");
+    let keys = ["{test-keys-in-slot}:a", "{test-keys-in-slot}:b"];
+
+    Arbiter::spawn_fn(move || {
+        let set = keys.iter().map(|key| {
+            addr.send(Set {
+                key: (*key).into(),
+                value: "value".into(),
+                expiration: Expiration::Ex("60".into()),
+            })
+        });
+        future::join_all(set.collect::<Vec<_>>())
+            .and_then(move |res| {
+                for set in res {
+                    set.unwrap();
+                }
+                addr.send(ClusterCountKeysInSlot { slot })
+                    .join(addr.send(ClusterGetKeysInSlot { slot, count: 10 }))
+            })
+            .map(move |(count, found)| {
+                assert_eq!(count.unwrap(), 2);
+                let mut found = found.unwrap();
+                found.sort();
+                assert_eq!(found, [keys[0].as_bytes(), keys[1].as_bytes()]);
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -20,7 +20,7 @@ fn test_cluster_lua_eval_sha() -> std::io::Result<()> {
         .then(move |res| match res {
             Ok(Ok(hash)) => addr
                 .send(EvalSha {
-                    hash: hash,
+                    hash,
                     keys: vec!["actix".into(), "actix".into()],
                     args: vec!["21".into()],
                 })
@@ -0,0 +1,86 @@
+mod common;
+
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_redis::{ClusterPing, Error, RedisClusterActor};
+use futures::Future;
+
+/// Minimal cluster node answering `CLUSTER SLOTS` with `slots` and `PING` after
+/// `delay`
+fn mock_node(listener: TcpListener, slots: String, delay: Duration) {
+    common::mock_node(listener, move |args| match args[0].as_str() {
+        "CLUSTER" => slots.clone(),
+        "PING" => {
+            thread::sleep(delay);
+            "+PONG\r\n".to_owned()
+        }
+        _ => "-ERR unknown command\r\n".to_owned(),
+    });
+}
+
+#[test]
+fn test_cluster_ping() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-ping");
+
+    let (a, b) = (
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+    );
+    let (a_addr, b_addr) = (
+        a.local_addr().unwrap().to_string(),
+        b.local_addr().unwrap().to_string(),
+    );
+    // nothing listens on the port of the replica of b
+    let replica_port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let replica_addr = format!("127.0.0.1:{}", replica_port);
+    let slots = format!(
+        "*2\r\n\
+         *3\r\n:0\r\n:8191\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n\
+         *4\r\n:8192\r\n:16383\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n\
+         *2\r\n$9\r\n127.0.0.1\r\n:{}\r\n",
+        a.local_addr().unwrap().port(),
+        b.local_addr().unwrap().port(),
+        replica_port
+    );
+    mock_node(a, slots.clone(), Duration::from_millis(0));
+    // a slow shard
+    mock_node(b, slots, Duration::from_millis(200));
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisClusterActor::start(a_addr.clone());
+
+        addr.send(ClusterPing { replicas: false })
+            .and_then(move |masters| {
+                let masters = masters.unwrap();
+                assert_eq!(masters.len(), 2);
+                assert_eq!(masters[0].0, a_addr);
+                assert!(masters[0].1.as_ref().unwrap() < &Duration::from_millis(200));
+                assert_eq!(masters[1].0, b_addr);
+                assert!(masters[1].1.as_ref().unwrap() >= &Duration::from_millis(200));
+
+                addr.send(ClusterPing { replicas: true })
+            })
+            .map(move |nodes| {
+                let nodes = nodes.unwrap();
+                assert_eq!(nodes.len(), 3);
+                assert!(nodes[0].1.is_ok() && nodes[1].1.is_ok());
+                assert_eq!(nodes[2].0, replica_addr);
+                match nodes[2].1 {
+                    Err(Error::NotConnected) => (),
+                    ref res => panic!("Should not happen {:?}", res),
+                }
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
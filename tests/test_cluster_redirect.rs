@@ -81,10 +81,7 @@ fn test_cluster_moved() -> std::io::Result<()> {
                         move |res| {
                             res.unwrap();
 
-                            addr.send(ClusterCountKeysInSlot {
-                                slot,
-                                target_node_slot: source_slot,
-                            })
+                            addr.send(ClusterCountKeysInSlot { slot })
                         }
                     })
                     .and_then({
@@ -95,35 +92,31 @@ fn test_cluster_moved() -> std::io::Result<()> {
                             let count = res.unwrap();
 
                             loop_fn(count, move |count| {
-                                addr.send(ClusterGetKeysInSlot {
-                                    slot,
-                                    count: 10,
-                                    target_node_slot: source_slot,
-                                })
-                                .and_then({
-                                    let addr = addr.clone();
-                                    let destination_host = destination_host.clone();
-                                    move |keys| {
-                                        let keys = keys.unwrap();
-                                        let len = keys.len();
-
-                                        join_all(keys.into_iter().map({
-                                            let addr = addr.clone();
-                                            let host = destination_host.clone();
-                                            let port = destination_port as usize;
-                                            move |key| {
-                                                addr.send(Migrate {
-                                                    host: host.clone(),
-                                                    port,
-                                                    key,
-                                                    db: 0,
-                                                    timeout: 100,
-                                                    target_node_slot: source_slot,
-                                                })
-                                            }
-                                        }))
-                                        .map(
-                                            move |res| {
+                                addr.send(ClusterGetKeysInSlot { slot, count: 10 })
+                                    .and_then({
+                                        let addr = addr.clone();
+                                        let destination_host = destination_host.clone();
+                                        move |keys| {
+                                            let keys = keys.unwrap();
+                                            let len = keys.len() as i64;
+
+                                            join_all(keys.into_iter().map({
+                                                let addr = addr.clone();
+                                                let host = destination_host.clone();
+                                                let port = destination_port as usize;
+                                                move |key| {
+                                                    addr.send(Migrate {
+                                                        host: host.clone(),
+                                                        port,
+                                                        key: String::from_utf8(key)
+                                                            .unwrap(),
+                                                        db: 0,
+                                                        timeout: 100,
+                                                        target_node_slot: source_slot,
+                                                    })
+                                                }
+                                            }))
+                                            .map(move |res| {
                                                 for res in res.into_iter() {
                                                     res.unwrap();
                                                 }
@@ -133,10 +126,9 @@ fn test_cluster_moved() -> std::io::Result<()> {
                                                 } else {
                                                     Loop::Continue(count - len)
                                                 }
-                                            },
-                                        )
-                                    }
-                                })
+                                            })
+                                        }
+                                    })
                             })
                         }
                     })
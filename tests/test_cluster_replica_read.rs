@@ -0,0 +1,90 @@
+mod common;
+
+use std::net::TcpListener;
+
+use actix::prelude::*;
+use actix_redis::{command::*, ReadReplica, RedisClusterActor};
+use futures::Future;
+
+use common::mock_node;
+
+/// Minimal cluster of a master serving all the slots and its replica, lagging
+/// `lag` bytes behind. Both answer `GET` with their role. Returns the address
+/// of the master.
+fn mock_cluster(lag: u64) -> String {
+    let (master, replica) = (
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+    );
+    let (master_port, replica_port) = (
+        master.local_addr().unwrap().port(),
+        replica.local_addr().unwrap().port(),
+    );
+    let slots = format!(
+        "*1\r\n\
+         *4\r\n:0\r\n:16383\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n\
+         *2\r\n$9\r\n127.0.0.1\r\n:{}\r\n",
+        master_port, replica_port
+    );
+    let info = format!(
+        "# Replication\r\nrole:master\r\nconnected_slaves:1\r\n\
+         slave0:ip=127.0.0.1,port={},state=online,offset={},lag=0\r\n\
+         master_repl_offset:{}\r\n",
+        replica_port,
+        10_000 - lag,
+        10_000
+    );
+    let info = format!("${}\r\n{}\r\n", info.len(), info);
+
+    mock_node(master, move |args| match args[0].as_str() {
+        "CLUSTER" => slots.clone(),
+        "INFO" => info.clone(),
+        "GET" => "$6\r\nmaster\r\n".to_owned(),
+        _ => "-ERR unknown command\r\n".to_owned(),
+    });
+    mock_node(replica, |args| match args[0].as_str() {
+        "READONLY" => "+OK\r\n".to_owned(),
+        "GET" => "$7\r\nreplica\r\n".to_owned(),
+        _ => "-ERR unknown command\r\n".to_owned(),
+    });
+
+    format!("127.0.0.1:{}", master_port)
+}
+
+fn read(
+    addr: &Addr<RedisClusterActor>,
+) -> impl Future<Item = String, Error = MailboxError> {
+    addr.send(ReadReplica(Get {
+        key: "test-replica-read".into(),
+    }))
+    .map(|res| String::from_utf8(res.unwrap().unwrap()).unwrap())
+}
+
+#[test]
+fn test_cluster_replica_read() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-replica-read");
+
+    let (close, far) = (mock_cluster(10), mock_cluster(5000));
+
+    Arbiter::spawn_fn(move || {
+        let any = RedisClusterActor::start(far.clone());
+        let close = RedisClusterActor::builder(close)
+            .max_replica_lag(100)
+            .start();
+        let far = RedisClusterActor::builder(far).max_replica_lag(100).start();
+
+        read(&any)
+            .join3(read(&close), read(&far))
+            .map(|(any, close, far)| {
+                // the lag is not checked without a bound
+                assert_eq!(any, "replica");
+                assert_eq!(close, "replica");
+                assert_eq!(far, "master");
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use std::net::TcpListener;
+
+use actix::prelude::*;
+use actix_redis::{command::*, slot::hash_slot, RedisClusterActor, Routed, Routing};
+use futures::Future;
+
+/// Minimal cluster node answering `CLUSTER SLOTS` with `slots`, and `PING`
+/// with its name
+fn mock_node(listener: TcpListener, name: &'static str, slots: String) {
+    common::mock_node(listener, move |args| match args[0].as_str() {
+        "CLUSTER" => slots.clone(),
+        "PING" => format!("+PONG-{}\r\n", name),
+        _ => "-ERR unknown command\r\n".to_owned(),
+    });
+}
+
+/// A key whose slot satisfies `pred`
+fn key(pred: impl Fn(u16) -> bool) -> String {
+    (0..)
+        .map(|i| format!("test-routing{}", i))
+        .find(|key| pred(hash_slot(key.as_bytes())))
+        .unwrap()
+}
+
+#[test]
+fn test_cluster_routing() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-routing");
+
+    let (a, b) = (
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+    );
+    let (a_addr, b_addr) = (
+        a.local_addr().unwrap().to_string(),
+        b.local_addr().unwrap().to_string(),
+    );
+    let slots = format!(
+        "*2\r\n\
+         *3\r\n:0\r\n:8191\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n\
+         *3\r\n:8192\r\n:16383\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n",
+        a.local_addr().unwrap().port(),
+        b.local_addr().unwrap().port()
+    );
+    mock_node(a, "a", slots.clone());
+    mock_node(b, "b", slots);
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisClusterActor::start(a_addr.clone());
+        let send = move |routing| {
+            addr.send(Routed {
+                routing,
+                command: Ping(None),
+            })
+            .map(|res| res.unwrap())
+        };
+
+        // unrouted, PING goes to a, the node the actor was started with
+        send(Routing::ToNode(b_addr.clone()))
+            .join4(
+                send(Routing::BySlotOfKey(key(|slot| slot >= 8192))),
+                send(Routing::ToAllMasters),
+                send(Routing::ToRandomMaster),
+            )
+            .map(move |(node, by_key, all, random)| {
+                let b = (b_addr.clone(), "PONG-b".to_owned());
+                assert_eq!(node, vec![b.clone()]);
+                assert_eq!(by_key, vec![b.clone()]);
+                assert_eq!(all, vec![(a_addr.clone(), "PONG-a".to_owned()), b]);
+                assert_eq!(random.len(), 1);
+                assert!(all.contains(&random[0]));
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "sample")]
+
+mod common;
+
+use std::net::TcpListener;
+
+use actix::prelude::*;
+use actix_redis::sample::ClusterSample;
+use actix_redis::RedisClusterActor;
+use futures::Future;
+
+use common::mock_node;
+
+/// Minimal cluster of two masters, the first holding 900 keys and the second
+/// 100, scanned 100 keys at a time. Returns the addresses of the masters.
+fn mock_cluster() -> (String, String) {
+    let (a, b) = (
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+    );
+    let (a_port, b_port) = (
+        a.local_addr().unwrap().port(),
+        b.local_addr().unwrap().port(),
+    );
+    let slots = format!(
+        "*2\r\n\
+         *3\r\n:0\r\n:8191\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n\
+         *3\r\n:8192\r\n:16383\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n",
+        a_port, b_port
+    );
+
+    mock_node(a, mock_master(slots.clone(), "a", 900));
+    mock_node(b, mock_master(slots, "b", 100));
+
+    (
+        format!("127.0.0.1:{}", a_port),
+        format!("127.0.0.1:{}", b_port),
+    )
+}
+
+fn mock_master(
+    slots: String,
+    prefix: &'static str,
+    keys: usize,
+) -> impl Fn(&[String]) -> String + Clone + Send + 'static {
+    move |args| match args[0].as_str() {
+        "CLUSTER" => slots.clone(),
+        "DBSIZE" => format!(":{}\r\n", keys),
+        "SCAN" => {
+            let cursor: usize = args[1].parse().unwrap();
+            let end = (cursor + 100).min(keys);
+            let next = if end == keys { 0 } else { end }.to_string();
+            let mut reply =
+                format!("*2\r\n${}\r\n{}\r\n*{}\r\n", next.len(), next, end - cursor);
+            for i in cursor..end {
+                let key = format!("{}:{}", prefix, i);
+                reply.push_str(&format!("${}\r\n{}\r\n", key.len(), key));
+            }
+            reply
+        }
+        "TTL" => ":60\r\n".to_owned(),
+        _ => "-ERR unknown command\r\n".to_owned(),
+    }
+}
+
+#[test]
+fn test_cluster_sample() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-sample");
+
+    let (a, b) = mock_cluster();
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisClusterActor::start(a.clone());
+
+        ClusterSample {
+            size: 100,
+            ..Default::default()
+        }
+        .start(addr)
+        .map(move |sample| {
+            assert_eq!(sample.len(), 100);
+            let mut keys = sample.iter().map(|s| s.key.clone()).collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            assert_eq!(keys.len(), 100);
+
+            let from_b = sample.iter().filter(|s| s.node == b).count();
+            for s in sample.iter() {
+                let prefix = if s.node == a { b"a:" } else { b"b:" };
+                assert!(s.key.starts_with(prefix));
+                assert_eq!(s.ttl, Some(60));
+            }
+            // 10 expected, the smaller master is not oversampled
+            assert!((2..=20).contains(&from_b), "{} keys of b", from_b);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
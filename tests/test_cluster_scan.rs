@@ -0,0 +1,88 @@
+mod common;
+
+use std::net::TcpListener;
+
+use actix::prelude::*;
+use actix_redis::scan::{ClusterScan, ScanResult};
+use actix_redis::RedisClusterActor;
+use futures::Future;
+
+/// Minimal cluster node answering `CLUSTER SLOTS` with `slots`, and `SCAN`
+/// with two pages of one key each. A node with `fail` set drops the connection
+/// instead of sending its second page.
+fn mock_node(listener: TcpListener, name: &'static str, slots: String, fail: bool) {
+    common::serve(listener, move || {
+        let slots = slots.clone();
+        move |args: &[String]| match (args[0].as_str(), args.get(1).map(String::as_str))
+        {
+            ("CLUSTER", _) => Some(slots.clone()),
+            ("SCAN", Some("0")) => Some(page(1, name, 1)),
+            ("SCAN", Some("1")) if fail => None,
+            ("SCAN", Some("1")) => Some(page(0, name, 2)),
+            _ => Some("-ERR unknown command\r\n".to_owned()),
+        }
+    });
+}
+
+fn page(cursor: u64, name: &str, i: usize) -> String {
+    let cursor = cursor.to_string();
+    let key = format!("{}-{}", name, i);
+    format!(
+        "*2\r\n${}\r\n{}\r\n*1\r\n${}\r\n{}\r\n",
+        cursor.len(),
+        cursor,
+        key.len(),
+        key
+    )
+}
+
+#[test]
+fn test_cluster_scan_node_failure() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-scan-node-failure");
+
+    let listeners = (0..3)
+        .map(|_| TcpListener::bind("127.0.0.1:0").unwrap())
+        .collect::<Vec<_>>();
+    let ports = listeners
+        .iter()
+        .map(|listener| listener.local_addr().unwrap().port())
+        .collect::<Vec<_>>();
+    let slots = format!(
+        "*3\r\n\
+         *3\r\n:0\r\n:5460\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n\
+         *3\r\n:5461\r\n:10922\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n\
+         *3\r\n:10923\r\n:16383\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n",
+        ports[0], ports[1], ports[2]
+    );
+    let failing = format!("127.0.0.1:{}", ports[1]);
+
+    let mut listeners = listeners.into_iter();
+    for &(name, fail) in [("a", false), ("b", true), ("c", false)].iter() {
+        mock_node(listeners.next().unwrap(), name, slots.clone(), fail);
+    }
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisClusterActor::start(format!("127.0.0.1:{}", ports[0]));
+
+        ClusterScan::default()
+            .start(addr)
+            .map(move |res| {
+                // the scan goes on over the other nodes
+                assert_eq!(
+                    res,
+                    ScanResult {
+                        keys: ["a-1", "a-2", "b-1", "c-1", "c-2"]
+                            .iter()
+                            .map(|key| key.as_bytes().to_vec())
+                            .collect(),
+                        skipped: vec![failing],
+                    }
+                );
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,83 @@
+use actix::prelude::*;
+use actix_redis::{
+    command::*,
+    pubsub::{PubSubEvent, SSubscribe},
+    RedisClusterActor,
+};
+use futures::future::{loop_fn, Loop};
+use futures::{Future, Stream};
+use std::time::Duration;
+use tokio_timer::sleep;
+
+#[test]
+fn test_cluster_sharded_pubsub() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-sharded-pubsub");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        let subscription = SSubscribe {
+            channels: vec!["{test-spubsub}a".into(), "{test-spubsub}b".into()],
+        }
+        .start(addr.clone())
+        .unwrap();
+
+        // publish until the subscription is established
+        let publish = loop_fn(addr, |addr| {
+            addr.send(SPublish {
+                channel: "{test-spubsub}b".into(),
+                message: b"hello".to_vec(),
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+            .and_then(move |res| {
+                let received = res.unwrap();
+                sleep(Duration::from_millis(100))
+                    .map_err(|e| panic!("Should not happen {:?}", e))
+                    .map(move |()| {
+                        if received > 0 {
+                            Loop::Break(())
+                        } else {
+                            Loop::Continue(addr)
+                        }
+                    })
+            })
+        });
+
+        subscription
+            .into_future()
+            .map_err(|(e, _)| panic!("Should not happen {:?}", e))
+            .join(publish)
+            .map(|((msg, _), ())| {
+                let msg = match msg {
+                    Some(PubSubEvent::Message(msg)) => msg,
+                    msg => panic!("Should not happen {:?}", msg),
+                };
+                assert_eq!(msg.channel, "{test-spubsub}b");
+                assert_eq!(msg.payload, b"hello");
+                System::current().stop();
+            })
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_sharded_pubsub_multiple_slots() -> std::io::Result<()> {
+    let sys = System::new("test-sharded-pubsub-multiple-slots");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        let res = SSubscribe {
+            channels: vec!["{a}".into(), "{b}".into()],
+        }
+        .start(addr);
+        assert!(res.is_err());
+
+        System::current().stop();
+        Ok(())
+    });
+
+    sys.run()
+}
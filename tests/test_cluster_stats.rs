@@ -0,0 +1,108 @@
+mod common;
+
+use std::net::TcpListener;
+
+use actix::prelude::*;
+use actix_redis::{
+    command::*, slot::hash_slot, ClusterStats, NodeStats, RedisClusterActor,
+};
+use futures::{future, Future};
+
+/// Minimal cluster node answering `CLUSTER SLOTS` with `slots`, `GET` with nil,
+/// or a redirection to `moved_to` if any for the keys starting with `moved`,
+/// and anything else with an error
+fn mock_node(listener: TcpListener, slots: String, moved_to: Option<String>) {
+    common::mock_node(listener, move |args| match args[0].as_str() {
+        "CLUSTER" => slots.clone(),
+        "GET" if args[1].starts_with("moved") && moved_to.is_some() => format!(
+            "-MOVED {} {}\r\n",
+            hash_slot(args[1].as_bytes()),
+            moved_to.as_ref().unwrap()
+        ),
+        "GET" => "$-1\r\n".to_owned(),
+        _ => "-ERR unknown command\r\n".to_owned(),
+    });
+}
+
+/// The `n` first keys with `prefix` whose slot satisfies `pred`
+fn keys(prefix: &str, n: usize, pred: impl Fn(u16) -> bool) -> Vec<String> {
+    (0..)
+        .map(|i| format!("{}{}", prefix, i))
+        .filter(|key| pred(hash_slot(key.as_bytes())))
+        .take(n)
+        .collect()
+}
+
+#[test]
+fn test_cluster_stats() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-stats");
+
+    let (a, b) = (
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+    );
+    let (a_addr, b_addr) = (
+        a.local_addr().unwrap().to_string(),
+        b.local_addr().unwrap().to_string(),
+    );
+    let slots = format!(
+        "*2\r\n\
+         *3\r\n:0\r\n:8191\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n\
+         *3\r\n:8192\r\n:16383\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n",
+        a.local_addr().unwrap().port(),
+        b.local_addr().unwrap().port()
+    );
+    mock_node(a, slots.clone(), Some(b_addr.clone()));
+    mock_node(b, slots, None);
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisClusterActor::start(a_addr.clone());
+
+        // a hot shard on b
+        let gets = keys("test-stats", 9, |slot| slot >= 8192)
+            .into_iter()
+            .chain(keys("test-stats", 1, |slot| slot < 8192))
+            .chain(keys("moved", 1, |slot| slot < 8192))
+            .map(|key| addr.send(Get { key }))
+            .collect::<Vec<_>>();
+        let failing = addr.send(Incr {
+            key: keys("test-stats", 1, |slot| slot < 8192).remove(0),
+        });
+
+        future::join_all(gets)
+            .join(failing)
+            .and_then(move |(gets, failing)| {
+                for res in gets {
+                    assert_eq!(res.unwrap(), None);
+                }
+                assert!(failing.unwrap().is_err());
+                addr.send(ClusterStats)
+            })
+            .map(move |stats| {
+                assert_eq!(
+                    stats[&a_addr],
+                    NodeStats {
+                        sent: 3,
+                        errors: 1,
+                        redirections: 1,
+                        in_flight: 0,
+                    }
+                );
+                // 9 keys of its own and the redirected one
+                assert_eq!(
+                    stats[&b_addr],
+                    NodeStats {
+                        sent: 10,
+                        errors: 0,
+                        redirections: 0,
+                        in_flight: 0,
+                    }
+                );
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
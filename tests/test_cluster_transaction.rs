@@ -0,0 +1,77 @@
+use actix::prelude::*;
+use actix_redis::{
+    command::*, slot::hash_slot, Error, RedisClusterActor, RespValue, Transaction,
+};
+use futures::Future;
+
+fn incr(key: &str) -> Incr {
+    Incr { key: key.into() }
+}
+
+#[test]
+fn test_cluster_transaction() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-transaction");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+    // same hash tag, same slot
+    let (a, b) = ("{test-transaction}a", "{test-transaction}b");
+    let other = "test-transaction-other";
+    assert_ne!(hash_slot(b"test-transaction"), hash_slot(other.as_bytes()));
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![a.into(), b.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(
+                    Transaction::new()
+                        .queue(incr(a))
+                        .queue(incr(b))
+                        .queue(incr(a)),
+                )
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(
+                    res.unwrap(),
+                    vec![
+                        RespValue::Integer(1),
+                        RespValue::Integer(1),
+                        RespValue::Integer(2)
+                    ]
+                );
+                addr.send(Transaction::new().queue(incr(a)).queue(incr(other)))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                // refused before anything is sent
+                match res {
+                    Err(Error::CrossSlot { slots }) => assert_eq!(
+                        slots,
+                        vec![
+                            hash_slot(b"test-transaction"),
+                            hash_slot(other.as_bytes())
+                        ]
+                    ),
+                    res => panic!("Should not happen {:?}", res),
+                }
+                addr.send(Get { key: a.into() })
+            }
+        })
+        .map(|res| {
+            assert_eq!(res.unwrap(), Some(b"2".to_vec()));
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use actix::prelude::*;
+use actix_redis::{command::*, RedisActor};
+use futures::Future;
+
+/// Minimal server knowing `CONFIG` as `SECRET-CONFIG` only, recording the
+/// commands it receives
+fn mock_server() -> (String, Arc<Mutex<Vec<String>>>) {
+    let commands = Arc::new(Mutex::new(vec![]));
+
+    let recorded = commands.clone();
+    let addr = common::mock_server(move |args| {
+        recorded.lock().unwrap().push(args.join(" "));
+        let reply = match args[0].as_str() {
+            "SECRET-CONFIG" => "+OK\r\n",
+            "PING" => "+PONG\r\n",
+            _ => "-ERR unknown command\r\n",
+        };
+        reply.to_owned()
+    });
+
+    (addr, commands)
+}
+
+#[test]
+fn test_command_rename() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-command-rename");
+    let (server, commands) = mock_server();
+
+    let mut renames = HashMap::new();
+    renames.insert("config", "SECRET-CONFIG");
+    let addr = RedisActor::builder(server)
+        .command_rename_map(renames)
+        .start();
+
+    Arbiter::spawn_fn(move || {
+        addr.send(ConfigSet {
+            parameter: "maxmemory".into(),
+            value: "100mb".into(),
+        })
+        .join(addr.send(Ping(None)))
+        .map(move |(set, ping)| {
+            set.unwrap().unwrap();
+            assert_eq!(ping.unwrap(), "PONG");
+            assert_eq!(
+                *commands.lock().unwrap(),
+                vec!["SECRET-CONFIG SET maxmemory 100mb", "PING"]
+            );
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,106 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::{RedisActor, RedisClusterActor, Routed, Routing};
+use futures::Future;
+
+fn config_get(parameter: &str) -> ConfigGet {
+    ConfigGet {
+        parameter: parameter.into(),
+    }
+}
+
+fn config_set(parameter: &str, value: &str) -> ConfigSet {
+    ConfigSet {
+        parameter: parameter.into(),
+        value: value.into(),
+    }
+}
+
+#[test]
+fn test_config() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-config");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(config_set("maxmemory", "104857600"))
+            .join(addr.send(config_set("no-such-parameter", "1")))
+            .and_then({
+                let addr = addr.clone();
+                move |(set, unknown)| {
+                    set.unwrap().unwrap();
+                    // refused by the server
+                    assert!(unknown.unwrap().unwrap_err().starts_with("ERR"));
+                    addr.send(config_get("maxmemory"))
+                        .join(addr.send(config_get("maxmemory*")))
+                        .join(addr.send(config_get("no-such-parameter")))
+                }
+            })
+            .and_then({
+                let addr = addr.clone();
+                move |((maxmemory, glob), none)| {
+                    let maxmemory = maxmemory.unwrap();
+                    assert_eq!(maxmemory.len(), 1);
+                    assert_eq!(maxmemory["maxmemory"], "104857600");
+                    let glob = glob.unwrap();
+                    assert!(glob.len() > 1);
+                    assert_eq!(glob["maxmemory"], "104857600");
+                    assert!(glob.contains_key("maxmemory-policy"));
+                    assert!(none.unwrap().is_empty());
+                    addr.send(config_set("maxmemory", "0"))
+                }
+            })
+            .map(|res| {
+                res.unwrap().unwrap();
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_cluster_config() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-config");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Routed {
+            routing: Routing::ToAllMasters,
+            command: config_set("maxmemory-samples", "7"),
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                let masters = res.unwrap();
+                assert_eq!(masters.len(), 1);
+                assert_eq!(masters[0].1, Ok(()));
+                addr.send(Routed {
+                    routing: Routing::ToNode(masters[0].0.clone()),
+                    command: config_get("maxmemory-samples"),
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap()[0].1["maxmemory-samples"], "7");
+                addr.send(Routed {
+                    routing: Routing::ToAllMasters,
+                    command: config_set("maxmemory-samples", "5"),
+                })
+            }
+        })
+        .map(|res| {
+            res.unwrap();
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,117 @@
+use actix::prelude::*;
+use actix_redis::{command::*, Error, RedisClusterActor};
+use futures::Future;
+
+fn copy(replace: bool) -> Copy {
+    Copy {
+        src: "{test-copy}src".into(),
+        dst: "{test-copy}dst".into(),
+        replace,
+        db: None,
+    }
+}
+
+#[test]
+fn test_copy() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-copy");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec!["{test-copy}dst".into()],
+        })
+        .join(addr.send(Set {
+            key: "{test-copy}src".into(),
+            value: "value1".into(),
+            expiration: Expiration::Infinite,
+        }))
+        .and_then({
+            let addr = addr.clone();
+            move |(del, set)| {
+                del.unwrap();
+                set.unwrap();
+                addr.send(copy(false))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert!(res.unwrap());
+                addr.send(Set {
+                    key: "{test-copy}src".into(),
+                    value: "value2".into(),
+                    expiration: Expiration::Infinite,
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                // dst exists already
+                addr.send(copy(false))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert!(!res.unwrap());
+                addr.send(Get {
+                    key: "{test-copy}dst".into(),
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), Some(b"value1".to_vec()));
+                addr.send(copy(true))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert!(res.unwrap());
+                addr.send(Get {
+                    key: "{test-copy}dst".into(),
+                })
+            }
+        })
+        .map(|res| {
+            assert_eq!(res.unwrap(), Some(b"value2".to_vec()));
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_copy_cross_slot() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-copy-cross-slot");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Copy {
+            src: "test-copy-src".into(),
+            dst: "test-copy-dst".into(),
+            replace: false,
+            db: None,
+        })
+        .map(|res| {
+            match res {
+                Err(Error::MultipleSlot(_)) => (),
+                res => panic!("Should not happen {:?}", res),
+            }
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
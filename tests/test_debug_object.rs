@@ -0,0 +1,90 @@
+use actix::prelude::*;
+use actix_redis::{command::*, RedisActor, RespValue};
+use futures::Future;
+
+/// Captured from `DEBUG OBJECT` of a small list on Redis 3.2
+const REDIS_3: &str = "Value at:0x7f5c1ce17e80 refcount:1 encoding:ziplist \
+                       serializedlength:21 lru:8539612 lru_seconds_idle:7";
+
+/// Captured from `DEBUG OBJECT` of a larger list on Redis 7.2
+const REDIS_7: &str = "Value at:0x7f0f4c0a2f00 refcount:1 encoding:quicklist \
+                       serializedlength:1305 lru:10409045 lru_seconds_idle:2 \
+                       ql_nodes:2 ql_avg_node:150.00 ql_listpack_max:-2 \
+                       ql_compressed:0 ql_uncompressed_size:2713";
+
+fn parse(info: &str) -> ObjectInfo {
+    DebugObject::from_response(RespValue::SimpleString(info.into()))
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn test_debug_object_parse() {
+    let info = parse(REDIS_3);
+    assert_eq!(info.refcount(), Some(1));
+    assert_eq!(info.encoding(), Some("ziplist"));
+    assert_eq!(info.serialized_length(), Some(21));
+    assert_eq!(info.lru(), Some(8_539_612));
+    assert_eq!(info.lru_seconds_idle(), Some(7));
+    assert_eq!(info.fields["at"], "0x7f5c1ce17e80");
+
+    let info = parse(REDIS_7);
+    assert_eq!(info.encoding(), Some("quicklist"));
+    assert_eq!(info.serialized_length(), Some(1305));
+    // fields without an accessor are kept
+    assert_eq!(info.fields["ql_nodes"], "2");
+    assert_eq!(info.fields["ql_avg_node"], "150.00");
+
+    // fields missing from older versions
+    let info = parse("Value at:0x1 refcount:2 encoding:int");
+    assert_eq!(info.refcount(), Some(2));
+    assert_eq!(info.lru(), None);
+
+    assert_eq!(
+        DebugObject::from_response(RespValue::Error("ERR no such key".into())).unwrap(),
+        None
+    );
+    assert!(DebugObject::from_response(RespValue::Error(
+        "ERR DEBUG command not allowed".into()
+    ))
+    .is_err());
+    assert!(DebugObject::from_response(RespValue::Integer(1)).is_err());
+}
+
+#[test]
+fn test_debug_object() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-debug-object");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Set {
+            key: "test-debug-object".into(),
+            value: "12345".into(),
+            expiration: Expiration::Infinite,
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(DebugObject {
+                    key: "test-debug-object".into(),
+                })
+                .join(addr.send(DebugObject {
+                    key: "test-debug-object-missing".into(),
+                }))
+            }
+        })
+        .map(|(info, missing)| {
+            let info = info.unwrap().unwrap();
+            assert_eq!(info.encoding(), Some("int"));
+            assert!(info.refcount().is_some());
+            assert_eq!(missing.unwrap(), None);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
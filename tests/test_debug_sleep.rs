@@ -0,0 +1,31 @@
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::{command::*, RedisClusterActor, Routed, Routing};
+use futures::Future;
+
+#[test]
+fn test_debug_sleep() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-debug-sleep");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        let start = Instant::now();
+        addr.send(Routed {
+            routing: Routing::ToNode("127.0.0.1:7000".into()),
+            command: DebugSleep { seconds: 0.2 },
+        })
+        .map(move |res| {
+            let (node, res) = res.unwrap().pop().unwrap();
+            assert_eq!(node, "127.0.0.1:7000");
+            res.unwrap();
+            assert!(start.elapsed() >= Duration::from_millis(200));
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,52 @@
+use actix::prelude::*;
+use actix_redis::{command::*, Drain, RedisActor};
+use futures::{Async, Future};
+
+#[test]
+fn test_drain() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-drain");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+    let key = "test-drain";
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![key.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                // nothing is pending
+                addr.send(Drain)
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                let mut incrs = (0..10)
+                    .map(|_| addr.send(Incr { key: key.into() }))
+                    .collect::<Vec<_>>();
+                addr.send(Drain).map(move |res| {
+                    res.unwrap();
+                    incrs
+                        .iter_mut()
+                        .map(|incr| match incr.poll() {
+                            Ok(Async::Ready(res)) => res.unwrap().unwrap(),
+                            res => panic!("Should not happen {:?}", res),
+                        })
+                        .collect::<Vec<_>>()
+                })
+            }
+        })
+        .map(|counts| {
+            assert_eq!(counts, (1..=10).collect::<Vec<_>>());
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
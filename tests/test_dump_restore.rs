@@ -0,0 +1,94 @@
+use actix::prelude::*;
+use actix_redis::{command::*, RedisActor, RedisClusterActor};
+use futures::Future;
+
+#[test]
+fn test_dump_restore() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-dump-restore");
+
+    let src = RedisActor::start("127.0.0.1:6379");
+    let dst = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        src.send(Set {
+            key: "test-dump".into(),
+            value: "value".into(),
+            expiration: Expiration::Ex("100".into()),
+        })
+        .and_then({
+            let src = src.clone();
+            move |res| {
+                res.unwrap();
+                src.send(Dump {
+                    key: "test-dump".into(),
+                })
+                .join(src.send(Pttl {
+                    key: "test-dump".into(),
+                }))
+            }
+        })
+        .and_then({
+            let dst = dst.clone();
+            move |(payload, ttl)| {
+                let payload = payload.unwrap().unwrap();
+                let ttl = ttl.unwrap().unwrap();
+                let restore = |replace| Restore {
+                    key: "test-dump".into(),
+                    ttl_ms: ttl,
+                    payload: payload.clone(),
+                    replace,
+                };
+                dst.send(restore(true))
+                    .join(dst.send(restore(false)))
+                    .join(dst.send(restore(true)))
+            }
+        })
+        .and_then({
+            let dst = dst.clone();
+            move |((created, busy), replaced)| {
+                created.unwrap();
+                // the key exists already
+                assert!(busy.is_err());
+                replaced.unwrap();
+
+                dst.send(Get {
+                    key: "test-dump".into(),
+                })
+                .join(dst.send(Ttl {
+                    key: "test-dump".into(),
+                }))
+            }
+        })
+        .map(|(value, ttl)| {
+            assert_eq!(value.unwrap(), Some(b"value".to_vec()));
+            let ttl = ttl.unwrap().unwrap();
+            assert!(ttl > 90 && ttl <= 100, "unexpected TTL {}", ttl);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_dump_missing_key() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-dump-missing-key");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Dump {
+            key: "test-dump-missing".into(),
+        })
+        .map(|res| {
+            assert_eq!(res.unwrap(), None);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::{command::*, Error, RedisActor};
+use futures::Future;
+
+#[test]
+fn test_eager_connect() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-eager-connect");
+
+    Arbiter::spawn_fn(move || {
+        RedisActor::start_eagerly("127.0.0.1:6379")
+            .and_then(|addr| {
+                // the connection is already up, so the first command is fast
+                let start = Instant::now();
+                addr.send(Ping(None))
+                    .map_err(|_| Error::Disconnected)
+                    .map(move |res| {
+                        assert_eq!(res.unwrap(), "PONG");
+                        assert!(start.elapsed() < Duration::from_millis(100));
+                    })
+            })
+            .map(|()| System::current().stop())
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_eager_connect_error() -> std::io::Result<()> {
+    let sys = System::new("test-eager-connect-error");
+
+    Arbiter::spawn_fn(move || {
+        RedisActor::start_eagerly("localhost:54000").then(|res| {
+            match res {
+                Err(Error::NotConnected) => (),
+                Err(e) => panic!("Should not happen {:?}", e),
+                Ok(_) => panic!("Should not happen"),
+            }
+            System::current().stop();
+            Ok(())
+        })
+    });
+
+    sys.run()
+}
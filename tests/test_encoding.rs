@@ -0,0 +1,117 @@
+mod common;
+
+use actix::prelude::*;
+use actix_redis::encoding::{EncodingAdvisory, EncodingReport, KeyEncoding};
+use actix_redis::RedisActor;
+use futures::Future;
+
+fn key(key: &str, encoding: &str, fields: Option<i64>) -> KeyEncoding {
+    KeyEncoding {
+        key: key.into(),
+        encoding: encoding.into(),
+        fields,
+    }
+}
+
+#[test]
+fn test_encoding_report() {
+    // captured `OBJECT ENCODING` and `HLEN` of sessions
+    let report = EncodingReport::new(
+        Some(128),
+        vec![
+            key("session:1", "listpack", Some(12)),
+            key("session:2", "listpack", Some(100)),
+            key("session:3", "hashtable", Some(200)),
+            key("session:4", "hashtable", Some(150)),
+            // grown by the size of a value
+            key("session:5", "hashtable", Some(3)),
+            key("session:6", "embstr", None),
+            // a set
+            key("user-sessions:1", "hashtable", None),
+        ],
+    );
+
+    assert_eq!(report.max_entries, Some(128));
+    assert_eq!(
+        report
+            .encodings
+            .iter()
+            .map(|(encoding, n)| (encoding.as_str(), *n))
+            .collect::<Vec<_>>(),
+        [("embstr", 1), ("hashtable", 4), ("listpack", 2)]
+    );
+    assert_eq!(
+        report.grown,
+        [
+            ("session:3".to_owned(), 200),
+            ("session:4".to_owned(), 150),
+            ("session:5".to_owned(), 3),
+        ]
+    );
+    assert_eq!(report.suggested_max_entries, Some(256));
+
+    // hashes too large to be kept compact
+    let report = EncodingReport::new(
+        Some(128),
+        vec![
+            key("session:1", "hashtable", Some(5000)),
+            key("session:2", "hashtable", Some(3)),
+        ],
+    );
+    assert_eq!(report.grown.len(), 2);
+    assert_eq!(report.suggested_max_entries, None);
+
+    // all compact
+    let report = EncodingReport::new(None, vec![key("session:1", "ziplist", Some(4))]);
+    assert!(report.grown.is_empty());
+    assert_eq!(report.suggested_max_entries, None);
+}
+
+/// Minimal server holding a compact hash `small`, a grown hash `large` and a
+/// string `string`
+fn mock_server() -> String {
+    common::mock_server(|args| {
+        let reply = match (args[0].as_str(), args.last().unwrap().as_str()) {
+            ("CONFIG", _) => "*2\r\n$24\r\nhash-max-ziplist-entries\r\n$3\r\n128\r\n",
+            ("OBJECT", "small") => "$8\r\nlistpack\r\n",
+            ("OBJECT", "large") => "$9\r\nhashtable\r\n",
+            ("OBJECT", "string") => "$6\r\nembstr\r\n",
+            ("OBJECT", _) => "$-1\r\n",
+            ("HLEN", "small") => ":10\r\n",
+            ("HLEN", "large") => ":300\r\n",
+            ("HLEN", _) => "-WRONGTYPE Operation against a key\r\n",
+            _ => "-ERR unknown command\r\n",
+        };
+        reply.to_owned()
+    })
+}
+
+#[test]
+fn test_encoding_advisory() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-encoding-advisory");
+    let server = mock_server();
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisActor::start(server);
+        addr.send(EncodingAdvisory {
+            keys: vec![
+                "small".into(),
+                "large".into(),
+                "string".into(),
+                "missing".into(),
+            ],
+        })
+        .map(|report| {
+            let report = report.unwrap();
+            assert_eq!(report.max_entries, Some(128));
+            assert_eq!(report.encodings.values().sum::<usize>(), 3);
+            assert_eq!(report.grown, [("large".to_owned(), 300)]);
+            assert_eq!(report.suggested_max_entries, Some(512));
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
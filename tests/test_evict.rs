@@ -0,0 +1,93 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use actix::prelude::*;
+use actix_redis::{evict::EvictIdlest, RedisActor};
+use futures::Future;
+
+/// Minimal server holding `keys` with their idle time and whether they expire,
+/// answering `RANDOMKEY` with each of them in turn
+fn mock_server(keys: &[(&str, i64, bool)]) -> (String, Arc<Mutex<Vec<Vec<String>>>>) {
+    let unlinked = Arc::new(Mutex::new(vec![]));
+    let keys = keys
+        .iter()
+        .map(|&(key, idle, expires)| (key.to_owned(), (idle, expires)))
+        .collect::<Vec<_>>();
+    let table = keys.iter().cloned().collect::<HashMap<_, _>>();
+    let next = Arc::new(AtomicUsize::new(0));
+
+    let log = unlinked.clone();
+    let addr = common::mock_server(move |args| match args[0].as_str() {
+        "RANDOMKEY" => {
+            let key = &keys[next.fetch_add(1, Ordering::SeqCst) % keys.len()].0;
+            format!("${}\r\n{}\r\n", key.len(), key)
+        }
+        "OBJECT" => format!(":{}\r\n", table[&args[2]].0),
+        "TTL" if table[&args[1]].1 => ":100\r\n".to_owned(),
+        "TTL" => ":-1\r\n".to_owned(),
+        "UNLINK" => {
+            log.lock().unwrap().push(args[1..].to_vec());
+            ":1\r\n".to_owned()
+        }
+        _ => "-ERR unknown command\r\n".to_owned(),
+    });
+
+    (addr, unlinked)
+}
+
+#[test]
+fn test_evict_idlest() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-evict-idlest");
+
+    let (addr, unlinked) = mock_server(&[
+        ("fresh", 1, true),
+        ("old", 300, false),
+        ("stale", 60, true),
+        ("recent", 10, true),
+        ("oldest", 900, true),
+    ]);
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisActor::start(addr);
+
+        // the fifth key is not sampled
+        addr.send(EvictIdlest {
+            sample: 4,
+            evict: 2,
+            volatile: false,
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |evicted| {
+                assert_eq!(
+                    evicted.unwrap(),
+                    vec![("old".to_owned(), 300), ("stale".to_owned(), 60)]
+                );
+                // sampled again, each key twice
+                addr.send(EvictIdlest {
+                    sample: 10,
+                    evict: 2,
+                    volatile: true,
+                })
+            }
+        })
+        .map(move |evicted| {
+            assert_eq!(
+                evicted.unwrap(),
+                vec![("oldest".to_owned(), 900), ("stale".to_owned(), 60)]
+            );
+            assert_eq!(
+                *unlinked.lock().unwrap(),
+                vec![vec!["old"], vec!["stale"], vec!["oldest"], vec!["stale"]]
+            );
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
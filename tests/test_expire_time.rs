@@ -0,0 +1,63 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix::prelude::*;
+use actix_redis::{command::*, RedisClusterActor};
+use futures::Future;
+
+const KEY: &str = "test-expire-time";
+const PERSISTENT: &str = "test-expire-time-persistent";
+
+#[test]
+fn test_expire_time() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-expire-time");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let at = now.as_secs() as i64 + 100;
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .join(addr.send(Set {
+            key: PERSISTENT.into(),
+            value: "value".into(),
+            expiration: Expiration::Infinite,
+        }))
+        .and_then({
+            let addr = addr.clone();
+            move |(del, set)| {
+                del.unwrap();
+                set.unwrap();
+                addr.send(ExpireTime { key: KEY.into() })
+                    .join(addr.send(ExpireTime {
+                        key: PERSISTENT.into(),
+                    }))
+                    .join(addr.send(Set {
+                        key: KEY.into(),
+                        value: "value".into(),
+                        expiration: Expiration::ExAt(at.to_string()),
+                    }))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |((missing, persistent), set)| {
+                assert_eq!(missing.unwrap(), -2);
+                assert_eq!(persistent.unwrap(), -1);
+                set.unwrap();
+                addr.send(ExpireTime { key: KEY.into() })
+                    .join(addr.send(PExpireTime { key: KEY.into() }))
+            }
+        })
+        .map(move |(seconds, millis)| {
+            assert_eq!(seconds.unwrap(), at);
+            assert_eq!(millis.unwrap(), at * 1000);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "test-util")]
+
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::fault::Faults;
+use actix_redis::{command::*, Error, RedisActor};
+use futures::Future;
+use tokio_timer::Timeout;
+
+#[test]
+fn test_fault_injection() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-fault-injection");
+
+    Arbiter::spawn_fn(|| {
+        let slow = RedisActor::builder("127.0.0.1:6379")
+            .faults(Faults::new().delay(1.0, Duration::from_millis(500)))
+            .start();
+        let failing = RedisActor::builder("127.0.0.1:6379")
+            .faults(Faults::new().fail(1.0))
+            .start();
+
+        // the application gives up on the slow server
+        Timeout::new(slow.send(Ping(None)), Duration::from_millis(100))
+            .then(move |res| {
+                match res {
+                    Err(ref e) if e.is_elapsed() => (),
+                    res => panic!("Should not happen {:?}", res.map(|_| ())),
+                }
+                let start = Instant::now();
+                failing.send(Ping(None)).map(move |res| (res, start))
+            })
+            .map(|(res, start)| {
+                match res {
+                    Err(Error::Disconnected) => (),
+                    res => panic!("Should not happen {:?}", res),
+                }
+                assert!(start.elapsed() < Duration::from_millis(100));
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,89 @@
+#![cfg(target_os = "linux")]
+
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::{RedisActor, RedisClusterActor};
+use futures::future::{self, Loop};
+use futures::Future;
+use tokio_timer::Delay;
+
+const ROUNDS: usize = 50;
+
+fn open_fds() -> usize {
+    std::fs::read_dir("/proc/self/fd").unwrap().count()
+}
+
+/// Run `round` `ROUNDS` times, giving the actors dropped in a round a moment
+/// to stop, and check that the number of open file descriptors does not grow
+fn churn<F, R>(round: F) -> impl Future<Item = (), Error = ()>
+where
+    F: Fn() -> R + 'static,
+    R: Future<Item = (), Error = ()> + 'static,
+{
+    let settle =
+        |()| Delay::new(Instant::now() + Duration::from_millis(20)).map_err(|_| ());
+
+    // warm up the resolver and the timer before counting
+    round()
+        .and_then(settle)
+        .map(|()| open_fds())
+        .and_then(move |before| {
+            future::loop_fn(0, move |i| {
+                round().and_then(settle).map(move |()| match i + 1 {
+                    ROUNDS => Loop::Break(()),
+                    next => Loop::Continue(next),
+                })
+            })
+            .map(move |()| {
+                let after = open_fds();
+                assert!(after < before + 10, "{} fds, {} before", after, before);
+            })
+        })
+}
+
+#[test]
+fn test_fd_leak() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-fd-leak");
+
+    Arbiter::spawn_fn(|| {
+        churn(|| {
+            let addr = RedisActor::start("127.0.0.1:6379");
+            addr.send(Ping(None))
+                // dropped once answered
+                .map(move |res| {
+                    assert_eq!(res.unwrap(), "PONG");
+                    drop(addr);
+                })
+                .map_err(|e| panic!("Should not happen {:?}", e))
+        })
+        .map(|()| System::current().stop())
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_cluster_fd_leak() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-fd-leak");
+
+    Arbiter::spawn_fn(|| {
+        churn(|| {
+            let addr = RedisClusterActor::start("127.0.0.1:7000");
+            addr.send(Get {
+                key: "test-fd-leak".into(),
+            })
+            .map(move |res| {
+                res.unwrap();
+                drop(addr);
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+        })
+        .map(|()| System::current().stop())
+    });
+
+    sys.run()
+}
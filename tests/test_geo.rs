@@ -0,0 +1,129 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::RedisClusterActor;
+use futures::Future;
+
+const KEY: &str = "test-geo";
+
+fn search(with: bool) -> GeoSearch {
+    GeoSearch {
+        key: KEY.into(),
+        from: GeoFrom::LonLat(15.0, 37.0),
+        by: GeoBy::Radius(200.0, GeoUnit::Kilometers),
+        order: Some(GeoOrder::Asc),
+        count: None,
+        any: false,
+        with_coord: with,
+        with_dist: with,
+    }
+}
+
+#[test]
+fn test_geo() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-geo");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(GeoAdd {
+                    key: KEY.into(),
+                    members: vec![
+                        (13.361389, 38.115556, "Palermo".into()),
+                        (15.087269, 37.502669, "Catania".into()),
+                        (12.496366, 41.902782, "Rome".into()),
+                    ],
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), 3);
+                addr.send(search(false))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                let members = res.unwrap();
+                let names: Vec<_> = members.iter().map(|m| m.member.as_str()).collect();
+                // Rome is out of the radius
+                assert_eq!(names, vec!["Catania", "Palermo"]);
+                assert!(members
+                    .iter()
+                    .all(|m| m.distance.is_none() && m.coordinates.is_none()));
+                addr.send(search(true))
+            }
+        })
+        .map(|res| {
+            let members = res.unwrap();
+            assert_eq!(members.len(), 2);
+            let catania = &members[0];
+            assert_eq!(catania.member, "Catania");
+            let distance = catania.distance.unwrap();
+            assert!((distance - 56.4413).abs() < 0.01, "{}", distance);
+            let (longitude, latitude) = catania.coordinates.unwrap();
+            assert!((longitude - 15.087269).abs() < 0.0001);
+            assert!((latitude - 37.502669).abs() < 0.0001);
+            assert!(members[1].distance.unwrap() > distance);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_geo_search_response() {
+    use actix_redis::RespValue::{Array, BulkString};
+
+    let res = Array(vec![
+        Array(vec![
+            BulkString(b"Catania".to_vec()),
+            BulkString(b"56.4413".to_vec()),
+            Array(vec![
+                BulkString(b"15.087267".to_vec()),
+                BulkString(b"37.502668".to_vec()),
+            ]),
+        ]),
+        Array(vec![
+            BulkString(b"Palermo".to_vec()),
+            BulkString(b"190.4424".to_vec()),
+        ]),
+    ]);
+    assert_eq!(
+        GeoSearch::from_response(res).unwrap(),
+        vec![
+            GeoMember {
+                member: "Catania".into(),
+                distance: Some(56.4413),
+                coordinates: Some((15.087267, 37.502668)),
+            },
+            GeoMember {
+                member: "Palermo".into(),
+                distance: Some(190.4424),
+                coordinates: None,
+            },
+        ]
+    );
+
+    let res = Array(vec![Array(vec![
+        BulkString(b"Catania".to_vec()),
+        Array(vec![BulkString(b"15".to_vec()), BulkString(b"37".to_vec())]),
+    ])]);
+    let members = GeoSearch::from_response(res).unwrap();
+    assert_eq!(members[0].distance, None);
+    assert_eq!(members[0].coordinates, Some((15.0, 37.0)));
+
+    let res = Array(vec![BulkString(b"Catania".to_vec())]);
+    assert_eq!(GeoSearch::from_response(res).unwrap()[0].member, "Catania");
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use std::collections::HashMap;
+
+use actix::prelude::*;
+use actix_redis::inspect::{GetAny, RedisValue};
+use actix_redis::RedisActor;
+use futures::{future, Future};
+
+/// Minimal server holding a key of each type, named after it
+fn mock_server() -> String {
+    common::mock_server(|args| match (args[0].as_str(), args[1].as_str()) {
+        ("TYPE", "missing") => "+none\r\n".to_owned(),
+        ("TYPE", t) => format!("+{}\r\n", t),
+        ("GET", "string") => "$5\r\nvalue\r\n".to_owned(),
+        ("LRANGE", "list") if args[2..] == ["0", "-1"] => {
+            "*2\r\n$1\r\na\r\n$1\r\nb\r\n".to_owned()
+        }
+        ("SMEMBERS", "set") => "*1\r\n$1\r\nm\r\n".to_owned(),
+        ("HGETALL", "hash") => "*2\r\n$1\r\nf\r\n$1\r\nv\r\n".to_owned(),
+        ("ZRANGE", "zset") if args[2..] == ["0", "-1", "WITHSCORES"] => {
+            "*4\r\n$1\r\nx\r\n$1\r\n1\r\n$1\r\ny\r\n$3\r\n2.5\r\n".to_owned()
+        }
+        _ => "-ERR unknown command\r\n".to_owned(),
+    })
+}
+
+#[test]
+fn test_get_any() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-get-any");
+    let server = mock_server();
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisActor::start(server);
+        let keys = ["string", "list", "set", "hash", "zset", "stream", "missing"];
+        let values = keys.iter().map(|key| {
+            addr.send(GetAny {
+                key: (*key).to_owned(),
+            })
+        });
+        future::join_all(values.collect::<Vec<_>>())
+            .map(|values| {
+                let values = values.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+                let mut hash = HashMap::new();
+                hash.insert(b"f".to_vec(), b"v".to_vec());
+                assert_eq!(
+                    values,
+                    [
+                        Some(RedisValue::String(b"value".to_vec())),
+                        Some(RedisValue::List(vec![b"a".to_vec(), b"b".to_vec()])),
+                        Some(RedisValue::Set(vec![b"m".to_vec()])),
+                        Some(RedisValue::Hash(hash)),
+                        Some(RedisValue::ZSet(vec![
+                            (b"x".to_vec(), 1.0),
+                            (b"y".to_vec(), 2.5)
+                        ])),
+                        Some(RedisValue::Other("stream".into())),
+                        None,
+                    ]
+                );
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,74 @@
+use actix::prelude::*;
+use actix_redis::{command::*, RedisActor};
+use futures::Future;
+
+#[test]
+fn test_getex() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-getex");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Set {
+            key: "test-getex".into(),
+            value: "value".into(),
+            expiration: Expiration::Ex("10".into()),
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+
+                // read the value and extend the TTL in one round trip
+                addr.send(GetEx {
+                    key: "test-getex".into(),
+                    expiration: Some(Expiration::Ex("1000".into())),
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap().unwrap(), b"value");
+
+                addr.send(Ttl {
+                    key: "test-getex".into(),
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                let ttl = res.unwrap().unwrap();
+                assert!(ttl > 10 && ttl <= 1000, "unexpected TTL {}", ttl);
+
+                // PERSIST clears the TTL
+                addr.send(GetEx {
+                    key: "test-getex".into(),
+                    expiration: Some(Expiration::Infinite),
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap().unwrap(), b"value");
+
+                addr.send(Ttl {
+                    key: "test-getex".into(),
+                })
+            }
+        })
+        .map(|res| {
+            match res.unwrap() {
+                Err(TtlError::NoExpire) => (),
+                res => panic!("Should not happen {:?}", res),
+            }
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,70 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::RedisClusterActor;
+use futures::Future;
+
+fn hsetnx(field: &str, value: &[u8]) -> HSetNx {
+    HSetNx {
+        key: "test-hash".into(),
+        field: field.into(),
+        value: value.to_vec(),
+    }
+}
+
+fn hexists(field: &str) -> HExists {
+    HExists {
+        key: "test-hash".into(),
+        field: field.into(),
+    }
+}
+
+#[test]
+fn test_hsetnx_hexists() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-hsetnx-hexists");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec!["test-hash".into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(hexists("flag"))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert!(!res.unwrap());
+                addr.send(hsetnx("flag", b"on"))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert!(res.unwrap());
+                addr.send(hsetnx("flag", b"off"))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                // the field already exists
+                assert!(!res.unwrap());
+                addr.send(hexists("flag")).join(addr.send(hexists("other")))
+            }
+        })
+        .map(|(flag, other)| {
+            assert!(flag.unwrap());
+            assert!(!other.unwrap());
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::{
+    ClusterHealth, ConnectionState, Health, RedisActor, RedisClusterActor,
+};
+use futures::Future;
+use tokio_timer::Delay;
+
+use common::mock_node;
+
+/// Minimal cluster whose only master serves the slots 0 to 8191: the master
+/// of the other half is missing from `CLUSTER SLOTS`. Returns its address.
+fn mock_partial_cluster() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let slots = format!(
+        "*1\r\n*3\r\n:0\r\n:8191\r\n*2\r\n$9\r\n127.0.0.1\r\n:{}\r\n",
+        port
+    );
+
+    mock_node(listener, move |args| match args[0].as_str() {
+        "CLUSTER" => slots.clone(),
+        "PING" => "+PONG\r\n".to_owned(),
+        _ => "-ERR unknown command\r\n".to_owned(),
+    });
+
+    format!("127.0.0.1:{}", port)
+}
+
+#[test]
+fn test_cluster_health_incomplete_slots() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-health-incomplete-slots");
+    let seed = mock_partial_cluster();
+
+    Arbiter::spawn_fn(move || {
+        let node = seed.clone();
+        RedisClusterActor::start_eagerly(seed)
+            .and_then(|addr| addr.send(ClusterHealth).then(|res| res.unwrap()))
+            .map(move |health| {
+                assert_eq!(health.slots_covered, 8192);
+                assert!(!health.slots_complete);
+                assert_eq!(health.last_error, None);
+                assert_eq!(health.nodes.len(), 1);
+                let report = &health.nodes[&node];
+                assert_eq!(report.addr, node);
+                assert_eq!(report.state, ConnectionState::Connected);
+                assert_eq!(report.reconnections, 0);
+                assert_eq!(report.in_flight, 0);
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_health_disconnected() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-health-disconnected");
+
+    // nothing listens on the port once the listener is dropped
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addr = RedisActor::start(format!("127.0.0.1:{}", port));
+
+    Arbiter::spawn_fn(move || {
+        Delay::new(Instant::now() + Duration::from_millis(200))
+            .map_err(|e| panic!("Should not happen {:?}", e))
+            .and_then(move |()| addr.send(Health))
+            .map(|health| {
+                assert_eq!(health.state, ConnectionState::Disconnected);
+                assert!(health.last_error.is_some());
+                assert_eq!(health.reconnections, 0);
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "serde")]
+
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::{RedisClusterActor, RespValue};
+use futures::Future;
+use serde::Deserialize;
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Mode {
+    Fast,
+    Safe,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Config {
+    name: String,
+    retries: u32,
+    ratio: f64,
+    enabled: bool,
+    mode: Mode,
+    comment: Option<String>,
+}
+
+#[test]
+fn test_hgetall_typed() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-hgetall-typed");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+    let key = "test-hgetall-typed";
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![key.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(HSet {
+                    key: key.into(),
+                    fields: vec![
+                        ("name".into(), b"primary".to_vec()),
+                        ("retries".into(), b"3".to_vec()),
+                        ("ratio".into(), b"0.5".to_vec()),
+                        ("enabled".into(), b"1".to_vec()),
+                        ("mode".into(), b"safe".to_vec()),
+                    ],
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), 5);
+                addr.send(HGetAllTyped::<Config>::new(key))
+            }
+        })
+        .map(|res| {
+            assert_eq!(
+                res.unwrap(),
+                Config {
+                    name: "primary".into(),
+                    retries: 3,
+                    ratio: 0.5,
+                    enabled: true,
+                    mode: Mode::Safe,
+                    comment: None,
+                }
+            );
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_hgetall_typed_response() {
+    let hash = |fields: &[(&str, &str)]| {
+        RespValue::Array(
+            fields
+                .iter()
+                .flat_map(|&(field, value)| vec![field.into(), value.into()])
+                .collect(),
+        )
+    };
+
+    let config = HGetAllTyped::<Config>::from_response(hash(&[
+        ("name", "42"),
+        ("retries", "0"),
+        ("ratio", "-1e3"),
+        ("enabled", "false"),
+        ("mode", "fast"),
+        ("comment", "numeric name"),
+        ("unknown", "ignored"),
+    ]))
+    .unwrap();
+    assert_eq!(
+        config,
+        Config {
+            name: "42".into(),
+            retries: 0,
+            ratio: -1000.0,
+            enabled: false,
+            mode: Mode::Fast,
+            comment: Some("numeric name".into()),
+        }
+    );
+
+    let e = HGetAllTyped::<Config>::from_response(hash(&[("name", "primary")]))
+        .unwrap_err()
+        .to_string();
+    assert!(e.contains("missing field `retries`"), "{}", e);
+
+    let e = HGetAllTyped::<Config>::from_response(hash(&[
+        ("name", "primary"),
+        ("retries", "three"),
+    ]))
+    .unwrap_err()
+    .to_string();
+    assert!(e.contains("invalid value"), "{}", e);
+
+    // a missing hash
+    assert!(HGetAllTyped::<Config>::from_response(hash(&[])).is_err());
+}
@@ -0,0 +1,93 @@
+use actix::prelude::*;
+use actix_redis::{command::*, Error, RedisClusterActor};
+use futures::Future;
+
+const A: &str = "{test-hll}a";
+const B: &str = "{test-hll}b";
+const ALL: &str = "{test-hll}all";
+
+fn pfadd(key: &str, visitors: std::ops::Range<u32>) -> PfAdd {
+    PfAdd {
+        key: key.into(),
+        elements: visitors
+            .map(|i| format!("visitor-{}", i).into_bytes())
+            .collect(),
+    }
+}
+
+fn pfcount(keys: &[&str]) -> PfCount {
+    PfCount {
+        keys: keys.iter().map(|&key| key.into()).collect(),
+    }
+}
+
+/// Within three standard errors of the true cardinality
+fn assert_estimates(count: i64, cardinality: i64) {
+    let margin = cardinality as f64 * 0.0081 * 3.0;
+    assert!(
+        ((count - cardinality) as f64).abs() <= margin,
+        "estimated {} for {}",
+        count,
+        cardinality
+    );
+}
+
+#[test]
+fn test_hyperloglog() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-hyperloglog");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![A.into(), B.into(), ALL.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(pfadd(A, 0..1000))
+                    .join3(addr.send(pfadd(B, 500..1500)), addr.send(pfadd(A, 0..10)))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |(a, b, again)| {
+                assert!(a.unwrap());
+                assert!(b.unwrap());
+                // already counted
+                assert!(!again.unwrap());
+                addr.send(pfcount(&[A]))
+                    .join3(
+                        addr.send(pfcount(&[A, B])),
+                        addr.send(PfMerge {
+                            dest: ALL.into(),
+                            sources: vec![A.into(), B.into()],
+                        }),
+                    )
+                    .join(addr.send(pfcount(&["test-hll-a", "test-hll-b"])))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |((a, union, merged), cross_slot)| {
+                assert_estimates(a.unwrap(), 1000);
+                assert_estimates(union.unwrap(), 1500);
+                merged.unwrap();
+                match cross_slot {
+                    Err(Error::MultipleSlot(_)) => (),
+                    res => panic!("Should not happen {:?}", res),
+                }
+                addr.send(pfcount(&[ALL]))
+            }
+        })
+        .map(|res| {
+            assert_estimates(res.unwrap(), 1500);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
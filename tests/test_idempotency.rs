@@ -0,0 +1,104 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::{idempotency, RedisClusterActor};
+use futures::Future;
+use tokio_timer::Delay;
+
+const KEY: &str = "test-idempotency";
+
+#[test]
+fn test_idempotency() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-idempotency");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        let redis = addr.clone().recipient();
+        let computed = Rc::new(Cell::new(0));
+        // two duplicate requests at once
+        let request = move |response: &'static [u8]| {
+            let computed = computed.clone();
+            idempotency::run(&redis, KEY, Duration::from_secs(60), move || {
+                computed.set(computed.get() + 1);
+                assert_eq!(computed.get(), 1, "computed twice");
+                Delay::new(Instant::now() + Duration::from_millis(200))
+                    .map(move |()| response.to_vec())
+                    .map_err(|e| panic!("Should not happen {:?}", e))
+            })
+        };
+
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+        .and_then(move |res| {
+            res.unwrap();
+            request(b"first").join(request(b"second"))
+        })
+        .map(|(a, b): (Vec<u8>, Vec<u8>)| {
+            assert_eq!(a, b);
+            System::current().stop();
+        })
+        .map_err(|e: actix_redis::Error| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_idempotency_abandon() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-idempotency-abandon");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+    let key = "test-idempotency-abandon";
+
+    Arbiter::spawn_fn(move || {
+        let redis = addr.clone().recipient();
+        addr.send(Del {
+            keys: vec![key.into()],
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+        .and_then({
+            let redis = redis.clone();
+            move |res| {
+                res.unwrap();
+                idempotency::run(&redis, key, Duration::from_secs(60), || {
+                    Err::<Vec<u8>, _>(actix_redis::Error::NotConnected)
+                })
+                .then(|res| {
+                    assert!(res.is_err());
+                    Ok(())
+                })
+            }
+        })
+        .and_then({
+            let redis = redis.clone();
+            // freed for a retry
+            move |()| idempotency::reserve(&redis, key, Duration::from_secs(60))
+        })
+        .and_then({
+            let redis = redis.clone();
+            move |reservation| {
+                assert_eq!(reservation, idempotency::Reservation::First);
+                idempotency::complete(&redis, key, b"done", Duration::from_secs(60))
+            }
+        })
+        .and_then(move |()| idempotency::reserve(&redis, key, Duration::from_secs(60)))
+        .map(|reservation| {
+            assert_eq!(
+                reservation,
+                idempotency::Reservation::Done(b"done".to_vec())
+            );
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,63 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::{RedisActor, RespValue};
+use futures::Future;
+
+/// Captured from `INFO` of a Redis 7.2 server, shortened
+const INFO: &str = "# Server\r
+redis_version:7.2.4\r
+redis_mode:standalone\r
+os:Linux 6.1.0 x86_64\r
+\r
+# Memory\r
+used_memory:1103960\r
+used_memory_human:1.05M\r
+maxmemory_policy:noeviction\r
+\r
+# Replication\r
+role:master\r
+connected_slaves:0\r
+master_replid:8d1c4ea5e5a1c4f0c3b0e2a0a5c1f1e7d6b3a2c1\r
+\r
+# Keyspace\r
+db0:keys=3,expires=1,avg_ttl=59000\r
+";
+
+#[test]
+fn test_info_parse() {
+    let sections = Info::from_response(RespValue::BulkString(INFO.into())).unwrap();
+
+    assert_eq!(sections.len(), 4);
+    assert_eq!(sections["Memory"]["used_memory"], "1103960");
+    assert_eq!(sections["Memory"].len(), 3);
+    // values containing the separator are kept whole
+    assert_eq!(sections["Server"]["os"], "Linux 6.1.0 x86_64");
+    assert_eq!(sections["Replication"]["role"], "master");
+    assert_eq!(
+        sections["Keyspace"]["db0"],
+        "keys=3,expires=1,avg_ttl=59000"
+    );
+}
+
+#[test]
+fn test_info() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-info");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Info {
+            section: Some("memory".into()),
+        })
+        .map(|res| {
+            let sections = res.unwrap();
+            let used_memory: u64 = sections["Memory"]["used_memory"].parse().unwrap();
+            assert!(used_memory > 0);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,129 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::slot::{HashError, Hasher};
+use actix_redis::{Error, RedisClusterActor, RespError, RespValue};
+use futures::Future;
+
+/// Not provided by the crate, only needed to set up the test
+struct RPush {
+    key: String,
+    elements: Vec<Vec<u8>>,
+}
+
+impl Message for RPush {
+    type Result = Result<RespValue, Error>;
+}
+
+impl Command for RPush {
+    type Output = RespValue;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["RPUSH".into(), self.key.into()];
+        v.extend(self.elements.into_iter().map(RespValue::BulkString));
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        Ok(res)
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+fn lmpop() -> LMPop {
+    LMPop {
+        keys: vec!["{test-lmpop}first".into(), "{test-lmpop}second".into()],
+        direction: Direction::Left,
+        count: 2,
+    }
+}
+
+#[test]
+fn test_lmpop() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-lmpop");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec!["{test-lmpop}first".into(), "{test-lmpop}second".into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(RPush {
+                    key: "{test-lmpop}second".into(),
+                    elements: vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()],
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                // the first list is empty
+                addr.send(lmpop())
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(
+                    res.unwrap(),
+                    Some((
+                        "{test-lmpop}second".into(),
+                        vec![b"a".to_vec(), b"b".to_vec()]
+                    ))
+                );
+                addr.send(lmpop())
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(
+                    res.unwrap(),
+                    Some(("{test-lmpop}second".into(), vec![b"c".to_vec()]))
+                );
+                addr.send(lmpop())
+            }
+        })
+        .map(|res| {
+            assert_eq!(res.unwrap(), None);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_lmpop_cross_slot() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-lmpop-cross-slot");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(LMPop {
+            keys: vec!["test-lmpop-first".into(), "test-lmpop-second".into()],
+            direction: Direction::Right,
+            count: 1,
+        })
+        .map(|res| {
+            match res {
+                Err(Error::MultipleSlot(_)) => (),
+                res => panic!("Should not happen {:?}", res),
+            }
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
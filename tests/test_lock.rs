@@ -0,0 +1,129 @@
+#![cfg(feature = "lock")]
+
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::lock;
+use actix_redis::{RedisActor, RedisClusterActor};
+use futures::Future;
+use tokio_timer::Delay;
+
+const KEY: &str = "test-lock";
+
+fn sleep(ms: u64) -> impl Future<Item = (), Error = actix_redis::Error> {
+    Delay::new(Instant::now() + Duration::from_millis(ms))
+        .map_err(|e| panic!("Should not happen {:?}", e))
+}
+
+#[test]
+fn test_lock_renew() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-lock-renew");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+    let redis = addr.clone().recipient();
+    let ttl = Duration::from_millis(300);
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+        .and_then({
+            let redis = redis.clone();
+            move |res| {
+                res.unwrap();
+                lock::acquire(&redis, KEY, ttl).join(sleep(10).and_then({
+                    let redis = redis.clone();
+                    move |()| lock::acquire(&redis, KEY, ttl)
+                }))
+            }
+        })
+        .and_then(|(guard, other)| {
+            let guard = guard.unwrap();
+            // already held
+            assert!(other.is_none());
+            // nearly expired
+            sleep(250).and_then(move |()| {
+                guard
+                    .renew(Duration::from_secs(1))
+                    .map(|renewed| (guard, renewed))
+            })
+        })
+        .and_then({
+            let redis = redis.clone();
+            move |(guard, renewed)| {
+                assert!(renewed);
+                // past the first time to live
+                sleep(200).and_then(move |()| {
+                    lock::acquire(&redis, KEY, ttl).map(|other| (guard, other))
+                })
+            }
+        })
+        .and_then(|(guard, other)| {
+            assert!(other.is_none());
+            guard.release()
+        })
+        .and_then(move |released| {
+            assert!(released);
+            lock::acquire(&redis, KEY, ttl)
+        })
+        .map(|guard| {
+            assert!(guard.is_some());
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_lock_lost() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-lock-lost");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+    let redis = addr.clone().recipient();
+    let key = "test-lock-lost";
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![key.into()],
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+        .and_then({
+            let redis = redis.clone();
+            move |res| {
+                res.unwrap();
+                lock::acquire(&redis, key, Duration::from_millis(50))
+            }
+        })
+        .and_then(move |guard| {
+            let guard = guard.unwrap();
+            // expires, and is acquired by someone else
+            sleep(100)
+                .and_then(move |()| lock::acquire(&redis, key, Duration::from_secs(10)))
+                .map(|other| (guard, other.unwrap()))
+        })
+        .and_then(|(guard, other)| {
+            guard
+                .renew(Duration::from_secs(10))
+                .join(other.renew(Duration::from_secs(10)))
+                .map(|renewed| (guard, other, renewed))
+        })
+        .and_then(|(guard, other, renewed)| {
+            assert_eq!(renewed, (false, true));
+            guard.release().join(other.release())
+        })
+        .map(|released| {
+            // the lock of the new holder is kept
+            assert_eq!(released, (false, true));
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
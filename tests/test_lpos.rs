@@ -0,0 +1,112 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::slot::{HashError, Hasher};
+use actix_redis::{Error, RedisClusterActor, RespError, RespValue};
+use futures::Future;
+
+const KEY: &str = "test-lpos";
+
+/// Not provided by the crate, only needed to set up the test
+struct RPush {
+    key: String,
+    elements: Vec<Vec<u8>>,
+}
+
+impl Message for RPush {
+    type Result = Result<RespValue, Error>;
+}
+
+impl Command for RPush {
+    type Output = RespValue;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec!["RPUSH".into(), self.key.into()];
+        v.extend(self.elements.into_iter().map(RespValue::BulkString));
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        Ok(res)
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(&self.key)
+    }
+}
+
+fn lpos(element: &str, rank: Option<i64>, count: Option<usize>) -> LPos {
+    LPos {
+        key: KEY.into(),
+        element: element.into(),
+        rank,
+        count,
+    }
+}
+
+#[test]
+fn test_lpos() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-lpos");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(RPush {
+                    key: KEY.into(),
+                    elements: ["a", "b", "c", "b", "d", "b"]
+                        .iter()
+                        .map(|e| e.as_bytes().to_vec())
+                        .collect(),
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(lpos("b", None, None))
+                    .join3(
+                        addr.send(lpos("b", Some(2), None)),
+                        addr.send(lpos("b", Some(-1), None)),
+                    )
+                    .join(addr.send(lpos("e", None, None)))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |((first, second, last), missing)| {
+                assert_eq!(first.unwrap(), LPosReply::Index(Some(1)));
+                assert_eq!(second.unwrap(), LPosReply::Index(Some(3)));
+                assert_eq!(last.unwrap(), LPosReply::Index(Some(5)));
+                assert_eq!(missing.unwrap(), LPosReply::Index(None));
+                addr.send(lpos("b", None, Some(2)))
+                    .join3(
+                        addr.send(lpos("b", Some(-1), Some(0))),
+                        addr.send(lpos("e", None, Some(0))),
+                    )
+                    .join(addr.send(lpos("b", Some(0), None)))
+            }
+        })
+        .map(|((two, all, none), zero)| {
+            assert_eq!(two.unwrap(), LPosReply::Indices(vec![1, 3]));
+            // from the tail
+            assert_eq!(all.unwrap(), LPosReply::Indices(vec![5, 3, 1]));
+            assert_eq!(none.unwrap(), LPosReply::Indices(vec![]));
+            match zero {
+                Err(Error::Redis(_)) => (),
+                res => panic!("Should not happen {:?}", res),
+            }
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
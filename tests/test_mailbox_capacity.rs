@@ -0,0 +1,79 @@
+use actix::prelude::*;
+use actix_redis::{command::*, RedisActor, RedisClusterActor};
+use futures::Future;
+
+const BURST: usize = 64;
+
+fn incr(key: &str) -> Incr {
+    Incr { key: key.into() }
+}
+
+#[test]
+fn test_mailbox_capacity() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-mailbox-capacity");
+
+    let key = "test-mailbox-capacity";
+    let default = RedisActor::start("127.0.0.1:6379");
+    let addr = RedisActor::builder("127.0.0.1:6379")
+        .mailbox_capacity(BURST)
+        .start();
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![key.into()],
+        })
+        .and_then(move |res| {
+            res.unwrap();
+
+            // the burst is enqueued before the actors process any of it
+            let rejected = (0..BURST)
+                .filter(|_| default.try_send(Ping(None)).is_err())
+                .count();
+            assert!(rejected > 0);
+            for _ in 0..BURST {
+                addr.try_send(incr(key)).unwrap();
+            }
+
+            addr.send(Get { key: key.into() })
+        })
+        .map(|res| {
+            assert_eq!(res.unwrap(), Some(BURST.to_string().into_bytes()));
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_cluster_mailbox_capacity() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-mailbox-capacity");
+
+    let key = "test-cluster-mailbox-capacity";
+    let addr = RedisClusterActor::builder("127.0.0.1:7000")
+        .mailbox_capacity(BURST)
+        .start();
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![key.into()],
+        })
+        .and_then(move |res| {
+            res.unwrap();
+            for _ in 0..BURST {
+                addr.try_send(incr(key)).unwrap();
+            }
+            addr.send(Get { key: key.into() })
+        })
+        .map(|res| {
+            assert_eq!(res.unwrap(), Some(BURST.to_string().into_bytes()));
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
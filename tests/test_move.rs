@@ -0,0 +1,97 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix::prelude::*;
+use actix_redis::{command::*, Error, RedisActor, RedisClusterActor};
+use futures::Future;
+
+/// There is no way to clean DB 1 up, so each run moves another key
+fn key() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    format!("test-move-{}", now.as_nanos())
+}
+
+fn move_to_db1(key: &str) -> Move {
+    Move {
+        key: key.into(),
+        db: 1,
+    }
+}
+
+fn set(key: &str, value: &str) -> Set {
+    Set {
+        key: key.into(),
+        value: value.into(),
+        expiration: Expiration::Infinite,
+    }
+}
+
+#[test]
+fn test_move() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-move");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+    let key = key();
+
+    Arbiter::spawn_fn(move || {
+        addr.send(set(&key, "archived"))
+            .and_then({
+                let (addr, key) = (addr.clone(), key.clone());
+                move |res| {
+                    res.unwrap();
+                    addr.send(move_to_db1(&key))
+                }
+            })
+            .and_then({
+                let (addr, key) = (addr.clone(), key.clone());
+                move |res| {
+                    assert!(res.unwrap());
+                    // gone from DB 0
+                    addr.send(Get { key: key.clone() })
+                        .join(addr.send(move_to_db1(&key)))
+                }
+            })
+            .and_then({
+                let (addr, key) = (addr.clone(), key.clone());
+                move |(get, again)| {
+                    assert_eq!(get.unwrap(), None);
+                    assert!(!again.unwrap());
+                    addr.send(set(&key, "current"))
+                }
+            })
+            .and_then(move |res| {
+                res.unwrap();
+                // present in DB 1
+                addr.send(move_to_db1(&key)).join(addr.send(Get { key }))
+            })
+            .map(|(moved, get)| {
+                assert!(!moved.unwrap());
+                assert_eq!(get.unwrap(), Some(b"current".to_vec()));
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_cluster_move() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-move");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(move_to_db1(&key())).then(|res| {
+            match res {
+                Ok(Err(Error::Redis(_))) => (),
+                res => panic!("Should not happen {:?}", res),
+            }
+            System::current().stop();
+            Ok(())
+        })
+    });
+
+    sys.run()
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::{command::*, Error, RedisActor};
+use futures::Future;
+
+type Connections = Arc<Mutex<Vec<Vec<String>>>>;
+
+/// Minimal server recording the commands of every connection. `PING drop`
+/// closes the connection.
+fn mock_server(client_reply: &'static str) -> (String, Connections) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let conns = Connections::default();
+
+    let recorded = conns.clone();
+    common::serve(listener, move || {
+        let id = {
+            let mut conns = recorded.lock().unwrap();
+            conns.push(vec![]);
+            conns.len() - 1
+        };
+        let recorded = recorded.clone();
+        move |args: &[String]| {
+            recorded.lock().unwrap()[id].push(args.join(" "));
+            let reply = match args[0].as_str() {
+                "CLIENT" => client_reply,
+                "PING" if args.len() > 1 => return None,
+                "PING" => "+PONG\r\n",
+                _ => "-ERR unknown command\r\n",
+            };
+            Some(reply.to_owned())
+        }
+    });
+
+    (addr, conns)
+}
+
+fn run_no_evict(client_reply: &'static str) -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-no-evict");
+    let (server, conns) = mock_server(client_reply);
+
+    Arbiter::spawn_fn(move || {
+        RedisActor::builder(server)
+            .no_evict(true)
+            .start_eagerly()
+            .and_then(|addr| {
+                // drop the connection, the actor reconnects
+                addr.send(Ping(Some("drop".into())))
+                    .then(|_| {
+                        tokio_timer::Delay::new(
+                            Instant::now() + Duration::from_millis(200),
+                        )
+                    })
+                    .then(move |_| addr.send(Ping(None)))
+                    .map_err(|_| Error::Disconnected)
+            })
+            .map(move |res| {
+                assert_eq!(res.unwrap(), "PONG");
+
+                let conns = conns.lock().unwrap();
+                assert_eq!(conns.len(), 2);
+                assert_eq!(conns[0], vec!["CLIENT NO-EVICT ON", "PING", "PING drop"]);
+                assert_eq!(conns[1], vec!["CLIENT NO-EVICT ON", "PING"]);
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_no_evict() -> std::io::Result<()> {
+    run_no_evict("+OK\r\n")
+}
+
+#[test]
+fn test_no_evict_unsupported() -> std::io::Result<()> {
+    run_no_evict("-ERR unknown subcommand 'NO-EVICT'\r\n")
+}
@@ -0,0 +1,105 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::slot::{HashError, Hasher};
+use actix_redis::{Error, RedisClusterActor, RespError, RespValue};
+use futures::Future;
+
+/// Not provided by the crate, only needed to set up the test
+struct SetPolicy(&'static str);
+
+impl Message for SetPolicy {
+    type Result = Result<RespValue, Error>;
+}
+
+impl Command for SetPolicy {
+    type Output = RespValue;
+
+    fn into_request(self) -> RespValue {
+        RespValue::Array(vec![
+            "CONFIG".into(),
+            "SET".into(),
+            "maxmemory-policy".into(),
+            self.0.into(),
+        ])
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        Ok(res)
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+fn object_freq() -> ObjectFreq {
+    ObjectFreq {
+        key: "test-object-freq".into(),
+    }
+}
+
+#[test]
+fn test_object_freq() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-object-freq");
+
+    // a single node cluster, so the policy applies to the node of the key
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(SetPolicy("noeviction"))
+            .join(addr.send(Set {
+                key: "test-object-freq".into(),
+                value: "value".into(),
+                expiration: Expiration::Infinite,
+            }))
+            .and_then({
+                let addr = addr.clone();
+                move |(policy, set)| {
+                    policy.unwrap();
+                    set.unwrap();
+                    addr.send(object_freq())
+                }
+            })
+            .and_then({
+                let addr = addr.clone();
+                move |res| {
+                    match res {
+                        Err(Error::Redis(RespError::RESP(ref msg, _)))
+                            if msg.contains("LFU") => {}
+                        res => panic!("Should not happen {:?}", res),
+                    }
+                    addr.send(SetPolicy("allkeys-lfu"))
+                }
+            })
+            .and_then({
+                let addr = addr.clone();
+                move |res| {
+                    res.unwrap();
+                    addr.send(Get {
+                        key: "test-object-freq".into(),
+                    })
+                    .join(addr.send(object_freq()))
+                    .join(addr.send(ObjectFreq {
+                        key: "test-object-freq-missing".into(),
+                    }))
+                }
+            })
+            .and_then({
+                let addr = addr.clone();
+                move |((get, freq), missing)| {
+                    get.unwrap();
+                    assert!(freq.unwrap().unwrap() > 0);
+                    assert_eq!(missing.unwrap(), None);
+                    addr.send(SetPolicy("noeviction"))
+                }
+            })
+            .map(|res| {
+                res.unwrap();
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,66 @@
+use actix::prelude::*;
+use actix_redis::{command::*, pipe::Pipe, Error, RedisActor, RedisClusterActor};
+use futures::Future;
+
+#[test]
+fn test_pipe() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-pipe");
+
+    let addr = RedisActor::start("127.0.0.1:6379").recipient();
+
+    Arbiter::spawn_fn(move || {
+        Pipe::new()
+            .set("test-pipe", "41")
+            .incr("test-pipe")
+            .get("test-pipe")
+            .execute(&addr)
+            .map(|((), incr, get): ((), i64, Option<Vec<u8>>)| {
+                assert_eq!(incr, 42);
+                assert_eq!(get, Some(b"42".to_vec()));
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_cluster_pipe() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-cluster-pipe");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000").recipient();
+
+    Arbiter::spawn_fn(move || {
+        // keys on different nodes
+        Pipe::new()
+            .set("test-pipe-a", "a")
+            .set("test-pipe-b", "b")
+            .command(Del {
+                keys: vec!["test-pipe-c".into()],
+            })
+            .get("test-pipe-a")
+            .get("test-pipe-b")
+            .execute(&addr)
+            .and_then({
+                let addr = addr.clone();
+                move |(_, _, _, a, b): ((), (), i64, String, String)| {
+                    assert_eq!((a.as_str(), b.as_str()), ("a", "b"));
+                    // INCR of a value that is not an integer
+                    Pipe::new().incr("test-pipe-a").execute::<(i64,)>(&addr)
+                }
+            })
+            .then(|res| {
+                match res {
+                    Err(Error::Redis(actix_redis::RespError::Remote(_))) => (),
+                    res => panic!("Should not happen {:?}", res),
+                }
+                System::current().stop();
+                Ok::<_, ()>(())
+            })
+    });
+
+    sys.run()
+}
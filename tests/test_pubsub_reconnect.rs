@@ -0,0 +1,77 @@
+mod common;
+
+use std::io::{BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use actix::prelude::*;
+use actix_redis::pubsub::{PubSubEvent, PubSubMessage, Subscribe};
+use futures::{Future, Stream};
+
+use common::read_command;
+
+/// Minimal server confirming `SUBSCRIBE news` and publishing `payloads` on
+/// successive connections, dropping all but the last one after publishing
+fn mock_server(listener: TcpListener, payloads: &'static [&'static str]) {
+    thread::spawn(move || {
+        for (i, stream) in listener.incoming().take(payloads.len()).enumerate() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let args = read_command(&mut reader).unwrap();
+            assert_eq!(args, vec!["SUBSCRIBE", "news"]);
+
+            let payload = payloads[i];
+            let reply = format!(
+                "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n\
+                 *3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n${}\r\n{}\r\n",
+                payload.len(),
+                payload
+            );
+            stream.write_all(reply.as_bytes()).unwrap();
+            if i + 1 == payloads.len() {
+                // keep the last connection open
+                while read_command(&mut reader).is_some() {}
+            }
+        }
+    });
+}
+
+fn message(payload: &str) -> PubSubEvent {
+    PubSubEvent::Message(PubSubMessage {
+        channel: "news".into(),
+        payload: payload.into(),
+    })
+}
+
+#[test]
+fn test_pubsub_reconnect() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-pubsub-reconnect");
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    mock_server(listener, &["before", "after"]);
+
+    Arbiter::spawn_fn(move || {
+        Subscribe {
+            channels: vec!["news".into()],
+        }
+        .start(addr)
+        .take(3)
+        .collect()
+        .map(|events| {
+            assert_eq!(
+                events,
+                vec![
+                    message("before"),
+                    PubSubEvent::Resubscribed,
+                    message("after")
+                ]
+            );
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,70 @@
+use actix::prelude::*;
+use actix_redis::{command::*, RedisActor};
+use futures::Future;
+
+#[test]
+fn test_random_key_and_touch() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-random-key");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Eval {
+            script: "return redis.call('FLUSHDB')",
+            keys: vec![],
+            args: vec![],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(RandomKey)
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), None);
+
+                futures::future::join_all((0..3).map({
+                    let addr = addr.clone();
+                    move |i| {
+                        addr.send(Set {
+                            key: format!("test-touch{}", i),
+                            value: "value".into(),
+                            expiration: Expiration::Infinite,
+                        })
+                        .map(|res| res.unwrap())
+                    }
+                }))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |_| {
+                addr.send(Touch {
+                    keys: vec![
+                        "test-touch0".into(),
+                        "test-touch2".into(),
+                        "test-touch-missing".into(),
+                    ],
+                })
+                .join(addr.send(RandomKey))
+            }
+        })
+        .map(|(touched, random)| {
+            assert_eq!(touched.unwrap(), 2);
+            let random = String::from_utf8(random.unwrap().unwrap()).unwrap();
+            assert!(
+                random.starts_with("test-touch"),
+                "unexpected key {}",
+                random
+            );
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,222 @@
+use std::collections::HashSet;
+
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::slot::{HashError, Hasher};
+use actix_redis::{Error, RedisClusterActor, RespError, RespValue};
+use futures::Future;
+
+/// Not provided by the crate, only needed to set up the test: `SADD`, `HSET`
+/// or `ZADD` with `args` following the key
+struct Add {
+    command: &'static str,
+    key: &'static str,
+    args: Vec<&'static str>,
+}
+
+impl Message for Add {
+    type Result = Result<RespValue, Error>;
+}
+
+impl Command for Add {
+    type Output = RespValue;
+
+    fn into_request(self) -> RespValue {
+        let mut v = vec![self.command.into(), self.key.into()];
+        v.extend(self.args.into_iter().map(Into::into));
+        RespValue::Array(v)
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        Ok(res)
+    }
+
+    fn hash_keys(&self, hasher: &mut Hasher) -> Result<(), HashError> {
+        hasher.hash_str(self.key)
+    }
+}
+
+fn distinct(elements: &[Vec<u8>]) -> usize {
+    elements.iter().collect::<HashSet<_>>().len()
+}
+
+#[test]
+fn test_srandmember() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-srandmember");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+    let key = "test-srandmember";
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![key.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(Add {
+                    command: "SADD",
+                    key,
+                    args: vec!["a", "b", "c", "d", "e"],
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(SRandMember {
+                    key: key.into(),
+                    count: Some(3),
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                let members = res.unwrap();
+                assert_eq!(members.len(), 3);
+                assert_eq!(distinct(&members), 3);
+
+                addr.send(SRandMember {
+                    key: key.into(),
+                    count: Some(-20),
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                // more members than the set has, some repeat
+                let members = res.unwrap();
+                assert_eq!(members.len(), 20);
+                assert!(distinct(&members) <= 5);
+
+                addr.send(SRandMember {
+                    key: key.into(),
+                    count: None,
+                })
+            }
+        })
+        .and_then(move |res| {
+            assert_eq!(res.unwrap().len(), 1);
+            addr.send(SRandMember {
+                key: "test-srandmember-missing".into(),
+                count: None,
+            })
+        })
+        .map(|res| {
+            assert_eq!(res.unwrap(), Vec::<Vec<u8>>::new());
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_hrandfield_zrandmember() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-hrandfield-zrandmember");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+    let hash = "test-hrandfield";
+    let zset = "test-zrandmember";
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![hash.into()],
+        })
+        .join(addr.send(Del {
+            keys: vec![zset.into()],
+        }))
+        .and_then({
+            let addr = addr.clone();
+            move |(res1, res2)| {
+                res1.unwrap();
+                res2.unwrap();
+                addr.send(Add {
+                    command: "HSET",
+                    key: hash,
+                    args: vec!["f1", "v1", "f2", "v2", "f3", "v3"],
+                })
+                .join(addr.send(Add {
+                    command: "ZADD",
+                    key: zset,
+                    args: vec!["1.5", "m"],
+                }))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |(res1, res2)| {
+                res1.unwrap();
+                res2.unwrap();
+                addr.send(HRandField {
+                    key: hash.into(),
+                    count: Some(2),
+                    with_values: true,
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                // each field is followed by its value
+                let elements = res.unwrap();
+                assert_eq!(elements.len(), 4);
+                let fields = elements.chunks(2).map(|pair| {
+                    assert_eq!(pair[0][1..], pair[1][1..]);
+                    pair[0].clone()
+                });
+                assert_eq!(fields.collect::<HashSet<_>>().len(), 2);
+
+                addr.send(HRandField {
+                    key: hash.into(),
+                    count: Some(-10),
+                    with_values: false,
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                let fields = res.unwrap();
+                assert_eq!(fields.len(), 10);
+                assert!(distinct(&fields) <= 3);
+
+                addr.send(ZRandMember {
+                    key: zset.into(),
+                    count: Some(-3),
+                    with_scores: true,
+                })
+            }
+        })
+        .and_then(move |res| {
+            // the only member, repeated
+            let elements = res.unwrap();
+            assert_eq!(elements.len(), 6);
+            for pair in elements.chunks(2) {
+                assert_eq!(pair[0], b"m");
+                assert_eq!(pair[1], b"1.5");
+            }
+
+            addr.send(ZRandMember {
+                key: zset.into(),
+                count: Some(5),
+                with_scores: false,
+            })
+        })
+        .map(|res| {
+            // no more than the members of the sorted set
+            assert_eq!(res.unwrap(), vec![b"m".to_vec()]);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,61 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::range::RangeStream;
+use actix_redis::RedisActor;
+use futures::{Future, Stream};
+
+#[test]
+fn test_range_stream() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-range-stream");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+    let key = "test-range-stream";
+    let value = (0..1024 * 1024)
+        .map(|i| (b'a' + (i % 26) as u8) as char)
+        .collect::<String>();
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Set {
+            key: key.into(),
+            value: value.clone(),
+            expiration: Expiration::Infinite,
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                RangeStream::new(addr.recipient(), key, 64 * 1024).collect()
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            let value = value.clone();
+            move |chunks| {
+                assert_eq!(chunks.len(), 16);
+                assert!(chunks.iter().all(|chunk| chunk.len() == 64 * 1024));
+                assert_eq!(chunks.concat(), value.as_bytes());
+
+                // a byte range not aligned to the chunks
+                RangeStream::new(addr.recipient(), key, 64 * 1024)
+                    .range(1000, Some(200_000))
+                    .collect()
+            }
+        })
+        .and_then(move |chunks| {
+            assert_eq!(chunks.len(), 4);
+            assert_eq!(chunks.concat(), &value.as_bytes()[1000..=200_000]);
+
+            RangeStream::new(addr.recipient(), "test-range-stream-missing", 1024)
+                .collect()
+        })
+        .map(|chunks| {
+            assert!(chunks.is_empty());
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
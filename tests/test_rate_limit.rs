@@ -0,0 +1,81 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::{command::*, Error, RateLimitPolicy, RedisActor};
+use futures::{future, Future};
+
+use common::mock_server;
+
+fn pings(
+    addr: &Addr<RedisActor>,
+    n: usize,
+) -> impl Future<Item = Vec<Result<String, Error>>, Error = ()> {
+    let addr = addr.clone();
+    future::join_all((0..n).map(move |_| {
+        addr.send(Ping(None))
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    }))
+}
+
+#[test]
+fn test_rate_limit_queue() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-rate-limit-queue");
+    let server = mock_server(|_| "+PONG\r\n".to_owned());
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisActor::builder(server)
+            .rate_limit(20, 5, RateLimitPolicy::Queue)
+            .start();
+
+        // a burst of 5, then 20 more at 20 per second
+        let start = Instant::now();
+        pings(&addr, 25).map(move |res| {
+            let elapsed = start.elapsed();
+            for pong in res {
+                assert_eq!(pong.unwrap(), "PONG");
+            }
+            assert!(elapsed >= Duration::from_millis(900), "{:?}", elapsed);
+            assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+            System::current().stop();
+        })
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_rate_limit_reject() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-rate-limit-reject");
+    let server = mock_server(|_| "+PONG\r\n".to_owned());
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisActor::builder(server)
+            .rate_limit(10, 2, RateLimitPolicy::Reject)
+            .start();
+
+        pings(&addr, 5).map(|res| {
+            let (mut sent, mut rejected) = (0, 0);
+            for pong in res {
+                match pong {
+                    Ok(_) => sent += 1,
+                    Err(Error::RateLimited) => rejected += 1,
+                    Err(e) => panic!("Should not happen {:?}", e),
+                }
+            }
+            assert_eq!((sent, rejected), (2, 3));
+            System::current().stop();
+        })
+    });
+
+    sys.run()
+}
+
+#[test]
+#[should_panic(expected = "rate limit must not be 0")]
+fn test_rate_limit_zero() {
+    RedisActor::builder("127.0.0.1:6379").rate_limit(0, 2, RateLimitPolicy::Queue);
+}
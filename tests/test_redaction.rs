@@ -0,0 +1,116 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_redis::{command::*, RedactionPolicy, RedisActor};
+use futures::future::{self, Loop};
+use futures::Future;
+use log::{Log, Metadata, Record};
+
+use common::mock_server;
+
+const SESSION_ID: &str = "Rz7qWkP0aXbN3sLmYc1TvHd8";
+const SESSION_VALUE: &str = "{\"user\":\"alice\",\"csrf\":\"s3cr3t-t0k3n\"}";
+
+/// Collects the records of the slow log
+struct SlowLog(Arc<Mutex<Vec<String>>>);
+
+impl Log for SlowLog {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == "actix_redis::slow_log"
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Answers `SET` after 100ms
+fn slow_server() -> String {
+    mock_server(|_| {
+        thread::sleep(Duration::from_millis(100));
+        "+OK\r\n".to_owned()
+    })
+}
+
+fn set_session(
+    server: &str,
+    policy: Option<RedactionPolicy>,
+) -> impl Future<Item = (), Error = ()> {
+    let mut builder = RedisActor::builder(server).slow_log(Duration::from_millis(50));
+    if let Some(policy) = policy {
+        builder = builder.redaction(policy);
+    }
+    builder
+        .start()
+        .send(Set {
+            key: SESSION_ID.into(),
+            value: SESSION_VALUE.into(),
+            expiration: Expiration::Ex("7200".into()),
+        })
+        .map(|res| {
+            res.unwrap();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+}
+
+#[test]
+fn test_redaction() -> std::io::Result<()> {
+    let records = Arc::new(Mutex::new(vec![]));
+    log::set_boxed_logger(Box::new(SlowLog(records.clone()))).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+
+    let sys = System::new("test-redaction");
+    let server = slow_server();
+
+    Arbiter::spawn_fn(move || {
+        // one after another, to tell their records apart
+        let policies = vec![
+            None,
+            Some(RedactionPolicy::KeyHash),
+            Some(RedactionPolicy::RedactValues),
+        ];
+        future::loop_fn(policies.into_iter(), move |mut policies| {
+            let records = records.clone();
+            match policies.next() {
+                Some(policy) => future::Either::A(
+                    set_session(&server, policy).map(|()| Loop::Continue(policies)),
+                ),
+                None => future::Either::B(future::ok(Loop::Break(
+                    records.lock().unwrap().clone(),
+                ))),
+            }
+        })
+        .map(|records| {
+            assert_eq!(records.len(), 3, "{:?}", records);
+            // the default only logs the length of the session id
+            assert!(
+                records[0].starts_with(&format!(
+                    "Slow command: SET key: <{} bytes> node: ",
+                    SESSION_ID.len()
+                )),
+                "{}",
+                records[0]
+            );
+            assert!(records[1].starts_with("Slow command: SET key: #"));
+            assert!(records[2]
+                .starts_with(&format!("Slow command: SET key: {} node: ", SESSION_ID)));
+            for record in &records[..2] {
+                assert!(!record.contains(SESSION_ID), "{}", record);
+            }
+            for record in &records {
+                assert!(!record.contains("s3cr3t"), "{}", record);
+            }
+            System::current().stop();
+        })
+    });
+
+    sys.run()
+}
@@ -1,7 +1,7 @@
 extern crate redis_async;
 
 use actix::prelude::*;
-use actix_redis::{command::*, Error, RedisActor};
+use actix_redis::{command::*, Error, RedisActor, RespValue};
 use futures::Future;
 
 #[test]
@@ -57,3 +57,83 @@ fn test_redis() -> std::io::Result<()> {
 
     sys.run()
 }
+
+#[test]
+fn test_set_expire_at() -> std::io::Result<()> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let sys = System::new("test");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Set {
+            key: "test-exat".into(),
+            value: "value".into(),
+            expiration: Expiration::ExAt((now.as_secs() + 100).to_string()),
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(Set {
+                    key: "test-pxat".into(),
+                    value: "value".into(),
+                    expiration: Expiration::PxAt(
+                        (now.as_secs() * 1000 + 200_000).to_string(),
+                    ),
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(Ttl {
+                    key: "test-exat".into(),
+                })
+                .join(addr.send(Ttl {
+                    key: "test-pxat".into(),
+                }))
+            }
+        })
+        .map(|(exat, pxat)| {
+            let exat = exat.unwrap().unwrap();
+            let pxat = pxat.unwrap().unwrap();
+            assert!(exat > 0 && exat <= 100, "unexpected TTL {}", exat);
+            assert!(pxat > 100 && pxat <= 200, "unexpected TTL {}", pxat);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_raw_command() -> std::io::Result<()> {
+    let sys = System::new("test-raw-command");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(actix_redis::redis::Command::new(("SET", "test-raw", 1.5)))
+            .and_then({
+                let addr = addr.clone();
+                move |res| {
+                    assert_eq!(res.unwrap(), RespValue::SimpleString("OK".into()));
+                    addr.send(Get {
+                        key: "test-raw".into(),
+                    })
+                }
+            })
+            .map(|res| {
+                assert_eq!(res.unwrap(), Some(b"1.5".to_vec()));
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,249 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_redis::resp::{Frame, FrameCodec};
+use actix_redis::{command::*, Bytes, Error, RedisActor};
+use bytes::BytesMut;
+use futures::Future;
+use tokio_codec::Decoder;
+
+fn decode_all(input: &[u8]) -> Vec<Frame> {
+    let mut codec = FrameCodec::default();
+    let mut buf = BytesMut::from(input);
+    let mut frames = vec![];
+    while let Some(frame) = codec.decode(&mut buf).unwrap() {
+        frames.push(frame);
+    }
+    assert!(buf.is_empty(), "left over: {:?}", buf);
+    frames
+}
+
+#[test]
+fn test_decode() {
+    let frames = decode_all(
+        b"+OK\r\n-ERR oops\r\n:-42\r\n$-1\r\n*-1\r\n$0\r\n\r\n$4\r\na\r\nb\r\n\
+          *2\r\n*1\r\n:1\r\n$3\r\nfoo\r\n",
+    );
+    assert_eq!(
+        frames,
+        vec![
+            Frame::SimpleString("OK".into()),
+            Frame::Error("ERR oops".into()),
+            Frame::Integer(-42),
+            Frame::Nil,
+            Frame::Nil,
+            Frame::BulkString(Bytes::new()),
+            Frame::BulkString(Bytes::from(&b"a\r\nb"[..])),
+            Frame::Array(vec![
+                Frame::Array(vec![Frame::Integer(1)]),
+                Frame::BulkString(Bytes::from(&b"foo"[..])),
+            ]),
+        ]
+    );
+}
+
+#[test]
+fn test_decode_byte_at_a_time() {
+    let input =
+        b"*3\r\n$5\r\nva\r\nl\r\n*2\r\n:12\r\n$-1\r\n+OK\r\n$10\r\n0123456789\r\n";
+
+    let mut codec = FrameCodec::default();
+    let mut buf = BytesMut::new();
+    let mut frames = vec![];
+    for byte in input.iter() {
+        buf.extend_from_slice(&[*byte]);
+        if let Some(frame) = codec.decode(&mut buf).unwrap() {
+            frames.push(frame);
+        }
+    }
+    assert!(buf.is_empty());
+    assert_eq!(frames, decode_all(input));
+    assert_eq!(frames.len(), 2);
+}
+
+#[test]
+fn test_decode_split_bulk_string() {
+    let mut codec = FrameCodec::default();
+    let mut buf = BytesMut::from(&b"$5\r\n"[..]);
+    assert_eq!(codec.decode(&mut buf).unwrap(), None);
+    buf.extend_from_slice(b"val");
+    assert_eq!(codec.decode(&mut buf).unwrap(), None);
+    buf.extend_from_slice(b"ue\r\n");
+    assert_eq!(
+        codec.decode(&mut buf).unwrap(),
+        Some(Frame::BulkString(Bytes::from(&b"value"[..])))
+    );
+}
+
+#[test]
+fn test_fragmented_reply() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-fragmented-reply");
+
+    // the header and the body of the reply arrive in separate packets
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let server = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.set_nodelay(true).unwrap();
+        let mut buf = [0; 64];
+        let _ = stream.read(&mut buf).unwrap();
+        stream.write_all(b"$5\r\n").unwrap();
+        thread::sleep(Duration::from_millis(50));
+        stream.write_all(b"value\r\n").unwrap();
+        let _ = stream.read(&mut buf);
+    });
+
+    Arbiter::spawn_fn(move || {
+        RedisActor::start(server)
+            .send(Get {
+                key: "test-fragmented".into(),
+            })
+            .map(|res| {
+                assert_eq!(res.unwrap(), Some(b"value".to_vec()));
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_decode_invalid() {
+    for input in &[
+        &b"$3\r\nfoobar\r\n"[..],
+        b":abc\r\n",
+        b"$-2\r\n",
+        b"*x\r\n",
+        b"hello\r\n+OK\r\n",
+    ] {
+        let mut buf = BytesMut::from(*input);
+        assert!(
+            FrameCodec::default().decode(&mut buf).is_err(),
+            "{:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_decode_nested_too_deep() {
+    let nested = |depth: usize| {
+        let mut input = vec![];
+        for _ in 0..depth {
+            input.extend_from_slice(b"*1\r\n");
+        }
+        input.extend_from_slice(b":1\r\n");
+        BytesMut::from(input)
+    };
+
+    let mut buf = nested(128);
+    assert!(FrameCodec::default().decode(&mut buf).unwrap().is_some());
+    // rather than overflowing the stack
+    let mut buf = nested(1_000_000);
+    match FrameCodec::default().decode(&mut buf) {
+        Err(Error::Parse(_)) => (),
+        res => panic!("Should not happen {:?}", res),
+    }
+}
+
+#[test]
+fn test_max_reply_bytes() {
+    let decode = |input: &[u8]| {
+        let mut buf = BytesMut::from(input);
+        FrameCodec::default().max_reply_bytes(16).decode(&mut buf)
+    };
+
+    match decode(b"$10000000000\r\n") {
+        Err(Error::ReplyTooLarge) => (),
+        res => panic!("Should not happen {:?}", res),
+    }
+    // the elements add up, although each of them is small
+    match decode(b"*3\r\n$4\r\nval1\r\n$4\r\nval2\r\n") {
+        Err(Error::ReplyTooLarge) => (),
+        res => panic!("Should not happen {:?}", res),
+    }
+    assert_eq!(
+        decode(b"*1\r\n$4\r\nval1\r\n").unwrap(),
+        Some(Frame::Array(vec![Frame::BulkString(Bytes::from(
+            &b"val1"[..]
+        ))]))
+    );
+}
+
+#[test]
+fn test_max_reply_bytes_connection() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-max-reply-bytes-connection");
+
+    // announces a huge bulk string and starts sending it
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let server = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 64];
+        let _ = stream.read(&mut buf).unwrap();
+        stream.write_all(b"$10000000000\r\n").unwrap();
+        let chunk = [b'x'; 1024];
+        while stream.write_all(&chunk).is_ok() {}
+    });
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisActor::builder(server)
+            .max_reply_bytes(1024 * 1024)
+            .start();
+        addr.send(Get {
+            key: "test-max-reply-bytes".into(),
+        })
+        .map(|res| {
+            match res {
+                Err(Error::ReplyTooLarge) => (),
+                res => panic!("Should not happen {:?}", res),
+            }
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_get_bytes() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-get-bytes");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Set {
+            key: "test-get-bytes".into(),
+            value: "value".into(),
+            expiration: Expiration::Infinite,
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(GetBytes {
+                    key: "test-get-bytes".into(),
+                })
+                .join(addr.send(GetBytes {
+                    key: "test-get-bytes-missing".into(),
+                }))
+            }
+        })
+        .map(|(value, missing)| {
+            assert_eq!(value.unwrap(), Some(Bytes::from(&b"value"[..])));
+            assert_eq!(missing.unwrap(), None);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,58 @@
+//! Compares the heap usage of decoding a large multi-bulk reply. Kept in its own
+//! test binary, as the allocator counts the allocations of every thread.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use actix_redis::resp::FrameCodec;
+use actix_redis::RespValue;
+use bytes::BytesMut;
+use redis_async::resp::RespCodec;
+use tokio_codec::Decoder;
+
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Bytes allocated while decoding `reply`, and the decoded reply
+fn decode<D: Decoder>(mut decoder: D, reply: &[u8]) -> (usize, D::Item)
+where
+    D::Error: std::fmt::Debug,
+{
+    let mut buf = BytesMut::from(reply);
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    let item = decoder.decode(&mut buf).unwrap().unwrap();
+    (ALLOCATED.load(Ordering::SeqCst) - before, item)
+}
+
+#[test]
+fn test_frame_codec_allocations() {
+    let value = vec![b'x'; 1024];
+    let mut reply = b"*1000\r\n".to_vec();
+    for _ in 0..1000 {
+        reply.extend_from_slice(b"$1024\r\n");
+        reply.extend_from_slice(&value);
+        reply.extend_from_slice(b"\r\n");
+    }
+
+    let (copied, expected) = decode(RespCodec, &reply);
+    let (shared, frame) = decode(FrameCodec::default(), &reply);
+    assert_eq!(RespValue::from(frame), expected);
+
+    // copying allocates at least the 1000 values, sharing only the array
+    assert!(copied > 1000 * 1024, "allocated {} bytes", copied);
+    assert!(shared < 100 * 1024, "allocated {} bytes", shared);
+}
@@ -0,0 +1,47 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::rolling::RollingBuffer;
+use actix_redis::RedisActor;
+use futures::Future;
+
+#[test]
+fn test_rolling_buffer() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-rolling-buffer");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+    let key = "test-rolling-buffer";
+
+    Arbiter::spawn_fn(move || {
+        let buffer = RollingBuffer::new(addr.clone().recipient(), key, 10);
+        addr.send(Del {
+            keys: vec![key.into()],
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+        .and_then(move |res| {
+            res.unwrap();
+            buffer
+                .append("abcdef")
+                .join(buffer.append("ghijkl"))
+                .join(buffer.append("mn"))
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |((first, second), third)| {
+                assert_eq!((first, second, third), (6, 10, 10));
+                addr.send(GetBytes { key: key.into() })
+                    .join(addr.send(Strlen { key: key.into() }))
+                    .map_err(|e| panic!("Should not happen {:?}", e))
+            }
+        })
+        .map(|(value, len)| {
+            // only the most recent bytes are kept
+            assert_eq!(&value.unwrap().unwrap()[..], b"efghijklmn");
+            assert_eq!(len.unwrap(), 10);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
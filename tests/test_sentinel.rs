@@ -0,0 +1,139 @@
+mod common;
+
+use std::io::{BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::{command::*, RedisActor};
+use futures::future::{self, Loop};
+use futures::Future;
+use tokio_timer::Delay;
+
+use common::{mock_node, read_command};
+
+/// Minimal master answering every command with `+<name>`
+fn mock_master(name: &'static str) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    mock_node(listener, move |_| format!("+{}\r\n", name));
+    port
+}
+
+/// The port of the current master, and the connections subscribed to
+/// `+switch-master`
+#[derive(Default)]
+struct Sentinel {
+    master: u16,
+    subscribers: Vec<TcpStream>,
+}
+
+/// Minimal Sentinel monitoring `mymaster`
+fn mock_sentinel(state: Arc<Mutex<Sentinel>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let state = state.clone();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                while let Some(args) = read_command(&mut reader) {
+                    let reply = match &args[0][..] {
+                        "SENTINEL" => {
+                            assert_eq!(
+                                args[1..],
+                                ["get-master-addr-by-name", "mymaster"]
+                            );
+                            let port = state.lock().unwrap().master.to_string();
+                            format!(
+                                "*2\r\n$9\r\n127.0.0.1\r\n${}\r\n{}\r\n",
+                                port.len(),
+                                port
+                            )
+                        }
+                        "SUBSCRIBE" => {
+                            assert_eq!(args[1..], ["+switch-master"]);
+                            let stream = stream.try_clone().unwrap();
+                            state.lock().unwrap().subscribers.push(stream);
+                            "*3\r\n$9\r\nsubscribe\r\n$14\r\n+switch-master\r\n:1\r\n"
+                                .into()
+                        }
+                        _ => "+PONG\r\n".into(),
+                    };
+                    stream.write_all(reply.as_bytes()).unwrap();
+                }
+            });
+        }
+    });
+    addr
+}
+
+/// Promote the master at `port` once the actor subscribed
+fn failover(state: Arc<Mutex<Sentinel>>, port: u16) {
+    thread::spawn(move || loop {
+        let mut state = state.lock().unwrap();
+        if state.subscribers.is_empty() {
+            drop(state);
+            thread::sleep(Duration::from_millis(10));
+            continue;
+        }
+        let payload = format!("mymaster 127.0.0.1 {} 127.0.0.1 {}", state.master, port);
+        let message = format!(
+            "*3\r\n$7\r\nmessage\r\n$14\r\n+switch-master\r\n${}\r\n{}\r\n",
+            payload.len(),
+            payload
+        );
+        state.master = port;
+        for subscriber in state.subscribers.iter_mut() {
+            subscriber.write_all(message.as_bytes()).unwrap();
+        }
+        return;
+    });
+}
+
+#[test]
+fn test_sentinel_failover() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-sentinel-failover");
+
+    let (a, b) = (mock_master("a"), mock_master("b"));
+    let state = Arc::new(Mutex::new(Sentinel {
+        master: a,
+        ..Default::default()
+    }));
+    // the first sentinel is down
+    let sentinels = vec!["127.0.0.1:1".into(), mock_sentinel(state.clone())];
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisActor::start_sentinel(sentinels, "mymaster");
+
+        addr.send(Ping(None))
+            .map_err(|e| panic!("Should not happen {:?}", e))
+            .and_then(move |res| {
+                assert_eq!(res.unwrap(), "a");
+                failover(state, b);
+
+                // commands fail while reconnecting to the new master
+                future::loop_fn((), move |()| {
+                    addr.send(Ping(None))
+                        .map_err(|e| panic!("Should not happen {:?}", e))
+                        .and_then(|res| match res {
+                            Ok(ref master) if master == "b" => {
+                                future::Either::A(future::ok(Loop::Break(())))
+                            }
+                            _ => future::Either::B(
+                                Delay::new(Instant::now() + Duration::from_millis(50))
+                                    .map(|()| Loop::Continue(()))
+                                    .map_err(|e| panic!("Should not happen {:?}", e)),
+                            ),
+                        })
+                })
+            })
+            .map(|()| System::current().stop())
+    });
+
+    sys.run()
+}
@@ -0,0 +1,39 @@
+use std::time::{Duration, SystemTime};
+
+use actix::prelude::*;
+use actix_redis::{command::Time, RedisActor, ServerNow};
+use futures::Future;
+
+const TOLERANCE: Duration = Duration::from_millis(50);
+
+/// Whether `time` is between `start` and `end`, within the tolerance
+fn between(time: SystemTime, start: SystemTime, end: SystemTime) -> bool {
+    start - TOLERANCE <= time && time <= end + TOLERANCE
+}
+
+#[test]
+fn test_server_now() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-server-now");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+
+    Arbiter::spawn_fn(move || {
+        let (a, b, c) = (addr.clone(), addr.clone(), addr.clone());
+        addr.send(Time)
+            .and_then(move |start| a.send(ServerNow).map(|now| (start, now)))
+            // answered from the time fetched by the first one
+            .and_then(move |times| b.send(ServerNow).map(|cached| (times, cached)))
+            .and_then(move |times| c.send(Time).map(|end| (times, end)))
+            .map(|(((start, now), cached), end)| {
+                let (start, now, cached, end) =
+                    (start.unwrap(), now.unwrap(), cached.unwrap(), end.unwrap());
+                assert!(between(now, start, end), "{:?}", now);
+                assert!(between(cached, now, end), "{:?}", cached);
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
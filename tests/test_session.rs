@@ -0,0 +1,1610 @@
+#![cfg(feature = "session")]
+
+mod common;
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use actix::Addr;
+use actix_redis::{
+    command::*, config::RedisSessionConfig, CookieEncoding, KeyHash, MaintenanceMode,
+    RedisActor, RedisSession, SessionConsistency, TypedSession, PERSIST_KEY,
+    TTL_OVERRIDE_KEY,
+};
+use actix_session::Session;
+use actix_web::cookie::{Cookie, CookieJar, Key, SameSite};
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App, Error, HttpRequest};
+use futures::future::{self, Either};
+use futures::Future;
+use serde::{Deserialize, Serialize};
+use tokio_timer::Delay;
+
+use common::{mock_server, read_command};
+
+/// Returns the session cookie set by the response and the session id it carries
+fn session_cookie(res: &ServiceResponse) -> (Cookie<'static>, String) {
+    let cookie = res
+        .response()
+        .cookies()
+        .find(|c| c.name() == "actix-session")
+        .expect("session cookie")
+        .into_owned();
+    let mut jar = CookieJar::new();
+    jar.add_original(cookie.clone());
+    let id = jar
+        .signed(&Key::from_master(&[0; 32]))
+        .get("actix-session")
+        .unwrap()
+        .value()
+        .to_owned();
+    (cookie, id)
+}
+
+fn ttl(redis: &Addr<RedisActor>, key: &str) -> i64 {
+    test::block_on(redis.send(Ttl { key: key.into() }))
+        .unwrap()
+        .unwrap()
+        .unwrap()
+}
+
+fn expire(redis: &Addr<RedisActor>, key: &str, seconds: &str) {
+    assert!(test::block_on(redis.send(Expire {
+        key: key.into(),
+        seconds: seconds.into(),
+    }))
+    .unwrap()
+    .unwrap());
+}
+
+#[test]
+fn test_session_refresh_on_load() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| {
+        RedisSession::new("127.0.0.1:6379", &[0; 32])
+            .ttl(60)
+            .refresh_on_load(true)
+    });
+    let mut app = test::init_service(
+        App::new()
+            .data(redis.clone())
+            .wrap(session)
+            .service(web::resource("/set").to(
+                |session: Session| -> Result<&'static str, Error> {
+                    session.set("counter", 1)?;
+                    Ok("ok")
+                },
+            ))
+            // observe the TTL after the session is loaded, but before it is
+            // written back at the end of the request
+            .service(web::resource("/ttl/{id}").to_async(
+                |id: web::Path<String>, redis: web::Data<Addr<RedisActor>>| {
+                    redis
+                        .send(Ttl {
+                            key: id.into_inner(),
+                        })
+                        .map_err(Error::from)
+                        .map(|res| format!("{}", res.unwrap().unwrap()))
+                },
+            )),
+    );
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/set").to_request());
+    let (cookie, id) = session_cookie(&res);
+    expire(&redis, &id, "5");
+
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri(&format!("/ttl/{}", id))
+            .cookie(cookie)
+            .to_request(),
+    );
+    let ttl: i64 = std::str::from_utf8(&body).unwrap().parse().unwrap();
+    assert!(ttl > 5 && ttl <= 60, "unexpected TTL {}", ttl);
+}
+
+#[test]
+fn test_session_ttl_override() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| RedisSession::new("127.0.0.1:6379", &[0; 32]).ttl(60));
+    let mut app = test::init_service(
+        App::new()
+            .wrap(session)
+            .service(web::resource("/set/{ttl}").to(
+                |ttl: web::Path<i64>, session: Session| -> Result<&'static str, Error> {
+                    session.set("counter", 1)?;
+                    session.set(TTL_OVERRIDE_KEY, ttl.into_inner())?;
+                    Ok("ok")
+                },
+            ))
+            .service(web::resource("/incr").to(
+                |session: Session| -> Result<String, Error> {
+                    let counter = session.get::<i32>("counter")?.unwrap_or(0);
+                    session.set("counter", counter + 1)?;
+                    Ok(format!("{:?}", session.get::<i64>(TTL_OVERRIDE_KEY)?))
+                },
+            )),
+    );
+
+    let res = test::call_service(
+        &mut app,
+        test::TestRequest::with_uri("/set/3600").to_request(),
+    );
+    let (cookie, id) = session_cookie(&res);
+    assert_eq!(cookie.max_age().map(|d| d.num_seconds()), Some(3600));
+    let ttl1 = ttl(&redis, &id);
+    assert!(ttl1 > 60 && ttl1 <= 3600, "unexpected TTL {}", ttl1);
+
+    // the override survives later writes, but is hidden from handlers
+    expire(&redis, &id, "5");
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri("/incr")
+            .cookie(cookie)
+            .to_request(),
+    );
+    assert_eq!(body, "None");
+    let ttl2 = ttl(&redis, &id);
+    assert!(ttl2 > 60 && ttl2 <= 3600, "unexpected TTL {}", ttl2);
+
+    // invalid overrides fall back to the default ttl and cookie max-age
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/set/0").to_request());
+    let (cookie, id) = session_cookie(&res);
+    assert_eq!(
+        cookie.max_age().map(|d| d.num_seconds()),
+        Some(7 * 24 * 60 * 60)
+    );
+    let ttl3 = ttl(&redis, &id);
+    assert!(ttl3 > 0 && ttl3 <= 60, "unexpected TTL {}", ttl3);
+}
+
+#[test]
+fn test_session_ttl_override_refresh_on_load() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| {
+        RedisSession::new("127.0.0.1:6379", &[0; 32])
+            .ttl(60)
+            .refresh_on_load(true)
+    });
+    let mut app = test::init_service(
+        App::new()
+            .data(redis.clone())
+            .wrap(session)
+            .service(web::resource("/set").to(
+                |session: Session| -> Result<&'static str, Error> {
+                    session.set(TTL_OVERRIDE_KEY, 3600)?;
+                    Ok("ok")
+                },
+            ))
+            .service(web::resource("/ttl/{id}").to_async(
+                |id: web::Path<String>, redis: web::Data<Addr<RedisActor>>| {
+                    redis
+                        .send(Ttl {
+                            key: id.into_inner(),
+                        })
+                        .map_err(Error::from)
+                        .map(|res| format!("{}", res.unwrap().unwrap()))
+                },
+            )),
+    );
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/set").to_request());
+    let (cookie, id) = session_cookie(&res);
+    expire(&redis, &id, "5");
+
+    // loading refreshes the session to the override, not the default ttl
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri(&format!("/ttl/{}", id))
+            .cookie(cookie)
+            .to_request(),
+    );
+    let ttl: i64 = std::str::from_utf8(&body).unwrap().parse().unwrap();
+    assert!(ttl > 60 && ttl <= 3600, "unexpected TTL {}", ttl);
+}
+
+#[test]
+fn test_session_key_hash() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| {
+        RedisSession::new("127.0.0.1:6379", &[0; 32]).key_hash(KeyHash::Sha256)
+    });
+    let mut app = test::init_service(App::new().wrap(session).service(
+        web::resource("/incr").to(|session: Session| -> Result<String, Error> {
+            let counter = session.get::<i32>("counter")?.unwrap_or(0) + 1;
+            session.set("counter", counter)?;
+            Ok(counter.to_string())
+        }),
+    ));
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/incr").to_request());
+    let (cookie, id) = session_cookie(&res);
+
+    // the session is stored under the hash of the id, not the id itself
+    let hashed: String = ring::digest::digest(&ring::digest::SHA256, id.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let get = |key: &str| test::block_on(redis.send(Get { key: key.into() })).unwrap();
+    assert_eq!(get(&id).unwrap(), None);
+    assert!(get(&hashed).unwrap().is_some());
+
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri("/incr")
+            .cookie(cookie)
+            .to_request(),
+    );
+    assert_eq!(body, "2");
+}
+
+#[test]
+fn test_session_cookie_encoding() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| {
+        RedisSession::new("127.0.0.1:6379", &[0; 32])
+            .cookie_encoding(CookieEncoding::Base64Url)
+    });
+    let mut app = test::init_service(App::new().wrap(session).service(
+        web::resource("/incr").to(|session: Session| -> Result<String, Error> {
+            let counter = session.get::<i32>("counter")?.unwrap_or(0) + 1;
+            session.set("counter", counter)?;
+            Ok(counter.to_string())
+        }),
+    ));
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/incr").to_request());
+    let (cookie, encoded) = session_cookie(&res);
+
+    // the session is stored under the decoded id
+    let id = base64::decode_config(&encoded, base64::URL_SAFE_NO_PAD).unwrap();
+    let id = String::from_utf8(id).unwrap();
+    assert_eq!(id.len(), 32);
+    assert_eq!(base64::encode_config(&id, base64::URL_SAFE_NO_PAD), encoded);
+    let get = |key: &str| test::block_on(redis.send(Get { key: key.into() })).unwrap();
+    assert_eq!(get(&encoded).unwrap(), None);
+    assert!(get(&id).unwrap().is_some());
+
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri("/incr")
+            .cookie(cookie)
+            .to_request(),
+    );
+    assert_eq!(body, "2");
+}
+
+#[test]
+fn test_session_client_state() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| {
+        RedisSession::new("127.0.0.1:6379", &[0; 32]).client_state(true)
+    });
+    let mut app = test::init_service(App::new().wrap(session).service(
+        web::resource("/incr").to(|session: Session| -> Result<String, Error> {
+            let counter = session.get::<i32>("counter")?.unwrap_or(0) + 1;
+            session.set("counter", counter)?;
+            Ok(counter.to_string())
+        }),
+    ));
+    let mut incr = |cookie: Option<Cookie<'static>>| {
+        let mut req = test::TestRequest::with_uri("/incr");
+        if let Some(cookie) = cookie {
+            req = req.cookie(cookie);
+        }
+        let res = test::call_service(&mut app, req.to_request());
+        let cookie = res
+            .response()
+            .cookies()
+            .find(|c| c.name() == "actix-session")
+            .expect("session cookie")
+            .into_owned();
+        let body = test::read_body(res);
+        (String::from_utf8(body.to_vec()).unwrap(), cookie)
+    };
+    // the id, the version and the state carried by an encrypted cookie
+    let carried = |cookie: &Cookie<'static>| {
+        let mut jar = CookieJar::new();
+        jar.add_original(cookie.clone());
+        let carried = jar
+            .private(&Key::from_master(&[0; 32]))
+            .get("actix-session")
+            .unwrap();
+        serde_json::from_str::<serde_json::Value>(carried.value()).unwrap()
+    };
+    let get = |key: &str| {
+        let value = test::block_on(redis.send(Get { key: key.into() })).unwrap();
+        value.unwrap().map(|v| String::from_utf8(v).unwrap())
+    };
+
+    let (body, first) = incr(None);
+    assert_eq!(body, "1");
+    let state = carried(&first);
+    let id = state["id"].as_str().unwrap().to_owned();
+    assert_eq!(state["state"]["counter"], 1);
+    // only the version is stored
+    assert_eq!(get(&id).unwrap(), state["version"]);
+
+    let (body, second) = incr(Some(first.clone()));
+    assert_eq!(body, "2");
+    assert_eq!(carried(&second)["id"], id.as_str());
+
+    // a superseded cookie is rejected
+    let (body, _) = incr(Some(first));
+    assert_eq!(body, "1");
+
+    // and so is a revoked one
+    test::block_on(redis.send(Del {
+        keys: vec![id.clone()],
+    }))
+    .unwrap()
+    .unwrap();
+    let (body, _) = incr(Some(second));
+    assert_eq!(body, "1");
+}
+
+#[test]
+fn test_session_wait_aof() {
+    let _ = env_logger::try_init();
+
+    let session = test::run_on(|| {
+        RedisSession::new("127.0.0.1:6379", &[0; 32]).wait_aof(0, 0, 100)
+    });
+    let mut app = test::init_service(App::new().wrap(session).service(
+        web::resource("/set").to(|session: Session| -> Result<&'static str, Error> {
+            session.set("counter", 1)?;
+            Ok("ok")
+        }),
+    ));
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/set").to_request());
+    assert!(res.status().is_success());
+    session_cookie(&res);
+}
+
+#[test]
+fn test_session_unchanged() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| RedisSession::new("127.0.0.1:6379", &[0; 32]).ttl(60));
+    let mut app = test::init_service(
+        App::new()
+            .wrap(session)
+            .service(web::resource("/get").to(
+                |session: Session| -> Result<String, Error> {
+                    Ok(format!("{:?}", session.get::<i32>("counter")?))
+                },
+            ))
+            .service(web::resource("/incr").to(
+                |session: Session| -> Result<&'static str, Error> {
+                    let counter = session.get::<i32>("counter")?.unwrap_or(0);
+                    session.set("counter", counter + 1)?;
+                    Ok("ok")
+                },
+            )),
+    );
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/incr").to_request());
+    let (cookie, id) = session_cookie(&res);
+
+    // stored the way the middleware would never write it, to tell whether it
+    // is written back
+    let stored = r#"{ "counter": "1" }"#;
+    test::block_on(redis.send(Set {
+        key: id.clone(),
+        value: stored.into(),
+        expiration: Expiration::Ex("100".into()),
+    }))
+    .unwrap()
+    .unwrap();
+    let get = || {
+        test::block_on(redis.send(Get { key: id.clone() }))
+            .unwrap()
+            .unwrap()
+    };
+
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri("/get")
+            .cookie(cookie.clone())
+            .to_request(),
+    );
+    assert_eq!(body, "Some(1)");
+    assert_eq!(get(), Some(stored.as_bytes().to_vec()));
+    // but its ttl is still refreshed
+    let ttl = ttl(&redis, &id);
+    assert!(ttl > 0 && ttl <= 60, "unexpected TTL {}", ttl);
+
+    let res = test::call_service(
+        &mut app,
+        test::TestRequest::with_uri("/incr")
+            .cookie(cookie)
+            .to_request(),
+    );
+    assert!(res.status().is_success());
+    // written back with JSON values
+    assert_eq!(get(), Some(br#"{"v":1,"values":{"counter":2}}"#.to_vec()));
+}
+
+#[test]
+fn test_session_fallback_keys() {
+    let _ = env_logger::try_init();
+
+    let old = test::run_on(|| RedisSession::new("127.0.0.1:6379", &[1; 32]));
+    let mut old = test::init_service(App::new().wrap(old).service(
+        web::resource("/incr").to(|session: Session| -> Result<&'static str, Error> {
+            session.set("counter", 1)?;
+            Ok("ok")
+        }),
+    ));
+    // signed with the current key, the old one is a fallback
+    let session = test::run_on(|| {
+        RedisSession::new("127.0.0.1:6379", &[0; 32]).with_fallback_keys(vec![&[1; 32]])
+    });
+    let mut app = test::init_service(
+        App::new()
+            .wrap(session)
+            .service(web::resource("/get").to(
+                |session: Session| -> Result<String, Error> {
+                    Ok(format!("{:?}", session.get::<i32>("counter")?))
+                },
+            ))
+            .service(web::resource("/incr").to(
+                |session: Session| -> Result<String, Error> {
+                    let counter = session.get::<i32>("counter")?.unwrap_or(0);
+                    session.set("counter", counter + 1)?;
+                    Ok(format!("{}", counter + 1))
+                },
+            )),
+    );
+
+    let res =
+        test::call_service(&mut old, test::TestRequest::with_uri("/incr").to_request());
+    let old_cookie = res
+        .response()
+        .cookies()
+        .find(|c| c.name() == "actix-session")
+        .expect("session cookie")
+        .into_owned();
+
+    // loaded with the fallback key, and signed again with the current one
+    let res = test::call_service(
+        &mut app,
+        test::TestRequest::with_uri("/incr")
+            .cookie(old_cookie.clone())
+            .to_request(),
+    );
+    let (cookie, _) = session_cookie(&res);
+    assert_ne!(cookie.value(), old_cookie.value());
+    assert_eq!(test::read_body(res), "2");
+
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri("/get")
+            .cookie(cookie)
+            .to_request(),
+    );
+    assert_eq!(body, "Some(2)");
+
+    // but a cookie signed with another key is not
+    let session = test::run_on(|| RedisSession::new("127.0.0.1:6379", &[0; 32]));
+    let mut app = test::init_service(App::new().wrap(session).service(
+        web::resource("/get").to(|session: Session| -> Result<String, Error> {
+            Ok(format!("{:?}", session.get::<i32>("counter")?))
+        }),
+    ));
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri("/get")
+            .cookie(old_cookie)
+            .to_request(),
+    );
+    assert_eq!(body, "None");
+}
+
+#[test]
+fn test_session_ttl_jitter() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| {
+        RedisSession::new("127.0.0.1:6379", &[0; 32])
+            .ttl(60)
+            .ttl_jitter(time::Duration::seconds(30))
+    });
+    let mut app = test::init_service(App::new().wrap(session).service(
+        web::resource("/set").to(|session: Session| -> Result<&'static str, Error> {
+            session.set("counter", 1)?;
+            Ok("ok")
+        }),
+    ));
+
+    // created in the same burst
+    let ttls = (0..10)
+        .map(|_| {
+            let res = test::call_service(
+                &mut app,
+                test::TestRequest::with_uri("/set").to_request(),
+            );
+            ttl(&redis, &session_cookie(&res).1)
+        })
+        .collect::<Vec<_>>();
+    for &ttl in ttls.iter() {
+        assert!((59..=90).contains(&ttl), "unexpected TTL {}", ttl);
+    }
+    assert!(
+        ttls.iter().any(|&ttl| ttl != ttls[0]),
+        "TTLs are not spread: {:?}",
+        ttls
+    );
+}
+
+#[test]
+fn test_session_failover() {
+    let _ = env_logger::try_init();
+
+    // nothing listens on the port
+    let down = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+    let secondary = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    // a separate server, which is not the secondary
+    let primary = test::run_on(|| RedisActor::start("127.0.0.1:7000"));
+    let exists = |redis: &Addr<RedisActor>, id: &str| {
+        test::block_on(redis.send(Get { key: id.into() }))
+            .unwrap()
+            .unwrap()
+            .is_some()
+    };
+
+    let app = |primary: &str| {
+        let session =
+            RedisSession::new(primary, &[0; 32]).with_failover("127.0.0.1:6379");
+        App::new()
+            .wrap(session)
+            .service(web::resource("/get").to(
+                |session: Session| -> Result<String, Error> {
+                    Ok(format!("{:?}", session.get::<i32>("counter")?))
+                },
+            ))
+            .service(web::resource("/incr").to(
+                |session: Session| -> Result<&'static str, Error> {
+                    let counter = session.get::<i32>("counter")?.unwrap_or(0);
+                    session.set("counter", counter + 1)?;
+                    Ok("ok")
+                },
+            ))
+    };
+
+    // the primary is down, the session is saved and loaded on the secondary
+    let mut app_down = test::init_service(test::run_on(|| app(&down)));
+    let res = test::call_service(
+        &mut app_down,
+        test::TestRequest::with_uri("/incr").to_request(),
+    );
+    let (cookie, id) = session_cookie(&res);
+    assert!(exists(&secondary, &id));
+    let res = test::call_service(
+        &mut app_down,
+        test::TestRequest::with_uri("/incr")
+            .cookie(cookie.clone())
+            .to_request(),
+    );
+    assert!(res.status().is_success());
+    let body = test::read_response(
+        &mut app_down,
+        test::TestRequest::with_uri("/get")
+            .cookie(cookie.clone())
+            .to_request(),
+    );
+    assert_eq!(body, "Some(2)");
+
+    // once the primary is back, the session is found on the secondary and
+    // moved to the primary
+    let mut app_up = test::init_service(test::run_on(|| app("127.0.0.1:7000")));
+    assert!(!exists(&primary, &id));
+    let body = test::read_response(
+        &mut app_up,
+        test::TestRequest::with_uri("/get")
+            .cookie(cookie)
+            .to_request(),
+    );
+    assert_eq!(body, "Some(2)");
+    assert!(exists(&primary, &id));
+}
+
+#[test]
+fn test_session_persist() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| RedisSession::new("127.0.0.1:6379", &[0; 32]).ttl(60));
+    let mut app = test::init_service(
+        App::new()
+            .wrap(session)
+            .service(web::resource("/persist/{persist}").to(
+                |persist: web::Path<bool>,
+                 session: Session|
+                 -> Result<&'static str, Error> {
+                    session.set("counter", 1)?;
+                    session.set(PERSIST_KEY, persist.into_inner())?;
+                    Ok("ok")
+                },
+            ))
+            .service(web::resource("/incr").to(
+                |session: Session| -> Result<String, Error> {
+                    let counter = session.get::<i32>("counter")?.unwrap_or(0);
+                    session.set("counter", counter + 1)?;
+                    Ok(format!("{:?}", session.get::<bool>(PERSIST_KEY)?))
+                },
+            )),
+    );
+    let no_expire = |id: &str| match test::block_on(redis.send(Ttl { key: id.into() }))
+        .unwrap()
+        .unwrap()
+    {
+        Err(TtlError::NoExpire) => (),
+        res => panic!("unexpected TTL {:?}", res),
+    };
+
+    let res = test::call_service(
+        &mut app,
+        test::TestRequest::with_uri("/persist/true").to_request(),
+    );
+    let (cookie, id) = session_cookie(&res);
+    assert_eq!(
+        cookie.max_age().map(|d| d.num_seconds()),
+        Some(30 * 24 * 60 * 60)
+    );
+    no_expire(&id);
+    let stored = test::block_on(redis.send(Get { key: id.clone() }))
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(!String::from_utf8(stored).unwrap().contains(PERSIST_KEY));
+
+    // later writes keep the session persisted, and handlers never see the marker
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri("/incr")
+            .cookie(cookie.clone())
+            .to_request(),
+    );
+    assert_eq!(body, "None");
+    no_expire(&id);
+
+    let res = test::call_service(
+        &mut app,
+        test::TestRequest::with_uri("/persist/false")
+            .cookie(cookie)
+            .to_request(),
+    );
+    let (cookie, _) = session_cookie(&res);
+    assert_eq!(
+        cookie.max_age().map(|d| d.num_seconds()),
+        Some(7 * 24 * 60 * 60)
+    );
+    let ttl = ttl(&redis, &id);
+    assert!(ttl > 0 && ttl <= 60, "unexpected TTL {}", ttl);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Profile {
+    name: String,
+    languages: Vec<String>,
+    visits: u32,
+}
+
+#[test]
+fn test_session_json_values() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| RedisSession::new("127.0.0.1:6379", &[0; 32]));
+    let mut app = test::init_service(
+        App::new()
+            .wrap(session)
+            .service(web::resource("/login").to(
+                |session: Session| -> Result<&'static str, Error> {
+                    session.set(
+                        "profile",
+                        Profile {
+                            name: "alice".into(),
+                            languages: vec!["rust".into(), "ocaml".into()],
+                            visits: 1,
+                        },
+                    )?;
+                    Ok("ok")
+                },
+            ))
+            .service(web::resource("/visit").to(
+                |session: Session| -> Result<String, Error> {
+                    let mut profile = session.get::<Profile>("profile")?.unwrap();
+                    profile.visits += 1;
+                    session.set("profile", &profile)?;
+                    Ok(format!("{:?}", profile))
+                },
+            )),
+    );
+    let stored = |id: &str| -> serde_json::Value {
+        let stored = test::block_on(redis.send(Get { key: id.into() }))
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        serde_json::from_slice(&stored).unwrap()
+    };
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/login").to_request());
+    let (cookie, id) = session_cookie(&res);
+    // a JSON object, not a string holding one
+    let profile = &stored(&id)["values"]["profile"];
+    assert_eq!(profile["languages"][1], "ocaml");
+    assert_eq!(profile["visits"], 1);
+
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri("/visit")
+            .cookie(cookie)
+            .to_request(),
+    );
+    let profile = Profile {
+        name: "alice".into(),
+        languages: vec!["rust".into(), "ocaml".into()],
+        visits: 2,
+    };
+    assert_eq!(body, format!("{:?}", profile));
+    assert_eq!(
+        serde_json::from_value::<Profile>(stored(&id)["values"]["profile"].clone())
+            .unwrap(),
+        profile
+    );
+}
+
+/// Minimal master recording the commands it receives, and answering `WAIT` as
+/// if `replicas` replicas acknowledged the writes. Sets hold the members `s1`
+/// and `s2`.
+fn mock_master(replicas: i64) -> (String, Arc<Mutex<Vec<Vec<String>>>>) {
+    let commands = Arc::new(Mutex::new(vec![]));
+
+    let log = commands.clone();
+    let addr = mock_server(move |args| {
+        let reply = match args[0].as_str() {
+            "SET" => "+OK\r\n".to_owned(),
+            "WAIT" => format!(":{}\r\n", replicas),
+            "SMEMBERS" => "*2\r\n$2\r\ns1\r\n$2\r\ns2\r\n".to_owned(),
+            "DEL" => ":1\r\n".to_owned(),
+            _ => "-ERR unknown command\r\n".to_owned(),
+        };
+        log.lock().unwrap().push(args.to_vec());
+        reply
+    });
+
+    (addr, commands)
+}
+
+#[test]
+fn test_typed_session() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| RedisSession::new("127.0.0.1:6379", &[0; 32]));
+    let mut app = test::init_service(
+        App::new()
+            .wrap(session)
+            .service(web::resource("/login").to(
+                |session: TypedSession| -> Result<&'static str, Error> {
+                    session.insert(
+                        "profile",
+                        Profile {
+                            name: "alice".into(),
+                            languages: vec!["rust".into(), "ocaml".into()],
+                            visits: 1,
+                        },
+                    )?;
+                    Ok("ok")
+                },
+            ))
+            .service(web::resource("/visit").to(
+                |session: TypedSession| -> Result<String, Error> {
+                    let mut profile = session.get::<Profile>("profile")?.unwrap();
+                    profile.visits += 1;
+                    session.insert("profile", &profile)?;
+                    Ok(format!("{:?}", profile))
+                },
+            ))
+            .service(web::resource("/reset").to(
+                |session: Session, typed: TypedSession| -> Result<String, Error> {
+                    session.set("theme", "dark")?;
+                    let visits = typed.get::<Profile>("profile")?.map(|p| p.visits);
+                    typed.remove("profile");
+                    Ok(format!("{:?}", visits))
+                },
+            )),
+    );
+    let stored = |id: &str| -> serde_json::Value {
+        let stored = test::block_on(redis.send(Get { key: id.into() }))
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        serde_json::from_slice(&stored).unwrap()
+    };
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/login").to_request());
+    let (cookie, id) = session_cookie(&res);
+    // a JSON object, not a string holding one
+    let profile = &stored(&id)["values"]["profile"];
+    assert_eq!(profile["languages"][1], "ocaml");
+    assert_eq!(profile["visits"], 1);
+
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri("/visit")
+            .cookie(cookie.clone())
+            .to_request(),
+    );
+    let profile = Profile {
+        name: "alice".into(),
+        languages: vec!["rust".into(), "ocaml".into()],
+        visits: 2,
+    };
+    assert_eq!(body, format!("{:?}", profile));
+    assert_eq!(
+        serde_json::from_value::<Profile>(stored(&id)["values"]["profile"].clone())
+            .unwrap(),
+        profile
+    );
+
+    // along with `Session`
+    let body = test::read_response(
+        &mut app,
+        test::TestRequest::with_uri("/reset")
+            .cookie(cookie)
+            .to_request(),
+    );
+    assert_eq!(body, "Some(2)");
+    assert_eq!(stored(&id)["values"], serde_json::json!({"theme": "dark"}));
+}
+
+#[test]
+fn test_session_consistency() {
+    let _ = env_logger::try_init();
+
+    // too few replicas acknowledge the write of the second app
+    for &replicas in [1, 0].iter() {
+        let (addr, commands) = mock_master(replicas);
+        let session = test::run_on(|| {
+            RedisSession::new(addr, &[0; 32])
+                .consistency(SessionConsistency::WaitReplica(1, 100))
+        });
+        let mut app = test::init_service(App::new().wrap(session).service(
+            web::resource("/set").to(
+                |session: Session| -> Result<&'static str, Error> {
+                    session.set("counter", 1)?;
+                    Ok("ok")
+                },
+            ),
+        ));
+
+        let res = test::call_service(
+            &mut app,
+            test::TestRequest::with_uri("/set").to_request(),
+        );
+        assert!(res.status().is_success());
+        let (_, id) = session_cookie(&res);
+
+        let commands = commands.lock().unwrap();
+        let names = commands.iter().map(|c| c[0].as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["SET", "WAIT"]);
+        assert_eq!(commands[0][1], id);
+        assert_eq!(commands[1], ["WAIT", "1", "100"]);
+    }
+}
+
+#[test]
+fn test_session_invalidate_all() {
+    let _ = env_logger::try_init();
+
+    // too few replicas acknowledge the deletions on the second server
+    for &replicas in [1, 0].iter() {
+        let (addr, commands) = mock_master(replicas);
+        let session = test::run_on(|| {
+            RedisSession::new(addr, &[0; 32]).user_index("user", "user-sessions:")
+        });
+        let index = session.user_index_key("alice").unwrap();
+
+        let invalidated = test::block_on(
+            session.invalidate_all("alice", SessionConsistency::WaitReplica(1, 100)),
+        )
+        .unwrap();
+        assert_eq!(invalidated.deleted, 2);
+        assert_eq!(invalidated.replicas, Some(replicas));
+        assert_eq!(invalidated.is_replicated(), replicas == 1);
+
+        let commands = commands.lock().unwrap();
+        assert_eq!(commands[0], ["SMEMBERS", index.as_str()]);
+        let mut deleted = commands[1..4].to_vec();
+        deleted.sort();
+        assert_eq!(
+            deleted,
+            [["DEL", "s1"], ["DEL", "s2"], ["DEL", index.as_str()]]
+        );
+        assert_eq!(commands[4], ["WAIT", "1", "100"]);
+        assert_eq!(commands.len(), 5);
+    }
+
+    // the deletions are not waited for
+    let (addr, commands) = mock_master(1);
+    let session = test::run_on(|| {
+        RedisSession::new(addr, &[0; 32]).user_index("user", "user-sessions:")
+    });
+    let invalidated =
+        test::block_on(session.invalidate_all("alice", SessionConsistency::Master))
+            .unwrap();
+    assert_eq!(invalidated.replicas, None);
+    assert!(invalidated.is_replicated());
+    assert!(commands.lock().unwrap().iter().all(|c| c[0] != "WAIT"));
+}
+
+#[test]
+fn test_session_from_config() {
+    let _ = env_logger::try_init();
+
+    let (addr, commands) = mock_master(0);
+    let config: RedisSessionConfig = toml::from_str(&format!(
+        r#"
+        ttl = "10m"
+        cookie_name = "sid"
+        cookie_max_age = "1h"
+        cookie_same_site = "strict"
+
+        [connection]
+        addr = "{}"
+        connect_timeout = "500ms"
+
+        [connection.circuit_breaker]
+        threshold = 5
+        cooldown = "30s"
+        "#,
+        addr
+    ))
+    .unwrap();
+    assert_eq!(config.ttl, Duration::from_secs(600));
+    assert_eq!(config.cookie_path, "/");
+    assert_eq!(
+        config.connection.connect_timeout,
+        Duration::from_millis(500)
+    );
+    assert_eq!(config.connection.mailbox_capacity, 16);
+    assert_eq!(
+        config.connection.circuit_breaker.as_ref().unwrap().cooldown,
+        Duration::from_secs(30)
+    );
+    // written back as read
+    let written = toml::to_string(&config).unwrap();
+    assert!(written.contains("cookie_max_age = \"1h\""));
+    assert_eq!(
+        toml::from_str::<RedisSessionConfig>(&written).unwrap(),
+        config
+    );
+    assert!(toml::from_str::<RedisSessionConfig>("ttl = \"10 years\"").is_err());
+
+    let session = test::run_on(|| RedisSession::from_config(&config, &[0; 32]));
+    let mut app = test::init_service(App::new().wrap(session).service(
+        web::resource("/set").to(|session: Session| -> Result<&'static str, Error> {
+            session.set("counter", 1)?;
+            Ok("ok")
+        }),
+    ));
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/set").to_request());
+    assert!(res.status().is_success());
+    let cookie = res
+        .response()
+        .cookies()
+        .find(|c| c.name() == "sid")
+        .expect("session cookie");
+    assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+    assert_eq!(cookie.max_age(), Some(time::Duration::hours(1)));
+
+    let commands = commands.lock().unwrap();
+    assert_eq!(commands[0][0], "SET");
+    assert_eq!(commands[0][3..], ["EX", "600"]);
+}
+
+/// Minimal master recording the names of the commands it receives in batches:
+/// the commands that arrive before it replies
+fn mock_pipelining_master() -> (String, Arc<Mutex<Vec<Vec<String>>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let batches = Arc::new(Mutex::new(vec![]));
+
+    let log = batches.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let log = log.clone();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                while let Some(first) = read_command(&mut reader) {
+                    let mut batch = vec![first];
+                    // the commands written without waiting for a reply
+                    stream
+                        .set_read_timeout(Some(std::time::Duration::from_millis(200)))
+                        .unwrap();
+                    while reader
+                        .fill_buf()
+                        .map(|buf| !buf.is_empty())
+                        .unwrap_or(false)
+                    {
+                        batch.push(read_command(&mut reader).unwrap());
+                    }
+                    stream.set_read_timeout(None).unwrap();
+
+                    let mut names = vec![];
+                    for args in batch {
+                        let reply = match args[0].as_str() {
+                            "SET" => "+OK\r\n",
+                            "EVAL" | "WAIT" => ":1\r\n",
+                            _ => "-ERR unknown command\r\n",
+                        };
+                        stream.write_all(reply.as_bytes()).unwrap();
+                        names.push(args[0].clone());
+                    }
+                    log.lock().unwrap().push(names);
+                }
+            });
+        }
+    });
+
+    (addr, batches)
+}
+
+fn login(session: Session) -> Result<&'static str, Error> {
+    session.set("user", "alice")?;
+    Ok("ok")
+}
+
+#[test]
+fn test_session_user_index_pipelined() {
+    let _ = env_logger::try_init();
+
+    let (addr, batches) = mock_pipelining_master();
+    let session = test::run_on(|| {
+        RedisSession::new(addr, &[0; 32])
+            .consistency(SessionConsistency::WaitReplica(1, 100))
+            .user_index("user", "user-sessions:")
+    });
+    assert_eq!(
+        session.user_index_key("alice"),
+        Some("user-sessions:{2bd806c97f0e00af}".to_owned())
+    );
+    let mut app = test::init_service(
+        App::new()
+            .wrap(session.clone())
+            .service(web::resource("/login").to(login)),
+    );
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/login").to_request());
+    assert!(res.status().is_success());
+    let (_, id) = session_cookie(&res);
+    // in the slot of the index
+    assert!(id.starts_with("{2bd806c97f0e00af}"));
+
+    // a single round trip
+    assert_eq!(*batches.lock().unwrap(), vec![vec!["SET", "EVAL", "WAIT"]]);
+}
+
+#[test]
+fn test_session_user_index() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:7000"));
+    // the hashed keys are not in the slot of the index
+    for &key_hash in [KeyHash::None, KeyHash::Sha256].iter() {
+        let session = test::run_on(|| {
+            RedisSession::new_cluster("127.0.0.1:7000", &[0; 32])
+                .key_hash(key_hash)
+                .user_index("user", "test-user-index:")
+        });
+        let index = session.user_index_key("alice").unwrap();
+        test::block_on(redis.send(Del {
+            keys: vec![index.clone()],
+        }))
+        .unwrap()
+        .unwrap();
+        let mut app = test::init_service(
+            App::new()
+                .wrap(session)
+                .service(web::resource("/login").to(login)),
+        );
+
+        let res = test::call_service(
+            &mut app,
+            test::TestRequest::with_uri("/login").to_request(),
+        );
+        let (_, id) = session_cookie(&res);
+
+        let key = match key_hash {
+            KeyHash::None => id,
+            KeyHash::Sha256 => {
+                ring::digest::digest(&ring::digest::SHA256, id.as_bytes())
+                    .as_ref()
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect()
+            }
+        };
+        let members = test::block_on(redis.send(SMIsMember {
+            key: index.clone(),
+            members: vec![key.into_bytes()],
+        }))
+        .unwrap()
+        .unwrap();
+        assert_eq!(members, vec![true]);
+        let ttl = ttl(&redis, &index);
+        assert!(ttl > 7000 && ttl <= 7200, "unexpected TTL {}", ttl);
+    }
+}
+
+#[test]
+fn test_session_user_index_ttl() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| {
+        RedisSession::new("127.0.0.1:6379", &[0; 32])
+            .ttl(60)
+            .user_index("user", "test-user-index-ttl:")
+    });
+    let index = session.user_index_key("alice").unwrap();
+    test::block_on(redis.send(Del {
+        keys: vec![index.clone()],
+    }))
+    .unwrap()
+    .unwrap();
+    let mut app = test::init_service(
+        App::new()
+            .wrap(session)
+            .service(web::resource("/login").to(login))
+            .service(web::resource("/login/{ttl}").to(
+                |ttl: web::Path<i64>, session: Session| -> Result<&'static str, Error> {
+                    session.set("user", "alice")?;
+                    session.set(TTL_OVERRIDE_KEY, ttl.into_inner())?;
+                    Ok("ok")
+                },
+            ))
+            .service(web::resource("/remember").to(
+                |session: Session| -> Result<&'static str, Error> {
+                    session.set("user", "alice")?;
+                    session.set(PERSIST_KEY, true)?;
+                    Ok("ok")
+                },
+            )),
+    );
+    let mut login = |uri: &str| {
+        let res =
+            test::call_service(&mut app, test::TestRequest::with_uri(uri).to_request());
+        assert!(res.status().is_success());
+        session_cookie(&res).1
+    };
+
+    // a shorter session does not shorten the expiration of the index
+    let long = login("/login/3600");
+    let short = login("/login");
+    let index_ttl = ttl(&redis, &index);
+    assert!(
+        index_ttl >= ttl(&redis, &long),
+        "unexpected TTL {}",
+        index_ttl
+    );
+    assert!(
+        index_ttl > ttl(&redis, &short),
+        "unexpected TTL {}",
+        index_ttl
+    );
+
+    // nor restores one once a persisted session removed it
+    login("/remember");
+    let short = login("/login");
+    let members = test::block_on(redis.send(SMIsMember {
+        key: index.clone(),
+        members: vec![short.into_bytes()],
+    }))
+    .unwrap()
+    .unwrap();
+    assert_eq!(members, vec![true]);
+    assert!(test::block_on(redis.send(Ttl { key: index }))
+        .unwrap()
+        .unwrap()
+        .is_err());
+}
+
+#[test]
+fn test_session_invalidate_all_longer_session() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| {
+        RedisSession::new("127.0.0.1:6379", &[0; 32])
+            .ttl(1)
+            .user_index("user", "test-invalidate-all:")
+    });
+    let mut app = test::init_service(
+        App::new()
+            .wrap(session.clone())
+            .service(web::resource("/login").to(login))
+            .service(web::resource("/remember").to(
+                |session: Session| -> Result<&'static str, Error> {
+                    session.set("user", "alice")?;
+                    session.set(TTL_OVERRIDE_KEY, 3600)?;
+                    Ok("ok")
+                },
+            )),
+    );
+    let res = test::call_service(
+        &mut app,
+        test::TestRequest::with_uri("/remember").to_request(),
+    );
+    let (_, long) = session_cookie(&res);
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/login").to_request());
+    assert!(res.status().is_success());
+
+    // past the time to live of the last session written
+    thread::sleep(Duration::from_millis(2100));
+
+    let invalidated =
+        test::block_on(session.invalidate_all("alice", SessionConsistency::Master))
+            .unwrap();
+    assert_eq!(invalidated.deleted, 1);
+    assert!(test::block_on(redis.send(Get { key: long }))
+        .unwrap()
+        .unwrap()
+        .is_none());
+}
+
+/// Minimal server answering every command after `delay`
+fn mock_slow_server(delay: std::time::Duration) -> String {
+    mock_server(move |args| {
+        thread::sleep(delay);
+        let reply = match args[0].as_str() {
+            "SET" => "+OK\r\n",
+            _ => ":0\r\n",
+        };
+        reply.to_owned()
+    })
+}
+
+/// Whether `app` is ready, without waiting
+fn is_ready<S: Service>(app: &mut S) -> bool {
+    test::block_on(future::lazy(|| {
+        Ok::<_, ()>(
+            app.poll_ready()
+                .map(|ready| ready.is_ready())
+                .unwrap_or(false),
+        )
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_session_backpressure() {
+    let _ = env_logger::try_init();
+
+    let server = mock_slow_server(std::time::Duration::from_millis(300));
+    let session = test::run_on(|| RedisSession::new(server, &[0; 32]).backpressure(1));
+    let mut app = test::init_service(App::new().wrap(session).service(
+        web::resource("/set").to(|session: Session| -> Result<&'static str, Error> {
+            session.set("counter", 1)?;
+            Ok("ok")
+        }),
+    ));
+    // ready once connected
+    test::block_on(future::poll_fn(|| app.poll_ready())).unwrap();
+    assert!(is_ready(&mut app));
+
+    // while the write of the session waits for its reply
+    let call = app.call(test::TestRequest::with_uri("/set").to_request());
+    let timeout = Delay::new(Instant::now() + std::time::Duration::from_millis(100));
+    let call = match test::block_on(call.select2(timeout)) {
+        Ok(Either::B((_, call))) => call,
+        _ => panic!("Should not happen"),
+    };
+    assert!(!is_ready(&mut app));
+
+    let res = test::block_on(call).unwrap();
+    assert!(res.status().is_success());
+    assert!(is_ready(&mut app));
+}
+
+/// Minimal server refusing every write, as at `maxmemory` with `noeviction`
+fn mock_full_server() -> String {
+    mock_server(|_| {
+        "-OOM command not allowed when used memory > 'maxmemory'.\r\n".to_owned()
+    })
+}
+
+#[test]
+fn test_session_out_of_memory() {
+    let _ = env_logger::try_init();
+
+    let session = test::run_on(|| RedisSession::new(mock_full_server(), &[0; 32]));
+    let mut app = test::init_service(App::new().wrap(session).service(
+        web::resource("/set").to(|session: Session| -> Result<&'static str, Error> {
+            session.set("counter", 1)?;
+            Ok("ok")
+        }),
+    ));
+
+    let err =
+        match test::block_on(app.call(test::TestRequest::with_uri("/set").to_request()))
+        {
+            Err(err) => err,
+            Ok(_) => panic!("Should not happen"),
+        };
+    assert_eq!(
+        err.as_response_error().error_response().status(),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(err.to_string(), actix_redis::Error::OutOfMemory.to_string());
+}
+
+#[test]
+fn test_session_heartbeat() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| RedisSession::new("127.0.0.1:6379", &[0; 32]).ttl(60));
+    let mut app =
+        test::init_service(
+            App::new()
+                .data(session.clone())
+                .service(web::scope("/app").wrap(session).service(
+                    web::resource("/set").to(
+                        |session: Session| -> Result<&'static str, Error> {
+                            session.set("counter", 1)?;
+                            Ok("ok")
+                        },
+                    ),
+                ))
+                .service(web::resource("/heartbeat").to_async(
+                    |req: HttpRequest, session: web::Data<RedisSession>| {
+                        session.heartbeat(&req).map(|alive| alive.to_string())
+                    },
+                )),
+        );
+    let res = test::call_service(
+        &mut app,
+        test::TestRequest::with_uri("/app/set").to_request(),
+    );
+    let (cookie, id) = session_cookie(&res);
+    let mut heartbeat = |cookie: Cookie<'static>| {
+        let body = test::read_response(
+            &mut app,
+            test::TestRequest::with_uri("/heartbeat")
+                .cookie(cookie)
+                .to_request(),
+        );
+        std::str::from_utf8(&body).unwrap().to_owned()
+    };
+
+    expire(&redis, &id, "5");
+    assert_eq!(heartbeat(cookie.clone()), "true");
+    let ttl1 = ttl(&redis, &id);
+    assert!(ttl1 > 5 && ttl1 <= 60, "unexpected TTL {}", ttl1);
+
+    // a persisted session is alive but gets no expiration
+    assert!(test::block_on(redis.send(Persist { key: id.clone() }))
+        .unwrap()
+        .unwrap());
+    assert_eq!(heartbeat(cookie.clone()), "true");
+    match test::block_on(redis.send(Ttl { key: id.clone() }))
+        .unwrap()
+        .unwrap()
+    {
+        Err(TtlError::NoExpire) => (),
+        res => panic!("unexpected TTL {:?}", res),
+    }
+
+    // an expired session is not recreated
+    test::block_on(redis.send(Del {
+        keys: vec![id.clone()],
+    }))
+    .unwrap()
+    .unwrap();
+    assert_eq!(heartbeat(cookie), "false");
+    assert!(test::block_on(redis.send(Get { key: id }))
+        .unwrap()
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_session_heartbeat_ttl_override() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| RedisSession::new("127.0.0.1:6379", &[0; 32]).ttl(60));
+    let mut app = test::init_service(
+        App::new()
+            .data(session.clone())
+            .service(web::scope("/app").wrap(session).service(
+                web::resource("/remember").to(
+                    |session: Session| -> Result<&'static str, Error> {
+                        session.set("counter", 1)?;
+                        session.set(TTL_OVERRIDE_KEY, 3600)?;
+                        Ok("ok")
+                    },
+                ),
+            ))
+            .service(web::resource("/heartbeat").to_async(
+                |req: HttpRequest, session: web::Data<RedisSession>| {
+                    session.heartbeat(&req).map(|alive| alive.to_string())
+                },
+            )),
+    );
+    let res = test::call_service(
+        &mut app,
+        test::TestRequest::with_uri("/app/remember").to_request(),
+    );
+    let (cookie, id) = session_cookie(&res);
+    let mut heartbeat = |cookie: Cookie<'static>| {
+        let body = test::read_response(
+            &mut app,
+            test::TestRequest::with_uri("/heartbeat")
+                .cookie(cookie)
+                .to_request(),
+        );
+        std::str::from_utf8(&body).unwrap().to_owned()
+    };
+
+    // the longer time to live is not shortened to the default one
+    assert_eq!(heartbeat(cookie.clone()), "true");
+    let ttl1 = ttl(&redis, &id);
+    assert!(ttl1 > 60 && ttl1 <= 3600, "unexpected TTL {}", ttl1);
+
+    // and is restored once it runs low
+    expire(&redis, &id, "5");
+    assert_eq!(heartbeat(cookie), "true");
+    let ttl2 = ttl(&redis, &id);
+    assert!(ttl2 > 60 && ttl2 <= 3600, "unexpected TTL {}", ttl2);
+}
+
+#[test]
+fn test_session_wrong_type() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let session = test::run_on(|| RedisSession::new("127.0.0.1:6379", &[0; 32]).ttl(60));
+    let mut app = test::init_service(
+        App::new()
+            .wrap(session)
+            .service(web::resource("/get").to(
+                |session: Session| -> Result<String, Error> {
+                    Ok(format!("{:?}", session.get::<i32>("counter")?))
+                },
+            ))
+            .service(web::resource("/set").to(
+                |session: Session| -> Result<&'static str, Error> {
+                    session.set("counter", 1)?;
+                    Ok("ok")
+                },
+            )),
+    );
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/set").to_request());
+    let (cookie, id) = session_cookie(&res);
+
+    // another application wrote a list at the session key
+    test::block_on(redis.send(Del {
+        keys: vec![id.clone()],
+    }))
+    .unwrap()
+    .unwrap();
+    test::block_on(redis.send(actix_redis::redis::Command::new(("RPUSH", &id, 1))))
+        .unwrap()
+        .unwrap();
+
+    let res = test::call_service(
+        &mut app,
+        test::TestRequest::with_uri("/get")
+            .cookie(cookie)
+            .to_request(),
+    );
+    assert!(res.status().is_success());
+    assert_eq!(test::read_body(res), "None");
+}
+
+#[test]
+fn test_session_maintenance_mode() {
+    let _ = env_logger::try_init();
+
+    let redis = test::run_on(|| RedisActor::start("127.0.0.1:6379"));
+    let mode = MaintenanceMode::new();
+    let session = test::run_on(|| {
+        RedisSession::new("127.0.0.1:6379", &[0; 32])
+            .ttl(60)
+            .maintenance_mode(&mode, 1)
+    });
+    let mut app = test::init_service(App::new().wrap(session.clone()).service(
+        web::resource("/incr").to(|session: Session| -> Result<String, Error> {
+            let counter = session.get::<i32>("counter")?.unwrap_or(0) + 1;
+            session.set("counter", counter)?;
+            Ok(counter.to_string())
+        }),
+    ));
+
+    let res =
+        test::call_service(&mut app, test::TestRequest::with_uri("/incr").to_request());
+    let (cookie, id) = session_cookie(&res);
+    let stored = || {
+        let stored = test::block_on(redis.send(Get { key: id.clone() }))
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let stored: serde_json::Value = serde_json::from_slice(&stored).unwrap();
+        stored["values"]["counter"].clone()
+    };
+    let mut incr = || {
+        test::read_response(
+            &mut app,
+            test::TestRequest::with_uri("/incr")
+                .cookie(cookie.clone())
+                .to_request(),
+        )
+    };
+
+    mode.enable();
+    // loaded from the cache, then from the buffered write
+    assert_eq!(incr(), "2");
+    assert_eq!(incr(), "3");
+    assert_eq!(stored(), 1);
+
+    // a write of another session is beyond the buffer
+    let err = match test::block_on(
+        app.call(test::TestRequest::with_uri("/incr").to_request()),
+    ) {
+        Err(err) => err,
+        Ok(_) => panic!("Should not happen"),
+    };
+    assert_eq!(
+        err.as_response_error().error_response().status(),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+
+    test::block_on(session.resume()).unwrap();
+    assert!(!mode.is_enabled());
+    assert_eq!(stored(), 3);
+}
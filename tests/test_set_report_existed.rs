@@ -0,0 +1,75 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::{RedisClusterActor, RespValue};
+use futures::Future;
+
+const KEY: &str = "test-set-report-existed";
+
+fn set(value: &str) -> SetReportExisted {
+    SetReportExisted(Set {
+        key: KEY.into(),
+        value: value.into(),
+        expiration: Expiration::Ex("60".into()),
+    })
+}
+
+#[test]
+fn test_set_report_existed() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-set-report-existed");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(set("first"))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert!(!res.unwrap());
+                addr.send(set("second"))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert!(res.unwrap());
+                addr.send(Get { key: KEY.into() })
+            }
+        })
+        .map(|res| {
+            assert_eq!(res.unwrap(), Some(b"second".to_vec()));
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_set_report_existed_request() {
+    match set("value").into_request() {
+        RespValue::Array(args) => {
+            assert_eq!(args[0], "EVAL".into());
+            assert_eq!(
+                args[2..],
+                ["1", KEY, "value", "EX", "60"]
+                    .iter()
+                    .map(|&arg| arg.into())
+                    .collect::<Vec<RespValue>>()[..]
+            );
+        }
+        req => panic!("unexpected request {:?}", req),
+    }
+    assert!(SetReportExisted::from_response(RespValue::Integer(1)).unwrap());
+    assert!(!SetReportExisted::from_response(RespValue::Integer(0)).unwrap());
+}
@@ -0,0 +1,91 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::{Error, RedisClusterActor};
+use futures::Future;
+
+const FIRST: &str = "{test-sintercard}first";
+const SECOND: &str = "{test-sintercard}second";
+
+fn sadd(key: &str, members: &[&str]) -> SAdd {
+    SAdd {
+        key: key.into(),
+        members: members.iter().map(|m| m.as_bytes().to_vec()).collect(),
+    }
+}
+
+fn sintercard(limit: Option<usize>) -> SInterCard {
+    SInterCard {
+        keys: vec![FIRST.into(), SECOND.into()],
+        limit,
+    }
+}
+
+#[test]
+fn test_sintercard() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-sintercard");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![FIRST.into(), SECOND.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(sadd(FIRST, &["a", "b", "c", "d"]))
+                    .join(addr.send(sadd(SECOND, &["b", "c", "d", "e"])))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |(first, second)| {
+                first.unwrap();
+                second.unwrap();
+                addr.send(sintercard(None)).join3(
+                    addr.send(sintercard(Some(2))),
+                    addr.send(sintercard(Some(10))),
+                )
+            }
+        })
+        .map(|(all, capped, above)| {
+            assert_eq!(all.unwrap(), 3);
+            assert_eq!(capped.unwrap(), 2);
+            assert_eq!(above.unwrap(), 3);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_sintercard_cross_slot() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-sintercard-cross-slot");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(SInterCard {
+            keys: vec![
+                "test-sintercard-first".into(),
+                "test-sintercard-second".into(),
+            ],
+            limit: None,
+        })
+        .map(|res| {
+            match res {
+                Err(Error::MultipleSlot(_)) => (),
+                res => panic!("Should not happen {:?}", res),
+            }
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
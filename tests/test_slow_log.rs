@@ -0,0 +1,79 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_redis::{command::*, RedactionPolicy, RedisActor};
+use futures::Future;
+use log::{Log, Metadata, Record};
+
+use common::mock_server;
+
+/// Collects the records of the slow log
+struct SlowLog(Arc<Mutex<Vec<String>>>);
+
+impl Log for SlowLog {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == "actix_redis::slow_log"
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Answers `GET` after 100ms and anything else immediately
+fn slow_server() -> String {
+    mock_server(|args| {
+        if args[0] == "GET" {
+            thread::sleep(Duration::from_millis(100));
+            "$-1\r\n".to_owned()
+        } else {
+            "+PONG\r\n".to_owned()
+        }
+    })
+}
+
+#[test]
+fn test_slow_log() -> std::io::Result<()> {
+    let records = Arc::new(Mutex::new(vec![]));
+    log::set_boxed_logger(Box::new(SlowLog(records.clone()))).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+
+    let sys = System::new("test-slow-log");
+
+    let server = slow_server();
+    let addr = RedisActor::builder(server.as_str())
+        .slow_log(Duration::from_millis(50))
+        .redaction(RedactionPolicy::RedactValues)
+        .start();
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Ping(None))
+            .join(addr.send(Get {
+                key: "test-slow-log".into(),
+            }))
+            .map(move |(ping, get)| {
+                ping.unwrap();
+                get.unwrap();
+
+                let records = records.lock().unwrap();
+                assert_eq!(records.len(), 1, "{:?}", records);
+                let prefix = format!(
+                    "Slow command: GET key: test-slow-log node: {} duration: ",
+                    server
+                );
+                assert!(records[0].starts_with(&prefix), "{}", records[0]);
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,99 @@
+use actix::prelude::*;
+use actix_redis::resp::FrameCodec;
+use actix_redis::{command::*, RedisClusterActor, RespValue};
+use bytes::BytesMut;
+use futures::Future;
+use tokio_codec::Decoder;
+
+// entries as sent by Redis 4.0 and later, and a last one as sent by older
+// servers, without the client fields
+const SLOWLOG: &[u8] = b"*3\r\n\
+*6\r\n:2\r\n:1718281828\r\n:12\r\n*3\r\n$3\r\nSET\r\n$3\r\nfoo\r\n$3\r\nbar\r\n\
+$15\r\n127.0.0.1:52610\r\n$6\r\nworker\r\n\
+*6\r\n:1\r\n:1718281827\r\n:5\r\n*1\r\n$4\r\nPING\r\n$15\r\n127.0.0.1:52608\r\n$0\r\n\r\n\
+*4\r\n:0\r\n:1718281826\r\n:30\r\n*3\r\n$7\r\nSLOWLOG\r\n$3\r\nGET\r\n$2\r\n10\r\n";
+
+fn decode(reply: &[u8]) -> RespValue {
+    let mut buf = BytesMut::from(reply);
+    FrameCodec::default()
+        .decode(&mut buf)
+        .unwrap()
+        .unwrap()
+        .into()
+}
+
+fn args(args: &[&str]) -> Vec<Vec<u8>> {
+    args.iter().map(|arg| arg.as_bytes().to_vec()).collect()
+}
+
+#[test]
+fn test_slowlog_get_response() {
+    let entries = SlowlogGet::from_response(decode(SLOWLOG)).unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            SlowlogEntry {
+                id: 2,
+                timestamp: 1718281828,
+                duration_us: 12,
+                args: args(&["SET", "foo", "bar"]),
+                client_addr: "127.0.0.1:52610".into(),
+                client_name: "worker".into(),
+            },
+            SlowlogEntry {
+                id: 1,
+                timestamp: 1718281827,
+                duration_us: 5,
+                args: args(&["PING"]),
+                client_addr: "127.0.0.1:52608".into(),
+                client_name: "".into(),
+            },
+            SlowlogEntry {
+                id: 0,
+                timestamp: 1718281826,
+                duration_us: 30,
+                args: args(&["SLOWLOG", "GET", "10"]),
+                client_addr: "".into(),
+                client_name: "".into(),
+            },
+        ]
+    );
+
+    assert_eq!(
+        SlowlogGet::from_response(decode(b"*0\r\n")).unwrap(),
+        vec![]
+    );
+    // the arguments are missing
+    let res = decode(b"*1\r\n*3\r\n:0\r\n:1718281826\r\n:30\r\n");
+    assert!(SlowlogGet::from_response(res).is_err());
+}
+
+#[test]
+fn test_slowlog() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-slowlog");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(SlowlogReset { slot: 0 })
+            .and_then({
+                let addr = addr.clone();
+                move |res| {
+                    res.unwrap();
+                    addr.send(SlowlogGet {
+                        count: Some(10),
+                        slot: 0,
+                    })
+                }
+            })
+            .map(|res| {
+                let entries = res.unwrap();
+                assert!(entries.len() <= 10);
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
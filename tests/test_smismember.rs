@@ -0,0 +1,50 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::RedisClusterActor;
+use futures::Future;
+
+#[test]
+fn test_smismember() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-smismember");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec!["test-smismember".into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(SAdd {
+                    key: "test-smismember".into(),
+                    members: vec![b"a".to_vec(), b"c".to_vec()],
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(SMIsMember {
+                    key: "test-smismember".into(),
+                    members: vec![
+                        b"a".to_vec(),
+                        b"b".to_vec(),
+                        b"c".to_vec(),
+                        b"d".to_vec(),
+                    ],
+                })
+            }
+        })
+        .map(|res| {
+            assert_eq!(res.unwrap(), vec![true, false, true, false]);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,132 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::{RedisClusterActor, RespValue};
+use futures::Future;
+
+const KEY: &str = "test-sort";
+
+fn values(values: &[&str]) -> Vec<Option<Vec<u8>>> {
+    values.iter().map(|v| Some(v.as_bytes().to_vec())).collect()
+}
+
+#[test]
+fn test_sort() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-sort");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(SAdd {
+                    key: KEY.into(),
+                    members: vec![
+                        b"10".to_vec(),
+                        b"9".to_vec(),
+                        b"100".to_vec(),
+                        b"2".to_vec(),
+                    ],
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(Sort {
+                    key: KEY.into(),
+                    ..Sort::default()
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), values(&["2", "9", "10", "100"]));
+                addr.send(SortRo(Sort {
+                    key: KEY.into(),
+                    alpha: true,
+                    ..Sort::default()
+                }))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), values(&["10", "100", "2", "9"]));
+                addr.send(Sort {
+                    key: KEY.into(),
+                    limit: Some((1, 2)),
+                    order: Some(SortOrder::Desc),
+                    ..Sort::default()
+                })
+            }
+        })
+        .map(|res| {
+            assert_eq!(res.unwrap(), values(&["10", "9"]));
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_sort_request() {
+    let sort = Sort {
+        key: "{user}:ids".into(),
+        by: Some("{user}:weight_*".into()),
+        get: vec!["#".into(), "{user}:name_*".into()],
+        limit: Some((0, 10)),
+        order: Some(SortOrder::Desc),
+        alpha: true,
+    };
+    assert!(sort.key_slot().is_ok());
+    let args: Vec<RespValue> = [
+        "SORT",
+        "{user}:ids",
+        "BY",
+        "{user}:weight_*",
+        "LIMIT",
+        "0",
+        "10",
+        "GET",
+        "#",
+        "GET",
+        "{user}:name_*",
+        "DESC",
+        "ALPHA",
+    ]
+    .iter()
+    .map(|&arg| arg.into())
+    .collect();
+    assert_eq!(sort.into_request(), RespValue::Array(args));
+
+    // patterns of keys in other slots
+    let sort = Sort {
+        key: "{user}:ids".into(),
+        get: vec!["name_*".into()],
+        ..Sort::default()
+    };
+    assert!(sort.key_slot().is_err());
+    let sort = SortRo(Sort {
+        key: "{user}:ids".into(),
+        by: Some("weight_*->field".into()),
+        ..Sort::default()
+    });
+    assert!(sort.key_slot().is_err());
+    // not sorting
+    let sort = Sort {
+        key: "{user}:ids".into(),
+        by: Some("nosort".into()),
+        ..Sort::default()
+    };
+    assert!(sort.key_slot().is_ok());
+}
@@ -0,0 +1,93 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::{Error, RedisClusterActor};
+use futures::Future;
+
+const KEY: &str = "test-stream";
+
+fn xadd(id: Option<&str>, value: &str) -> XAdd {
+    XAdd {
+        key: KEY.into(),
+        id: id.map(Into::into),
+        fields: vec![("value".into(), value.into())],
+    }
+}
+
+fn xread(after: &str, count: Option<usize>, block_ms: Option<u64>) -> XRead {
+    XRead {
+        streams: vec![(KEY.into(), after.into())],
+        count,
+        block_ms,
+    }
+}
+
+fn entry(id: &str, value: &str) -> StreamEntry {
+    StreamEntry {
+        id: id.into(),
+        fields: vec![("value".into(), value.into())],
+    }
+}
+
+#[test]
+fn test_stream() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-stream");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(xadd(Some("1-1"), "first"))
+                    .join3(
+                        addr.send(xadd(Some("1-2"), "second")),
+                        addr.send(xadd(None, "third")),
+                    )
+                    .join(addr.send(xadd(Some("1-1"), "again")))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |((first, second, third), again)| {
+                assert_eq!(first.unwrap(), "1-1");
+                assert_eq!(second.unwrap(), "1-2");
+                let third = third.unwrap();
+                assert_ne!(third, "1-2");
+                match again {
+                    Err(Error::Redis(_)) => (),
+                    res => panic!("Should not happen {:?}", res),
+                }
+                addr.send(xread("0-0", Some(2), None))
+                    .join3(
+                        addr.send(xread("1-2", None, None)),
+                        addr.send(xread(&third, None, Some(100))),
+                    )
+                    .map(move |res| (res, third))
+            }
+        })
+        .map(|((page, rest, none), third)| {
+            assert_eq!(
+                page.unwrap(),
+                vec![(
+                    KEY.to_owned(),
+                    vec![entry("1-1", "first"), entry("1-2", "second")]
+                )]
+            );
+            assert_eq!(
+                rest.unwrap(),
+                vec![(KEY.to_owned(), vec![entry(&third, "third")])]
+            );
+            // timed out
+            assert!(none.unwrap().is_empty());
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
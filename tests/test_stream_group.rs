@@ -0,0 +1,124 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::RedisClusterActor;
+use futures::Future;
+
+const KEY: &str = "test-stream-group";
+const GROUP: &str = "workers";
+
+fn xadd(id: &str) -> XAdd {
+    XAdd {
+        key: KEY.into(),
+        id: Some(id.into()),
+        fields: vec![("job".into(), id.into())],
+    }
+}
+
+fn read(consumer: &str, after: &str, count: Option<usize>) -> XReadGroup {
+    XReadGroup {
+        group: GROUP.into(),
+        consumer: consumer.into(),
+        streams: vec![(KEY.into(), after.into())],
+        count,
+        block_ms: None,
+    }
+}
+
+fn ack(ids: &[&str]) -> XAck {
+    XAck {
+        key: KEY.into(),
+        group: GROUP.into(),
+        ids: ids.iter().map(|&id| id.into()).collect(),
+    }
+}
+
+/// The ids of the entries read
+fn ids(streams: Vec<(String, Vec<StreamEntry>)>) -> Vec<String> {
+    streams
+        .into_iter()
+        .flat_map(|(key, entries)| {
+            assert_eq!(key, KEY);
+            entries.into_iter().map(|entry| entry.id)
+        })
+        .collect()
+}
+
+#[test]
+fn test_stream_group() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-stream-group");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+    let create = || XGroupCreate {
+        key: KEY.into(),
+        group: GROUP.into(),
+        id: "0".into(),
+        mkstream: false,
+    };
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(create())
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                // no stream yet
+                assert!(res.unwrap().is_err());
+                addr.send(xadd("1-1"))
+                    .join3(addr.send(xadd("1-2")), addr.send(xadd("1-3")))
+                    .join3(addr.send(create()), addr.send(create()))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |((a, b, c), created, busy)| {
+                a.unwrap();
+                b.unwrap();
+                c.unwrap();
+                created.unwrap().unwrap();
+                assert!(busy.unwrap().unwrap_err().starts_with("BUSYGROUP"));
+                addr.send(read("alice", ">", Some(2)))
+                    .join(addr.send(read("bob", ">", None)))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |(alice, bob)| {
+                // each entry is delivered to one consumer only
+                assert_eq!(ids(alice.unwrap()), vec!["1-1", "1-2"]);
+                assert_eq!(ids(bob.unwrap()), vec!["1-3"]);
+                addr.send(read("alice", "0", None))
+                    .join3(addr.send(ack(&["1-1"])), addr.send(ack(&["1-1"])))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |(pending, acked, again)| {
+                assert_eq!(ids(pending.unwrap()), vec!["1-1", "1-2"]);
+                assert_eq!((acked.unwrap(), again.unwrap()), (1, 0));
+                addr.send(read("alice", "0", None)).join3(
+                    addr.send(read("bob", "0", None)),
+                    addr.send(read("alice", ">", None)),
+                )
+            }
+        })
+        .map(|(alice, bob, new)| {
+            // acked entries left the pending list
+            assert_eq!(ids(alice.unwrap()), vec!["1-2"]);
+            assert_eq!(ids(bob.unwrap()), vec!["1-3"]);
+            assert!(new.unwrap().is_empty());
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
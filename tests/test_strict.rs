@@ -0,0 +1,99 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::slot::{HashError, Hasher};
+use actix_redis::{Error, RedisActor, RespError, RespValue};
+use futures::Future;
+
+/// Sends an integer where the server expects a bulk string
+struct Inline;
+
+impl Message for Inline {
+    type Result = Result<RespValue, Error>;
+}
+
+impl Command for Inline {
+    type Output = RespValue;
+
+    fn into_request(self) -> RespValue {
+        RespValue::Array(vec!["PING".into(), RespValue::Integer(1)])
+    }
+
+    fn from_response(res: RespValue) -> Result<Self::Output, RespError> {
+        Ok(res)
+    }
+
+    fn hash_keys(&self, _hasher: &mut Hasher) -> Result<(), HashError> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_garbage_reply() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-garbage-reply");
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let server = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 64];
+        let _ = stream.read(&mut buf).unwrap();
+        stream.write_all(b"hello\r\n+OK\r\n").unwrap();
+        let _ = stream.read(&mut buf);
+    });
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisActor::builder(server).strict(true).start();
+        addr.send(Get {
+            key: "test-garbage".into(),
+        })
+        .join(addr.send(Get {
+            key: "test-garbage".into(),
+        }))
+        .map(|(first, second)| {
+            match first {
+                Err(Error::Parse(_)) => (),
+                res => panic!("Should not happen {:?}", res),
+            }
+            // the connection is dropped, later replies are not mis-framed
+            match second {
+                Err(Error::Disconnected) => (),
+                res => panic!("Should not happen {:?}", res),
+            }
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_strict_request() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-strict-request");
+
+    Arbiter::spawn_fn(move || {
+        let addr = RedisActor::builder("127.0.0.1:6379").strict(true).start();
+        addr.send(Inline)
+            .join(addr.send(IncrBy {
+                key: "test-strict-incrby".into(),
+                increment: 2,
+            }))
+            .map(|(inline, incr)| {
+                match inline {
+                    Err(Error::Redis(_)) => (),
+                    res => panic!("Should not happen {:?}", res),
+                }
+                assert!(incr.unwrap().is_ok());
+                System::current().stop();
+            })
+            .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
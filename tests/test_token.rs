@@ -0,0 +1,42 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::{token, RedisActor};
+use futures::Future;
+
+#[test]
+fn test_token_consume() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-token-consume");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+    let key = "test-token";
+
+    Arbiter::spawn_fn(move || {
+        let redis = addr.clone().recipient();
+        addr.send(Set {
+            key: key.into(),
+            value: "user:42".into(),
+            expiration: Expiration::Ex("60".into()),
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+        .and_then({
+            let redis = redis.clone();
+            move |res| {
+                res.unwrap();
+                token::consume(&redis, key)
+            }
+        })
+        .and_then(move |payload| {
+            assert_eq!(payload, Some(b"user:42".to_vec()));
+            // a replay finds nothing
+            token::consume(&redis, key)
+        })
+        .map(|payload| {
+            assert_eq!(payload, None);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
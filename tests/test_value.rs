@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use actix_redis::command::{FromRedisValue, ToRedisArgs};
+use actix_redis::value::Iter;
+use actix_redis::{Bytes, RespValue};
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(s.as_bytes().to_vec())
+}
+
+fn decode<T: FromRedisValue>(value: RespValue) -> Result<T, RespValue> {
+    T::from_redis_value(value)
+}
+
+#[test]
+fn test_i64() {
+    assert_eq!(decode::<i64>(RespValue::Integer(-42)), Ok(-42));
+    assert_eq!(decode::<i64>(bulk("42")), Err(bulk("42")));
+}
+
+#[test]
+fn test_bool() {
+    assert_eq!(decode::<bool>(RespValue::Integer(0)), Ok(false));
+    assert_eq!(decode::<bool>(RespValue::Integer(1)), Ok(true));
+    assert_eq!(
+        decode::<bool>(RespValue::Integer(2)),
+        Err(RespValue::Integer(2))
+    );
+}
+
+#[test]
+fn test_string() {
+    assert_eq!(decode::<String>(bulk("foo")), Ok("foo".to_owned()));
+    assert_eq!(
+        decode::<String>(RespValue::SimpleString("OK".into())),
+        Ok("OK".to_owned())
+    );
+    let invalid = RespValue::BulkString(vec![0xff]);
+    assert_eq!(decode::<String>(invalid.clone()), Err(invalid));
+    assert_eq!(decode::<String>(RespValue::Nil), Err(RespValue::Nil));
+}
+
+#[test]
+fn test_bytes() {
+    assert_eq!(decode::<Vec<u8>>(bulk("foo")), Ok(b"foo".to_vec()));
+    assert_eq!(
+        decode::<Vec<u8>>(RespValue::Integer(1)),
+        Err(RespValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_option() {
+    assert_eq!(decode::<Option<i64>>(RespValue::Nil), Ok(None));
+    assert_eq!(decode::<Option<i64>>(RespValue::Integer(1)), Ok(Some(1)));
+    assert_eq!(decode::<Option<i64>>(bulk("1")), Err(bulk("1")));
+}
+
+#[test]
+fn test_vec() {
+    let values = RespValue::Array(vec![RespValue::Integer(1), RespValue::Integer(2)]);
+    assert_eq!(decode::<Vec<i64>>(values), Ok(vec![1, 2]));
+    assert_eq!(decode::<Vec<i64>>(RespValue::Array(vec![])), Ok(vec![]));
+    // the element of an unexpected type
+    let values = RespValue::Array(vec![RespValue::Integer(1), bulk("2")]);
+    assert_eq!(decode::<Vec<i64>>(values), Err(bulk("2")));
+}
+
+#[test]
+fn test_nested() {
+    let values = RespValue::Array(vec![bulk("a"), RespValue::Nil, bulk("b")]);
+    assert_eq!(
+        decode::<Vec<Option<String>>>(values),
+        Ok(vec![Some("a".to_owned()), None, Some("b".to_owned())])
+    );
+    let values = RespValue::Array(vec![RespValue::Array(vec![bulk("a")])]);
+    assert_eq!(
+        decode::<Vec<Vec<String>>>(values),
+        Ok(vec![vec!["a".to_owned()]])
+    );
+}
+
+#[test]
+fn test_hash_map() {
+    let values = RespValue::Array(vec![
+        bulk("a"),
+        RespValue::Integer(1),
+        bulk("b"),
+        RespValue::Integer(2),
+    ]);
+    let mut expected = HashMap::new();
+    expected.insert("a".to_owned(), 1);
+    expected.insert("b".to_owned(), 2);
+    assert_eq!(decode::<HashMap<String, i64>>(values), Ok(expected));
+
+    // a key without a value
+    let values = RespValue::Array(vec![bulk("a")]);
+    assert_eq!(decode::<HashMap<String, i64>>(values.clone()), Err(values));
+    let values = RespValue::Array(vec![bulk("a"), bulk("1")]);
+    assert_eq!(decode::<HashMap<String, i64>>(values), Err(bulk("1")));
+}
+
+#[test]
+fn test_unit() {
+    assert_eq!(decode::<()>(RespValue::SimpleString("OK".into())), Ok(()));
+    assert_eq!(decode::<()>(RespValue::Nil), Ok(()));
+    let error = RespValue::Error("ERR".into());
+    assert_eq!(decode::<()>(error.clone()), Err(error));
+}
+
+#[test]
+fn test_tuple() {
+    let values = RespValue::Array(vec![
+        RespValue::SimpleString("OK".into()),
+        RespValue::Integer(1),
+        bulk("a"),
+    ]);
+    assert_eq!(
+        decode::<((), i64, Option<String>)>(values),
+        Ok(((), 1, Some("a".to_owned())))
+    );
+    // not as many elements
+    let values = RespValue::Array(vec![RespValue::Integer(1)]);
+    assert_eq!(decode::<(i64, i64)>(values.clone()), Err(values));
+    let values = RespValue::Array(vec![RespValue::Integer(1), bulk("2")]);
+    assert_eq!(decode::<(i64, i64)>(values), Err(bulk("2")));
+}
+
+/// The arguments as strings
+fn args<A: ToRedisArgs>(args: A) -> Vec<String> {
+    args.to_redis_args()
+        .into_iter()
+        .map(|arg| match arg {
+            RespValue::BulkString(arg) => String::from_utf8(arg).unwrap(),
+            arg => panic!("Should not happen {:?}", arg),
+        })
+        .collect()
+}
+
+#[test]
+fn test_integer_args() {
+    assert_eq!(args(-42i64), vec!["-42"]);
+    assert_eq!(args(42u16), vec!["42"]);
+    assert_eq!(args(4_294_967_296u64), vec!["4294967296"]);
+    assert_eq!(args(7usize), vec!["7"]);
+}
+
+#[test]
+fn test_float_args() {
+    assert_eq!(args(1.0), vec!["1"]);
+    assert_eq!(args(-0.5), vec!["-0.5"]);
+    assert_eq!(args(0.1), vec!["0.1"]);
+    // no exponent, no separators
+    assert_eq!(args(1234567.25), vec!["1234567.25"]);
+    assert_eq!(args(1e21), vec!["1000000000000000000000"]);
+    assert_eq!(args(1.5f32), vec!["1.5"]);
+    let infinity: f64 = "inf".parse().unwrap();
+    assert_eq!(args(infinity), vec!["inf"]);
+    assert_eq!(args(-infinity), vec!["-inf"]);
+}
+
+#[test]
+fn test_string_args() {
+    assert_eq!(args("foo"), vec!["foo"]);
+    assert_eq!(args("foo".to_owned()), vec!["foo"]);
+}
+
+#[test]
+fn test_bytes_args() {
+    let expected = vec![RespValue::BulkString(vec![0, 0xff])];
+    assert_eq!(vec![0u8, 0xff].to_redis_args(), expected);
+    assert_eq!((&[0u8, 0xff][..]).to_redis_args(), expected);
+    assert_eq!(Bytes::from(&[0u8, 0xff][..]).to_redis_args(), expected);
+}
+
+#[test]
+fn test_sequence_args() {
+    assert_eq!(args(vec![1, 2]), vec!["1", "2"]);
+    assert_eq!(args(&["a", "b"][..]), vec!["a", "b"]);
+    assert_eq!(args(&["a".to_owned()][..]), vec!["a"]);
+    assert_eq!(args(Vec::<i64>::new()), Vec::<String>::new());
+    assert_eq!(args(Some(1)), vec!["1"]);
+    assert_eq!(args(None::<i64>), Vec::<String>::new());
+    assert_eq!(
+        args(("ZADD", "key", vec![(1.5, "a"), (2.0, "b")])),
+        vec!["ZADD", "key", "1.5", "a", "2", "b"]
+    );
+    assert_eq!(args(Iter((1..4).map(|i| i * 2))), vec!["2", "4", "6"]);
+}
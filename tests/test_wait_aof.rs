@@ -0,0 +1,53 @@
+use actix::prelude::*;
+use actix_redis::{command::*, RedisActor, RespValue};
+use futures::Future;
+
+#[test]
+fn test_wait_aof_response() {
+    let res = RespValue::Array(vec![RespValue::Integer(1), RespValue::Integer(2)]);
+    assert_eq!(WaitAof::from_response(res).unwrap(), Ok((1, 2)));
+
+    let msg =
+        "ERR WAITAOF cannot be used when numlocal is set but appendonly is disabled.";
+    let res = RespValue::Error(msg.into());
+    assert_eq!(WaitAof::from_response(res).unwrap(), Err(msg.into()));
+
+    let res = RespValue::Array(vec![RespValue::Integer(1)]);
+    assert!(WaitAof::from_response(res).is_err());
+}
+
+#[test]
+fn test_wait_aof() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-wait-aof");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Set {
+            key: "test-wait-aof".into(),
+            value: "value".into(),
+            expiration: Expiration::Infinite,
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                // num_local 0 is accepted with AOF disabled
+                addr.send(WaitAof {
+                    num_local: 0,
+                    num_replicas: 0,
+                    timeout_ms: 100,
+                })
+            }
+        })
+        .map(|res| {
+            let (local, replicas) = res.unwrap().unwrap();
+            assert!(local >= 0 && replicas >= 0);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,87 @@
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_redis::{command::*, pubsub::WaitForKey, RedisActor};
+use futures::Future;
+use tokio_timer::Delay;
+
+const KEY: &str = "test-wait-for-key";
+
+fn wait(
+    addr: &Addr<RedisActor>,
+    timeout: Duration,
+) -> impl Future<Item = bool, Error = actix_redis::Error> {
+    WaitForKey {
+        key: KEY.into(),
+        timeout,
+    }
+    .start("127.0.0.1:6379", addr.clone().recipient())
+}
+
+#[test]
+fn test_wait_for_key() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-wait-for-key");
+
+    let addr = RedisActor::start("127.0.0.1:6379");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(ConfigSet {
+            parameter: "notify-keyspace-events".into(),
+            value: "K$".into(),
+        })
+        .join(addr.send(Del {
+            keys: vec![KEY.into()],
+        }))
+        .and_then({
+            let addr = addr.clone();
+            move |(config, del)| {
+                config.unwrap().unwrap();
+                del.unwrap();
+                // not set meanwhile
+                wait(&addr, Duration::from_millis(200))
+                    .map_err(|e| panic!("Should not happen {:?}", e))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |found| {
+                assert!(!found);
+
+                let start = Instant::now();
+                let set = Delay::new(Instant::now() + Duration::from_millis(200))
+                    .map_err(|e| panic!("Should not happen {:?}", e))
+                    .and_then({
+                        let addr = addr.clone();
+                        move |()| {
+                            addr.send(Set {
+                                key: KEY.into(),
+                                value: "done".into(),
+                                expiration: Expiration::Infinite,
+                            })
+                        }
+                    })
+                    .map(|res| res.unwrap());
+                wait(&addr, Duration::from_secs(5))
+                    .map_err(|e| panic!("Should not happen {:?}", e))
+                    .join(set)
+                    .map(move |(found, ())| {
+                        assert!(found);
+                        assert!(start.elapsed() < Duration::from_secs(1));
+                    })
+            }
+        })
+        .and_then(move |()| {
+            // already set
+            wait(&addr, Duration::from_secs(5))
+                .map_err(|e| panic!("Should not happen {:?}", e))
+        })
+        .map(|found| {
+            assert!(found);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
@@ -0,0 +1,89 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::RedisClusterActor;
+use futures::Future;
+
+fn zadd(score: f64) -> ZAdd {
+    ZAdd {
+        key: "test-zadd".into(),
+        members: vec![(score, b"player".to_vec())],
+        condition: None,
+        comparison: None,
+        ch: false,
+        incr: false,
+    }
+}
+
+#[test]
+fn test_zadd() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-zadd");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec!["test-zadd".into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(zadd(10.0))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), ZAddReply::Count(1));
+                addr.send(ZAdd {
+                    comparison: Some(ZAddComparison::Gt),
+                    ch: true,
+                    ..zadd(5.0)
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                // GT refuses to lower the score
+                assert_eq!(res.unwrap(), ZAddReply::Count(0));
+                addr.send(ZAdd {
+                    incr: true,
+                    ..zadd(2.5)
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), ZAddReply::Score(Some(12.5)));
+                addr.send(ZAdd {
+                    comparison: Some(ZAddComparison::Gt),
+                    ch: true,
+                    ..zadd(20.0)
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                // a raised score is counted with CH
+                assert_eq!(res.unwrap(), ZAddReply::Count(1));
+                addr.send(ZAdd {
+                    condition: Some(ZAddCondition::Nx),
+                    incr: true,
+                    ..zadd(1.0)
+                })
+            }
+        })
+        .map(|res| {
+            // NX refuses to update the existing member
+            assert_eq!(res.unwrap(), ZAddReply::Score(None));
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
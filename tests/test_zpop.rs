@@ -0,0 +1,119 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::RedisClusterActor;
+use futures::Future;
+
+const KEY: &str = "test-zpop";
+
+fn zpop_min(count: Option<usize>) -> ZPopMin {
+    ZPopMin {
+        key: KEY.into(),
+        count,
+    }
+}
+
+#[test]
+fn test_zpop() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-zpop");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec![KEY.into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(ZAdd {
+                    key: KEY.into(),
+                    members: vec![
+                        (30.0, b"job-3".to_vec()),
+                        (10.0, b"job-1".to_vec()),
+                        (40.0, b"job-4".to_vec()),
+                        (20.0, b"job-2".to_vec()),
+                    ],
+                    condition: None,
+                    comparison: None,
+                    ch: false,
+                    incr: false,
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), ZAddReply::Count(4));
+                addr.send(zpop_min(None))
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), vec![(b"job-1".to_vec(), 10.0)]);
+                addr.send(ZPopMax {
+                    key: KEY.into(),
+                    count: None,
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), vec![(b"job-4".to_vec(), 40.0)]);
+                addr.send(BZPopMin {
+                    keys: vec![KEY.into()],
+                    timeout: 1.0,
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(
+                    res.unwrap(),
+                    Some((KEY.to_string(), b"job-2".to_vec(), 20.0))
+                );
+                addr.send(zpop_min(Some(5)))
+            }
+        })
+        .map(|res| {
+            assert_eq!(res.unwrap(), vec![(b"job-3".to_vec(), 30.0)]);
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}
+
+#[test]
+fn test_zpop_response() {
+    use actix_redis::RespValue::{Array, BulkString, Nil};
+
+    let res = Array(vec![
+        BulkString(b"job-1".to_vec()),
+        BulkString(b"10".to_vec()),
+        BulkString(b"job-2".to_vec()),
+        BulkString(b"20.5".to_vec()),
+    ]);
+    assert_eq!(
+        ZPopMin::from_response(res).unwrap(),
+        vec![(b"job-1".to_vec(), 10.0), (b"job-2".to_vec(), 20.5)]
+    );
+    assert_eq!(ZPopMax::from_response(Array(vec![])).unwrap(), vec![]);
+    assert!(ZPopMin::from_response(Array(vec![BulkString(b"job-1".to_vec())])).is_err());
+
+    let res = Array(vec![
+        BulkString(KEY.into()),
+        BulkString(b"job-1".to_vec()),
+        BulkString(b"10".to_vec()),
+    ]);
+    assert_eq!(
+        BZPopMin::from_response(res).unwrap(),
+        Some((KEY.to_string(), b"job-1".to_vec(), 10.0))
+    );
+    assert_eq!(BZPopMin::from_response(Nil).unwrap(), None);
+}
@@ -0,0 +1,95 @@
+use actix::prelude::*;
+use actix_redis::command::*;
+use actix_redis::RedisClusterActor;
+use futures::Future;
+
+/// Members between `[prefix` and `(prefix\xff`
+fn complete(prefix: &str, limit: Option<(i64, i64)>) -> ZRangeByLex {
+    ZRangeByLex {
+        key: "test-zrangebylex".into(),
+        min: LexBound::Inclusive(prefix.into()),
+        max: LexBound::Exclusive([prefix.as_bytes(), b"\xff"].concat()),
+        limit,
+    }
+}
+
+#[test]
+fn test_lex_bound_parse() {
+    assert_eq!(LexBound::parse(b"-"), Some(LexBound::Min));
+    assert_eq!(LexBound::parse(b"+"), Some(LexBound::Max));
+    assert_eq!(
+        LexBound::parse(b"[app"),
+        Some(LexBound::Inclusive(b"app".to_vec()))
+    );
+    assert_eq!(LexBound::parse(b"("), Some(LexBound::Exclusive(vec![])));
+    assert_eq!(LexBound::parse(b"app"), None);
+    assert_eq!(LexBound::parse(b"-a"), None);
+    assert_eq!(LexBound::parse(b""), None);
+}
+
+#[test]
+fn test_zrangebylex() -> std::io::Result<()> {
+    let _ = env_logger::try_init();
+    let sys = System::new("test-zrangebylex");
+
+    let addr = RedisClusterActor::start("127.0.0.1:7000");
+    let words = ["apple", "application", "apply", "banana", "appeal", "ape"];
+
+    Arbiter::spawn_fn(move || {
+        addr.send(Del {
+            keys: vec!["test-zrangebylex".into()],
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                res.unwrap();
+                addr.send(ZAdd {
+                    key: "test-zrangebylex".into(),
+                    members: words
+                        .iter()
+                        .map(|w| (0.0, w.as_bytes().to_vec()))
+                        .collect(),
+                    condition: None,
+                    comparison: None,
+                    ch: false,
+                    incr: false,
+                })
+            }
+        })
+        .and_then({
+            let addr = addr.clone();
+            move |res| {
+                assert_eq!(res.unwrap(), ZAddReply::Count(6));
+                addr.send(complete("app", None))
+                    .join3(
+                        addr.send(complete("app", Some((1, 2)))),
+                        addr.send(ZLexCount {
+                            key: "test-zrangebylex".into(),
+                            min: LexBound::Min,
+                            max: LexBound::Exclusive(b"b".to_vec()),
+                        }),
+                    )
+                    .join(addr.send(complete("cherry", None)))
+            }
+        })
+        .map(|((all, page, count), none)| {
+            let words = |words: &[&str]| {
+                words
+                    .iter()
+                    .map(|w| w.as_bytes().to_vec())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(
+                all.unwrap(),
+                words(&["appeal", "apple", "application", "apply"])
+            );
+            assert_eq!(page.unwrap(), words(&["apple", "application"]));
+            assert_eq!(count.unwrap(), 5);
+            assert!(none.unwrap().is_empty());
+            System::current().stop();
+        })
+        .map_err(|e| panic!("Should not happen {:?}", e))
+    });
+
+    sys.run()
+}